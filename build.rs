@@ -1,12 +1,27 @@
-use std::process::Command;
+use std::{
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-fn main() {
+fn git(args: &[&str]) -> String {
     let output = Command::new("git")
-        .args(["describe", "--tags", "--always"])
+        .args(args)
         .output()
         .expect("Failed to execute git command");
 
-    let git_description = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+fn main() {
+    let git_description = git(&["describe", "--tags", "--always"]);
+    let git_sha = git(&["rev-parse", "HEAD"]);
+    // Unix seconds, formatted at runtime so the build needs no date crate
+    let build_timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System clock is before the unix epoch")
+        .as_secs();
 
     println!("cargo:rustc-env=GIT_VERSION={}", git_description);
+    println!("cargo:rustc-env=GIT_SHA={}", git_sha);
+    println!("cargo:rustc-env=BUILD_TIMESTAMP={}", build_timestamp);
 }
@@ -1,13 +1,11 @@
 use std::collections::HashMap;
 
-use aws_config::meta::region::RegionProviderChain;
-use aws_config::retry::RetryConfig;
-use aws_config::{BehaviorVersion, Region};
-use aws_sdk_cognitoidentityprovider::operation::respond_to_auth_challenge::RespondToAuthChallengeOutput;
-use aws_sdk_cognitoidentityprovider::types::{
-    AuthFlowType, AuthenticationResultType, ChallengeNameType,
+use aws_config::{meta::region::RegionProviderChain, retry::RetryConfig, BehaviorVersion, Region};
+use aws_sdk_cognitoidentityprovider::{
+    operation::respond_to_auth_challenge::RespondToAuthChallengeOutput,
+    types::{AuthFlowType, AuthenticationResultType, ChallengeNameType},
+    Client,
 };
-use aws_sdk_cognitoidentityprovider::Client;
 use cognito_srp::SrpClient;
 
 use crate::error::CognitoSrpAuthError;
@@ -1,8 +1,11 @@
 use std::io;
 
-use aws_sdk_cognitoidentityprovider::error::SdkError;
-use aws_sdk_cognitoidentityprovider::operation::initiate_auth::InitiateAuthError;
-use aws_sdk_cognitoidentityprovider::operation::respond_to_auth_challenge::RespondToAuthChallengeError;
+use aws_sdk_cognitoidentityprovider::{
+    error::SdkError,
+    operation::{
+        initiate_auth::InitiateAuthError, respond_to_auth_challenge::RespondToAuthChallengeError,
+    },
+};
 use cognito_srp::CognitoSrpError;
 use thiserror::Error;
 
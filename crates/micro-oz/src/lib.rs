@@ -1,15 +1,19 @@
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, AtomicU64};
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64},
+        Arc,
+    },
+};
 
 use anyhow::Context;
 use chrono::Utc;
-use ethers::prelude::k256::ecdsa::SigningKey;
-use ethers::prelude::SignerMiddleware;
-use ethers::providers::{Http, Middleware, Provider};
-use ethers::signers::{LocalWallet, Signer};
-use ethers::types::transaction::eip2718::TypedTransaction;
-use ethers::types::{Eip1559TransactionRequest, U64};
+use ethers::{
+    prelude::{k256::ecdsa::SigningKey, SignerMiddleware},
+    providers::{Http, Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::{transaction::eip2718::TypedTransaction, Eip1559TransactionRequest, U64},
+};
 use oz_api::data::transactions::{RelayerTransactionBase, SendBaseTransactionRequestOwned, Status};
 use tokio::sync::{mpsc, Mutex};
 
@@ -1,8 +1,7 @@
 use std::time::{Duration, Instant};
 
 use cognitoauth::cognito_srp_auth::{auth, CognitoAuthInput};
-use hyper::http::HeaderValue;
-use hyper::HeaderMap;
+use hyper::{http::HeaderValue, HeaderMap};
 use tracing::info;
 
 use crate::error::Error;
@@ -1,6 +1,5 @@
 use cognitoauth::error::CognitoSrpAuthError;
-use hyper::header::InvalidHeaderValue;
-use hyper::StatusCode;
+use hyper::{header::InvalidHeaderValue, StatusCode};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -121,6 +121,18 @@ impl OzApi {
         Self::json_or_error(res).await
     }
 
+    /// Cancels a transaction that isn't mined yet. Defender replaces it
+    /// with a no-op at the same nonce and returns that.
+    pub async fn cancel_transaction(&self, tx_id: &str) -> Result<RelayerTransactionBase> {
+        let url = self.txs_url()?.join("txs/")?.join(tx_id)?;
+
+        let headers = self.headers().await?;
+
+        let res = headers.apply(self.client.delete(url)).send().await?;
+
+        Self::json_or_error(res).await
+    }
+
     fn txs_url(&self) -> Result<Url> {
         Ok(self.api_url.join("txs")?)
     }
@@ -1,5 +1,4 @@
-use testcontainers::clients::Cli;
-use testcontainers::{Container, RunnableImage};
+use testcontainers::{clients::Cli, Container, RunnableImage};
 use testcontainers_modules::postgres::Postgres;
 
 pub struct DockerContainer<'a> {
@@ -64,6 +64,22 @@ impl TxSitterClient {
         self.json_get(&format!("{}/tx/{}", self.url, tx_id)).await
     }
 
+    /// Like `get_tx`, but returns `None` when the tx sitter has no record of
+    /// the transaction.
+    #[instrument(skip(self))]
+    pub async fn find_tx(&self, tx_id: &str) -> anyhow::Result<Option<GetTxResponse>> {
+        let url = format!("{}/tx/{}", self.url, tx_id);
+        let response = self.client.get(&url).send().await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let response = Self::validate_response(response).await?;
+
+        Ok(Some(response.json().await?))
+    }
+
     #[instrument(skip(self))]
     pub async fn get_txs(&self) -> anyhow::Result<Vec<GetTxResponse>> {
         let url = format!("{}/txs", self.url);
@@ -0,0 +1,59 @@
+//! Runs the bridge inside another binary, without our HTTP server.
+//!
+//! ```sh
+//! cargo run --example embedded --no-default-features -- config.toml
+//! ```
+//!
+//! The status is printed every 30 seconds instead of being served, which is
+//! where an embedder would hook up their own transport.
+
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use scroll_service::{
+    app::App,
+    config::load_config,
+    utils::shutdown::{watch_shutdown_signals, Shutdown},
+};
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(30);
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let config_path = std::env::args_os().nth(1).map(PathBuf::from);
+    let config = load_config(config_path.as_deref())?;
+
+    let shutdown = Arc::new(Shutdown::new());
+    watch_shutdown_signals(shutdown.clone());
+
+    let app = App::new(config).await?;
+    app.initialize_server().await?;
+    app.await_clean_slate().await?;
+
+    let tasks = app.spawn_tasks(shutdown.clone()).await;
+
+    // Don't wait for the first sync check
+    match app.trigger_propagation().await {
+        Ok(state) => tracing::info!(?state, "Checked the roots"),
+        Err(err) => tracing::warn!(%err, "Didn't trigger a propagation"),
+    }
+
+    let mut interval = tokio::time::interval(REPORT_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let status = app.status().await?;
+                let roots = app.latest_roots().await?;
+                println!("{}", serde_json::to_string(&status)?);
+                println!("mainnet {:#x}, scroll {:?}", roots.mainnet, roots.scroll);
+            }
+            () = shutdown.await_shutdown() => break,
+        }
+    }
+
+    tasks.shutdown().await?;
+    app.shutdown().await?;
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! Drives the processor from another binary: one scan, one sync check and,
+//! if Scroll is behind, one propagation. Neither the HTTP server nor the
+//! task monitor is started.
+//!
+//! ```sh
+//! cargo run --example processor --no-default-features -- config.toml
+//! ```
+//!
+//! An orchestrator would run this on its own schedule, in place of the
+//! service's sync check.
+
+use std::path::PathBuf;
+
+use scroll_service::{config::load_config, BridgeProcessor, Processor, Propagation, SyncState};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let config_path = std::env::args_os().nth(1).map(PathBuf::from);
+    let config = load_config(config_path.as_deref())?;
+
+    let processor = BridgeProcessor::new(&config).await?;
+
+    // The sync check goes by the scanned roots once there are any
+    let new_roots = processor.scan_roots().await?;
+    println!("scanned, WorldID moved to {new_roots} new roots");
+
+    let reason = match processor.sync_state().await? {
+        SyncState::Synced => {
+            println!("Scroll has the mainnet root, nothing to propagate");
+            return Ok(());
+        }
+        SyncState::Unsynced(reason) => reason,
+    };
+    println!("unsynced ({reason:?}), propagating");
+
+    match processor.propagate_root().await? {
+        Propagation::Submitted {
+            transaction_id,
+            root,
+        } => {
+            println!("sent {root:#x} as {transaction_id}, waiting for it to mine");
+            let succeeded = processor.mine_transaction(transaction_id).await?;
+            println!("mined, succeeded: {succeeded}");
+        }
+        Propagation::Deferred { root, age } => {
+            println!("{root:#x} is only {age:?} old, try again later");
+        }
+        other => println!("nothing sent: {other:?}"),
+    }
+
+    Ok(())
+}
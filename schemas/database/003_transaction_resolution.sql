@@ -0,0 +1,8 @@
+ALTER TYPE tx_status ADD VALUE IF NOT EXISTS 'failed';
+ALTER TYPE tx_status ADD VALUE IF NOT EXISTS 'abandoned';
+ALTER TYPE tx_status ADD VALUE IF NOT EXISTS 'superseded';
+
+ALTER TABLE transactions
+    ADD COLUMN tx_hash           VARCHAR(66) NULL,
+    ADD COLUMN resolution_reason TEXT        NULL,
+    ADD COLUMN resolved_at       TIMESTAMPTZ NULL;
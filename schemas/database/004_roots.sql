@@ -0,0 +1,16 @@
+CREATE TYPE root_source AS ENUM ('bridge', 'scroll');
+
+-- Roots observed on chain: `bridge` rows come from RootPropagated on the L1
+-- state bridge, `scroll` rows from RootAdded on the Scroll WorldID contract.
+CREATE TABLE roots
+  (
+    source        root_source  NOT NULL,
+    root          VARCHAR(66)  NOT NULL,
+    block_number  BIGINT       NOT NULL,
+    tx_hash       VARCHAR(66)  NOT NULL,
+    log_index     BIGINT       NOT NULL,
+    observed_at   TIMESTAMPTZ  NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (source, tx_hash, log_index)
+  );
+
+CREATE INDEX roots_source_block_number ON roots (source, block_number);
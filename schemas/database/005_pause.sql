@@ -0,0 +1,7 @@
+ALTER TABLE service_status
+    ADD COLUMN paused           BOOLEAN     NOT NULL DEFAULT FALSE,
+    ADD COLUMN pause_reason     TEXT        NULL,
+    ADD COLUMN paused_at        TIMESTAMPTZ NULL,
+    ADD COLUMN pause_expires_at TIMESTAMPTZ NULL,
+    ADD COLUMN resume_reason    TEXT        NULL,
+    ADD COLUMN resumed_at       TIMESTAMPTZ NULL;
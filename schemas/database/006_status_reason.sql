@@ -0,0 +1,2 @@
+ALTER TABLE service_status
+    ADD COLUMN status_reason TEXT NULL;
@@ -0,0 +1,3 @@
+-- The mainnet root a propagation was sent for, NULL for older transactions
+ALTER TABLE transactions
+    ADD COLUMN root TEXT NULL;
@@ -0,0 +1,8 @@
+-- The next block a backfill scans for each source, so an interrupted backfill
+-- resumes where it stopped
+CREATE TABLE scan_progress
+  (
+    source      root_source  PRIMARY KEY,
+    next_block  BIGINT       NOT NULL,
+    updated_at  TIMESTAMPTZ  NOT NULL DEFAULT CURRENT_TIMESTAMP
+  );
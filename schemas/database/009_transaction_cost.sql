@@ -0,0 +1,5 @@
+-- What a propagation cost once its receipt was read, gasUsed *
+-- effectiveGasPrice in wei as hex. NULL for transactions without a receipt
+-- and for older ones
+ALTER TABLE transactions
+    ADD COLUMN cost TEXT NULL;
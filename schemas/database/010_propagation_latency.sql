@@ -0,0 +1,11 @@
+-- When a propagation was seen mined on mainnet and when Scroll was seen adding
+-- its root, recorded in whichever order the two are observed. NULL until
+-- then, and for older transactions
+ALTER TABLE transactions
+    ADD COLUMN mined_at           TIMESTAMPTZ NULL,
+    ADD COLUMN delivered_at       TIMESTAMPTZ NULL,
+    ADD COLUMN delivery_timed_out BOOLEAN     NOT NULL DEFAULT FALSE;
+
+ALTER TABLE transactions
+    ADD COLUMN submit_to_mined    INTERVAL GENERATED ALWAYS AS (mined_at - created_at) STORED,
+    ADD COLUMN mined_to_delivered INTERVAL GENERATED ALWAYS AS (delivered_at - mined_at) STORED;
@@ -0,0 +1,9 @@
+-- Which replica holds the leadership advisory lock, written by the leader on
+-- every heartbeat so the others can report it
+CREATE TABLE leader
+  (
+    id            INT          PRIMARY KEY CHECK (id = 1),
+    instance      TEXT         NOT NULL,
+    acquired_at   TIMESTAMPTZ  NOT NULL,
+    heartbeat_at  TIMESTAMPTZ  NOT NULL
+  );
@@ -0,0 +1,7 @@
+-- When the Scroll block a `scroll` root was added in was first seen
+-- finalized, NULL until then and for `bridge` rows
+ALTER TABLE roots
+    ADD COLUMN finalized_at TIMESTAMPTZ NULL;
+
+CREATE INDEX roots_unfinalized ON roots (block_number)
+    WHERE source = 'scroll' AND finalized_at IS NULL;
@@ -0,0 +1,5 @@
+-- `/propagations` pages through the transactions newest first, and looks up
+-- each one's root on Scroll
+CREATE INDEX transactions_created_at_id ON transactions (created_at DESC, transaction_id DESC);
+
+CREATE INDEX roots_source_root ON roots (source, root, observed_at);
@@ -0,0 +1,7 @@
+-- What a propagation's cost is made of: gasUsed, and the price paid per gas
+-- in wei as hex. cost_estimated when the receipt had no effectiveGasPrice
+-- and the transaction's gasPrice was used instead
+ALTER TABLE transactions
+    ADD COLUMN gas_used BIGINT NULL,
+    ADD COLUMN effective_gas_price TEXT NULL,
+    ADD COLUMN cost_estimated BOOLEAN NOT NULL DEFAULT FALSE;
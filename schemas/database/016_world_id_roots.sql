@@ -0,0 +1,2 @@
+-- The roots WorldID on mainnet moves to, scanned from its TreeChanged logs
+ALTER TYPE root_source ADD VALUE 'world_id';
@@ -0,0 +1,2 @@
+-- A cancelled propagation whose original mined before its replacement
+ALTER TYPE tx_status ADD VALUE IF NOT EXISTS 'superseded_by_original';
@@ -1,43 +1,240 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
-use tracing::{info, instrument};
-use crate::config::Config;
-use crate::contracts::ScrollBridge;
-use crate::database::Database;
-use crate::database::query::DatabaseQuery;
-use crate::processor::{Processor, BridgeProcessor};
-use crate::ethereum::Ethereum;
-use crate::server::data::ServerStatusResponse;
+use crate::{
+    balance::BalanceState,
+    build_info::BuildInfo,
+    config::{Config, ConfigSources},
+    contracts,
+    contracts::abi_check::{check_contract_abis, ContractAbiReport},
+    database::{self, query::DatabaseQuery, types::TxStatus, Database},
+    ethereum::{Ethereum, PendingState, PendingTransaction, TransactionState},
+    exit,
+    finality::Finality,
+    leadership::Leadership,
+    metrics,
+    notifier::{Event, EventKind, Notifier},
+    pause::{Pause, PauseControl},
+    processor::{
+        self,
+        cancel::{self, CancelOutcome},
+        clean_slate::{self, Action, Outcome},
+        startup::{self, Correction},
+        status::BridgeStatus,
+        BridgeProcessor, Processor, RootBuffer, ScannersStatus,
+    },
+    task_monitor::{
+        health::TaskHealth,
+        tasks::{delivery, maintenance, monitor_txs},
+        wake::PropagationWake,
+        TaskHandles, TaskMonitor,
+    },
+    unsynced::UnsyncedState,
+    utils::{secret::Redacted, shutdown::Shutdown, TransactionId},
+};
+use anyhow::anyhow;
+use ethers::types::U256;
+use serde::{Deserialize, Serialize};
+use tokio::{sync::Notify, time::Instant};
+use tracing::{info, instrument, warn};
 
-pub struct App {
-    pub config: Config,
-    pub database: Arc<Database>,
-    pub bridge_processor: Arc<dyn Processor>,
+// What the read-side methods return, for embedders
+pub use crate::{
+    balance::LowBalance,
+    contracts::{RootLookup, RootStatus},
+    database::types::{LeaderRecord, ServerStatus},
+    pause::PauseStatus,
+    processor::{
+        cancel::Cancel,
+        clean_slate::CleanSlate,
+        startup::Reconciliation,
+        status::{SyncState, UnsyncedReason},
+        LatestRoots,
+    },
+};
+
+/// How often a cancel looks up which transaction mined.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("invalid pause: {0}")]
+    InvalidPause(String),
+    #[error("propagation is paused")]
+    Paused,
+    #[error("service is not initialized")]
+    Uninitialized,
+    #[error("no pending transaction {0}")]
+    NotPending(TransactionId),
+    #[error("the relayer can't cancel transaction {0}")]
+    CannotCancel(TransactionId),
+    #[error(transparent)]
+    Database(#[from] database::Error),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
 }
 
-use crate::server::error::Error as ServerError;
+/// The service status as reported on `/serviceStatus`.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ServiceStatus {
+    #[serde(flatten)]
+    pub status:      ServerStatus,
+    /// Present while propagation is paused
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pause:       Option<PauseStatus>,
+    /// Present while propagation is stopped by a low wallet balance
+    #[serde(
+        default,
+        rename = "lowBalance",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub low_balance: Option<LowBalance>,
+    /// Where the scanners stand, absent from the one-shot `status` command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scanners:    Option<ScannersStatus>,
+    /// The replica that last took the leadership, present when
+    /// `[leadership]` is enabled and one has
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leader:      Option<LeaderRecord>,
+    /// Whether Scroll finalized the root the bridge is synced to, present
+    /// while synced with `[finality]` enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finality:    Option<Finality>,
+}
+
+pub struct App {
+    pub config:            Config,
+    pub database:          Arc<Database>,
+    pub ethereum:          Ethereum,
+    /// Observed roots waiting to be written to `database`
+    pub root_buffer:       Arc<RootBuffer>,
+    pub bridge_processor:  Arc<dyn Processor>,
+    /// Operator pause, persisted in `service_status`
+    pub pause:             Arc<PauseControl>,
+    /// Filled in by the task monitor once it starts
+    pub task_health:       Arc<TaskHealth>,
+    /// Updated by the wallet balance task
+    pub balance:           Arc<BalanceState>,
+    /// Updated by the sync check
+    pub unsynced:          Arc<UnsyncedState>,
+    /// Updated by the leader election, when enabled
+    pub leadership:        Arc<Leadership>,
+    /// Wakes the propagation task
+    pub(crate) wake:       Arc<PropagationWake>,
+    /// Wakes the sync check ahead of its interval, once WorldID on mainnet
+    /// moved to a new root
+    pub(crate) sync_check: Arc<Notify>,
+    /// Webhooks under `[notifications]`
+    pub(crate) notifier:   Arc<Notifier>,
+    /// The result of comparing our event bindings against the deployed
+    /// contracts at startup
+    pub contract_abis:     Vec<ContractAbiReport>,
+}
 
 impl App {
     /// # Errors
-    /// Will return `Err` if the internal Ethereum handler errors
-    ///
+    /// Will return `Err` if the config is invalid or the internal Ethereum
+    /// handler errors
     #[instrument(name = "App::new", level = "debug", skip_all)]
     pub async fn new(config: Config) -> anyhow::Result<Arc<Self>> {
+        config
+            .validate()
+            .map_err(|errors| exit::Error::Config(errors.into()))?;
+
+        metrics::register();
+
         let db = Database::new(&config.database).await?;
         let database = Arc::new(db);
         let ethereum = Ethereum::new(&config).await?;
-        let scroll_bridge = Arc::new(ScrollBridge::new(&config, ethereum.clone()).await?);
+        let bridge = contracts::connect(&config, ethereum.clone()).await?;
+        info!(chain = bridge.chain(), "Connected to the state bridge");
+
+        let contract_abis = check_contract_abis(
+            &ethereum,
+            bridge.as_ref(),
+            config.app.scanner.abi_check_lookback_blocks,
+        )
+        .await?;
+
+        let mismatched: Vec<_> = contract_abis
+            .iter()
+            .filter(|report| report.critical_mismatch)
+            .map(|report| report.contract)
+            .collect();
+
+        if config.app.scanner.strict_abi_check && !mismatched.is_empty() {
+            return Err(exit::Error::Config(anyhow!(
+                "critical events don't match the deployed contracts: {}",
+                mismatched.join(", ")
+            ))
+            .into());
+        }
+        let pause = database.get_pause().await?.map(Pause::restore);
+        if let Some(pause) = &pause {
+            warn!(reason = %pause.reason, expires_at = ?pause.expires_at, "Starting paused");
+        }
+        metrics::PAUSED.set(if pause.is_some() { 1.0 } else { 0.0 });
+
+        metrics::record_info(&metrics::ServiceInfo {
+            environment:             config.service.environment.clone(),
+            l1_chain_id:             ethereum.l1_provider().chain_id,
+            l2_chain_id:             ethereum.l2_provider().chain_id,
+            bridge_address:          bridge.addresses().bridge,
+            scroll_world_id_address: bridge.addresses().target_world_id,
+            signer_address:          ethereum.address(),
+        });
+
+        let root_buffer = Arc::new(RootBuffer::spawn(
+            "roots",
+            &config.persistence,
+            database.clone(),
+        ));
+        let subscribed = config
+            .providers
+            .as_ref()
+            .map(processor::subscriptions)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(source, _)| source)
+            .collect();
+        let notifier = Arc::new(Notifier::new(
+            config.notifications.clone(),
+            config.service.service_name.clone(),
+        ));
         let bridge_processor = Arc::new(
-            BridgeProcessor::new(
+            BridgeProcessor::from_parts(
                 ethereum.clone(),
-                scroll_bridge.clone()   
+                database.clone(),
+                root_buffer.clone(),
+                bridge,
+                config.app.scanner.clone(),
+                config.app.propagation.clone(),
+                &config.root_check,
             )
             .await?
+            .with_subscribed(subscribed)
+            .with_notifier(notifier.clone()),
         );
+        let leadership = Arc::new(Leadership::new(config.leadership.enabled));
+        metrics::record_leader(leadership.is_leader());
         let app = Arc::new(Self {
             config,
             database,
-            bridge_processor
+            ethereum,
+            root_buffer,
+            bridge_processor,
+            pause: Arc::new(PauseControl::new(pause)),
+            task_health: Arc::new(TaskHealth::default()),
+            balance: Arc::new(BalanceState::default()),
+            unsynced: Arc::new(UnsyncedState::default()),
+            leadership,
+            wake: Arc::new(PropagationWake::default()),
+            sync_check: Arc::new(Notify::new()),
+            notifier,
+            contract_abis,
         });
         Ok(app)
     }
@@ -45,20 +242,696 @@ impl App {
     /// Initializes the server state.
     #[instrument(level = "debug", skip(self))]
     pub async fn initialize_server(&self) -> anyhow::Result<()> {
-        if !self.database.server_initialized().await? {
-            self.database.initialize_server().await?;
-            self.database.mark_status_as_unsynced().await?;
+        if self.database.initialize_server().await? {
+            self.database.mark_status_as_unsynced(None).await?;
+            info!("DB initialized");
+        } else {
+            info!("DB already initialized");
         }
-        info!("DB initialized");
         Ok(())
     }
 
-    pub async fn get_service_status(&self) -> Result<ServerStatusResponse, ServerError>{
-        let status = match  self.database.get_service_status().await? {
-            Some(status) => status,
-            None => return Err(ServerError::UNITIALIZED),
+    /// Reconciles the database with the chains before the tasks start, e.g.
+    /// after it was restored from a backup. Pending transactions the relayer
+    /// or the chain already settled are resolved, mined ones with `mined_at`
+    /// taken from their block, and the status is corrected to match the
+    /// latest roots. Skipped when `startup_reconciliation` is off, and with
+    /// leader election, where the leader owns the status.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the roots or the database can't be read, or the
+    /// status can't be written.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn reconcile(&self) -> anyhow::Result<Option<Reconciliation>> {
+        if !self.config.maintenance.startup_reconciliation {
+            info!("Startup reconciliation is disabled");
+            return Ok(None);
+        }
+        if self.config.leadership.enabled {
+            info!("Leader election is enabled, the status is left to the leader");
+            return Ok(None);
+        }
+
+        let mut resolved = 0;
+        let mut mined_at_backfilled = 0;
+        let mut pending = 0;
+        for transaction_id in self.database.get_pending_transactions().await? {
+            let state = match self
+                .bridge_processor
+                .transaction_state(transaction_id.clone())
+                .await
+            {
+                // The relayer may not have caught up, the clean slate waits
+                Ok(TransactionState::Pending | TransactionState::Unknown) => None,
+                Ok(state) => Some(state),
+                Err(err) => {
+                    warn!(%transaction_id, %err, "Failed to look up a pending transaction");
+                    None
+                }
+            };
+            let Some((state, resolution)) = state.and_then(|state| {
+                maintenance::classify(
+                    &state,
+                    Duration::ZERO,
+                    self.config.maintenance.abandon_transaction_after,
+                )
+                .map(|resolution| (state, resolution))
+            }) else {
+                pending += 1;
+                continue;
+            };
+
+            if !self
+                .database
+                .resolve_transaction(
+                    &transaction_id,
+                    resolution.status,
+                    resolution.tx_hash,
+                    resolution.reason.as_deref(),
+                )
+                .await?
+            {
+                continue;
+            }
+            resolved += 1;
+            info!(
+                %transaction_id,
+                status = ?resolution.status,
+                reason = ?resolution.reason,
+                "Resolved a pending transaction"
+            );
+
+            monitor_txs::record_cost(&self.database, &transaction_id, Some(&state)).await?;
+            let TransactionState::Mined { hash, .. } = state else {
+                continue;
+            };
+            if resolution.status != TxStatus::Mined {
+                continue;
+            }
+
+            let mined_at = match self.ethereum.mined_at(hash).await {
+                Ok(mined_at) => mined_at,
+                Err(err) => {
+                    warn!(%transaction_id, %err, "Failed to read when a transaction mined");
+                    None
+                }
+            };
+            match mined_at {
+                Some(mined_at)
+                    if self
+                        .database
+                        .backfill_mined_at(&transaction_id, mined_at)
+                        .await? =>
+                {
+                    mined_at_backfilled += 1;
+                }
+                _ => delivery::record_mined(&*self.database, &transaction_id).await?,
+            }
+        }
+
+        let roots = self.bridge_processor.latest_roots().await?;
+        let sync_state = self.bridge_processor.sync_state().await?;
+        let recorded = self
+            .database
+            .get_db_status()
+            .await?
+            .unwrap_or(BridgeStatus::Unsynced);
+        let propagation_pending = self.database.get_pending_propagation().await?.is_some();
+        let last_mined = self
+            .database
+            .get_last_transaction_status()
+            .await?
+            .is_some_and(TxStatus::is_mined);
+
+        let mut status = recorded;
+        for correction in
+            startup::corrections(recorded, sync_state, propagation_pending, last_mined)
+        {
+            let changed = TaskMonitor::status_changed(match correction {
+                Correction::Unsynced(reason) => self.database.mark_status_as_unsynced(reason).await,
+                Correction::Pending => self.database.mark_status_as_pending().await,
+                Correction::Synced => self.database.mark_status_as_synced().await,
+            })?;
+            if !changed {
+                break;
+            }
+            status = correction.status();
+        }
+
+        let summary = Reconciliation {
+            mainnet_root: roots.mainnet,
+            scroll_root: roots.scroll,
+            sync_state,
+            recorded,
+            status,
+            resolved,
+            mined_at_backfilled,
+            pending,
+        };
+        if summary.corrected() {
+            warn!(
+                ?recorded,
+                ?status,
+                ?sync_state,
+                "Recorded status didn't match the chains, corrected it"
+            );
+            self.notifier.notify(Event::new(
+                EventKind::StatusChanged,
+                format!("Bridge status corrected from {recorded:?} to {status:?} at startup")
+                    .to_lowercase(),
+            ));
+        }
+        info!(
+            mainnet_root = ?summary.mainnet_root,
+            scroll_root = ?summary.scroll_root,
+            ?recorded,
+            ?status,
+            resolved,
+            mined_at_backfilled,
+            pending,
+            "Reconciled the database with the chains"
+        );
+
+        Ok(Some(summary))
+    }
+
+    /// Waits for the transactions a previous run left pending, so
+    /// propagation starts from a known state. `clean_slate_policy` may
+    /// cancel or replace some first, by the state the relayer has them in.
+    /// Failures and timeouts are logged and noted on the transaction rather
+    /// than failing startup.
+    /// With leader election they are left to the leader's monitor, they may
+    /// be another replica's.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the pending transactions can't be listed.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn await_clean_slate(&self) -> anyhow::Result<CleanSlate> {
+        if self.config.leadership.enabled {
+            info!("Leader election is enabled, pending transactions are left to the leader");
+            return Ok(CleanSlate::default());
+        }
+
+        let pending = self.database.get_pending_transactions().await?;
+
+        if pending.is_empty() {
+            return Ok(CleanSlate::default());
+        }
+
+        let pending = match self
+            .bridge_processor
+            .pending_transactions(pending.clone())
+            .await
+        {
+            Ok(pending) => pending,
+            Err(err) => {
+                warn!(
+                    %err,
+                    "Failed to look up pending transactions on the relayer, waiting for all of them"
+                );
+                pending
+                    .into_iter()
+                    .map(|transaction_id| {
+                        PendingTransaction::new(transaction_id, PendingState::Unknown, None)
+                    })
+                    .collect()
+            }
+        };
+        let states: HashMap<_, _> = pending
+            .iter()
+            .map(|transaction| (transaction.transaction_id.clone(), transaction.state))
+            .collect();
+
+        let config = &self.config.maintenance;
+        let mut outcomes = vec![];
+        let mut waiting = vec![];
+        for transaction in pending {
+            let intervened = match Action::new(config.clean_slate_policy, transaction.state) {
+                Action::Wait => Ok(None),
+                Action::Cancel => self
+                    .bridge_processor
+                    .cancel_transaction(transaction.transaction_id.clone())
+                    .await
+                    .map(|cancelled| cancelled.then_some(Outcome::Cancelled)),
+                Action::Replace => self
+                    .bridge_processor
+                    .replace_transaction(&transaction)
+                    .await
+                    .map(|replaced| replaced.then_some(Outcome::Replaced)),
+            };
+
+            match intervened {
+                Ok(Some(outcome)) => outcomes.push((transaction.transaction_id, outcome)),
+                Ok(None) => waiting.push(transaction.transaction_id),
+                Err(err) => {
+                    warn!(
+                        transaction_id = %transaction.transaction_id,
+                        %err,
+                        "Failed to cancel or replace a pending transaction, waiting for it"
+                    );
+                    waiting.push(transaction.transaction_id);
+                }
+            }
+        }
+
+        info!(count = waiting.len(), "Waiting for pending transactions");
+
+        outcomes.extend(
+            clean_slate::settle(
+                waiting,
+                config.clean_slate_transaction_timeout,
+                config.clean_slate_timeout,
+                |transaction_id| self.bridge_processor.mine_transaction(transaction_id),
+            )
+            .await,
+        );
+
+        for (transaction_id, outcome) in &outcomes {
+            let recorded = match outcome {
+                Outcome::Mined => {
+                    match self
+                        .database
+                        .resolve_transaction(transaction_id, TxStatus::Mined, None, None)
+                        .await
+                    {
+                        Ok(true) => delivery::record_mined(&*self.database, transaction_id).await,
+                        result => result.map(drop),
+                    }
+                }
+                Outcome::Failed(reason) => {
+                    warn!(%transaction_id, %reason, "Pending transaction failed");
+                    self.database
+                        .resolve_transaction(
+                            transaction_id,
+                            TxStatus::Failed,
+                            None,
+                            Some(reason.as_str()),
+                        )
+                        .await
+                        .map(drop)
+                }
+                Outcome::Cancelled => self
+                    .database
+                    .resolve_transaction(
+                        transaction_id,
+                        TxStatus::Failed,
+                        None,
+                        Some("cancelled at startup"),
+                    )
+                    .await
+                    .map(drop),
+                Outcome::Replaced => self
+                    .database
+                    .resolve_transaction(
+                        transaction_id,
+                        TxStatus::Superseded,
+                        None,
+                        Some("replaced at startup"),
+                    )
+                    .await
+                    .map(drop),
+                Outcome::TimedOut => {
+                    warn!(%transaction_id, "Pending transaction didn't mine in time");
+                    self.database
+                        .set_resolution_reason(transaction_id, "timed out at startup")
+                        .await
+                }
+            };
+
+            if let Err(err) = recorded {
+                warn!(%transaction_id, %err, "Failed to record pending transaction outcome");
+            }
+        }
+
+        let summary =
+            CleanSlate::from_outcomes(outcomes.iter().map(|(transaction_id, outcome)| {
+                let state = states
+                    .get(transaction_id)
+                    .copied()
+                    .unwrap_or(PendingState::Unknown);
+                (state, outcome)
+            }));
+        info!(?summary, "Settled pending transactions");
+
+        Ok(summary)
+    }
+
+    /// Logs, in one event, what this instance runs: the build, the chains
+    /// it verified, its signer, and every config value along with the layer
+    /// that set it. Secrets are redacted as by `/config`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the config doesn't serialize.
+    pub fn log_effective_config(&self, sources: &ConfigSources) -> anyhow::Result<()> {
+        let build = BuildInfo::get();
+        let config = serde_json::to_value(Redacted(&self.config))?;
+        let sources = serde_json::to_string(&sources.fields(&config))?;
+
+        info!(
+            version = build.version,
+            git_version = build.git_version,
+            git_sha = build.git_sha,
+            l1_chain_id = %self.ethereum.l1_provider().chain_id,
+            l2_chain_id = %self.ethereum.l2_provider().chain_id,
+            signer = ?self.ethereum.address(),
+            config = %config,
+            sources = %sources,
+            "Effective configuration"
+        );
+
+        Ok(())
+    }
+
+    /// Writes the buffered roots and closes the database pool. Call this
+    /// after the tasks have stopped.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn shutdown(&self) -> anyhow::Result<()> {
+        let flushed = self.root_buffer.close().await;
+        self.database.pool.close().await;
+        info!("Database closed");
+        flushed
+    }
+
+    /// Starts the background tasks, which run until `shutdown` is signalled
+    /// or [`TaskHandles::shutdown`] is called. The HTTP server isn't needed.
+    pub async fn spawn_tasks(self: &Arc<Self>, shutdown: Arc<Shutdown>) -> TaskHandles {
+        let monitor = TaskMonitor::new(self.clone(), shutdown);
+        monitor.start().await;
+        TaskHandles::new(monitor)
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the service isn't initialized or the database
+    /// can't be reached.
+    pub async fn status(&self) -> Result<ServiceStatus, Error> {
+        let Some(mut status) = self.database.get_service_status().await? else {
+            return Err(Error::Uninitialized);
+        };
+        let finality = Finality::read(&self.database, &self.config.finality, &mut status).await?;
+
+        let leader = if self.config.leadership.enabled {
+            self.database.get_leader().await?
+        } else {
+            None
+        };
+
+        Ok(ServiceStatus {
+            status,
+            pause: self.pause.current().as_ref().map(PauseStatus::from),
+            low_balance: self.balance.low(),
+            scanners: Some(self.bridge_processor.scanner_status()),
+            leader,
+            finality,
+        })
+    }
+
+    /// The latest root of each chain, read from the contracts.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a provider can't be reached.
+    pub async fn latest_roots(&self) -> Result<LatestRoots, Error> {
+        Ok(self.bridge_processor.latest_roots().await?)
+    }
+
+    /// Where `root` stands on mainnet and on Scroll, read from the contracts.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a provider can't be reached.
+    pub async fn lookup_root(&self, root: U256) -> Result<RootLookup, Error> {
+        Ok(self.bridge_processor.lookup_root(root).await?)
+    }
+
+    /// [`Self::lookup_root`] for every root in `roots`, in the same order.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a provider can't be reached.
+    pub async fn lookup_roots(&self, roots: &[U256]) -> Result<Vec<RootLookup>, Error> {
+        Ok(self.bridge_processor.lookup_roots(roots).await?)
+    }
+
+    /// Those of `roots` Scroll added in a finalized block, as far as the
+    /// scanned roots go. `None` without `[finality]`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the roots can't be read.
+    pub async fn finalized_roots(&self, roots: &[U256]) -> Result<Option<HashSet<U256>>, Error> {
+        if !self.config.finality.enabled {
+            return Ok(None);
+        }
+
+        Ok(Some(self.database.get_finalized_roots(roots).await?))
+    }
+
+    /// Checks the roots now instead of at the next sync check, and wakes the
+    /// propagation task if they differ. A propagation already in flight isn't
+    /// duplicated.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if propagation is paused or the check fails.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn trigger_propagation(&self) -> Result<SyncState, Error> {
+        if self.pause.is_paused() {
+            return Err(Error::Paused);
+        }
+
+        let state = self.bridge_processor.sync_state().await?;
+        if let SyncState::Unsynced(reason) = state {
+            // Pending is left alone, its transaction is still being mined
+            let marked_synced = self.database.get_db_status().await? == Some(BridgeStatus::Synced);
+            if marked_synced {
+                self.database.mark_status_as_unsynced(Some(reason)).await?;
+            }
+
+            info!(?state, "Propagation triggered");
+            self.wake_propagation();
+        }
+
+        Ok(state)
+    }
+
+    /// Replaces the pending propagation `transaction_id` at its nonce and
+    /// waits up to `cancel_timeout` for it or the replacement to mine. A
+    /// cancel leaves the status unsynced and wakes propagation, the original
+    /// mining first is recorded and reported as such. Still pending once the
+    /// wait is over, the monitor settles it.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the transaction isn't pending, the relayer can't
+    /// cancel it, or the relayer or the database fail.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn cancel_propagation(&self, transaction_id: TransactionId) -> Result<Cancel, Error> {
+        let pending = self.database.get_pending_transactions().await?;
+        if !pending.contains(&transaction_id) {
+            return Err(Error::NotPending(transaction_id));
+        }
+
+        let transaction = self
+            .bridge_processor
+            .pending_transactions(vec![transaction_id.clone()])
+            .await?
+            .pop()
+            .unwrap_or_else(|| {
+                PendingTransaction::new(transaction_id.clone(), PendingState::Unknown, None)
+            });
+        if !self.bridge_processor.cancel_in_flight(&transaction).await? {
+            return Err(Error::CannotCancel(transaction_id));
+        }
+        info!(
+            %transaction_id,
+            state = ?transaction.state,
+            "Cancel sent, waiting for it or the original to mine"
+        );
+
+        let deadline = Instant::now() + self.config.maintenance.cancel_timeout;
+        let (outcome, state) = loop {
+            match self.cancel_state(&transaction_id).await {
+                Ok((state, Some(outcome))) => break (outcome, Some(state)),
+                Ok((_, None)) => {}
+                Err(err) => {
+                    warn!(%transaction_id, %err, "Failed to look up a cancelled transaction")
+                }
+            }
+            if Instant::now() + CANCEL_POLL_INTERVAL > deadline {
+                break (CancelOutcome::Pending, None);
+            }
+            tokio::time::sleep(CANCEL_POLL_INTERVAL).await;
+        };
+
+        let tx_hash = match state {
+            Some(TransactionState::Mined { hash, .. }) => {
+                monitor_txs::record_cost(&self.database, &transaction_id, state.as_ref()).await?;
+                Some(hash)
+            }
+            _ => None,
         };
-        Ok(status.into())
+        let recorded_hash = tx_hash.map(|hash| format!("{hash:?}"));
+
+        match outcome {
+            CancelOutcome::Cancelled => {
+                if !self
+                    .database
+                    .resolve_transaction(
+                        &transaction_id,
+                        TxStatus::Cancelled,
+                        recorded_hash,
+                        Some("cancelled by operator"),
+                    )
+                    .await?
+                {
+                    warn!(%transaction_id, "Cancelled transaction was already resolved");
+                }
+                TaskMonitor::status_changed(
+                    self.database
+                        .mark_status_as_unsynced(Some(UnsyncedReason::PropagationFailed))
+                        .await,
+                )?;
+                self.wake_propagation();
+            }
+            CancelOutcome::SupersededByOriginal
+                if matches!(
+                    state,
+                    Some(TransactionState::Mined {
+                        succeeded: false,
+                        ..
+                    })
+                ) =>
+            {
+                if !self
+                    .database
+                    .resolve_transaction(
+                        &transaction_id,
+                        TxStatus::Failed,
+                        recorded_hash,
+                        Some("reverted before its cancel"),
+                    )
+                    .await?
+                {
+                    warn!(%transaction_id, "Reverted transaction was already resolved");
+                }
+                TaskMonitor::status_changed(
+                    self.database
+                        .mark_status_as_unsynced(Some(UnsyncedReason::PropagationFailed))
+                        .await,
+                )?;
+                self.wake_propagation();
+            }
+            CancelOutcome::SupersededByOriginal => {
+                if self
+                    .database
+                    .resolve_transaction(
+                        &transaction_id,
+                        TxStatus::SupersededByOriginal,
+                        recorded_hash,
+                        Some("mined before its cancel"),
+                    )
+                    .await?
+                {
+                    delivery::record_mined(&*self.database, &transaction_id).await?;
+                }
+            }
+            CancelOutcome::Pending => {
+                self.database
+                    .set_resolution_reason(&transaction_id, "cancel requested")
+                    .await?;
+            }
+        }
+        info!(%transaction_id, ?outcome, ?tx_hash, "Cancel settled");
+
+        Ok(Cancel {
+            transaction_id,
+            outcome,
+            tx_hash,
+        })
+    }
+
+    /// The cancelled transaction's state, and the outcome once decided.
+    async fn cancel_state(
+        &self,
+        transaction_id: &TransactionId,
+    ) -> anyhow::Result<(TransactionState, Option<CancelOutcome>)> {
+        let state = self
+            .bridge_processor
+            .transaction_state(transaction_id.clone())
+            .await?;
+        let sent_to_self = match state {
+            TransactionState::Mined { hash, .. } => {
+                self.bridge_processor.sent_to_self(hash).await?
+            }
+            _ => false,
+        };
+
+        Ok((state, cancel::outcome(&state, sent_to_self)))
+    }
+
+    /// Wakes the propagation task, which continues the trace of the current
+    /// span.
+    pub(crate) fn wake_propagation(&self) {
+        self.wake.wake();
+    }
+
+    /// Stops propagation until `duration` has passed, or until resumed when no
+    /// duration is given. Pausing again while paused replaces the pause, which
+    /// is how a pause is extended or shortened.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the reason is empty, the duration is out of range
+    /// or the pause can't be persisted.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn pause(
+        &self,
+        reason: String,
+        duration: Option<Duration>,
+    ) -> Result<PauseStatus, Error> {
+        if reason.trim().is_empty() {
+            return Err(Error::InvalidPause("a reason is required".to_string()));
+        }
+        if duration.is_some_and(|duration| duration.is_zero()) {
+            return Err(Error::InvalidPause(
+                "duration must be greater than 0".to_string(),
+            ));
+        }
+
+        let pause =
+            Pause::new(reason, duration).map_err(|err| Error::InvalidPause(err.to_string()))?;
+        self.database.pause_service(&pause.record()).await?;
+
+        info!(reason = %pause.reason, expires_at = ?pause.expires_at, "Paused");
+        let status = PauseStatus::from(&pause);
+        self.pause.pause(pause);
+        metrics::PAUSED.set(1.0);
+
+        Ok(status)
+    }
+
+    /// Lifts the current pause. Returns `false` if the service wasn't paused.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the resume can't be persisted.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn resume(&self, reason: &str) -> Result<bool, Error> {
+        if !self.pause.is_paused() {
+            return Ok(false);
+        }
+
+        self.database.resume_service(reason).await?;
+        let resumed = self.pause.resume();
+        if resumed {
+            info!(reason, "Resumed");
+            metrics::PAUSED.set(0.0);
+        }
+
+        Ok(resumed)
     }
-    
 }
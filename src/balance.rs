@@ -0,0 +1,96 @@
+//! The relayer wallet's balance, and whether it is too low to propagate.
+
+use std::sync::RwLock;
+
+use ethers::types::U256;
+use serde::{Deserialize, Serialize};
+
+use crate::config::BalanceConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Healthy,
+    /// Below the warning threshold, propagation continues
+    Low,
+    /// Below the critical threshold, propagation stops
+    Critical,
+}
+
+impl Level {
+    #[must_use]
+    pub fn of(balance: U256, config: &BalanceConfig) -> Self {
+        if balance < config.critical_threshold.into() {
+            Self::Critical
+        } else if balance < config.warning_threshold.into() {
+            Self::Low
+        } else {
+            Self::Healthy
+        }
+    }
+}
+
+/// Reported on the status endpoint while propagation is stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LowBalance {
+    pub balance:            U256,
+    pub critical_threshold: U256,
+}
+
+#[derive(Debug, Default)]
+pub struct BalanceState {
+    low: RwLock<Option<LowBalance>>,
+}
+
+impl BalanceState {
+    #[must_use]
+    pub fn low(&self) -> Option<LowBalance> {
+        *self.low.read().unwrap()
+    }
+
+    /// Records the latest check. Returns whether propagation was stopped or
+    /// resumed by it.
+    pub fn record(&self, low: Option<LowBalance>) -> bool {
+        let mut current = self.low.write().unwrap();
+        let changed = current.is_some() != low.is_some();
+        *current = low;
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn balance_is_classified_against_both_thresholds() {
+        let config = BalanceConfig {
+            warning_threshold: 100,
+            critical_threshold: 10,
+            ..BalanceConfig::default()
+        };
+
+        assert_eq!(Level::of(U256::from(9), &config), Level::Critical);
+        assert_eq!(Level::of(U256::from(10), &config), Level::Low);
+        assert_eq!(Level::of(U256::from(99), &config), Level::Low);
+        assert_eq!(Level::of(U256::from(100), &config), Level::Healthy);
+    }
+
+    #[test]
+    fn only_crossing_the_critical_threshold_is_a_change() {
+        let state = BalanceState::default();
+        let low = |balance: u64| {
+            Some(LowBalance {
+                balance:            balance.into(),
+                critical_threshold: 10.into(),
+            })
+        };
+
+        assert!(!state.record(None));
+        assert!(state.record(low(5)));
+        assert!(!state.record(low(4)));
+        assert_eq!(state.low(), low(4));
+        assert!(state.record(None));
+        assert_eq!(state.low(), None);
+    }
+}
@@ -0,0 +1,54 @@
+//! What was built, embedded by `build.rs`.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// `git describe`, e.g. `v0.3.1-4-g1a2b3c4`
+pub const GIT_VERSION: &str = env!("GIT_VERSION");
+
+pub const GIT_SHA: &str = env!("GIT_SHA");
+
+/// Unix seconds
+const BUILD_TIMESTAMP: &str = env!("BUILD_TIMESTAMP");
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildInfo {
+    pub version:         &'static str,
+    pub git_version:     &'static str,
+    pub git_sha:         &'static str,
+    /// `None` if `build.rs` embedded something that isn't a timestamp
+    pub build_timestamp: Option<DateTime<Utc>>,
+}
+
+impl BuildInfo {
+    #[must_use]
+    pub fn get() -> Self {
+        Self {
+            version:         VERSION,
+            git_version:     GIT_VERSION,
+            git_sha:         GIT_SHA,
+            build_timestamp: BUILD_TIMESTAMP
+                .parse()
+                .ok()
+                .and_then(|secs| DateTime::from_timestamp(secs, 0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_info_is_embedded() {
+        let info = BuildInfo::get();
+
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert!(info
+            .build_timestamp
+            .is_some_and(|built| built <= Utc::now()));
+    }
+}
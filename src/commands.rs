@@ -0,0 +1,224 @@
+//! One-shot commands for runbooks, run instead of the service. None of them
+//! starts the tasks or the HTTP server.
+
+use anyhow::bail;
+use ethers::types::U256;
+use serde::Serialize;
+use tracing::info;
+
+use crate::{
+    app::{App, ServiceStatus},
+    config::{Config, DatabaseConfig},
+    contracts,
+    database::{query::DatabaseQuery, Database},
+    ethereum::{Ethereum, TransactionState},
+    finality::Finality,
+    notifier::{Event, EventKind},
+    pause::{Pause, PauseStatus},
+    processor::Propagation,
+    task_monitor::{tasks::monitor_txs, TaskMonitor},
+};
+
+/// The latest root of each chain, read from the contracts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncCheck {
+    pub mainnet_root: String,
+    pub scroll_root:  String,
+    pub synced:       bool,
+}
+
+impl SyncCheck {
+    fn new(mainnet_root: U256, scroll_root: U256) -> Self {
+        Self {
+            mainnet_root: format!("{mainnet_root:#066x}"),
+            scroll_root:  format!("{scroll_root:#066x}"),
+            synced:       mainnet_root == scroll_root,
+        }
+    }
+}
+
+/// Compares the latest mainnet and Scroll roots. Only the providers are
+/// needed, neither the database nor the relayer is touched.
+///
+/// # Errors
+///
+/// Will return `Err` if a provider or a contract can't be reached.
+pub async fn check_sync(config: &Config) -> anyhow::Result<SyncCheck> {
+    let ethereum = Ethereum::read_only(config).await?;
+    let bridge = contracts::connect(config, ethereum).await?;
+
+    let (mainnet_root, scroll_root) = futures::try_join!(
+        bridge.get_world_id_latest_root(),
+        bridge.get_target_latest_root(),
+    )?;
+
+    Ok(SyncCheck::new(mainnet_root, scroll_root))
+}
+
+/// The status in `service_status`, with the pause if there is one. The
+/// database isn't migrated, and `lowBalance` is never set, only the running
+/// service checks the wallet.
+///
+/// # Errors
+///
+/// Will return `Err` if the database can't be reached or isn't initialized.
+pub async fn service_status(config: &Config) -> anyhow::Result<ServiceStatus> {
+    let database = Database::new(&DatabaseConfig {
+        migrate: false,
+        ..config.database.clone()
+    })
+    .await?;
+
+    let status = database.get_service_status().await?;
+    let pause = database.get_pause().await?.map(Pause::restore);
+    let leader = if config.leadership.enabled {
+        database.get_leader().await?
+    } else {
+        None
+    };
+
+    let Some(mut status) = status else {
+        database.pool.close().await;
+        bail!("the service isn't initialized, service_status is empty");
+    };
+    let finality = Finality::read(&database, &config.finality, &mut status).await?;
+    database.pool.close().await;
+
+    Ok(ServiceStatus {
+        status,
+        pause: pause.as_ref().map(PauseStatus::from),
+        low_balance: None,
+        scanners: None,
+        leader,
+        finality,
+    })
+}
+
+/// How [`propagate_once`] went.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(
+    tag = "outcome",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum PropagateOnce {
+    Mined {
+        transaction_id: String,
+        tx_hash:        Option<String>,
+        root:           String,
+    },
+    /// Reverted or dropped, recorded as a failure as the service would
+    Failed {
+        transaction_id: String,
+        tx_hash:        Option<String>,
+        root:           String,
+    },
+    /// The latest root is younger than `min_root_age`
+    Deferred {
+        root:        String,
+        age_seconds: u64,
+    },
+    /// `dry_run` is set, the simulation succeeded and nothing was sent
+    Simulated,
+    /// Scroll already had the latest root, nothing was sent and the status
+    /// was marked synced
+    AlreadySynced { root: String },
+}
+
+impl PropagateOnce {
+    #[must_use]
+    pub const fn failed(&self) -> bool {
+        matches!(self, Self::Failed { .. })
+    }
+}
+
+/// Simulates and sends one propagation of the latest root, whatever the sync
+/// state, and waits for it to mine. The transaction is recorded as the
+/// service records its own, so a service started later doesn't send another
+/// while it is pending.
+///
+/// # Errors
+///
+/// Will return `Err` if the app can't start, propagation is paused, a
+/// propagation is already pending or the simulation reverts.
+pub async fn propagate_once(config: Config) -> anyhow::Result<PropagateOnce> {
+    let app = App::new(config).await?;
+    app.initialize_server().await?;
+
+    let propagated = propagate(&app).await;
+    app.shutdown().await?;
+
+    propagated
+}
+
+async fn propagate(app: &App) -> anyhow::Result<PropagateOnce> {
+    if let Some(pause) = app.pause.current() {
+        bail!("propagation is paused: {}", pause.reason);
+    }
+
+    if let Some(pending) = app.database.get_pending_propagation().await? {
+        bail!("propagation {} is still pending", pending.transaction_id);
+    }
+
+    let (transaction_id, root) = match app.bridge_processor.propagate_root().await? {
+        Propagation::Submitted {
+            transaction_id,
+            root,
+        } => (transaction_id, root),
+        Propagation::Deferred { root, age } => {
+            return Ok(PropagateOnce::Deferred {
+                root:        format!("{root:#066x}"),
+                age_seconds: age.as_secs(),
+            });
+        }
+        Propagation::Simulated => return Ok(PropagateOnce::Simulated),
+        Propagation::AlreadySynced { root } => {
+            TaskMonitor::status_changed(app.database.mark_status_as_synced().await)?;
+            return Ok(PropagateOnce::AlreadySynced {
+                root: format!("{root:#066x}"),
+            });
+        }
+    };
+
+    app.database
+        .insert_new_transaction(&transaction_id, root)
+        .await?;
+    app.notifier.notify(
+        Event::new(
+            EventKind::PropagationSubmitted,
+            format!("Propagating root {root:#x}"),
+        )
+        .with_root(root)
+        .with_transaction(transaction_id.clone()),
+    );
+    TaskMonitor::status_changed(app.database.mark_status_as_pending().await)?;
+
+    info!(%transaction_id, "Waiting for the propagation to mine");
+    let mined = monitor_txs::monitor_tx(app, transaction_id.clone()).await?;
+
+    let tx_hash = match app
+        .bridge_processor
+        .transaction_state(transaction_id.clone())
+        .await
+    {
+        Ok(TransactionState::Mined { hash, .. }) => Some(format!("{hash:#x}")),
+        _ => None,
+    };
+    let transaction_id = transaction_id.to_string();
+    let root = format!("{root:#066x}");
+
+    Ok(if mined {
+        PropagateOnce::Mined {
+            transaction_id,
+            tx_hash,
+            root,
+        }
+    } else {
+        PropagateOnce::Failed {
+            transaction_id,
+            tx_hash,
+            root,
+        }
+    })
+}
@@ -2,10 +2,9 @@
 
 use ethers::prelude::abigen;
 
-
 abigen!(
-  WorldId,
-  r#"[
+    WorldId,
+    r#"[
       struct RootInfo { uint256 root; uint128 supersededTimestamp; bool isValid }
       event TreeChanged(uint256 indexed preRoot, uint8 indexed kind, uint256 indexed postRoot)
       function registerIdentities(uint256[8] calldata insertionProof, uint256 preRoot, uint32 startIndex, uint256[] calldata identityCommitments, uint256 postRoot) public virtual
@@ -30,10 +29,35 @@ abigen!(
 abigen!(
     ScrollStateBridge,
     r#"[
+        error AddressZero()
+        error CannotRenounceOwnership()
+        error GasLimitZero()
+        error OwnableInvalidOwner(address owner)
+        error OwnableUnauthorizedAccount(address account)
         event RootPropagated(uint256 root)
+        event OwnershipTransferred(address indexed previousOwner, address indexed newOwner)
         function propagateRoot() external payable
         function owner() public view virtual returns (address)
         function scrollWorldIDAddress() public view returns (address)
         function worldIDAddress() public view returns (address)
     ]"#
 );
+
+abigen!(
+    L2GasPriceOracle,
+    r#"[
+        function l2BaseFee() external view returns (uint256)
+    ]"#
+);
+
+// The OP Stack bridges (Optimism, Base) emit the same `RootPropagated`, only
+// the target getter differs. Their WorldID shares `ScrollWorldId`'s interface.
+abigen!(
+    OpStateBridge,
+    r#"[
+        function propagateRoot() external
+        function owner() public view virtual returns (address)
+        function opWorldIDAddress() public view returns (address)
+        function worldIDAddress() public view returns (address)
+    ]"#
+);
@@ -0,0 +1,316 @@
+//! Startup check that the event signatures in our bindings match what the
+//! deployed contracts emit.
+//!
+//! ABI drift otherwise only shows up as scanners that never decode anything.
+
+use std::collections::BTreeSet;
+
+use ethers::{
+    contract::EthEvent,
+    providers::Middleware,
+    types::{Address, BlockNumber, Filter, H256},
+};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use super::{
+    abi::{OwnershipTransferredFilter, RootAddedFilter, RootPropagatedFilter, TreeChangedFilter},
+    StateBridge,
+};
+use crate::ethereum::{Ethereum, ReadProvider};
+
+/// An event our bindings decode from a watched contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExpectedEvent {
+    pub name:     String,
+    pub topic0:   H256,
+    /// Whether a mismatch on this event should fail a strict check
+    pub critical: bool,
+}
+
+impl ExpectedEvent {
+    fn of<E: EthEvent>(critical: bool) -> Self {
+        Self {
+            name: E::name().into_owned(),
+            topic0: E::signature(),
+            critical,
+        }
+    }
+}
+
+/// What the check was able to base its conclusion on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Evidence {
+    /// Topics of recently emitted logs
+    Logs,
+    /// Some events weren't in the recent logs, so the bytecode was searched
+    /// for their topics. This is a heuristic: proxies and unusual compilers
+    /// defeat it.
+    BytecodeHeuristic,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ContractAbiReport {
+    pub contract:          &'static str,
+    pub address:           Address,
+    pub evidence:          Evidence,
+    /// Expected events that were observed
+    pub matched:           Vec<String>,
+    /// Expected events that were not observed
+    pub never_seen:        Vec<String>,
+    /// Observed topics that none of our bindings know
+    pub unknown_topics:    Vec<H256>,
+    /// A critical event is missing in a way that suggests its signature
+    /// changed
+    pub critical_mismatch: bool,
+}
+
+/// Compares the expected events against the topics observed in recent logs.
+/// An event missing from them is looked for in the contract `bytecode`
+/// instead: other events in the logs, e.g. a proxy's `Upgraded`, say nothing
+/// about its signature.
+#[must_use]
+pub fn classify(
+    contract: &'static str,
+    address: Address,
+    expected: &[ExpectedEvent],
+    observed_topics: &BTreeSet<H256>,
+    bytecode: &[u8],
+) -> ContractAbiReport {
+    let in_logs = |event: &ExpectedEvent| observed_topics.contains(&event.topic0);
+    let evidence = if expected.iter().all(in_logs) {
+        Evidence::Logs
+    } else {
+        Evidence::BytecodeHeuristic
+    };
+
+    let (matched, never_seen): (Vec<_>, Vec<_>) = expected
+        .iter()
+        .partition(|event| in_logs(event) || contains(bytecode, event.topic0.as_bytes()));
+
+    let unknown_topics: Vec<H256> = observed_topics
+        .iter()
+        .filter(|topic| !expected.iter().any(|event| event.topic0 == **topic))
+        .copied()
+        .collect();
+
+    // Neither emitted recently nor in the bytecode. Without any bytecode
+    // there is no contract to compare against.
+    let critical_mismatch = never_seen.iter().any(|event| event.critical) && !bytecode.is_empty();
+
+    ContractAbiReport {
+        contract,
+        address,
+        evidence,
+        matched: matched
+            .into_iter()
+            .map(|event| event.name.clone())
+            .collect(),
+        never_seen: never_seen
+            .into_iter()
+            .map(|event| event.name.clone())
+            .collect(),
+        unknown_topics,
+        critical_mismatch,
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+/// Checks every contract we scan or decode events from.
+///
+/// # Errors
+///
+/// Will return `Err` if the logs or bytecode can't be fetched.
+pub async fn check_contract_abis(
+    ethereum: &Ethereum,
+    bridge: &dyn StateBridge,
+    lookback_blocks: u64,
+) -> anyhow::Result<Vec<ContractAbiReport>> {
+    let addresses = bridge.addresses();
+    let contracts = [
+        (
+            "scroll_state_bridge",
+            addresses.bridge,
+            ethereum.l1_provider(),
+            vec![
+                ExpectedEvent::of::<RootPropagatedFilter>(true),
+                ExpectedEvent::of::<OwnershipTransferredFilter>(false),
+            ],
+        ),
+        (
+            "scroll_world_id",
+            addresses.target_world_id,
+            ethereum.l2_provider(),
+            vec![ExpectedEvent::of::<RootAddedFilter>(true)],
+        ),
+        (
+            "world_id",
+            addresses.world_id,
+            ethereum.l1_provider(),
+            vec![ExpectedEvent::of::<TreeChangedFilter>(true)],
+        ),
+    ];
+
+    let mut reports = Vec::with_capacity(contracts.len());
+
+    for (contract, address, provider, expected) in contracts {
+        let observed_topics = recent_topics(provider, address, lookback_blocks).await?;
+
+        let missing = expected
+            .iter()
+            .any(|event| !observed_topics.contains(&event.topic0));
+        let bytecode = if missing {
+            provider.get_code(address, None).await?.to_vec()
+        } else {
+            Vec::new()
+        };
+
+        let report = classify(contract, address, &expected, &observed_topics, &bytecode);
+
+        if report.critical_mismatch || !report.never_seen.is_empty() {
+            warn!(?report, "Contract events don't match the bindings");
+        } else {
+            info!(?report, "Contract events match the bindings");
+        }
+
+        reports.push(report);
+    }
+
+    Ok(reports)
+}
+
+async fn recent_topics(
+    provider: &ReadProvider,
+    address: Address,
+    lookback_blocks: u64,
+) -> anyhow::Result<BTreeSet<H256>> {
+    let head = provider.get_block_number().await?.as_u64();
+
+    let filter = Filter::new()
+        .address(address)
+        .from_block(BlockNumber::Number(
+            head.saturating_sub(lookback_blocks).into(),
+        ))
+        .to_block(BlockNumber::Number(head.into()));
+
+    let logs = provider.get_logs(&filter).await?;
+
+    Ok(logs
+        .iter()
+        .filter_map(|log| log.topics.first().copied())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expected() -> Vec<ExpectedEvent> {
+        vec![
+            ExpectedEvent::of::<RootPropagatedFilter>(true),
+            ExpectedEvent {
+                name:     "Other".to_string(),
+                topic0:   H256::repeat_byte(0x22),
+                critical: false,
+            },
+        ]
+    }
+
+    fn topics(topics: &[H256]) -> BTreeSet<H256> {
+        topics.iter().copied().collect()
+    }
+
+    #[test]
+    fn matching_logs() {
+        let observed = topics(&[RootPropagatedFilter::signature(), H256::repeat_byte(0x22)]);
+
+        let report = classify("bridge", Address::zero(), &expected(), &observed, &[]);
+
+        assert_eq!(report.evidence, Evidence::Logs);
+        assert_eq!(report.matched, vec!["RootPropagated", "Other"]);
+        assert!(report.never_seen.is_empty());
+        assert!(report.unknown_topics.is_empty());
+        assert!(!report.critical_mismatch);
+    }
+
+    #[test]
+    fn drifted_critical_event() {
+        // The contract emits a different RootPropagated signature
+        let drifted = H256::repeat_byte(0x33);
+        let observed = topics(&[drifted, H256::repeat_byte(0x22)]);
+        let mut bytecode = vec![0x60, 0x80, 0x7f];
+        bytecode.extend_from_slice(drifted.as_bytes());
+
+        let report = classify("bridge", Address::zero(), &expected(), &observed, &bytecode);
+
+        assert_eq!(report.evidence, Evidence::BytecodeHeuristic);
+        assert_eq!(report.matched, vec!["Other"]);
+        assert_eq!(report.never_seen, vec!["RootPropagated"]);
+        assert_eq!(report.unknown_topics, vec![drifted]);
+        assert!(report.critical_mismatch);
+    }
+
+    #[test]
+    fn an_unrelated_event_in_the_logs_is_not_a_mismatch() {
+        // Only a proxy's Upgraded in the lookback window, RootPropagated is
+        // in the bytecode
+        let upgraded = H256::repeat_byte(0x44);
+        let observed = topics(&[upgraded]);
+        let mut bytecode = vec![0x60, 0x80, 0x7f];
+        bytecode.extend_from_slice(RootPropagatedFilter::signature().as_bytes());
+
+        let report = classify("bridge", Address::zero(), &expected(), &observed, &bytecode);
+
+        assert_eq!(report.evidence, Evidence::BytecodeHeuristic);
+        assert_eq!(report.matched, vec!["RootPropagated"]);
+        assert_eq!(report.never_seen, vec!["Other"]);
+        assert_eq!(report.unknown_topics, vec![upgraded]);
+        assert!(!report.critical_mismatch);
+    }
+
+    #[test]
+    fn no_contract_is_not_a_mismatch() {
+        let observed = topics(&[H256::repeat_byte(0x22)]);
+
+        let report = classify("bridge", Address::zero(), &expected(), &observed, &[]);
+
+        assert_eq!(report.never_seen, vec!["RootPropagated"]);
+        assert!(!report.critical_mismatch);
+    }
+
+    #[test]
+    fn no_events_falls_back_to_bytecode() {
+        let mut bytecode = vec![0x60, 0x80, 0x7f];
+        bytecode.extend_from_slice(RootPropagatedFilter::signature().as_bytes());
+        bytecode.push(0xa1);
+
+        let report = classify(
+            "bridge",
+            Address::zero(),
+            &expected(),
+            &BTreeSet::new(),
+            &bytecode,
+        );
+
+        assert_eq!(report.evidence, Evidence::BytecodeHeuristic);
+        assert_eq!(report.matched, vec!["RootPropagated"]);
+        assert_eq!(report.never_seen, vec!["Other"]);
+        assert!(!report.critical_mismatch);
+    }
+
+    #[test]
+    fn no_events_and_missing_selector() {
+        let report = classify("bridge", Address::zero(), &expected(), &BTreeSet::new(), &[
+            0x60, 0x80,
+        ]);
+
+        assert_eq!(report.evidence, Evidence::BytecodeHeuristic);
+        assert!(report.critical_mismatch);
+    }
+}
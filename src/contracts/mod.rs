@@ -1,180 +1,566 @@
 //! Functionality for interacting with smart contracts deployed on chain.
 pub mod abi;
+pub mod abi_check;
+pub mod optimism;
+pub mod scanner;
+pub mod state_bridge;
 
-use anyhow::{anyhow, bail};
-use ethers::providers::Middleware;
-use ethers::types::U256;
-use tracing::{error, info, instrument};
+use std::{sync::Arc, time::Duration};
 
-use self::abi::{ScrollStateBridge, ScrollWorldId, WorldId};
-use crate::config::Config;
-use crate::ethereum::{Ethereum, ReadProvider};
-use crate::utils::TransactionId;
+use anyhow::bail;
+use async_trait::async_trait;
+use ethers::{
+    abi::AbiDecode,
+    contract::{ContractError, EthError},
+    providers::{Middleware, RpcError},
+    types::{Address, BlockId, BlockNumber, TransactionRequest, H256, U256},
+};
+use serde::Serialize;
+use thiserror::Error;
+use tracing::{info, instrument, warn};
 
-/// A structure representing the interface to the batch-based identity manager
-/// contract.
-#[derive(Debug)]
-pub struct ScrollBridge {
-    ethereum:       Ethereum,
-    bridge_abi:            ScrollStateBridge<ReadProvider>,
-    scroll_world_id_abi:  ScrollWorldId<ReadProvider>,
-    world_id_abi:   WorldId<ReadProvider>
+#[cfg(feature = "server")]
+pub use self::state_bridge::CallTimeout;
+pub use self::state_bridge::{BridgeAddresses, StateBridge};
+use self::{
+    abi::{L2GasPriceOracle, ScrollStateBridge, ScrollStateBridgeErrors},
+    optimism::OpBridge,
+    state_bridge::{require_code, WorldIdPair},
+};
+use crate::{
+    config::{BridgeKind, Config, MessengerFeeConfig},
+    ethereum::{Ethereum, ReadProvider},
+    utils::TransactionId,
+};
+
+/// Why the `propagateRoot` simulation didn't succeed.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum SimulationError {
+    /// The call reverted, carrying the decoded revert reason when there is one
+    #[error("propagateRoot would revert: {reason}")]
+    WouldRevert { reason: String },
+
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
 }
 
-impl ScrollBridge {
-    // TODO: I don't like these public getters
-    pub fn bridge_abi(&self) -> &ScrollStateBridge<ReadProvider> {
-        &self.bridge_abi
+/// Where a root stands on Scroll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum RootStatus {
+    /// Mainnet doesn't know the root
+    NotOnMainnet,
+    /// The root is Scroll's latest root
+    Latest,
+    /// Scroll received the root and has since moved on
+    Superseded,
+    /// Scroll hasn't received the root, or has never received any root
+    UnknownOnDestination,
+}
+
+impl RootStatus {
+    /// Classifies `root` from what Scroll reports about it. A zero
+    /// `latest_root` means nothing has been bridged yet, so no root is known.
+    #[must_use]
+    pub fn on_destination(root: U256, latest_root: U256, root_timestamp: u128) -> Self {
+        if latest_root.is_zero() {
+            Self::UnknownOnDestination
+        } else if root == latest_root {
+            Self::Latest
+        } else if root_timestamp != 0 {
+            Self::Superseded
+        } else {
+            Self::UnknownOnDestination
+        }
     }
 
-    pub fn scroll_world_id_abi(&self) -> &ScrollWorldId<ReadProvider> {
-        &self.scroll_world_id_abi
+    #[must_use]
+    pub const fn is_mined(self) -> bool {
+        matches!(self, Self::Latest | Self::Superseded)
     }
+}
 
-    pub fn world_id_abi(&self) -> &WorldId<ReadProvider> {
-      &self.world_id_abi
-  }
+/// Everything the contracts report about one root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RootLookup {
+    pub status:           RootStatus,
+    /// When Scroll received the root, `None` if it hasn't
+    pub scroll_timestamp: Option<u128>,
+}
 
-    #[instrument(level = "debug", skip_all)]
-    pub async fn new(config: &Config, ethereum: Ethereum) -> anyhow::Result<Self>
-    where
-        Self: Sized,
-    {
-        let Some(network_config) = &config.network else {
-            bail!("Network config is required for ScrollBridge.");
+impl RootLookup {
+    #[must_use]
+    pub fn new(root: U256, on_mainnet: bool, latest_root: U256, root_timestamp: u128) -> Self {
+        let status = if on_mainnet {
+            RootStatus::on_destination(root, latest_root, root_timestamp)
+        } else {
+            RootStatus::NotOnMainnet
         };
 
-        // Check that there is code deployed at the target address.
-        let address = network_config.scroll_bridge_address;
-        let code = ethereum.l1_provider().get_code(address, None).await?;
-        if code.as_ref().is_empty() {
-            error!(
-                ?address,
-                "No contract code is deployed at the provided address."
-            );
+        Self {
+            status,
+            scroll_timestamp: (root_timestamp != 0).then_some(root_timestamp),
         }
+    }
+}
 
-        // Connect to the running batching contract.
-        let bridge_abi = ScrollStateBridge::new(
+/// The Scroll state bridge: `ScrollStateBridge` on mainnet, `ScrollWorldId`
+/// on Scroll.
+#[derive(Debug)]
+pub struct ScrollBridge {
+    ethereum:   Ethereum,
+    bridge_abi: ScrollStateBridge<ReadProvider>,
+    world_ids:  WorldIdPair,
+    fee:        MessengerFeeConfig,
+    oracle:     Option<L2GasPriceOracle<ReadProvider>>,
+}
+
+/// What the messenger charges to relay `l2_gas_limit` at `l2_base_fee`, with
+/// the configured margin.
+#[must_use]
+pub fn messenger_fee(l2_base_fee: U256, config: &MessengerFeeConfig) -> U256 {
+    l2_base_fee
+        .saturating_mul(config.l2_gas_limit.into())
+        .saturating_mul(config.fee_multiplier_percent.into())
+        / 100
+}
+
+impl ScrollBridge {
+    /// Connects to the state bridge at `address` and the WorldIDs it bridges
+    /// between.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a contract has no code, unless
+    /// `allow_missing_code` is set, or can't be called.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn new(
+        address: Address,
+        fee: MessengerFeeConfig,
+        call_timeout: Duration,
+        allow_missing_code: bool,
+        ethereum: Ethereum,
+    ) -> anyhow::Result<Self> {
+        require_code(
+            ethereum.l1_provider(),
             address,
-            ethereum.l1_provider().clone(),
-        );
+            "scroll state bridge",
+            allow_missing_code,
+        )
+        .await?;
 
-        let owner = bridge_abi.owner().call().await?;
-        // if owner != ethereum.address() {
-        //     error!(?owner, signer = ?ethereum.address(), "Signer is not the owner of the state bridge contract.");
-        //     panic!("Cannot currently continue in read-only mode.")
-        // }
+        // Connect to the running batching contract.
+        let bridge_abi = ScrollStateBridge::new(address, ethereum.l1_provider().clone());
 
+        let owner = bridge_abi.owner().call().await?;
         info!(
             ?address,
             ?owner,
             "Connected to the Scroll State WorldID Bridge"
         );
 
-        // get scrollworldID address from scoll bridge
         let scroll_world_id_address = bridge_abi.scroll_world_id_address().call().await?;
-        info!(?scroll_world_id_address);
-
-        let code = ethereum.l2_provider().get_code(scroll_world_id_address, None).await?;
-        if code.as_ref().is_empty() {
-            error!(
-                ?scroll_world_id_address,
-                "No contract code is deployed at the scroll world id address."
-            );
-            panic!("Cannot continue")
-        }
+        let world_id_address = bridge_abi.world_id_address().call().await?;
+        info!(?scroll_world_id_address, ?world_id_address);
 
-        let scroll_world_id_abi = ScrollWorldId::new(
+        let world_ids = WorldIdPair::connect(
+            ethereum.clone(),
+            world_id_address,
             scroll_world_id_address,
-            ethereum.l2_provider().clone()
-        );
+            call_timeout,
+            allow_missing_code,
+        )
+        .await?;
 
-        // get worldId address from scroll bridge
-        let world_id_address = bridge_abi.world_id_address().call().await?;
-        let code = ethereum.l1_provider().get_code(world_id_address, None).await?;
-        if code.as_ref().is_empty() {
-            error!(
-                ?world_id_address,
-                "No contract code is deployed at the scroll world id address."
-            );
-            panic!("Cannot continue");
-        }
-        let world_id_abi = WorldId::new(
-            world_id_address,
-            ethereum.l1_provider().clone()
-        );
+        let oracle = fee
+            .oracle_address
+            .filter(|_| fee.enabled)
+            .map(|oracle_address| {
+                L2GasPriceOracle::new(oracle_address, ethereum.l1_provider().clone())
+            });
 
-        let scroll_bridge = Self {
+        Ok(Self {
             ethereum,
             bridge_abi,
-            scroll_world_id_abi,
-            world_id_abi
-        };
+            world_ids,
+            fee,
+            oracle,
+        })
+    }
+}
+
+#[async_trait]
+impl StateBridge for ScrollBridge {
+    fn chain(&self) -> &'static str {
+        "scroll"
+    }
 
-        Ok(scroll_bridge)
+    fn addresses(&self) -> BridgeAddresses {
+        BridgeAddresses {
+            bridge:          self.bridge_abi.address(),
+            target_world_id: self.world_ids.target_world_id.address(),
+            world_id:        self.world_ids.world_id.address(),
+        }
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn simulate_propagate_root(&self, value_in_wei: U256) -> Result<(), SimulationError> {
+        let call = self
+            .bridge_abi
+            .propagate_root()
+            .from(self.ethereum.address())
+            .value(value_in_wei);
+
+        match call.call().await {
+            Ok(()) => Ok(()),
+            Err(err) => Err(simulation_error(err)),
+        }
     }
 
-    #[instrument(level = "debug")]
-    pub async fn propagate_root(&self) -> anyhow::Result<TransactionId> {
-        let mut propagate_root_transaction: ethers::types::transaction::eip2718::TypedTransaction  = self.bridge_abi.propagate_root().tx;
-        let value_in_wei: U256 = ethers::utils::parse_ether("0.1").unwrap();
+    #[instrument(level = "debug", skip(self))]
+    async fn propagate_root(&self, value_in_wei: U256) -> anyhow::Result<TransactionId> {
+        let mut propagate_root_transaction = self.bridge_abi.propagate_root().tx;
         propagate_root_transaction.set_value(value_in_wei);
         self.ethereum
             .send_transaction(propagate_root_transaction, true)
             .await
-            .map_err(|tx_err| anyhow!("{}", tx_err.to_string()))
+            .map_err(anyhow::Error::from)
     }
 
-    #[instrument(level = "debug", skip_all)]
-    pub async fn get_scroll_latest_root(&self) -> anyhow::Result<U256> {
-        let latest_root = self.scroll_world_id_abi.latest_root().call().await?;
-        Ok(latest_root)
+    /// Estimates the messenger fee from the L2 gas price oracle, falling back
+    /// to the static fee rather than sending an underpaid transaction.
+    #[instrument(level = "debug", skip(self))]
+    async fn propagation_value(&self) -> U256 {
+        let static_fee = U256::from(self.fee.static_fee);
+        if !self.fee.enabled {
+            return U256::zero();
+        }
+
+        let Some(oracle) = &self.oracle else {
+            info!(fee = %static_fee, "Paying the static messenger fee");
+            return static_fee;
+        };
+
+        match oracle.l_2_base_fee().call().await {
+            Ok(l2_base_fee) => {
+                let fee = messenger_fee(l2_base_fee, &self.fee);
+                info!(
+                    %fee,
+                    %l2_base_fee,
+                    l2_gas_limit = self.fee.l2_gas_limit,
+                    multiplier_percent = self.fee.fee_multiplier_percent,
+                    "Estimated the messenger fee"
+                );
+                fee
+            }
+            Err(err) => {
+                warn!(
+                    ?err,
+                    fee = %static_fee,
+                    "Couldn't read the L2 gas price oracle, paying the static messenger fee"
+                );
+                static_fee
+            }
+        }
     }
 
     #[instrument(level = "debug", skip_all)]
-    pub async fn get_world_id_latest_root(&self) -> anyhow::Result<U256> {
-        let latest_root = self.world_id_abi.latest_root().call().await?;
-        Ok(latest_root)
+    async fn get_world_id_latest_root(&self) -> anyhow::Result<U256> {
+        self.world_ids.latest_root().await
+    }
+
+    async fn is_root_on_mainnet(&self, root: U256) -> anyhow::Result<bool> {
+        self.world_ids.is_root_on_mainnet(root).await
     }
-    
-    #[instrument(level = "debug", skip_all)]
-    pub async fn check_sync_state(&self) -> anyhow::Result<bool>{
-        let latest_root_scroll_world_id = match self.get_scroll_latest_root().await {
-            Ok(id) => id,
-            Err(err) => {
-                // Log the error with context but do not panic
-                error!(%err, "Failed to get the latest root world ID");
-                // Return false or another appropriate value to indicate failure
-                return Ok(false);
-            }
-        };
 
-        let latest_root_world_id = self.get_world_id_latest_root().await?;
-        // info!("latest_root_world_id: {:?}", latest_root_world_id);
-        Ok(latest_root_scroll_world_id == latest_root_world_id)
+    #[instrument(level = "debug", skip(self))]
+    async fn get_world_id_root_age(
+        &self,
+        root: U256,
+        within: Duration,
+    ) -> anyhow::Result<Option<Duration>> {
+        self.world_ids.root_age(root, within).await
     }
-    
+
+    async fn get_root_history_expiry(&self) -> anyhow::Result<Duration> {
+        self.world_ids.root_history_expiry().await
+    }
+
     #[instrument(level = "debug", skip_all)]
-    pub async fn is_root_mined(&self, root: U256) -> anyhow::Result<bool> {
-        let (root_on_mainnet, ..) = self.world_id_abi.query_root(root).call().await?;
+    async fn get_target_latest_root(&self) -> anyhow::Result<U256> {
+        self.world_ids.target_latest_root().await
+    }
+
+    async fn get_target_root_timestamp(&self, root: U256) -> anyhow::Result<u128> {
+        self.world_ids.target_root_timestamp(root).await
+    }
+
+    #[instrument(level = "debug", skip_all, fields(roots = roots.len()))]
+    async fn lookup_roots(&self, roots: &[U256]) -> anyhow::Result<Vec<RootLookup>> {
+        self.world_ids.lookup_roots(roots).await
+    }
+}
+
+/// Connects to the bridge `[network]` names.
+///
+/// # Errors
+///
+/// Will return `Err` if the network config is missing or the contracts can't
+/// be reached.
+pub async fn connect(config: &Config, ethereum: Ethereum) -> anyhow::Result<Arc<dyn StateBridge>> {
+    let Some(network_config) = &config.network else {
+        bail!("Network config is required to connect to the state bridge.");
+    };
+
+    let address = network_config.scroll_bridge_address;
+    let allow_missing_code = network_config.allow_missing_code;
+    let bridge: Arc<dyn StateBridge> = match network_config.kind {
+        BridgeKind::Scroll => Arc::new(
+            ScrollBridge::new(
+                address,
+                config.messenger_fee.clone(),
+                config.root_check.call_timeout,
+                allow_missing_code,
+                ethereum,
+            )
+            .await?,
+        ),
+        BridgeKind::Optimism => Arc::new(
+            OpBridge::new(
+                address,
+                config.root_check.call_timeout,
+                allow_missing_code,
+                ethereum,
+            )
+            .await?,
+        ),
+    };
 
-        if root_on_mainnet.is_zero() {
-            return Ok(false);
+    Ok(bridge)
+}
+
+/// Why a call reverted with `data`: the `require` message, the state
+/// bridge's custom error, e.g. `OwnableUnauthorizedAccount(0x…)`, or the
+/// data as hex when it is neither.
+#[must_use]
+pub fn revert_reason(data: &[u8]) -> String {
+    if let Some(reason) = String::decode_with_selector(data) {
+        return reason;
+    }
+
+    let Ok(error) = ScrollStateBridgeErrors::decode(data) else {
+        return format!("0x{}", hex::encode(data));
+    };
+
+    match error {
+        ScrollStateBridgeErrors::AddressZero(_) => "AddressZero".to_string(),
+        ScrollStateBridgeErrors::CannotRenounceOwnership(_) => {
+            "CannotRenounceOwnership".to_string()
+        }
+        ScrollStateBridgeErrors::GasLimitZero(_) => "GasLimitZero".to_string(),
+        ScrollStateBridgeErrors::OwnableInvalidOwner(error) => {
+            format!("OwnableInvalidOwner({:?})", error.owner)
         }
+        ScrollStateBridgeErrors::OwnableUnauthorizedAccount(error) => {
+            format!("OwnableUnauthorizedAccount({:?})", error.account)
+        }
+        ScrollStateBridgeErrors::RevertString(reason) => reason,
+    }
+}
+
+/// Replays the mined transaction `hash` with `eth_call` at its block, and
+/// decodes why it reverts. `None` if the replay doesn't revert, e.g. the
+/// state it reverted on has since changed.
+///
+/// # Errors
+///
+/// Will return `Err` if the transaction can't be found or the call fails for
+/// another reason than a revert.
+pub async fn replay_revert(provider: &ReadProvider, hash: H256) -> anyhow::Result<Option<String>> {
+    let Some(tx) = provider.get_transaction(hash).await? else {
+        bail!("transaction {hash:?} not found");
+    };
+    let Some(block) = tx.block_number else {
+        bail!("transaction {hash:?} isn't mined");
+    };
 
-        let root_timestamp = self.scroll_world_id_abi.root_history(root).call().await?;
+    let mut call = TransactionRequest::new()
+        .from(tx.from)
+        .data(tx.input)
+        .value(tx.value)
+        .gas(tx.gas);
+    call.to = tx.to.map(Into::into);
+
+    let block = BlockId::Number(BlockNumber::Number(block));
+    match provider.call(&call.into(), Some(block)).await {
+        Ok(_) => Ok(None),
+        Err(err) => match err
+            .as_error_response()
+            .and_then(|response| response.as_revert_data())
+        {
+            Some(data) => Ok(Some(revert_reason(&data))),
+            // Some providers report a revert without any data
+            None => match err.as_error_response() {
+                Some(response) if response.message.contains("revert") => {
+                    Ok(Some(response.message.clone()))
+                }
+                _ => Err(err.into()),
+            },
+        },
+    }
+}
 
-        // root_history only returns superseded roots, so we must also check the latest
-        // root
-        let latest_root = self.scroll_world_id_abi.latest_root().call().await?;
+pub(crate) fn simulation_error(err: ContractError<ReadProvider>) -> SimulationError {
+    if let Some(data) = err.as_revert() {
+        return SimulationError::WouldRevert {
+            reason: revert_reason(data),
+        };
+    }
 
-        // If root is not superseded and it's not the latest root
-        // then it's not mined
-        if root_timestamp == 0 && root != latest_root {
-            return Ok(false);
+    // Some providers report a revert without any data
+    if let ContractError::MiddlewareError { e } = &err {
+        if let Some(response) = e.as_error_response() {
+            if response.message.contains("revert") {
+                return SimulationError::WouldRevert {
+                    reason: response.message.clone(),
+                };
+            }
         }
+    }
+
+    SimulationError::Other(err.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{
+        abi::{encode, Token},
+        types::Bytes,
+    };
+
+    use super::*;
+
+    #[test]
+    fn no_root_is_known_before_the_first_propagation() {
+        let root = U256::from(0xabc);
+
+        assert_eq!(
+            RootStatus::on_destination(root, U256::zero(), 0),
+            RootStatus::UnknownOnDestination
+        );
+        // Even a zero root, which equals the zero latest root
+        assert_eq!(
+            RootStatus::on_destination(U256::zero(), U256::zero(), 0),
+            RootStatus::UnknownOnDestination
+        );
+
+        // After the first RootAdded
+        assert_eq!(
+            RootStatus::on_destination(root, root, 0),
+            RootStatus::Latest
+        );
+        assert_eq!(
+            RootStatus::on_destination(root, U256::from(0xdef), 1_700_000_000),
+            RootStatus::Superseded
+        );
+        assert_eq!(
+            RootStatus::on_destination(U256::from(0x123), root, 0),
+            RootStatus::UnknownOnDestination
+        );
+    }
+
+    #[test]
+    fn lookup_reports_mainnet_before_scroll() {
+        let root = U256::from(0xabc);
+        let latest = U256::from(0xdef);
+
+        assert_eq!(RootLookup::new(root, false, latest, 0), RootLookup {
+            status:           RootStatus::NotOnMainnet,
+            scroll_timestamp: None,
+        });
+        assert_eq!(RootLookup::new(root, true, latest, 0), RootLookup {
+            status:           RootStatus::UnknownOnDestination,
+            scroll_timestamp: None,
+        });
+        assert_eq!(
+            RootLookup::new(root, true, latest, 1_700_000_000),
+            RootLookup {
+                status:           RootStatus::Superseded,
+                scroll_timestamp: Some(1_700_000_000),
+            }
+        );
+        assert_eq!(
+            RootLookup::new(root, true, root, 1_700_000_000),
+            RootLookup {
+                status:           RootStatus::Latest,
+                scroll_timestamp: Some(1_700_000_000),
+            }
+        );
+    }
+
+    #[test]
+    fn messenger_fee_covers_the_l2_gas_with_a_margin() {
+        let config = MessengerFeeConfig {
+            l2_gas_limit: 200_000,
+            fee_multiplier_percent: 150,
+            ..MessengerFeeConfig::default()
+        };
+
+        // 200k gas at 2 gwei, plus half
+        assert_eq!(
+            messenger_fee(U256::from(2_000_000_000_u64), &config),
+            U256::from(600_000_000_000_000_u64)
+        );
+        assert_eq!(messenger_fee(U256::zero(), &config), U256::zero());
+        assert_eq!(messenger_fee(U256::MAX, &config), U256::MAX / 100);
+    }
+
+    #[test]
+    fn revert_reason_is_decoded() {
+        // Error(string)
+        let mut data = vec![0x08, 0xc3, 0x79, 0xa0];
+        data.extend(encode(&[Token::String(
+            "root already propagated".to_string(),
+        )]));
+
+        let err = simulation_error(ContractError::Revert(Bytes::from(data)));
+
+        assert!(matches!(
+            err,
+            SimulationError::WouldRevert { reason } if reason == "root already propagated"
+        ));
+    }
+
+    #[test]
+    fn custom_errors_are_named() {
+        assert_eq!(
+            revert_reason(&abi::GasLimitZero::selector()),
+            "GasLimitZero"
+        );
+
+        let account = Address::from_low_u64_be(0xabc);
+        let mut data = abi::OwnableUnauthorizedAccount::selector().to_vec();
+        data.extend(encode(&[Token::Address(account)]));
+        assert_eq!(
+            revert_reason(&data),
+            format!("OwnableUnauthorizedAccount({account:?})")
+        );
+
+        assert_eq!(revert_reason(&[0xde, 0xad, 0xbe, 0xef]), "0xdeadbeef");
+    }
+
+    #[test]
+    fn undecodable_revert_is_reported_as_hex() {
+        let err = simulation_error(ContractError::Revert(Bytes::from(vec![
+            0xde, 0xad, 0xbe, 0xef,
+        ])));
 
-        Ok(true)
+        assert!(matches!(
+            err,
+            SimulationError::WouldRevert { reason } if reason == "0xdeadbeef"
+        ));
     }
 }
@@ -0,0 +1,149 @@
+//! The OP Stack state bridges, e.g. Optimism and Base: `OpStateBridge` on
+//! mainnet, `OpWorldID` on the target chain.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use ethers::types::{Address, U256};
+use tracing::{info, instrument};
+
+use super::{
+    abi::OpStateBridge,
+    simulation_error,
+    state_bridge::{require_code, BridgeAddresses, StateBridge, WorldIdPair},
+    RootLookup, SimulationError,
+};
+use crate::{
+    ethereum::{Ethereum, ReadProvider},
+    utils::TransactionId,
+};
+
+#[derive(Debug)]
+pub struct OpBridge {
+    ethereum:   Ethereum,
+    bridge_abi: OpStateBridge<ReadProvider>,
+    world_ids:  WorldIdPair,
+}
+
+impl OpBridge {
+    #[instrument(level = "debug", skip_all)]
+    pub async fn new(
+        address: Address,
+        call_timeout: Duration,
+        allow_missing_code: bool,
+        ethereum: Ethereum,
+    ) -> anyhow::Result<Self> {
+        require_code(
+            ethereum.l1_provider(),
+            address,
+            "op state bridge",
+            allow_missing_code,
+        )
+        .await?;
+
+        let bridge_abi = OpStateBridge::new(address, ethereum.l1_provider().clone());
+
+        let owner = bridge_abi.owner().call().await?;
+        info!(?address, ?owner, "Connected to the OP State WorldID Bridge");
+
+        let op_world_id_address = bridge_abi.op_world_id_address().call().await?;
+        let world_id_address = bridge_abi.world_id_address().call().await?;
+        info!(?op_world_id_address, ?world_id_address);
+
+        let world_ids = WorldIdPair::connect(
+            ethereum.clone(),
+            world_id_address,
+            op_world_id_address,
+            call_timeout,
+            allow_missing_code,
+        )
+        .await?;
+
+        Ok(Self {
+            ethereum,
+            bridge_abi,
+            world_ids,
+        })
+    }
+}
+
+#[async_trait]
+impl StateBridge for OpBridge {
+    fn chain(&self) -> &'static str {
+        "optimism"
+    }
+
+    fn addresses(&self) -> BridgeAddresses {
+        BridgeAddresses {
+            bridge:          self.bridge_abi.address(),
+            target_world_id: self.world_ids.target_world_id.address(),
+            world_id:        self.world_ids.world_id.address(),
+        }
+    }
+
+    /// `propagateRoot` isn't payable on the OP bridges, so a value is only
+    /// attached when one is configured.
+    #[instrument(level = "debug", skip(self))]
+    async fn simulate_propagate_root(&self, value_in_wei: U256) -> Result<(), SimulationError> {
+        let mut call = self
+            .bridge_abi
+            .propagate_root()
+            .from(self.ethereum.address());
+        if !value_in_wei.is_zero() {
+            call = call.value(value_in_wei);
+        }
+
+        match call.call().await {
+            Ok(()) => Ok(()),
+            Err(err) => Err(simulation_error(err)),
+        }
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn propagate_root(&self, value_in_wei: U256) -> anyhow::Result<TransactionId> {
+        let mut propagate_root_transaction = self.bridge_abi.propagate_root().tx;
+        if !value_in_wei.is_zero() {
+            propagate_root_transaction.set_value(value_in_wei);
+        }
+        self.ethereum
+            .send_transaction(propagate_root_transaction, true)
+            .await
+            .map_err(anyhow::Error::from)
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn get_world_id_latest_root(&self) -> anyhow::Result<U256> {
+        self.world_ids.latest_root().await
+    }
+
+    async fn is_root_on_mainnet(&self, root: U256) -> anyhow::Result<bool> {
+        self.world_ids.is_root_on_mainnet(root).await
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn get_world_id_root_age(
+        &self,
+        root: U256,
+        within: Duration,
+    ) -> anyhow::Result<Option<Duration>> {
+        self.world_ids.root_age(root, within).await
+    }
+
+    async fn get_root_history_expiry(&self) -> anyhow::Result<Duration> {
+        self.world_ids.root_history_expiry().await
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn get_target_latest_root(&self) -> anyhow::Result<U256> {
+        self.world_ids.target_latest_root().await
+    }
+
+    async fn get_target_root_timestamp(&self, root: U256) -> anyhow::Result<u128> {
+        self.world_ids.target_root_timestamp(root).await
+    }
+
+    #[instrument(level = "debug", skip_all, fields(roots = roots.len()))]
+    async fn lookup_roots(&self, roots: &[U256]) -> anyhow::Result<Vec<RootLookup>> {
+        self.world_ids.lookup_roots(roots).await
+    }
+}
@@ -0,0 +1,874 @@
+use std::collections::BTreeMap;
+
+use ethers::{
+    providers::Middleware,
+    types::{
+        Address, BlockId, BlockNumber, Filter, FilterBlockOption, Log, Topic, ValueOrArray, H256,
+    },
+};
+use tracing::{debug, warn};
+
+/// Successful windows in a row before a shrunk window is doubled again.
+const GROW_AFTER: u32 = 5;
+
+/// Fragments of the errors providers return for a range they won't serve in
+/// one call, lowercased. Specific to the range being too large, an invalid
+/// range also names the block range.
+const RANGE_TOO_LARGE: [&str; 10] = [
+    // Infura, Geth
+    "query returned more than",
+    "more than 10000 results",
+    "too many results",
+    // Alchemy
+    "response size exceeded",
+    // QuickNode
+    "is limited to a",
+    // Ankr, Erigon
+    "block range is too wide",
+    // Chainstack, BSC and other Geth forks
+    "exceed maximum block range",
+    "exceeds max block range",
+    "range too large",
+    "payload too large",
+];
+
+/// Whether `err` asks for a smaller range rather than reporting a failure.
+fn is_range_too_large(err: &impl std::fmt::Display) -> bool {
+    let message = err.to_string().to_lowercase();
+    RANGE_TOO_LARGE
+        .iter()
+        .any(|fragment| message.contains(fragment))
+}
+
+/// The topics of a filter matching the logs of any of the events with
+/// `signatures`, whatever their indexed arguments.
+#[must_use]
+pub fn event_topics(signatures: &[H256]) -> [Option<Topic>; 4] {
+    let topic0 = match signatures {
+        [signature] => ValueOrArray::Value(Some(*signature)),
+        signatures => ValueOrArray::Array(signatures.iter().copied().map(Some).collect()),
+    };
+    [Some(topic0), None, None, None]
+}
+
+/// A log returned by [`BlockScanner::next`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannedLog {
+    pub log:       Log,
+    /// Set when the log's block was scanned before and is being scanned again
+    /// after a reorg.
+    pub rescanned: bool,
+}
+
+/// A reorg detected by the scanner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reorg {
+    /// The first block that is scanned again. Anything recorded from this
+    /// block onwards may no longer be canonical.
+    pub rewound_to:   u64,
+    /// The last block scanned before the reorg was detected.
+    pub last_scanned: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanResult {
+    pub logs:  Vec<ScannedLog>,
+    pub reorg: Option<Reorg>,
+}
+
+/// A clone scans on from the same cursor, so a window can be scanned on a
+/// clone that only replaces the scanner once its logs are recorded.
+#[derive(Clone)]
+pub struct BlockScanner<T> {
+    read_provider:     T,
+    current_block:     u64,
+    /// The configured ceiling
+    max_window_size:   u64,
+    /// The window actually scanned, shrunk while the provider rejects ranges
+    window_size:       u64,
+    /// Successful windows since the window last shrunk or grew
+    successes:         u32,
+    chain_head_offset: u64,
+    rewind_depth:      u64,
+    /// The number and hash of the last block covered by the previous window.
+    last_scanned:      Option<(u64, H256)>,
+    /// Blocks up to and including this one have been scanned before.
+    rescan_until:      Option<u64>,
+    /// The latest block the provider last reported, before the offset
+    chain_head:        Option<u64>,
+    /// A chain head read by [`Self::head`] that the next scan goes up to
+    /// instead of reading it again
+    fresh_head:        Option<u64>,
+    /// How deep a log has to be below the chain head before it is returned
+    confirmations:     u64,
+    /// Scanned logs not yet `confirmations` deep, oldest first
+    unconfirmed:       Vec<ScannedLog>,
+}
+
+impl<T> BlockScanner<T>
+where
+    T: Middleware,
+    <T as Middleware>::Error: 'static,
+{
+    pub async fn new_latest(read_provider: T, window_size: u64) -> Result<Self, T::Error> {
+        let latest_block = read_provider.get_block_number().await?;
+
+        Ok(Self::new(read_provider, latest_block.as_u64(), window_size))
+    }
+
+    pub const fn new(read_provider: T, start_block: u64, window_size: u64) -> Self {
+        Self {
+            read_provider,
+            current_block: start_block,
+            max_window_size: if window_size == 0 { 1 } else { window_size },
+            window_size: if window_size == 0 { 1 } else { window_size },
+            successes: 0,
+            chain_head_offset: 0,
+            rewind_depth: 0,
+            last_scanned: None,
+            rescan_until: None,
+            chain_head: None,
+            fresh_head: None,
+            confirmations: 0,
+            unconfirmed: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub const fn with_offset(mut self, chain_head_offset: u64) -> Self {
+        self.chain_head_offset = chain_head_offset;
+        self
+    }
+
+    /// Sets how many blocks to go back when the last scanned block is no
+    /// longer part of the canonical chain.
+    #[must_use]
+    pub const fn with_rewind_depth(mut self, rewind_depth: u64) -> Self {
+        self.rewind_depth = rewind_depth;
+        self
+    }
+
+    /// Holds logs back until their block is `confirmations` deep below the
+    /// chain head. 0 returns them as soon as they are scanned.
+    #[must_use]
+    pub const fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    /// The next block to be scanned.
+    #[must_use]
+    pub const fn current_block(&self) -> u64 {
+        self.current_block
+    }
+
+    /// The first block whose logs haven't all been returned: the oldest held
+    /// back log's, or the next block to be scanned.
+    #[must_use]
+    pub fn resume_block(&self) -> u64 {
+        self.unconfirmed
+            .iter()
+            .find_map(|scanned| scanned.log.block_number)
+            .map_or(self.current_block, |block| {
+                block.as_u64().min(self.current_block)
+            })
+    }
+
+    /// The window currently scanned, at most the configured size.
+    #[must_use]
+    pub const fn window_size(&self) -> u64 {
+        self.window_size
+    }
+
+    /// The latest block as of the last head read, `None` before the first.
+    #[must_use]
+    pub const fn chain_head(&self) -> Option<u64> {
+        self.chain_head
+    }
+
+    /// Blocks left to scan up to the last head read, less the offset.
+    #[must_use]
+    pub fn lag(&self) -> Option<u64> {
+        let head = self.chain_head?.saturating_sub(self.chain_head_offset);
+        Some(head.saturating_add(1).saturating_sub(self.current_block))
+    }
+
+    /// The last block that would be scanned, behind the chain head by the
+    /// offset. The next scan goes up to this head rather than reading it
+    /// again.
+    pub async fn head(&mut self) -> Result<u64, T::Error> {
+        let chain_head = self.read_chain_head().await?;
+        self.fresh_head = Some(chain_head);
+        Ok(chain_head.saturating_sub(self.chain_head_offset))
+    }
+
+    async fn read_chain_head(&mut self) -> Result<u64, T::Error> {
+        let chain_head = self.read_provider.get_block_number().await?.as_u64();
+        self.chain_head = Some(chain_head);
+        Ok(chain_head)
+    }
+
+    pub async fn next(
+        &mut self,
+        address: Option<ValueOrArray<Address>>,
+        topics: [Option<Topic>; 4],
+    ) -> Result<ScanResult, T::Error> {
+        let reorg = self.detect_reorg().await?;
+
+        let chain_head = match self.fresh_head.take() {
+            Some(chain_head) => chain_head,
+            None => self.read_chain_head().await?,
+        };
+        let latest_block = chain_head.saturating_sub(self.chain_head_offset);
+
+        if self.current_block > latest_block {
+            let logs = self.confirm(Vec::new(), chain_head).await?;
+            return Ok(ScanResult { logs, reorg });
+        }
+
+        let from_block = self.current_block;
+
+        // Nothing moves until a range is served, so a rejected range is
+        // retried smaller rather than skipped
+        let (to_block, to_block_hash, logs) = loop {
+            let to_block = latest_block.min(from_block + self.window_size - 1);
+
+            // The hash is read before the logs: if the range reorgs in between,
+            // the next call sees a mismatch and rescans it.
+            let to_block_hash = self.block_hash(to_block).await?;
+
+            let filter = Filter {
+                block_option: FilterBlockOption::Range {
+                    from_block: Some(BlockNumber::Number(from_block.into())),
+                    to_block:   Some(BlockNumber::Number(to_block.into())),
+                },
+                address:      address.clone(),
+                topics:       topics.clone(),
+            };
+
+            match self.read_provider.get_logs(&filter).await {
+                Ok(logs) => break (to_block, to_block_hash, logs),
+                Err(err) if self.window_size > 1 && is_range_too_large(&err) => {
+                    self.window_size = (self.window_size / 2).max(1);
+                    self.successes = 0;
+                    warn!(
+                        from_block,
+                        to_block,
+                        window_size = self.window_size,
+                        %err,
+                        "Provider rejected the range, shrinking the scanning window"
+                    );
+                }
+                Err(err) => return Err(err),
+            }
+        };
+
+        self.grow_window();
+
+        let logs: Vec<_> = logs
+            .into_iter()
+            .map(|log| {
+                let block_number = log.block_number.map(|n| n.as_u64());
+                let rescanned = matches!(
+                    (block_number, self.rescan_until),
+                    (Some(block), Some(until)) if block <= until
+                );
+                ScannedLog { log, rescanned }
+            })
+            .collect();
+
+        self.current_block = to_block + 1;
+        self.last_scanned = to_block_hash.map(|hash| (to_block, hash));
+        if self.rescan_until.is_some_and(|until| until <= to_block) {
+            self.rescan_until = None;
+        }
+
+        let logs = self.confirm(logs, chain_head).await?;
+        Ok(ScanResult { logs, reorg })
+    }
+
+    /// Holds `logs` back with the earlier ones and returns those at least
+    /// `confirmations` deep below `chain_head`. A log whose block was
+    /// replaced while it was held back is dropped, the rewind after the reorg
+    /// scans its block again.
+    async fn confirm(
+        &mut self,
+        logs: Vec<ScannedLog>,
+        chain_head: u64,
+    ) -> Result<Vec<ScannedLog>, T::Error> {
+        if self.confirmations == 0 && self.unconfirmed.is_empty() {
+            return Ok(logs);
+        }
+
+        self.unconfirmed.extend(logs);
+        let deep = self.unconfirmed.partition_point(|scanned| {
+            !scanned
+                .log
+                .block_number
+                .is_some_and(|block| block.as_u64() + self.confirmations > chain_head)
+        });
+        let released: Vec<_> = self.unconfirmed.drain(..deep).collect();
+
+        let mut canonical = BTreeMap::new();
+        let mut confirmed = Vec::with_capacity(released.len());
+        for scanned in released {
+            let (Some(block), Some(hash)) = (scanned.log.block_number, scanned.log.block_hash)
+            else {
+                confirmed.push(scanned);
+                continue;
+            };
+            let block = block.as_u64();
+
+            let canonical_hash = match canonical.get(&block) {
+                Some(canonical_hash) => *canonical_hash,
+                None => {
+                    let canonical_hash = self.block_hash(block).await?;
+                    canonical.insert(block, canonical_hash);
+                    canonical_hash
+                }
+            };
+
+            if canonical_hash == Some(hash) {
+                confirmed.push(scanned);
+            } else {
+                warn!(
+                    block,
+                    ?hash,
+                    ?canonical_hash,
+                    transaction_hash = ?scanned.log.transaction_hash,
+                    "Dropping a held back log whose block is no longer canonical"
+                );
+            }
+        }
+
+        Ok(confirmed)
+    }
+
+    /// Doubles a shrunk window back toward the configured size after a run of
+    /// successful windows.
+    fn grow_window(&mut self) {
+        if self.window_size == self.max_window_size {
+            return;
+        }
+
+        self.successes += 1;
+        if self.successes >= GROW_AFTER {
+            self.window_size = (self.window_size * 2).min(self.max_window_size);
+            self.successes = 0;
+            debug!(
+                window_size = self.window_size,
+                "Growing the scanning window"
+            );
+        }
+    }
+
+    /// Checks that the last scanned block is still canonical, and rewinds the
+    /// cursor if it isn't.
+    async fn detect_reorg(&mut self) -> Result<Option<Reorg>, T::Error> {
+        let Some((last_block, last_hash)) = self.last_scanned else {
+            return Ok(None);
+        };
+
+        let canonical_hash = self.block_hash(last_block).await?;
+        if canonical_hash == Some(last_hash) {
+            return Ok(None);
+        }
+
+        let rewound_to = (last_block + 1).saturating_sub(self.rewind_depth.max(1));
+
+        warn!(
+            last_block,
+            ?last_hash,
+            ?canonical_hash,
+            rewound_to,
+            "Last scanned block is no longer canonical, rewinding"
+        );
+
+        self.current_block = rewound_to;
+        self.last_scanned = None;
+        // The rewound range is scanned again, along with its logs
+        self.unconfirmed.retain(|scanned| {
+            scanned
+                .log
+                .block_number
+                .is_some_and(|block| block.as_u64() < rewound_to)
+        });
+        self.rescan_until = Some(
+            self.rescan_until
+                .map_or(last_block, |until| until.max(last_block)),
+        );
+
+        Ok(Some(Reorg {
+            rewound_to,
+            last_scanned: last_block,
+        }))
+    }
+
+    async fn block_hash(&self, block_number: u64) -> Result<Option<H256>, T::Error> {
+        let block = self
+            .read_provider
+            .get_block(BlockId::Number(BlockNumber::Number(block_number.into())))
+            .await?;
+
+        Ok(block.and_then(|block| block.hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fmt::Debug,
+        sync::{Arc, Mutex},
+    };
+
+    use async_trait::async_trait;
+    use ethers::{
+        providers::{JsonRpcClient, JsonRpcError, MockError, Provider},
+        types::{Block, U64},
+    };
+    use serde::{de::DeserializeOwned, Serialize};
+    use serde_json::Value;
+
+    use super::*;
+
+    /// A chain whose blocks can be swapped out from under the scanner.
+    #[derive(Debug, Default)]
+    struct Chain {
+        head:       u64,
+        hashes:     BTreeMap<u64, H256>,
+        logs:       Vec<Log>,
+        /// The widest range `eth_getLogs` serves, like public endpoints
+        max_range:  Option<u64>,
+        /// Every range asked of `eth_getLogs`
+        queried:    Vec<(u64, u64)>,
+        /// Fails every `eth_getLogs`, like an unreachable endpoint
+        fail_logs:  bool,
+        /// Calls to `eth_blockNumber`
+        head_reads: usize,
+    }
+
+    impl Chain {
+        fn with_blocks(head: u64, fork: u8) -> Self {
+            let mut chain = Self::default();
+            for block in 0..=head {
+                chain.push_block(block, fork);
+            }
+            chain
+        }
+
+        fn push_block(&mut self, number: u64, fork: u8) {
+            let mut hash = H256::from_low_u64_be(number);
+            hash.0[0] = fork;
+            self.hashes.insert(number, hash);
+            self.head = self.head.max(number);
+        }
+
+        fn push_log(&mut self, block: u64, tag: u64) {
+            self.logs.push(Log {
+                block_number: Some(U64::from(block)),
+                block_hash: self.hashes.get(&block).copied(),
+                transaction_hash: Some(H256::from_low_u64_be(tag)),
+                log_index: Some(0.into()),
+                ..Log::default()
+            });
+        }
+
+        /// Replaces every block from `from` onwards, and the logs in them.
+        fn reorg(&mut self, from: u64, fork: u8) {
+            self.logs
+                .retain(|log| log.block_number.unwrap().as_u64() < from);
+            for block in from..=self.head {
+                self.push_block(block, fork);
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Default)]
+    struct MockChain(Arc<Mutex<Chain>>);
+
+    #[async_trait]
+    impl JsonRpcClient for MockChain {
+        type Error = MockError;
+
+        async fn request<P, R>(&self, method: &str, params: P) -> Result<R, Self::Error>
+        where
+            P: Debug + Serialize + Send + Sync,
+            R: DeserializeOwned + Send,
+        {
+            let params = serde_json::to_value(params)?;
+            let mut chain = self.0.lock().unwrap();
+
+            let block_param = |value: &Value| {
+                U64::from_str_radix(value.as_str().unwrap().trim_start_matches("0x"), 16)
+                    .unwrap()
+                    .as_u64()
+            };
+
+            let response = match method {
+                "eth_blockNumber" => {
+                    chain.head_reads += 1;
+                    serde_json::to_value(U64::from(chain.head))?
+                }
+                "eth_getBlockByNumber" => {
+                    let number = block_param(&params[0]);
+                    let block = chain.hashes.get(&number).map(|hash| Block::<H256> {
+                        hash: Some(*hash),
+                        number: Some(number.into()),
+                        ..Block::default()
+                    });
+                    serde_json::to_value(block)?
+                }
+                "eth_getLogs" => {
+                    let from = block_param(&params[0]["fromBlock"]);
+                    let to = block_param(&params[0]["toBlock"]);
+                    chain.queried.push((from, to));
+                    if chain.fail_logs {
+                        return Err(MockError::EmptyResponses);
+                    }
+                    if chain
+                        .max_range
+                        .is_some_and(|max_range| to - from + 1 > max_range)
+                    {
+                        return Err(MockError::JsonRpcError(JsonRpcError {
+                            code:    -32005,
+                            message: "query returned more than 10000 results".to_string(),
+                            data:    None,
+                        }));
+                    }
+                    let logs: Vec<&Log> = chain
+                        .logs
+                        .iter()
+                        .filter(|log| (from..=to).contains(&log.block_number.unwrap().as_u64()))
+                        .collect();
+                    serde_json::to_value(logs)?
+                }
+                _ => return Err(MockError::EmptyResponses),
+            };
+
+            Ok(serde_json::from_value(response)?)
+        }
+    }
+
+    fn scanner(mock: &MockChain, start_block: u64) -> BlockScanner<Provider<MockChain>> {
+        BlockScanner::new(Provider::new(mock.clone()), start_block, 10).with_rewind_depth(5)
+    }
+
+    fn tags(result: &ScanResult) -> Vec<(u64, bool)> {
+        result
+            .logs
+            .iter()
+            .map(|scanned| {
+                (
+                    scanned.log.transaction_hash.unwrap().to_low_u64_be(),
+                    scanned.rescanned,
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn scans_without_reorg() -> anyhow::Result<()> {
+        let mock = MockChain::default();
+        {
+            let mut chain = mock.0.lock().unwrap();
+            *chain = Chain::with_blocks(20, 0);
+            chain.push_log(3, 1);
+            chain.push_log(15, 2);
+        }
+
+        let mut scanner = scanner(&mock, 0);
+
+        let first = scanner.next(None, Default::default()).await?;
+        assert_eq!(tags(&first), vec![(1, false)]);
+        assert_eq!(first.reorg, None);
+
+        let second = scanner.next(None, Default::default()).await?;
+        assert_eq!(tags(&second), vec![(2, false)]);
+        assert_eq!(second.reorg, None);
+        assert_eq!(scanner.current_block(), 20);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rewinds_when_last_scanned_block_is_replaced() -> anyhow::Result<()> {
+        let mock = MockChain::default();
+        {
+            let mut chain = mock.0.lock().unwrap();
+            *chain = Chain::with_blocks(9, 0);
+            chain.push_log(7, 1);
+        }
+
+        let mut scanner = scanner(&mock, 0);
+
+        let first = scanner.next(None, Default::default()).await?;
+        assert_eq!(tags(&first), vec![(1, false)]);
+
+        // Blocks 7..=9 are replaced, and the log moves from block 7 to 8.
+        {
+            let mut chain = mock.0.lock().unwrap();
+            chain.reorg(7, 1);
+            chain.push_log(8, 2);
+        }
+
+        let second = scanner.next(None, Default::default()).await?;
+        assert_eq!(
+            second.reorg,
+            Some(Reorg {
+                rewound_to:   5,
+                last_scanned: 9,
+            })
+        );
+        assert_eq!(tags(&second), vec![(2, true)]);
+        assert_eq!(scanner.current_block(), 10);
+
+        // Once past the rewound range, logs are no longer flagged.
+        {
+            let mut chain = mock.0.lock().unwrap();
+            chain.push_block(10, 1);
+            chain.push_log(10, 3);
+        }
+
+        let third = scanner.next(None, Default::default()).await?;
+        assert_eq!(third.reorg, None);
+        assert_eq!(tags(&third), vec![(3, false)]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn a_failed_scan_on_a_clone_leaves_the_cursor_alone() -> anyhow::Result<()> {
+        let mock = MockChain::default();
+        *mock.0.lock().unwrap() = Chain::with_blocks(9, 0);
+
+        let mut scanner = scanner(&mock, 0);
+        scanner.next(None, Default::default()).await?;
+
+        // The reorg is detected and the clone rewound before the logs fail
+        {
+            let mut chain = mock.0.lock().unwrap();
+            chain.reorg(7, 1);
+            chain.fail_logs = true;
+        }
+        let mut failed = scanner.clone();
+        assert!(failed.next(None, Default::default()).await.is_err());
+        assert_eq!(failed.current_block(), 5);
+
+        // The scanner itself still reports the reorg once the logs are back
+        mock.0.lock().unwrap().fail_logs = false;
+        assert_eq!(scanner.current_block(), 10);
+        let result = scanner.next(None, Default::default()).await?;
+        assert_eq!(result.reorg.map(|reorg| reorg.rewound_to), Some(5));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rewinds_when_chain_shrinks_below_last_scanned_block() -> anyhow::Result<()> {
+        let mock = MockChain::default();
+        *mock.0.lock().unwrap() = Chain::with_blocks(9, 0);
+
+        let mut scanner = scanner(&mock, 0);
+        scanner.next(None, Default::default()).await?;
+
+        {
+            let mut chain = mock.0.lock().unwrap();
+            chain.hashes.remove(&9);
+            chain.head = 8;
+            chain.reorg(8, 1);
+        }
+
+        let result = scanner.next(None, Default::default()).await?;
+        assert_eq!(result.reorg.map(|reorg| reorg.rewound_to), Some(5));
+        assert_eq!(scanner.current_block(), 9);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn shrinks_the_window_for_rejected_ranges_and_grows_it_back() -> anyhow::Result<()> {
+        let mock = MockChain::default();
+        {
+            let mut chain = mock.0.lock().unwrap();
+            *chain = Chain::with_blocks(200, 0);
+            chain.max_range = Some(3);
+            chain.push_log(2, 1);
+        }
+
+        // Halved from 10 to 5 to 2, without skipping a block
+        let mut scanner = scanner(&mock, 0);
+        let first = scanner.next(None, Default::default()).await?;
+        assert!(first.logs.is_empty());
+        assert_eq!(scanner.window_size(), 2);
+        assert_eq!(scanner.current_block(), 2);
+        assert_eq!(mock.0.lock().unwrap().queried, vec![(0, 9), (0, 4), (0, 1)]);
+
+        // Block 2 is scanned next, its log isn't lost
+        mock.0.lock().unwrap().max_range = None;
+        let second = scanner.next(None, Default::default()).await?;
+        assert_eq!(tags(&second), vec![(1, false)]);
+
+        for _ in 0..GROW_AFTER * 2 {
+            scanner.next(None, Default::default()).await?;
+        }
+        assert_eq!(scanner.window_size(), 8);
+        for _ in 0..GROW_AFTER {
+            scanner.next(None, Default::default()).await?;
+        }
+        assert_eq!(scanner.window_size(), 10);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn a_head_read_before_the_scan_is_reused() -> anyhow::Result<()> {
+        let mock = MockChain::default();
+        *mock.0.lock().unwrap() = Chain::with_blocks(30, 0);
+
+        let mut scanner = scanner(&mock, 0).with_offset(2);
+        assert_eq!(scanner.lag(), None);
+
+        assert_eq!(scanner.head().await?, 28);
+        scanner.next(None, Default::default()).await?;
+        assert_eq!(mock.0.lock().unwrap().head_reads, 1);
+        assert_eq!(scanner.chain_head(), Some(30));
+        assert_eq!(scanner.lag(), Some(19));
+
+        // Without a fresh head the scan reads its own
+        mock.0.lock().unwrap().push_block(31, 0);
+        scanner.next(None, Default::default()).await?;
+        assert_eq!(mock.0.lock().unwrap().head_reads, 2);
+        assert_eq!(scanner.chain_head(), Some(31));
+        assert_eq!(scanner.lag(), Some(10));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn logs_are_held_back_until_confirmed() -> anyhow::Result<()> {
+        let mock = MockChain::default();
+        {
+            let mut chain = mock.0.lock().unwrap();
+            *chain = Chain::with_blocks(9, 0);
+            chain.push_log(3, 1);
+            chain.push_log(8, 2);
+        }
+
+        let mut scanner = scanner(&mock, 0).with_confirmations(3);
+
+        let first = scanner.next(None, Default::default()).await?;
+        assert_eq!(tags(&first), vec![(1, false)]);
+        assert_eq!(scanner.current_block(), 10);
+        assert_eq!(scanner.resume_block(), 8);
+
+        // Nothing new to scan, block 8 is still 2 deep
+        mock.0.lock().unwrap().push_block(10, 0);
+        scanner = scanner.with_offset(1);
+        assert!(scanner
+            .next(None, Default::default())
+            .await?
+            .logs
+            .is_empty());
+
+        mock.0.lock().unwrap().push_block(11, 0);
+        let third = scanner.next(None, Default::default()).await?;
+        assert_eq!(tags(&third), vec![(2, false)]);
+        assert_eq!(scanner.resume_block(), 11);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn held_back_logs_of_replaced_blocks_are_dropped() -> anyhow::Result<()> {
+        let mock = MockChain::default();
+        {
+            let mut chain = mock.0.lock().unwrap();
+            *chain = Chain::with_blocks(9, 0);
+            chain.push_log(7, 1);
+            chain.push_log(8, 2);
+        }
+
+        let mut scanner = scanner(&mock, 0).with_confirmations(4);
+        assert!(scanner
+            .next(None, Default::default())
+            .await?
+            .logs
+            .is_empty());
+
+        // Only block 7 is replaced, after the scanner has moved past it
+        {
+            let mut chain = mock.0.lock().unwrap();
+            chain.push_block(7, 1);
+            for block in 10..=12 {
+                chain.push_block(block, 0);
+            }
+        }
+
+        let result = scanner.next(None, Default::default()).await?;
+        assert_eq!(result.reorg, None);
+        assert_eq!(tags(&result), vec![(2, false)]);
+        assert_eq!(scanner.resume_block(), 13);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn a_reorg_drops_held_back_logs_in_the_rewound_range() -> anyhow::Result<()> {
+        let mock = MockChain::default();
+        {
+            let mut chain = mock.0.lock().unwrap();
+            *chain = Chain::with_blocks(9, 0);
+            chain.push_log(3, 1);
+            chain.push_log(7, 2);
+        }
+
+        let mut scanner = scanner(&mock, 0).with_confirmations(10);
+        assert!(scanner
+            .next(None, Default::default())
+            .await?
+            .logs
+            .is_empty());
+
+        // The log moves from block 7 to 8, and is only returned once
+        {
+            let mut chain = mock.0.lock().unwrap();
+            chain.reorg(7, 1);
+            chain.push_log(8, 3);
+            for block in 10..=18 {
+                chain.push_block(block, 1);
+            }
+        }
+
+        let result = scanner.next(None, Default::default()).await?;
+        assert_eq!(result.reorg.map(|reorg| reorg.rewound_to), Some(5));
+        assert_eq!(tags(&result), vec![(1, false), (3, true)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn range_errors_are_told_apart_from_failures() {
+        for rejected in [
+            "(code: -32005, message: query returned more than 10000 results, data: None)",
+            "HTTP status client error (413 Payload Too Large) for url (https://rpc.scroll.io/)",
+            "(code: -32005, message: too many results, data: None)",
+            "eth_getLogs block range too large, range: 10000, max: 3000",
+            "(code: -32602, message: Log response size exceeded. You can make eth_getLogs \
+             requests with up to a 2K block range and no limit on the response size, data: None)",
+            "(code: -32614, message: eth_getLogs is limited to a 10,000 range, data: None)",
+            "(code: -32600, message: block range is too wide, data: None)",
+            "(code: -32000, message: exceed maximum block range: 5000, data: None)",
+            "(code: -32000, message: query exceeds max block range 100000, data: None)",
+        ] {
+            assert!(is_range_too_large(&rejected), "{rejected}");
+        }
+
+        assert!(!is_range_too_large(&"header not found"));
+        assert!(!is_range_too_large(&"connection refused"));
+        // Invalid ranges aren't fixed by a smaller window
+        assert!(!is_range_too_large(&"invalid block range params"));
+        assert!(!is_range_too_large(
+            &"(code: -32000, message: fromBlock 20 is after toBlock 10 in block range, data: None)"
+        ));
+    }
+}
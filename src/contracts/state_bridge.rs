@@ -0,0 +1,510 @@
+//! What the processor needs from a WorldID state bridge, whichever chain it
+//! bridges to.
+//!
+//! Every bridge pairs the WorldID contract on mainnet with a copy on a target
+//! chain: `propagateRoot` on the mainnet state bridge sends the latest root,
+//! and the target's WorldID records it in `rootHistory`.
+
+use std::{future::Future, sync::Arc, time::Duration};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use ethers::{
+    abi::{Detokenize, Token},
+    contract::{ContractCall, EthEvent, Multicall, MULTICALL_ADDRESS},
+    providers::Middleware,
+    types::{Address, BlockNumber, Bytes, Filter, H256, U256},
+};
+use futures::future::{self, Either};
+use thiserror::Error;
+use tokio::time;
+use tracing::{debug, error, warn};
+
+use super::{
+    abi::{ScrollWorldId, TreeChangedFilter, WorldId},
+    RootLookup, RootStatus, SimulationError,
+};
+use crate::{
+    ethereum::{Ethereum, ReadProvider},
+    exit,
+    utils::TransactionId,
+};
+
+/// The contracts a bridge is made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BridgeAddresses {
+    /// The state bridge on mainnet
+    pub bridge:          Address,
+    /// WorldID on the target chain
+    pub target_world_id: Address,
+    /// WorldID on mainnet
+    pub world_id:        Address,
+}
+
+/// Mainnet blocks are at least a slot apart.
+pub(crate) const SLOT: Duration = Duration::from_secs(12);
+
+/// A World ID call that didn't answer within `call_timeout` under
+/// `[root_check]`.
+#[derive(Debug, Error)]
+#[error("{call} timed out after {timeout:?}")]
+pub struct CallTimeout {
+    pub call:    &'static str,
+    pub timeout: Duration,
+}
+
+/// A deployed state bridge and the two WorldIDs it bridges between.
+#[async_trait]
+pub trait StateBridge: Send + Sync + 'static {
+    /// The target chain, for logs.
+    fn chain(&self) -> &'static str;
+
+    fn addresses(&self) -> BridgeAddresses;
+
+    /// Runs `propagateRoot` as an `eth_call` from the relayer address against
+    /// the latest state, without sending anything.
+    async fn simulate_propagate_root(&self, value_in_wei: U256) -> Result<(), SimulationError>;
+
+    async fn propagate_root(&self, value_in_wei: U256) -> anyhow::Result<TransactionId>;
+
+    /// The value to send along with `propagateRoot`, the 0.1 ether it
+    /// always sent for bridges without a messenger fee.
+    async fn propagation_value(&self) -> U256 {
+        U256::exp10(17)
+    }
+
+    async fn get_world_id_latest_root(&self) -> anyhow::Result<U256>;
+
+    /// Whether WorldID on mainnet knows `root`.
+    async fn is_root_on_mainnet(&self, root: U256) -> anyhow::Result<bool>;
+
+    /// How long ago `root` became the latest root on mainnet, `None` if that
+    /// was more than `within` ago.
+    async fn get_world_id_root_age(
+        &self,
+        root: U256,
+        within: Duration,
+    ) -> anyhow::Result<Option<Duration>>;
+
+    /// How long WorldID on mainnet keeps a superseded root valid.
+    async fn get_root_history_expiry(&self) -> anyhow::Result<Duration>;
+
+    /// The target's latest root, zero until the first propagation lands.
+    async fn get_target_latest_root(&self) -> anyhow::Result<U256>;
+
+    /// When the target received `root`, zero if it hasn't.
+    async fn get_target_root_timestamp(&self, root: U256) -> anyhow::Result<u128>;
+
+    /// Asks mainnet and the target at once, without waiting for the target
+    /// once mainnet turns out not to know the root.
+    async fn root_status(&self, root: U256) -> anyhow::Result<RootStatus> {
+        // root_history only returns superseded roots, so we must also check
+        // the latest root
+        let on_mainnet = self.is_root_on_mainnet(root);
+        let target = future::try_join(
+            self.get_target_latest_root(),
+            self.get_target_root_timestamp(root),
+        );
+        futures::pin_mut!(on_mainnet, target);
+
+        let (latest_root, root_timestamp) = match future::select(on_mainnet, target).await {
+            Either::Left((on_mainnet, target)) => {
+                if !on_mainnet? {
+                    return Ok(RootStatus::NotOnMainnet);
+                }
+                target.await?
+            }
+            Either::Right((target, on_mainnet)) => {
+                let target = target?;
+                if !on_mainnet.await? {
+                    return Ok(RootStatus::NotOnMainnet);
+                }
+                target
+            }
+        };
+
+        Ok(RootStatus::on_destination(
+            root,
+            latest_root,
+            root_timestamp,
+        ))
+    }
+
+    /// Whether `root` is known on the target, as the latest root or in its
+    /// history.
+    async fn is_root_mined(&self, root: U256) -> anyhow::Result<bool> {
+        Ok(self.root_status(root).await?.is_mined())
+    }
+
+    /// Like [`Self::root_status`], but always asks mainnet and reads every
+    /// contract concurrently.
+    async fn lookup_root(&self, root: U256) -> anyhow::Result<RootLookup> {
+        let (on_mainnet, latest_root, root_timestamp) = futures::try_join!(
+            self.is_root_on_mainnet(root),
+            self.get_target_latest_root(),
+            self.get_target_root_timestamp(root),
+        )?;
+
+        Ok(RootLookup::new(
+            root,
+            on_mainnet,
+            latest_root,
+            root_timestamp,
+        ))
+    }
+
+    /// [`Self::lookup_root`] for every root in `roots`, in the same order.
+    /// Bridges that can batch their calls override it.
+    async fn lookup_roots(&self, roots: &[U256]) -> anyhow::Result<Vec<RootLookup>> {
+        future::try_join_all(roots.iter().map(|&root| self.lookup_root(root))).await
+    }
+
+    /// [`Self::is_root_mined`] for every root in `roots`, in the same order.
+    async fn are_roots_mined(&self, roots: &[U256]) -> anyhow::Result<Vec<bool>> {
+        let lookups = self.lookup_roots(roots).await?;
+        Ok(lookups
+            .iter()
+            .map(|lookup| lookup.status.is_mined())
+            .collect())
+    }
+}
+
+/// WorldID on mainnet and its copy on the target chain. The target contracts
+/// share `ScrollWorldId`'s interface on every chain. Every call fails with
+/// [`CallTimeout`] after `call_timeout`.
+#[derive(Debug)]
+pub struct WorldIdPair {
+    ethereum:            Ethereum,
+    call_timeout:        Duration,
+    pub world_id:        WorldId<ReadProvider>,
+    pub target_world_id: ScrollWorldId<ReadProvider>,
+}
+
+impl WorldIdPair {
+    /// # Errors
+    ///
+    /// Will return `Err` if either contract has no code deployed, unless
+    /// `allow_missing_code` is set.
+    pub async fn connect(
+        ethereum: Ethereum,
+        world_id_address: Address,
+        target_world_id_address: Address,
+        call_timeout: Duration,
+        allow_missing_code: bool,
+    ) -> anyhow::Result<Self> {
+        require_code(
+            ethereum.l1_provider(),
+            world_id_address,
+            "world id",
+            allow_missing_code,
+        )
+        .await?;
+        require_code(
+            ethereum.l2_provider(),
+            target_world_id_address,
+            "target world id",
+            allow_missing_code,
+        )
+        .await?;
+
+        Ok(Self {
+            world_id: WorldId::new(world_id_address, ethereum.l1_provider().clone()),
+            target_world_id: ScrollWorldId::new(
+                target_world_id_address,
+                ethereum.l2_provider().clone(),
+            ),
+            ethereum,
+            call_timeout,
+        })
+    }
+
+    async fn timed<T, E>(
+        &self,
+        call: &'static str,
+        future: impl Future<Output = Result<T, E>>,
+    ) -> anyhow::Result<T>
+    where
+        E: Into<anyhow::Error>,
+    {
+        match time::timeout(self.call_timeout, future).await {
+            Ok(result) => result.map_err(Into::into),
+            Err(_) => Err(CallTimeout {
+                call,
+                timeout: self.call_timeout,
+            }
+            .into()),
+        }
+    }
+
+    pub async fn latest_root(&self) -> anyhow::Result<U256> {
+        self.timed("latestRoot", self.world_id.latest_root().call())
+            .await
+    }
+
+    pub async fn root_history_expiry(&self) -> anyhow::Result<Duration> {
+        let expiry = self
+            .timed(
+                "getRootHistoryExpiry",
+                self.world_id.get_root_history_expiry().call(),
+            )
+            .await?;
+
+        Ok(Duration::from_secs(expiry.try_into().unwrap_or(u64::MAX)))
+    }
+
+    pub async fn is_root_on_mainnet(&self, root: U256) -> anyhow::Result<bool> {
+        let query_root = self.world_id.query_root(root);
+        let result = self
+            .timed("queryRoot", async {
+                Ok::<_, anyhow::Error>(query_root.call().await)
+            })
+            .await?;
+
+        match result {
+            Ok((root_on_mainnet, ..)) => Ok(!root_on_mainnet.is_zero()),
+            // The identity manager reverts on roots it doesn't know
+            Err(err) if err.as_revert().is_some() => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Finds the `TreeChanged` that produced `root` in the blocks of the last
+    /// `within`, and how old its block is by chain time.
+    pub async fn root_age(&self, root: U256, within: Duration) -> anyhow::Result<Option<Duration>> {
+        let provider = self.ethereum.l1_provider();
+        let block = |number: BlockNumber| async move {
+            self.timed("eth_getBlockByNumber", provider.get_block(number))
+                .await?
+                .with_context(|| format!("block {number:?} doesn't exist"))
+        };
+
+        let head = block(BlockNumber::Latest).await?;
+        let head_number = head.number.context("latest block has no number")?.as_u64();
+        let cutoff = head.timestamp.saturating_sub(within.as_secs().into());
+
+        // Widened until the window reaches back far enough, for chains with
+        // shorter slots
+        let mut lookback = within.as_secs().div_ceil(SLOT.as_secs()).max(1);
+        let from_block = loop {
+            let from_block = head_number.saturating_sub(lookback);
+            if from_block == 0 || block(from_block.into()).await?.timestamp <= cutoff {
+                break from_block;
+            }
+            lookback *= 2;
+        };
+
+        let mut post_root = [0; 32];
+        root.to_big_endian(&mut post_root);
+        let filter = Filter::new()
+            .address(self.world_id.address())
+            .topic0(TreeChangedFilter::signature())
+            .topic3(H256(post_root))
+            .from_block(from_block)
+            .to_block(head_number);
+
+        let logs = self
+            .timed("eth_getLogs", provider.get_logs(&filter))
+            .await?;
+        let Some(inserted_at) = logs.iter().filter_map(|log| log.block_number).max() else {
+            return Ok(None);
+        };
+
+        let inserted = block(inserted_at.into()).await?;
+        let age = head.timestamp.saturating_sub(inserted.timestamp).as_u64();
+        Ok(Some(Duration::from_secs(age)))
+    }
+
+    pub async fn target_latest_root(&self) -> anyhow::Result<U256> {
+        self.timed("latestRoot", self.target_world_id.latest_root().call())
+            .await
+    }
+
+    pub async fn target_root_timestamp(&self, root: U256) -> anyhow::Result<u128> {
+        self.timed(
+            "rootHistory",
+            self.target_world_id.root_history(root).call(),
+        )
+        .await
+    }
+
+    /// Looks `roots` up with one Multicall3 call per chain: `queryRoot` for
+    /// each on mainnet, `latestRoot` and `rootHistory` for each on the
+    /// target. Entries that fail inside the multicall are asked again one by
+    /// one, and so is every entry of a multicall that fails as a whole, e.g.
+    /// on a chain without Multicall3.
+    pub async fn lookup_roots(&self, roots: &[U256]) -> anyhow::Result<Vec<RootLookup>> {
+        if roots.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let (on_mainnet, (latest_root, root_timestamps)) = futures::try_join!(
+            self.roots_on_mainnet(roots),
+            self.target_root_timestamps(roots),
+        )?;
+
+        Ok(roots
+            .iter()
+            .zip(on_mainnet.into_iter().zip(root_timestamps))
+            .map(|(&root, (on_mainnet, root_timestamp))| {
+                RootLookup::new(root, on_mainnet, latest_root, root_timestamp)
+            })
+            .collect())
+    }
+
+    async fn roots_on_mainnet(&self, roots: &[U256]) -> anyhow::Result<Vec<bool>> {
+        let calls = roots.iter().map(|&root| self.world_id.query_root(root));
+        let results = self.multicall(self.ethereum.l1_provider(), calls).await;
+
+        let mut on_mainnet = Vec::with_capacity(roots.len());
+        for (&root, result) in roots.iter().zip(results) {
+            // A root mainnet doesn't know reverts too, the single call tells
+            // that apart from a failure
+            let known = match result.as_ref().and_then(first_uint) {
+                Some(root_on_mainnet) => !root_on_mainnet.is_zero(),
+                None => self.is_root_on_mainnet(root).await?,
+            };
+            on_mainnet.push(known);
+        }
+
+        Ok(on_mainnet)
+    }
+
+    /// The target's latest root, and when it received each of `roots`.
+    async fn target_root_timestamps(&self, roots: &[U256]) -> anyhow::Result<(U256, Vec<u128>)> {
+        let mut multicall = self.new_multicall(self.ethereum.l2_provider()).await;
+        if let Some(multicall) = &mut multicall {
+            multicall.add_call(self.target_world_id.latest_root(), true);
+            for &root in roots {
+                multicall.add_call(self.target_world_id.root_history(root), true);
+            }
+        }
+        let mut results = self
+            .call_multicall(multicall, roots.len() + 1)
+            .await
+            .into_iter();
+
+        let latest_root = match results.next().flatten().as_ref().and_then(first_uint) {
+            Some(latest_root) => latest_root,
+            None => self.target_latest_root().await?,
+        };
+
+        let mut root_timestamps = Vec::with_capacity(roots.len());
+        for (&root, result) in roots.iter().zip(results) {
+            let root_timestamp = match result.as_ref().and_then(first_uint) {
+                Some(root_timestamp) => root_timestamp.low_u128(),
+                None => self.target_root_timestamp(root).await?,
+            };
+            root_timestamps.push(root_timestamp);
+        }
+
+        Ok((latest_root, root_timestamps))
+    }
+
+    /// Runs `calls` in one multicall, `None` for every entry that failed.
+    async fn multicall<D>(
+        &self,
+        provider: &Arc<ReadProvider>,
+        calls: impl ExactSizeIterator<Item = ContractCall<ReadProvider, D>>,
+    ) -> Vec<Option<Token>>
+    where
+        D: Detokenize,
+    {
+        let len = calls.len();
+        let mut multicall = self.new_multicall(provider).await;
+        if let Some(multicall) = &mut multicall {
+            for call in calls {
+                multicall.add_call(call, true);
+            }
+        }
+
+        self.call_multicall(multicall, len).await
+    }
+
+    async fn new_multicall(&self, provider: &Arc<ReadProvider>) -> Option<Multicall<ReadProvider>> {
+        match Multicall::new(provider.clone(), Some(MULTICALL_ADDRESS)).await {
+            Ok(multicall) => Some(multicall),
+            Err(err) => {
+                warn!(%err, "Couldn't set up a multicall, calling one by one");
+                None
+            }
+        }
+    }
+
+    async fn call_multicall(
+        &self,
+        multicall: Option<Multicall<ReadProvider>>,
+        len: usize,
+    ) -> Vec<Option<Token>> {
+        let results = match multicall {
+            Some(multicall) => self.timed("multicall", multicall.call_raw()).await,
+            None => return vec![None; len],
+        };
+
+        match results {
+            Ok(results) => results
+                .into_iter()
+                .map(|result| {
+                    result
+                        .map_err(|revert: Bytes| {
+                            debug!(%revert, "A multicall entry failed, calling it alone");
+                        })
+                        .ok()
+                })
+                .collect(),
+            Err(err) => {
+                warn!(?err, calls = len, "Multicall failed, calling one by one");
+                vec![None; len]
+            }
+        }
+    }
+}
+
+/// The first value a call returned, as a uint. `queryRoot` returns the root
+/// first.
+fn first_uint(token: &Token) -> Option<U256> {
+    match token {
+        Token::Uint(value) => Some(*value),
+        Token::Tuple(tokens) => tokens.first().and_then(|token| token.clone().into_uint()),
+        _ => None,
+    }
+}
+
+/// Fails startup when nothing is deployed at `address`, naming the contract.
+/// With `allow_missing` it only warns.
+///
+/// # Errors
+///
+/// Will return `Err` if the code can't be fetched, or is empty and
+/// `allow_missing` is unset.
+pub async fn require_code(
+    provider: &ReadProvider,
+    address: Address,
+    contract: &str,
+    allow_missing: bool,
+) -> anyhow::Result<()> {
+    let code = provider.get_code(address, None).await?;
+    if !code.as_ref().is_empty() {
+        return Ok(());
+    }
+
+    if allow_missing {
+        warn!(
+            ?address,
+            contract,
+            "No contract code is deployed at the address, continuing as allow_missing_code is set."
+        );
+        return Ok(());
+    }
+
+    error!(
+        ?address,
+        contract, "No contract code is deployed at the address."
+    );
+    // The address or the provider is misconfigured, restarting won't help
+    Err(exit::Error::Config(anyhow::anyhow!(
+        "no {contract} contract is deployed at {address:?} on chain {}, set \
+         network.allow_missing_code to start anyway",
+        provider.chain_id
+    ))
+    .into())
+}
@@ -4,27 +4,36 @@
     clippy::cast_possible_wrap
 )]
 
-use std::cmp::Ordering;
-use std::ops::Deref;
+use std::{cmp::Ordering, env, ops::Deref};
 
 use anyhow::{anyhow, Context, Error as ErrReport};
-use sqlx::migrate::{Migrate, MigrateDatabase, Migrator};
-use sqlx::pool::PoolOptions;
-use sqlx::{Executor, Pool, Postgres, Row};
+use sqlx::{
+    migrate::{Migrate, MigrateDatabase, Migrator},
+    pool::PoolOptions,
+    Executor, Pool, Postgres, Row,
+};
 use thiserror::Error;
+use tokio::time;
 use tracing::{error, info, instrument, warn};
 
-use crate::config::DatabaseConfig;
-use crate::database::query::DatabaseQuery;
+use crate::{
+    config::DatabaseConfig, database::query::DatabaseQuery, exit, processor::status::BridgeStatus,
+    utils::secret::SecretUrl,
+};
 // use crate::identity_tree::Hash;
 
 pub mod query;
+pub mod store;
 pub mod transaction;
 pub mod types;
 
 // Statically link in migration files
 static MIGRATOR: Migrator = sqlx::migrate!("schemas/database");
 
+/// Migrations that older deployments applied by hand, with the table each one
+/// creates.
+const BASELINE: [(i64, &str); 2] = [(1, "service_status"), (2, "transactions")];
+
 pub struct Database {
     pub pool: Pool<Postgres>,
 }
@@ -42,27 +51,8 @@ impl<'a, T> DatabaseQuery<'a> for T where T: Executor<'a, Database = Postgres> {
 impl Database {
     #[instrument(skip_all)]
     pub async fn new(config: &DatabaseConfig) -> Result<Self, ErrReport> {
-        info!(url = %&config.database, "Connecting to database");
-
-        // Create database if requested and does not exist
-        if config.migrate && !Postgres::database_exists(config.database.expose()).await? {
-            warn!(url = %&config.database, "Database does not exist, creating database");
-            Postgres::create_database(config.database.expose()).await?;
-        }
-
-        // Create a connection pool
-        let pool = PoolOptions::<Postgres>::new()
-            .max_connections(config.max_connections)
-            .after_connect(|conn, _| {
-                Box::pin(async move {
-                    conn.execute("SET DEFAULT_TRANSACTION_ISOLATION TO 'SERIALIZABLE'")
-                        .await?;
-                    Ok(())
-                })
-            })
-            .connect(config.database.expose())
-            .await
-            .context("error connecting to database")?;
+        let url = connection_url(config)?;
+        let pool = Self::connect(config, &url).await?;
 
         let version = pool
             .fetch_one("SELECT version()")
@@ -79,6 +69,7 @@ impl Database {
             .version;
 
         if config.migrate {
+            Self::baseline(&pool).await?;
             info!(url = %&config.database, "Running migrations");
             MIGRATOR.run(&pool).await?;
         }
@@ -86,6 +77,17 @@ impl Database {
         // Validate database schema version
         let mut conn = pool.acquire().await?;
 
+        if !table_exists(&mut *conn, "_sqlx_migrations").await? {
+            error!(
+                url = %&config.database,
+                expected = latest,
+                "Database has no migrations applied."
+            );
+            return Err(anyhow!(
+                "Database has no migrations applied, set database.migrate = true to apply them"
+            ));
+        }
+
         if conn.dirty_version().await?.is_some() {
             error!(
                 url = %&config.database,
@@ -100,8 +102,7 @@ impl Database {
             .list_applied_migrations()
             .await?
             .last()
-            .expect("Missing migrations")
-            .version;
+            .map_or(0, |migration| migration.version);
 
         match version.cmp(&latest) {
             Ordering::Less => {
@@ -109,9 +110,10 @@ impl Database {
                     url = %&config.database,
                     version,
                     expected = latest,
-                    "Database is not up to date, try rerunning with --database-migrate",);
+                    "Database is not up to date, set database.migrate = true to migrate it",);
                 return Err(anyhow!(
-                    "Database is not up to date, try rerunning with --database-migrate"
+                    "Database is at version {version} but this binary expects {latest}, set \
+                     database.migrate = true to migrate it"
                 ));
             }
             Ordering::Greater => {
@@ -121,7 +123,8 @@ impl Database {
                     latest,
                     "Database version is newer than this version of the software, please update.",);
                 return Err(anyhow!(
-                    "Database version is newer than this version of the software, please update."
+                    "Database is at version {version}, newer than the {latest} this binary knows \
+                     about, please update."
                 ));
             }
             Ordering::Equal => {
@@ -136,10 +139,972 @@ impl Database {
 
         Ok(Self { pool })
     }
+
+    /// Connects as [`Self::try_connect`] does, retrying `connect_attempts`
+    /// times so a database that comes up after the service doesn't fail it.
+    async fn connect(config: &DatabaseConfig, url: &SecretUrl) -> anyhow::Result<Pool<Postgres>> {
+        let mut backoff = config.connect_backoff;
+        let mut attempt = 1;
+
+        loop {
+            match Self::try_connect(config, url).await {
+                Ok(pool) => return Ok(pool),
+                Err(err) if attempt < config.connect_attempts => {
+                    warn!(
+                        %url,
+                        error = format!("{err:#}"),
+                        attempt,
+                        ?backoff,
+                        "Failed to connect to database, retrying"
+                    );
+                    time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                Err(err) => {
+                    return Err(
+                        err.context(format!("database unreachable after {attempt} attempts"))
+                    )
+                }
+            }
+        }
+    }
+
+    /// Creates the database if `migrate` is set and it doesn't exist, then
+    /// opens the pool. Once open, the pool replaces connections that are lost
+    /// on its own.
+    async fn try_connect(
+        config: &DatabaseConfig,
+        url: &SecretUrl,
+    ) -> anyhow::Result<Pool<Postgres>> {
+        info!(%url, "Connecting to database");
+
+        // Create database if requested and does not exist
+        if config.migrate && !Postgres::database_exists(url.expose()).await? {
+            warn!(%url, "Database does not exist, creating database");
+            Postgres::create_database(url.expose()).await?;
+        }
+
+        let statement_timeout = format!(
+            "SET statement_timeout = {}",
+            config.statement_timeout.as_millis()
+        );
+
+        // Create a connection pool
+        PoolOptions::<Postgres>::new()
+            .max_connections(config.max_connections)
+            .min_connections(config.min_connections)
+            .acquire_timeout(config.acquire_timeout)
+            .after_connect(move |conn, _| {
+                let statement_timeout = statement_timeout.clone();
+                Box::pin(async move {
+                    conn.execute("SET DEFAULT_TRANSACTION_ISOLATION TO 'SERIALIZABLE'")
+                        .await?;
+                    conn.execute(statement_timeout.as_str()).await?;
+                    Ok(())
+                })
+            })
+            .connect(url.expose())
+            .await
+            .context("error connecting to database")
+    }
+
+    /// Exports how many of the pool's connections are idle and in use.
+    pub fn record_pool_metrics(&self) {
+        crate::metrics::record_database_connections(self.pool.size(), self.pool.num_idle());
+    }
+
+    /// Records the [`BASELINE`] migrations as applied on a database whose
+    /// tables were created by hand, so migrating it doesn't fail on tables
+    /// that already exist. Stops at the first table that is missing, which
+    /// the migrations then create as usual.
+    async fn baseline(pool: &Pool<Postgres>) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+
+        if table_exists(&mut *conn, "_sqlx_migrations").await? {
+            return Ok(());
+        }
+
+        let mut applied = Vec::new();
+        for (version, table) in BASELINE {
+            if !table_exists(&mut *conn, table).await? {
+                break;
+            }
+            applied.push(version);
+        }
+
+        if applied.is_empty() {
+            return Ok(());
+        }
+
+        conn.ensure_migrations_table().await?;
+
+        for version in applied {
+            let migration = MIGRATOR
+                .migrations
+                .iter()
+                .find(|migration| migration.version == version)
+                .expect("baseline migrations are embedded");
+
+            warn!(
+                version,
+                description = %migration.description,
+                "Schema was created by hand, recording the migration as applied"
+            );
+
+            sqlx::query(
+                r#"
+                INSERT INTO _sqlx_migrations
+                    (version, description, success, checksum, execution_time)
+                VALUES ($1, $2, TRUE, $3, 0)
+                "#,
+            )
+            .bind(migration.version)
+            .bind(&*migration.description)
+            .bind(&*migration.checksum)
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `database`, with the password from `password_env` when set.
+fn connection_url(config: &DatabaseConfig) -> anyhow::Result<SecretUrl> {
+    let Some(var) = &config.password_env else {
+        return Ok(config.database.clone());
+    };
+
+    let password = env::var(var).map_err(|err| {
+        exit::Error::Config(anyhow!(
+            "database.password_env names {var}, which is unreadable: {err}"
+        ))
+    })?;
+
+    config.database.with_password(&password).ok_or_else(|| {
+        exit::Error::Config(anyhow!("database.database can't carry a password")).into()
+    })
+}
+
+async fn table_exists<'a, E>(executor: E, table: &str) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    Ok(sqlx::query(
+        r#"
+        SELECT EXISTS (
+            SELECT FROM information_schema.tables
+            WHERE table_schema = current_schema() AND table_name = $1
+        )
+        "#,
+    )
+    .bind(table)
+    .fetch_one(executor)
+    .await?
+    .get::<bool, _>(0))
 }
 
 #[derive(Debug, Error)]
 pub enum Error {
-    #[error("database error: {0}")]
-    InternalError(#[from] sqlx::Error),
+    /// `service_status` has no row, the server was never initialized
+    #[error("service status row is missing, the server isn't initialized")]
+    StatusRowMissing,
+    /// `service_status` holds a status this service doesn't know, e.g. after
+    /// a manual edit
+    #[error("service status {status:?} isn't a known status")]
+    UnknownStatus { status: String },
+    #[error("invalid status transition from {from:?} to {to:?}")]
+    InvalidStatusTransition {
+        from: BridgeStatus,
+        to:   BridgeStatus,
+    },
+    #[error("failed to {context}")]
+    Query {
+        context: &'static str,
+        #[source]
+        source:  sqlx::Error,
+    },
+}
+
+/// Errors from beginning or committing a transaction, which no single query
+/// caused.
+impl From<sqlx::Error> for Error {
+    fn from(source: sqlx::Error) -> Self {
+        Self::Query {
+            context: "run a database transaction",
+            source,
+        }
+    }
+}
+
+impl Error {
+    /// Whether running the same operation again may succeed. Lost
+    /// connections, pool timeouts, serialization failures, deadlocks and
+    /// statement timeouts are retryable, a missing row, an unknown status, an
+    /// invalid transition or a bad query are not.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::StatusRowMissing
+            | Self::UnknownStatus { .. }
+            | Self::InvalidStatusTransition { .. } => false,
+            Self::Query { source, .. } => match source {
+                sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::WorkerCrashed => true,
+                sqlx::Error::Database(err) => err.code().is_some_and(|code| {
+                    // serialization_failure, deadlock_detected, query_canceled
+                    // by statement_timeout and the connection_exception class
+                    code == "40001" || code == "40P01" || code == "57014" || code.starts_with("08")
+                }),
+                _ => false,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, str::FromStr, time::Duration};
+
+    use ethers::types::{H256, U256};
+    use postgres_docker_utils::DockerContainer;
+    use sqlx::Connection;
+    use testcontainers::clients::Cli;
+
+    use super::*;
+    use crate::{
+        config::default,
+        database::types::{ObservedRoot, PendingPropagation, RootSource, Spend, TxStatus},
+        ethereum::GasCost,
+        utils::{secret::SecretUrl, TransactionId},
+    };
+
+    fn config(db_container: &DockerContainer, migrate: bool) -> anyhow::Result<DatabaseConfig> {
+        let url = format!(
+            "postgres://postgres:postgres@{}/database",
+            db_container.address()
+        );
+
+        Ok(DatabaseConfig {
+            database: SecretUrl::from_str(&url)?,
+            password_env: None,
+            migrate,
+            max_connections: 1,
+            min_connections: 0,
+            acquire_timeout: default::acquire_timeout(),
+            statement_timeout: default::statement_timeout(),
+            connect_attempts: 1,
+            connect_backoff: default::connect_backoff(),
+        })
+    }
+
+    async fn setup_db(docker: &Cli) -> anyhow::Result<(Database, DockerContainer)> {
+        let db_container = postgres_docker_utils::setup(docker).await?;
+        let db = Database::new(&config(&db_container, true)?).await?;
+
+        Ok((db, db_container))
+    }
+
+    fn root(root: u64, block_number: u64, log_index: u64) -> ObservedRoot {
+        ObservedRoot {
+            root: U256::from(root),
+            block_number,
+            tx_hash: H256::from_low_u64_be(block_number),
+            log_index,
+        }
+    }
+
+    async fn counts(db: &Database) -> anyhow::Result<(u64, u64)> {
+        Ok((
+            db.count_roots(RootSource::Bridge).await?,
+            db.count_roots(RootSource::Scroll).await?,
+        ))
+    }
+
+    #[tokio::test]
+    async fn scanning_the_same_window_twice_is_idempotent() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, _db_container) = setup_db(&docker).await?;
+
+        let bridge = [root(1, 10, 0), root(2, 10, 1)];
+        let window: Vec<_> = bridge
+            .iter()
+            .map(|root| (RootSource::Bridge, *root))
+            .chain([(RootSource::Scroll, root(1, 11, 0))])
+            .collect();
+
+        db.insert_roots(&window).await?;
+        let once = counts(&db).await?;
+        assert_eq!(once, (2, 1));
+
+        // The same window again, then twice within one batch
+        db.insert_roots(&window).await?;
+        db.insert_roots(&[window.clone(), window.clone()].concat())
+            .await?;
+        assert_eq!(counts(&db).await?, once);
+
+        // A rescan after a reorg replaces the range rather than adding to it
+        db.record_roots(RootSource::Bridge, Some(10), &bridge)
+            .await?;
+        assert_eq!(counts(&db).await?, once);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn world_id_roots_are_kept_apart_from_the_bridge() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, _db_container) = setup_db(&docker).await?;
+
+        db.record_backfill(
+            RootSource::WorldId,
+            None,
+            &[root(1, 10, 0), root(2, 20, 0)],
+            100,
+        )
+        .await?;
+        db.insert_roots(&[(RootSource::Bridge, root(1, 12, 0))])
+            .await?;
+
+        assert_eq!(db.count_roots(RootSource::WorldId).await?, 2);
+        assert!(
+            db.is_root_observed(RootSource::WorldId, U256::from(2))
+                .await?
+        );
+        assert!(
+            !db.is_root_observed(RootSource::Bridge, U256::from(2))
+                .await?
+        );
+        assert_eq!(db.get_scan_progress(RootSource::WorldId).await?, Some(100));
+        assert_eq!(db.get_scan_progress(RootSource::Bridge).await?, None);
+
+        // Only propagated roots can be outstanding
+        assert_eq!(db.get_outstanding_roots(0).await?, [U256::from(1)]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pruning_keeps_the_latest_and_the_pending() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, _db_container) = setup_db(&docker).await?;
+
+        let pending = TransactionId::job("tx-pending")?;
+        db.insert_new_transaction(&pending, U256::from(2)).await?;
+        for (id, status) in [
+            ("tx-mined", TxStatus::Mined),
+            ("tx-failed", TxStatus::Failed),
+        ] {
+            let id = TransactionId::job(id)?;
+            db.insert_new_transaction(&id, U256::from(0xb)).await?;
+            db.resolve_transaction(&id, status, None, None).await?;
+        }
+
+        let window = [
+            (RootSource::Bridge, root(1, 10, 0)),
+            (RootSource::Bridge, root(2, 11, 0)),
+            (RootSource::Bridge, root(3, 12, 0)),
+            (RootSource::Scroll, root(1, 11, 0)),
+        ];
+        db.insert_roots(&window).await?;
+
+        // Everything is older than the cutoff, only one row of each table may go
+        let cutoff = chrono::Utc::now() + chrono::Duration::hours(1);
+        assert_eq!(db.prune_roots(cutoff, 1).await?, 1);
+        assert_eq!(db.prune_roots(cutoff, 1).await?, 0);
+        assert_eq!(counts(&db).await?, (2, 1));
+        assert!(
+            !db.is_root_observed(RootSource::Bridge, U256::from(1))
+                .await?
+        );
+
+        assert_eq!(db.prune_transactions(cutoff, 10).await?, 1);
+        assert_eq!(db.prune_transactions(cutoff, 10).await?, 0);
+        assert_eq!(
+            db.get_last_transaction_status().await?,
+            Some(TxStatus::Failed)
+        );
+        assert_eq!(db.get_last_transaction_id().await?, Some(pending));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn roots_follow_the_finalized_block_both_ways() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, _db_container) = setup_db(&docker).await?;
+
+        assert_eq!(db.is_latest_root_finalized().await?, None);
+        db.insert_roots(&[
+            (RootSource::Bridge, root(1, 5, 0)),
+            (RootSource::Scroll, root(1, 10, 0)),
+            (RootSource::Scroll, root(2, 20, 0)),
+        ])
+        .await?;
+        let roots = [U256::from(1), U256::from(2)];
+
+        assert_eq!(db.finalize_roots(15).await?, 1);
+        assert_eq!(db.finalize_roots(15).await?, 0);
+        assert_eq!(
+            db.get_finalized_roots(&roots).await?,
+            HashSet::from([U256::from(1)])
+        );
+        assert_eq!(db.is_latest_root_finalized().await?, Some(false));
+
+        assert_eq!(db.finalize_roots(20).await?, 1);
+        assert_eq!(db.is_latest_root_finalized().await?, Some(true));
+
+        // The finalized block went back, e.g. behind another node
+        assert_eq!(db.unfinalize_roots(15).await?, 1);
+        assert_eq!(
+            db.get_finalized_roots(&roots).await?,
+            HashSet::from([U256::from(1)])
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn roots_before_the_expiry_are_not_outstanding() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, _db_container) = setup_db(&docker).await?;
+
+        db.insert_roots(&[
+            (RootSource::Bridge, root(1, 10, 0)),
+            (RootSource::Scroll, root(1, 11, 0)),
+            // Never delivered, one long expired
+            (RootSource::Bridge, root(2, 20, 0)),
+            (RootSource::Bridge, root(3, 1_000, 0)),
+        ])
+        .await?;
+
+        assert_eq!(db.get_outstanding_roots(0).await?, [
+            U256::from(2),
+            U256::from(3)
+        ]);
+        assert_eq!(db.get_outstanding_roots(500).await?, [U256::from(3)]);
+        assert!(db.get_outstanding_roots(2_000).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn the_last_sync_comes_with_the_database_clock() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, _db_container) = setup_db(&docker).await?;
+
+        let uninitialized = db.get_last_sync_timestamp().await?;
+        assert_eq!(
+            (uninitialized.status, uninitialized.last_synced),
+            (None, None)
+        );
+
+        db.initialize_server().await?;
+        db.mark_status_as_synced().await?;
+        let last_sync = db.get_last_sync_timestamp().await?;
+        assert_eq!(last_sync.status, Some(BridgeStatus::Synced));
+        assert!(last_sync
+            .last_synced
+            .is_some_and(|last_synced| last_synced <= last_sync.now));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn status_changes_need_an_initialized_server() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, _db_container) = setup_db(&docker).await?;
+
+        let err = db.mark_status_as_pending().await.unwrap_err();
+        assert!(matches!(err, Error::StatusRowMissing), "{err:?}");
+        assert!(!err.is_retryable());
+
+        assert!(db.initialize_server().await?);
+        assert!(!db.initialize_server().await?);
+
+        db.mark_status_as_pending().await?;
+        let err = db.mark_status_as_pending().await.unwrap_err();
+        assert!(
+            matches!(err, Error::InvalidStatusTransition {
+                from: BridgeStatus::Pending,
+                to:   BridgeStatus::Pending,
+            }),
+            "{err:?}"
+        );
+        db.mark_status_as_synced().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn a_deleted_status_row_is_missing_until_initialized_again() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, _db_container) = setup_db(&docker).await?;
+        db.initialize_server().await?;
+        db.mark_status_as_synced().await?;
+
+        sqlx::query("DELETE FROM service_status")
+            .execute(&db.pool)
+            .await?;
+        assert_eq!(db.get_db_status().await?, None);
+        assert!(db.get_service_status().await?.is_none());
+        let err = db.mark_status_as_unsynced(None).await.unwrap_err();
+        assert!(matches!(err, Error::StatusRowMissing), "{err:?}");
+
+        assert!(db.initialize_server().await?);
+        assert_eq!(db.get_db_status().await?, Some(BridgeStatus::Unsynced));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn an_unknown_status_fails_to_read_until_overwritten() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, _db_container) = setup_db(&docker).await?;
+        db.initialize_server().await?;
+
+        let pool = &db.pool;
+        let set_status = move |status: &'static str| {
+            sqlx::query("UPDATE service_status SET status = $1 WHERE id = 1")
+                .bind(status)
+                .execute(pool)
+        };
+
+        set_status("syncd").await?;
+        let err = db.get_db_status().await.unwrap_err();
+        assert!(
+            matches!(&err, Error::UnknownStatus { status } if status == "syncd"),
+            "{err:?}"
+        );
+        assert!(!err.is_retryable());
+        let err = db.get_service_status().await.unwrap_err();
+        assert!(matches!(err, Error::UnknownStatus { .. }), "{err:?}");
+
+        // An unknown status counts as unsynced when it's overwritten
+        db.mark_status_as_unsynced(None).await?;
+        assert_eq!(db.get_db_status().await?, Some(BridgeStatus::Unsynced));
+
+        // Written by older versions
+        set_status("Synced").await?;
+        assert_eq!(db.get_db_status().await?, Some(BridgeStatus::Synced));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn concurrent_transitions_are_compared_and_set() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, db_container) = setup_db(&docker).await?;
+        let other = Database::new(&config(&db_container, false)?).await?;
+        db.initialize_server().await?;
+
+        // Both connections see unsynced, only one gets to mark it pending
+        let (first, second) =
+            tokio::join!(db.mark_status_as_pending(), other.mark_status_as_pending());
+        let results = [first, second];
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        let err = results.into_iter().find_map(Result::err).unwrap();
+        assert!(
+            matches!(err, Error::InvalidStatusTransition {
+                from: BridgeStatus::Pending,
+                to:   BridgeStatus::Pending,
+            }),
+            "{err:?}"
+        );
+
+        // Synced isn't recorded twice, so last_synced only advances when the
+        // bridge gets synced
+        db.mark_status_as_synced().await?;
+        let last_synced = db
+            .get_service_status()
+            .await?
+            .and_then(|status| status.last_synced);
+        let err = other.mark_status_as_synced().await.unwrap_err();
+        assert!(
+            matches!(err, Error::InvalidStatusTransition {
+                from: BridgeStatus::Synced,
+                to:   BridgeStatus::Synced,
+            }),
+            "{err:?}"
+        );
+        assert_eq!(
+            db.get_service_status()
+                .await?
+                .and_then(|status| status.last_synced),
+            last_synced
+        );
+
+        // Drifting apart is allowed from anywhere, and leaving synced is the
+        // last time it was synced
+        other.mark_status_as_unsynced(None).await?;
+        let left_synced = db
+            .get_service_status()
+            .await?
+            .and_then(|status| status.last_synced);
+        assert!(left_synced > last_synced);
+        other.mark_status_as_unsynced(None).await?;
+        assert_eq!(
+            db.get_service_status()
+                .await?
+                .and_then(|status| status.last_synced),
+            left_synced
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn only_transient_query_errors_are_retryable() {
+        let query = |source| Error::Query {
+            context: "count roots",
+            source,
+        };
+
+        assert!(query(sqlx::Error::PoolTimedOut).is_retryable());
+        assert!(!query(sqlx::Error::RowNotFound).is_retryable());
+        assert!(!Error::InvalidStatusTransition {
+            from: BridgeStatus::Pending,
+            to:   BridgeStatus::Pending,
+        }
+        .is_retryable());
+        assert_eq!(
+            query(sqlx::Error::RowNotFound).to_string(),
+            "failed to count roots"
+        );
+    }
+
+    #[tokio::test]
+    async fn hand_created_schema_is_baselined() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let db_container = postgres_docker_utils::setup(&docker).await?;
+        let config = config(&db_container, true)?;
+
+        // What older deployments ran by hand
+        let pool = Pool::<Postgres>::connect(config.database.expose()).await?;
+        pool.execute(include_str!("../../schemas/database/001_init.sql"))
+            .await?;
+        pool.execute(include_str!("../../schemas/database/002_transactions.sql"))
+            .await?;
+        pool.close().await;
+
+        let unmigrated = Database::new(&DatabaseConfig {
+            migrate: false,
+            ..config.clone()
+        })
+        .await;
+        let err = unmigrated.err().expect("an unmigrated database is refused");
+        assert!(err.to_string().contains("database.migrate"), "{err}");
+
+        let db = Database::new(&config).await?;
+        db.insert_new_transaction(&TransactionId::job("tx-1")?, U256::one())
+            .await?;
+        assert_eq!(db.count_roots(RootSource::Bridge).await?, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pending_propagation_carries_its_root() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, _db_container) = setup_db(&docker).await?;
+
+        assert_eq!(db.get_pending_propagation().await?, None);
+
+        let tx = TransactionId::job("tx-1")?;
+        let root = U256::from_str("0x0a1b2c")?;
+        db.insert_new_transaction(&tx, root).await?;
+        // Queued twice, recorded once
+        db.insert_new_transaction(&tx, root).await?;
+        assert_eq!(db.get_pending_transactions().await?, vec![tx.clone()]);
+
+        assert_eq!(
+            db.get_pending_propagation().await?,
+            Some(PendingPropagation {
+                transaction_id: tx.clone(),
+                root:           Some(root),
+            })
+        );
+
+        db.update_transaction(&tx, TxStatus::Mined).await?;
+        assert_eq!(db.get_pending_propagation().await?, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn spend_sums_recorded_costs_and_counts_unknown_ones() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, _db_container) = setup_db(&docker).await?;
+        let since = chrono::Utc::now() - chrono::Duration::days(1);
+
+        let [mined, reverted, unrecorded, dropped] = ["mined", "reverted", "unrecorded", "dropped"]
+            .map(|id| TransactionId::job(id).unwrap());
+        for tx in [&mined, &reverted, &unrecorded, &dropped] {
+            db.insert_new_transaction(tx, U256::one()).await?;
+        }
+        assert_eq!(db.get_last_mined_propagation_at().await?, None);
+
+        let cost = |gas_used: u64, estimated| GasCost {
+            gas_used: U256::from(gas_used),
+            gas_price: U256::from(10),
+            estimated,
+        };
+        db.resolve_transaction(&mined, TxStatus::Mined, None, None)
+            .await?;
+        assert!(db.set_transaction_cost(&mined, &cost(30, false)).await?);
+        // Seen again, e.g. by the maintenance task, it isn't counted twice
+        assert!(!db.set_transaction_cost(&mined, &cost(30, false)).await?);
+        db.resolve_transaction(
+            &reverted,
+            TxStatus::Failed,
+            Some(format!("{:?}", H256::zero())),
+            None,
+        )
+        .await?;
+        assert!(db.set_transaction_cost(&reverted, &cost(5, true)).await?);
+        db.resolve_transaction(&unrecorded, TxStatus::Mined, None, None)
+            .await?;
+        db.resolve_transaction(&dropped, TxStatus::Failed, None, None)
+            .await?;
+
+        assert_eq!(db.get_spend_since(since).await?, Spend {
+            total:     U256::from(350),
+            count:     2,
+            gas_used:  35,
+            estimated: 1,
+            unknown:   1,
+        });
+        assert_eq!(
+            db.get_spend_since(chrono::Utc::now()).await?,
+            Spend::default()
+        );
+        assert!(db.get_last_mined_propagation_at().await?.is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stored_invalid_transaction_id_fails_to_decode() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, _db_container) = setup_db(&docker).await?;
+
+        sqlx::query(
+            "INSERT INTO transactions (transaction_id, created_at) VALUES ('', CURRENT_TIMESTAMP)",
+        )
+        .execute(&db.pool)
+        .await?;
+
+        let err = db.get_pending_propagation().await.unwrap_err();
+        assert!(matches!(err, Error::Query { .. }));
+        assert!(db.get_pending_transactions().await.is_err());
+        assert!(db
+            .get_transactions_pending_since(chrono::Utc::now())
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn backfill_progress_is_recorded_with_its_roots() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, _db_container) = setup_db(&docker).await?;
+
+        assert_eq!(db.get_scan_progress(RootSource::Bridge).await?, None);
+
+        db.record_backfill(RootSource::Bridge, None, &[root(1, 10, 0)], 100)
+            .await?;
+        db.record_backfill(RootSource::Bridge, None, &[root(2, 150, 0)], 200)
+            .await?;
+
+        assert_eq!(db.get_scan_progress(RootSource::Bridge).await?, Some(200));
+        assert_eq!(db.get_scan_progress(RootSource::Scroll).await?, None);
+        assert_eq!(counts(&db).await?, (2, 0));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn only_one_session_holds_the_leadership_lock() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, db_container) = setup_db(&docker).await?;
+        let other = Database::new(&config(&db_container, false)?).await?;
+
+        let mut leader = db.pool.acquire().await?.detach();
+        assert!((&mut leader).try_advisory_lock(1).await?);
+        assert!(!other.try_advisory_lock(1).await?);
+
+        assert_eq!(other.get_leader().await?, None);
+        (&mut leader).record_leader("replica-1").await?;
+        let recorded = other.get_leader().await?.unwrap();
+        assert_eq!(recorded.instance, "replica-1");
+
+        (&mut leader).record_leader_heartbeat().await?;
+        let beat = other.get_leader().await?.unwrap();
+        assert_eq!(beat.acquired_at, recorded.acquired_at);
+        assert!(beat.heartbeat_at > recorded.heartbeat_at);
+
+        // The lock goes with the session
+        leader.close().await?;
+        assert!(other.try_advisory_lock(1).await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn statements_over_the_timeout_are_cancelled_and_retryable() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let db_container = postgres_docker_utils::setup(&docker).await?;
+        let db = Database::new(&DatabaseConfig {
+            statement_timeout: Duration::from_millis(100),
+            ..config(&db_container, true)?
+        })
+        .await?;
+
+        let source = sqlx::query("SELECT pg_sleep(1)")
+            .execute(&db.pool)
+            .await
+            .unwrap_err();
+        let err = Error::Query {
+            context: "sleep",
+            source,
+        };
+        assert!(err.is_retryable(), "{err:?}");
+
+        db.record_pool_metrics();
+        db.get_db_status().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delivery_is_recorded_in_either_order_and_times_out_once() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, _db_container) = setup_db(&docker).await?;
+
+        // Scroll adds the root before the monitor sees the transaction mine
+        let delivered = TransactionId::job("tx-delivered")?;
+        db.insert_new_transaction(&delivered, U256::from(0xa1))
+            .await?;
+        assert!(db.set_delivered_at().await?.is_empty());
+
+        db.insert_root(RootSource::Scroll, &root(0xa1, 5, 0))
+            .await?;
+        let times = db.set_delivered_at().await?;
+        assert_eq!(times.len(), 1);
+        assert_eq!(
+            (times[0].mined_at, times[0].until_delivered()),
+            (None, None)
+        );
+        assert!(db.set_delivered_at().await?.is_empty());
+
+        db.resolve_transaction(&delivered, TxStatus::Mined, None, None)
+            .await?;
+        let times = db.set_mined_at(&delivered).await?.expect("mined once");
+        assert!(times.mined_at <= times.delivered_at);
+        assert_eq!(times.until_delivered(), Some(Duration::ZERO));
+        assert_eq!(db.set_mined_at(&delivered).await?, None);
+
+        // Mined, but never added on Scroll
+        let lost = TransactionId::job("tx-lost")?;
+        db.insert_new_transaction(&lost, U256::from(0xa2)).await?;
+        db.resolve_transaction(&lost, TxStatus::Mined, None, None)
+            .await?;
+        assert!(db.set_mined_at(&lost).await?.is_some());
+
+        let cutoff = chrono::Utc::now() + chrono::Duration::hours(1);
+        assert_eq!(db.set_delivery_timed_out(cutoff).await?, vec![(
+            lost,
+            Some(U256::from(0xa2))
+        )]);
+        assert!(db.set_delivery_timed_out(cutoff).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn mined_at_is_backfilled_from_the_block_once() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, _db_container) = setup_db(&docker).await?;
+
+        let transaction_id = TransactionId::job("tx-restored")?;
+        db.insert_new_transaction(&transaction_id, U256::from(0xb1))
+            .await?;
+        db.resolve_transaction(&transaction_id, TxStatus::Mined, None, None)
+            .await?;
+
+        let mined_at = chrono::Utc::now() - chrono::Duration::days(1);
+        assert!(db.backfill_mined_at(&transaction_id, mined_at).await?);
+        assert!(
+            !db.backfill_mined_at(&transaction_id, chrono::Utc::now())
+                .await?
+        );
+
+        // Already recorded, the monitor doesn't observe it again
+        assert_eq!(db.set_mined_at(&transaction_id).await?, None);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn propagations_are_paged_newest_first_with_their_scroll_side() -> anyhow::Result<()> {
+        let docker = Cli::default();
+        let (db, _db_container) = setup_db(&docker).await?;
+
+        let delivered = TransactionId::job("tx-1")?;
+        let in_flight = TransactionId::job("tx-2")?;
+        db.insert_new_transaction(&delivered, U256::from(0xb1))
+            .await?;
+        db.insert_new_transaction(&in_flight, U256::from(0xb2))
+            .await?;
+        db.resolve_transaction(
+            &delivered,
+            TxStatus::Mined,
+            Some(format!("{:?}", H256::repeat_byte(1))),
+            None,
+        )
+        .await?;
+        db.insert_root(RootSource::Scroll, &root(0xb1, 7, 0))
+            .await?;
+
+        let first = db.get_propagations(None, 1).await?;
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].transaction_id, in_flight);
+        assert_eq!(first[0].outcome, TxStatus::Pending);
+        assert_eq!(
+            (first[0].root_added_at, &first[0].root_added_tx_hash),
+            (None, &None)
+        );
+
+        let second = db.get_propagations(Some(&first[0].cursor()), 1).await?;
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].transaction_id, delivered);
+        assert_eq!(
+            second[0].root.as_deref(),
+            Some(format!("{:#066x}", 0xb1).as_str())
+        );
+        assert_eq!(
+            second[0].tx_hash,
+            Some(format!("{:?}", H256::repeat_byte(1)))
+        );
+        assert_eq!(second[0].outcome, TxStatus::Mined);
+        assert!(second[0].root_added_at >= Some(second[0].submitted_at));
+        assert_eq!(
+            second[0].root_added_tx_hash,
+            Some(format!("{:?}", H256::from_low_u64_be(7)))
+        );
+
+        assert!(db
+            .get_propagations(Some(&second[0].cursor()), 1)
+            .await?
+            .is_empty());
+        assert_eq!(
+            db.get_propagations(None, 10).await?,
+            [first, second].concat()
+        );
+
+        Ok(())
+    }
 }
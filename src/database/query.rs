@@ -1,63 +1,115 @@
-// use chrono::{DateTime, Utc};
-use sqlx::{Executor, Postgres, Row};
+use std::collections::HashSet;
 
-use crate::database::{types, Error};
+use chrono::{DateTime, Utc};
+use ethers::types::U256;
+use sqlx::{postgres::PgRow, Executor, Postgres, QueryBuilder, Row};
 
-use crate::processor::status::BridgeStatus;
-use types::{ServerStatus, TxStatus};
+use crate::{
+    database::{types, Error},
+    ethereum::GasCost,
+};
+
+use crate::{
+    processor::status::{BridgeStatus, UnknownStatus},
+    utils::TransactionId,
+};
+#[cfg(feature = "server")]
+use types::{LastSync, PropagationCursor, PropagationRecord};
+use types::{
+    LeaderRecord, ObservedRoot, PauseRecord, PendingPropagation, PropagationTimes, RootSource,
+    ServerStatus, Spend, TxStatus,
+};
+
+/// Names the query a sqlx error came from.
+trait QueryContext<T> {
+    fn context(self, context: &'static str) -> Result<T, Error>;
+}
+
+impl<T> QueryContext<T> for Result<T, sqlx::Error> {
+    fn context(self, context: &'static str) -> Result<T, Error> {
+        self.map_err(|source| Error::Query { context, source })
+    }
+}
+
+/// Updates of `service_status` match no row until the server is initialized.
+const fn status_row_updated(rows_affected: u64) -> Result<(), Error> {
+    if rows_affected == 0 {
+        return Err(Error::StatusRowMissing);
+    }
+    Ok(())
+}
+
+/// A status read from `service_status`.
+fn parse_status(status: String) -> Result<BridgeStatus, Error> {
+    match status.parse() {
+        Ok(parsed) => Ok(parsed),
+        Err(UnknownStatus) => Err(Error::UnknownStatus { status }),
+    }
+}
+
+fn parse_root(row: &PgRow) -> Option<U256> {
+    U256::from_str_radix(row.get::<String, _>(0).trim_start_matches("0x"), 16).ok()
+}
+
+fn propagation_times(row: &PgRow) -> PropagationTimes {
+    PropagationTimes {
+        created_at:   row.get(0),
+        mined_at:     row.get(1),
+        delivered_at: row.get(2),
+    }
+}
 
 /// This trait provides the individual and composable queries to the database.
 /// Each method is a single atomic query, and can be composed within a
 /// transaction.
 pub trait DatabaseQuery<'a>: Executor<'a, Database = Postgres> {
-
-    async fn initialize_server(
-        self,
-    ) -> Result<(), Error> {
+    /// Creates the service status row. Returns `false` if it already
+    /// exists, leaving it untouched.
+    async fn initialize_server(self) -> Result<bool, Error> {
         let initialize_server_query = sqlx::query(
             r#"
-            INSERT INTO service_status (status, last_synced)
-            VALUES ($1, CURRENT_TIMESTAMP)
+            INSERT INTO service_status (id, status, last_synced)
+            VALUES (1, $1, CURRENT_TIMESTAMP)
+            ON CONFLICT (id) DO NOTHING
             "#,
         )
         .bind(<&str>::from(BridgeStatus::Unsynced));
 
-        self.execute(initialize_server_query).await?;
-        Ok(())
-    }
-
-    async fn server_initialized(self) -> Result<bool, Error> {
-        Ok(sqlx::query(
-            r#"
-            select
-            EXISTS (select id from service_status where id = 1)
-            "#,
-        )
-        .fetch_one(self)
-        .await?
-        .get::<bool, _>(0))
+        let result = self
+            .execute(initialize_server_query)
+            .await
+            .context("initialize the service status")?;
+        Ok(result.rows_affected() > 0)
     }
 
+    /// Records a propagation of `root`. Recording the same transaction again
+    /// is a no-op.
     async fn insert_new_transaction(
         self,
-        transaction_id: &String
+        transaction_id: &TransactionId,
+        root: U256,
     ) -> Result<(), Error> {
         let query = sqlx::query(
             r#"
             INSERT INTO transactions(
                 transaction_id,
+                root,
                 created_at
-            ) VALUES ($1, CURRENT_TIMESTAMP)
+            ) VALUES ($1, $2, CURRENT_TIMESTAMP)
+            ON CONFLICT (transaction_id) DO NOTHING
             "#,
         )
-        .bind(transaction_id);
-        self.execute(query).await?;
+        .bind(transaction_id)
+        .bind(format!("{root:#066x}"));
+        self.execute(query)
+            .await
+            .context("insert a new transaction")?;
         Ok(())
     }
 
     async fn update_transaction(
         self,
-        transaction_id: &String,
+        transaction_id: &TransactionId,
         tx_status: TxStatus,
     ) -> Result<(), Error> {
         let query = sqlx::query(
@@ -69,60 +121,107 @@ pub trait DatabaseQuery<'a>: Executor<'a, Database = Postgres> {
         )
         .bind(tx_status)
         .bind(transaction_id);
-        self.execute(query).await?;
+        self.execute(query)
+            .await
+            .context("update a transaction's status")?;
         Ok(())
     }
 
-
-    async fn update_server_status(
-        self,
-        status: BridgeStatus
-    ) -> Result<(), Error> {
-        let query = if matches!(status, BridgeStatus::Synced) {
-            // Update the status and last_synced if the status is Synced
-            sqlx::query(
-                r#"
-                UPDATE service_status
-                SET status = $1, last_synced = CURRENT_TIMESTAMP
+    /// Moves the service to `status` if the current status is one of its
+    /// [predecessors](BridgeStatus::predecessors), as a single compare and
+    /// set. The row is locked first, so a concurrent change is waited for
+    /// and compared against rather than overwritten. `last_synced` advances
+    /// when the status becomes synced.
+    async fn update_server_status(self, status: BridgeStatus) -> Result<(), Error> {
+        // Statuses this service doesn't know count as unsynced, as they do
+        // when read. Leaving synced also sets last_synced, so it is when the
+        // bridge was last synced whatever the status.
+        let query = sqlx::query(
+            r#"
+            WITH current AS (
+                SELECT status
+                FROM service_status
                 WHERE id = 1
-                "#
-            )
-            .bind(status)
-        } else {
-            // Only update the status if the status is not Synced
-            sqlx::query(
-                r#"
+                FOR UPDATE
+            ), updated AS (
                 UPDATE service_status
-                SET status = $1
-                WHERE id = 1
-                "#
+                SET status = $1,
+                    last_synced = CASE
+                        WHEN $3 OR lower(current.status) = 'synced' THEN CURRENT_TIMESTAMP
+                        ELSE last_synced
+                    END
+                FROM current
+                WHERE service_status.id = 1
+                  AND CASE lower(current.status)
+                        WHEN 'pending' THEN 'pending'
+                        WHEN 'synced' THEN 'synced'
+                        ELSE 'unsynced'
+                      END = ANY($2)
+                RETURNING service_status.id
             )
-            .bind(<&str>::from(status))
+            SELECT (SELECT status FROM current), EXISTS (SELECT FROM updated)
+            "#,
+        )
+        .bind(<&str>::from(status))
+        .bind(
+            status
+                .predecessors()
+                .iter()
+                .map(|status| <&str>::from(*status))
+                .collect::<Vec<_>>(),
+        )
+        .bind(status == BridgeStatus::Synced);
+
+        let row = self
+            .fetch_one(query)
+            .await
+            .context("update the service status")?;
+        let Some(current) = row.get::<Option<String>, _>(0) else {
+            return Err(Error::StatusRowMissing);
         };
 
-        self.execute(query).await?;
+        if !row.get::<bool, _>(1) {
+            return Err(Error::InvalidStatusTransition {
+                from: current.parse().unwrap_or(BridgeStatus::Unsynced),
+                to:   status,
+            });
+        }
+
         Ok(())
     }
 
-    async fn get_last_transaction_status(
-        self
-    ) -> Result<Option<TxStatus>, Error> {
+    /// Records why the service is unsynced, `None` to clear it.
+    async fn set_status_reason(self, reason: Option<&str>) -> Result<(), Error> {
+        let query = sqlx::query(
+            r#"
+            UPDATE service_status
+            SET status_reason = $1
+            WHERE id = 1
+            "#,
+        )
+        .bind(reason);
+        let result = self.execute(query).await.context("set the status reason")?;
+        status_row_updated(result.rows_affected())
+    }
+
+    async fn get_last_transaction_status(self) -> Result<Option<TxStatus>, Error> {
         let query = sqlx::query(
             r#"
             SELECT status
             FROM transactions
             ORDER BY created_at DESC
             LIMIT 1;
-            "#
+            "#,
         );
-        let row = self.fetch_optional(query).await?;
+        let row = self
+            .fetch_optional(query)
+            .await
+            .context("fetch the last transaction status")?;
 
         Ok(row.map(|r| r.get::<TxStatus, _>(0)))
     }
 
-    async fn get_last_transaction_id(
-        self
-    ) -> Result<Option<String>, Error> {
+    async fn get_last_transaction_id(self) -> Result<Option<TransactionId>, Error> {
         let query = sqlx::query(
             r#"
             SELECT transaction_id
@@ -130,46 +229,935 @@ pub trait DatabaseQuery<'a>: Executor<'a, Database = Postgres> {
             WHERE status = $1
             ORDER BY created_at DESC
             LIMIT 1;
-            "#
-        ).bind(TxStatus::Pending);
-        let row = self.fetch_optional(query).await?;
+            "#,
+        )
+        .bind(TxStatus::Pending);
+        let row = self
+            .fetch_optional(query)
+            .await
+            .context("fetch the last pending transaction id")?;
+
+        row.map(|r| r.try_get(0))
+            .transpose()
+            .context("decode the last pending transaction id")
+    }
+
+    /// Returns the latest pending transaction and the root it propagates.
+    async fn get_pending_propagation(self) -> Result<Option<PendingPropagation>, Error> {
+        let query = sqlx::query(
+            r#"
+            SELECT transaction_id, root
+            FROM transactions
+            WHERE status = $1
+            ORDER BY created_at DESC
+            LIMIT 1;
+            "#,
+        )
+        .bind(TxStatus::Pending);
+        let Some(row) = self
+            .fetch_optional(query)
+            .await
+            .context("fetch the pending propagation")?
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(PendingPropagation {
+            transaction_id: row
+                .try_get(0)
+                .context("decode the pending transaction id")?,
+            root:           row
+                .get::<Option<String>, _>(1)
+                .and_then(|root| U256::from_str_radix(root.trim_start_matches("0x"), 16).ok()),
+        }))
+    }
+
+    /// Returns the id of every pending transaction, oldest first.
+    async fn get_pending_transactions(self) -> Result<Vec<TransactionId>, Error> {
+        let query = sqlx::query(
+            r#"
+            SELECT transaction_id
+            FROM transactions
+            WHERE status = $1
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(TxStatus::Pending);
+        let rows = self
+            .fetch_all(query)
+            .await
+            .context("list the pending transactions")?;
+
+        rows.into_iter()
+            .map(|r| r.try_get(0))
+            .collect::<Result<_, sqlx::Error>>()
+            .context("decode the pending transaction ids")
+    }
+
+    /// Returns the id and creation time of every pending transaction created
+    /// before `cutoff`, oldest first.
+    async fn get_transactions_pending_since(
+        self,
+        cutoff: DateTime<Utc>,
+    ) -> Result<Vec<(TransactionId, DateTime<Utc>)>, Error> {
+        let query = sqlx::query(
+            r#"
+            SELECT transaction_id, created_at
+            FROM transactions
+            WHERE status = $1 AND created_at < $2
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(TxStatus::Pending)
+        .bind(cutoff);
+        let rows = self
+            .fetch_all(query)
+            .await
+            .context("list the pending transactions")?;
+
+        rows.into_iter()
+            .map(|r| Ok((r.try_get(0)?, r.get::<DateTime<Utc>, _>(1))))
+            .collect::<Result<_, sqlx::Error>>()
+            .context("decode the pending transaction ids")
+    }
+
+    /// Moves a pending transaction to a terminal status. Returns `false` if
+    /// the transaction was already resolved by someone else.
+    async fn resolve_transaction(
+        self,
+        transaction_id: &TransactionId,
+        tx_status: TxStatus,
+        tx_hash: Option<String>,
+        reason: Option<&str>,
+    ) -> Result<bool, Error> {
+        let query = sqlx::query(
+            r#"
+            UPDATE transactions
+            SET status = $1,
+                tx_hash = COALESCE($2, tx_hash),
+                resolution_reason = $3,
+                resolved_at = CURRENT_TIMESTAMP
+            WHERE transaction_id = $4 AND status = $5
+            "#,
+        )
+        .bind(tx_status)
+        .bind(tx_hash)
+        .bind(reason)
+        .bind(transaction_id)
+        .bind(TxStatus::Pending);
+        let result = self.execute(query).await.context("resolve a transaction")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Records that `transaction_id` was seen mined, unless that was already
+    /// recorded. A root Scroll already added bounds when its propagation
+    /// mined. Returns `None` when nothing was recorded.
+    async fn set_mined_at(
+        self,
+        transaction_id: &TransactionId,
+    ) -> Result<Option<PropagationTimes>, Error> {
+        let query = sqlx::query(
+            r#"
+            UPDATE transactions
+            SET mined_at = LEAST(CURRENT_TIMESTAMP, COALESCE(delivered_at, CURRENT_TIMESTAMP))
+            WHERE transaction_id = $1 AND mined_at IS NULL
+            RETURNING created_at, mined_at, delivered_at
+            "#,
+        )
+        .bind(transaction_id);
+        let row = self
+            .fetch_optional(query)
+            .await
+            .context("record when a transaction mined")?;
 
-        Ok(row.map(|r| r.get::<String, _>(0)))
+        Ok(row.map(|row| propagation_times(&row)))
     }
 
+    /// Records that `transaction_id` mined at `mined_at`, read from its block,
+    /// unless when it mined was already recorded. Returns whether it was
+    /// recorded.
+    async fn backfill_mined_at(
+        self,
+        transaction_id: &TransactionId,
+        mined_at: DateTime<Utc>,
+    ) -> Result<bool, Error> {
+        let query = sqlx::query(
+            r#"
+            UPDATE transactions
+            SET mined_at = LEAST($2, COALESCE(delivered_at, $2))
+            WHERE transaction_id = $1 AND mined_at IS NULL
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(mined_at);
+        let result = self
+            .execute(query)
+            .await
+            .context("backfill when a transaction mined")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Records when Scroll added the root of every propagation whose root the
+    /// scans have since seen on Scroll, whether or not the propagation was
+    /// seen mined yet. Failed propagations are left alone, another one
+    /// delivered their root. Returns the propagations recorded.
+    async fn set_delivered_at(self) -> Result<Vec<PropagationTimes>, Error> {
+        let query = sqlx::query(
+            r#"
+            WITH added AS (
+                SELECT transactions.transaction_id, MIN(roots.observed_at) AS observed_at
+                FROM transactions
+                JOIN roots
+                  ON roots.source = 'scroll'
+                 AND roots.root = transactions.root
+                 AND roots.observed_at >= transactions.created_at
+                WHERE transactions.delivered_at IS NULL
+                  AND transactions.status IN
+                      ('pending', 'mined', 'finalized', 'superseded_by_original')
+                GROUP BY transactions.transaction_id
+            )
+            UPDATE transactions
+            SET delivered_at = added.observed_at
+            FROM added
+            WHERE transactions.transaction_id = added.transaction_id
+            RETURNING transactions.created_at, transactions.mined_at, transactions.delivered_at
+            "#,
+        );
+        let rows = self
+            .fetch_all(query)
+            .await
+            .context("record delivered propagations")?;
+
+        Ok(rows.iter().map(propagation_times).collect())
+    }
+
+    /// Marks the propagations that mined before `cutoff` and whose root
+    /// Scroll hasn't been seen adding as timed out, returning their ids and
+    /// roots. Each one is returned once.
+    async fn set_delivery_timed_out(
+        self,
+        cutoff: DateTime<Utc>,
+    ) -> Result<Vec<(TransactionId, Option<U256>)>, Error> {
+        let query = sqlx::query(
+            r#"
+            UPDATE transactions
+            SET delivery_timed_out = TRUE
+            WHERE mined_at < $1
+              AND delivered_at IS NULL
+              AND NOT delivery_timed_out
+              AND status IN ('mined', 'finalized', 'superseded_by_original')
+            RETURNING transaction_id, root
+            "#,
+        )
+        .bind(cutoff);
+        let rows = self
+            .fetch_all(query)
+            .await
+            .context("time out undelivered propagations")?;
+
+        rows.into_iter()
+            .map(|row| {
+                let root = row
+                    .get::<Option<String>, _>(1)
+                    .and_then(|root| U256::from_str_radix(root.trim_start_matches("0x"), 16).ok());
+                Ok((row.try_get(0)?, root))
+            })
+            .collect::<Result<_, sqlx::Error>>()
+            .context("decode the timed out transaction ids")
+    }
+
+    /// Records what `transaction_id` cost, unless it already was. Returns
+    /// whether it was recorded now, so it is only counted once however many
+    /// paths see the receipt.
+    async fn set_transaction_cost(
+        self,
+        transaction_id: &TransactionId,
+        cost: &GasCost,
+    ) -> Result<bool, Error> {
+        let query = sqlx::query(
+            r#"
+            UPDATE transactions
+            SET cost = $1,
+                gas_used = $2,
+                effective_gas_price = $3,
+                cost_estimated = $4
+            WHERE transaction_id = $5 AND cost IS NULL
+            "#,
+        )
+        .bind(format!("{:#x}", cost.wei()))
+        .bind(i64::try_from(cost.gas_used).unwrap_or(i64::MAX))
+        .bind(format!("{:#x}", cost.gas_price))
+        .bind(cost.estimated)
+        .bind(transaction_id);
+        let result = self
+            .execute(query)
+            .await
+            .context("record a transaction's cost")?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Sums what the propagations created since `cutoff` cost. Ones that
+    /// mined, or reverted on chain, without a recorded cost are counted as
+    /// unknown.
+    async fn get_spend_since(self, cutoff: DateTime<Utc>) -> Result<Spend, Error> {
+        let query = sqlx::query(
+            r#"
+            SELECT cost, gas_used, cost_estimated
+            FROM transactions
+            WHERE created_at >= $1
+              AND (cost IS NOT NULL OR tx_hash IS NOT NULL OR status IN ($2, $3, $4))
+            "#,
+        )
+        .bind(cutoff)
+        .bind(TxStatus::Mined)
+        .bind(TxStatus::Finalized)
+        .bind(TxStatus::SupersededByOriginal);
+        let rows = self
+            .fetch_all(query)
+            .await
+            .context("sum the recent propagation costs")?;
+
+        let mut spend = Spend::default();
+        for row in rows {
+            let cost = row
+                .get::<Option<String>, _>(0)
+                .and_then(|cost| U256::from_str_radix(cost.trim_start_matches("0x"), 16).ok());
+            match cost {
+                Some(cost) => {
+                    spend.total = spend.total.saturating_add(cost);
+                    spend.count += 1;
+                    spend.gas_used += row.get::<Option<i64>, _>(1).unwrap_or_default() as u64;
+                    spend.estimated += u64::from(row.get::<bool, _>(2));
+                }
+                None => spend.unknown += 1,
+            }
+        }
+
+        Ok(spend)
+    }
+
+    /// When the latest propagation that mined was created.
+    async fn get_last_mined_propagation_at(self) -> Result<Option<DateTime<Utc>>, Error> {
+        let query = sqlx::query(
+            r#"
+            SELECT MAX(created_at)
+            FROM transactions
+            WHERE status IN ($1, $2, $3)
+            "#,
+        )
+        .bind(TxStatus::Mined)
+        .bind(TxStatus::Finalized)
+        .bind(TxStatus::SupersededByOriginal);
+        let row = self
+            .fetch_one(query)
+            .await
+            .context("fetch the last mined propagation")?;
+
+        Ok(row.get::<Option<DateTime<Utc>>, _>(0))
+    }
+
+    /// Up to `limit` propagations, newest first and after `before` when given,
+    /// each with the first `RootAdded` of its root the Scroll scan saw after
+    /// it was submitted.
+    #[cfg(feature = "server")]
+    async fn get_propagations(
+        self,
+        before: Option<&PropagationCursor>,
+        limit: u64,
+    ) -> Result<Vec<PropagationRecord>, Error> {
+        let query = sqlx::query(
+            r#"
+            SELECT transactions.transaction_id,
+                   transactions.root,
+                   transactions.created_at,
+                   transactions.tx_hash,
+                   transactions.cost,
+                   transactions.mined_at,
+                   added.observed_at,
+                   added.tx_hash,
+                   transactions.status,
+                   transactions.resolution_reason
+            FROM transactions
+            LEFT JOIN LATERAL (
+                SELECT roots.observed_at, roots.tx_hash
+                FROM roots
+                WHERE roots.source = 'scroll'
+                  AND roots.root = transactions.root
+                  AND roots.observed_at >= transactions.created_at
+                ORDER BY roots.observed_at ASC
+                LIMIT 1
+            ) added ON TRUE
+            WHERE $1::TIMESTAMPTZ IS NULL
+               OR (transactions.created_at, transactions.transaction_id) < ($1, $2)
+            ORDER BY transactions.created_at DESC, transactions.transaction_id DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(before.map(|cursor| cursor.submitted_at))
+        .bind(before.map(|cursor| cursor.transaction_id.as_str()))
+        .bind(i64::try_from(limit).unwrap_or(i64::MAX));
+        let rows = self
+            .fetch_all(query)
+            .await
+            .context("list the propagations")?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(PropagationRecord {
+                    transaction_id:     row.try_get(0)?,
+                    root:               row.try_get(1)?,
+                    submitted_at:       row.try_get(2)?,
+                    tx_hash:            row.try_get(3)?,
+                    cost:               row.try_get(4)?,
+                    mined_at:           row.try_get(5)?,
+                    root_added_at:      row.try_get(6)?,
+                    root_added_tx_hash: row.try_get(7)?,
+                    outcome:            row.try_get(8)?,
+                    resolution_reason:  row.try_get(9)?,
+                })
+            })
+            .collect::<Result<_, sqlx::Error>>()
+            .context("decode the propagations")
+    }
+
+    /// Counts the propagations that failed since the last one that mined, or
+    /// since the last resume if that came later.
+    async fn count_consecutive_failures(self) -> Result<u64, Error> {
+        let query = sqlx::query(
+            r#"
+            SELECT COUNT(*)
+            FROM transactions
+            WHERE status = $1
+              AND created_at > COALESCE(
+                  GREATEST(
+                      (SELECT MAX(created_at) FROM transactions WHERE status IN ($2, $3, $4)),
+                      (SELECT resumed_at FROM service_status WHERE id = 1)
+                  ),
+                  '-infinity'::TIMESTAMPTZ
+              )
+            "#,
+        )
+        .bind(TxStatus::Failed)
+        .bind(TxStatus::Mined)
+        .bind(TxStatus::Finalized)
+        .bind(TxStatus::SupersededByOriginal);
+        let row = self
+            .fetch_one(query)
+            .await
+            .context("count consecutive failed propagations")?;
+
+        Ok(row.get::<i64, _>(0) as u64)
+    }
+
+    /// Notes why a transaction is still pending, leaving it for the
+    /// maintenance task to resolve.
+    async fn set_resolution_reason(
+        self,
+        transaction_id: &TransactionId,
+        reason: &str,
+    ) -> Result<(), Error> {
+        let query = sqlx::query(
+            r#"
+            UPDATE transactions
+            SET resolution_reason = $1
+            WHERE transaction_id = $2 AND status = $3
+            "#,
+        )
+        .bind(reason)
+        .bind(transaction_id)
+        .bind(TxStatus::Pending);
+        self.execute(query)
+            .await
+            .context("set a transaction's resolution reason")?;
+        Ok(())
+    }
+
+    /// Records a root observed on chain. Logs that were already recorded are
+    /// ignored, so overlapping scans are harmless.
+    async fn insert_root(self, source: RootSource, root: &ObservedRoot) -> Result<(), Error> {
+        let query = sqlx::query(
+            r#"
+            INSERT INTO roots (source, root, block_number, tx_hash, log_index)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (source, tx_hash, log_index) DO NOTHING
+            "#,
+        )
+        .bind(source)
+        .bind(format!("{:#066x}", root.root))
+        .bind(root.block_number as i64)
+        .bind(format!("{:?}", root.tx_hash))
+        .bind(root.log_index as i64);
+        self.execute(query).await.context("insert a root")?;
+        Ok(())
+    }
+
+    /// Inserts several roots in one statement. Callers keep `rows` well under
+    /// the bind parameter limit.
+    async fn insert_roots(self, rows: &[(RootSource, ObservedRoot)]) -> Result<(), Error> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "INSERT INTO roots (source, root, block_number, tx_hash, log_index) ",
+        );
+        builder.push_values(rows, |mut row, (source, root)| {
+            row.push_bind(*source)
+                .push_bind(format!("{:#066x}", root.root))
+                .push_bind(root.block_number as i64)
+                .push_bind(format!("{:?}", root.tx_hash))
+                .push_bind(root.log_index as i64);
+        });
+        builder.push(" ON CONFLICT (source, tx_hash, log_index) DO NOTHING");
+
+        self.execute(builder.build())
+            .await
+            .context("insert roots")?;
+        Ok(())
+    }
+
+    /// Counts the distinct logs a root was observed in.
+    async fn count_roots(self, source: RootSource) -> Result<u64, Error> {
+        let query = sqlx::query(
+            r#"
+            SELECT COUNT(*)
+            FROM roots
+            WHERE source = $1
+            "#,
+        )
+        .bind(source);
+        let row = self.fetch_one(query).await.context("count roots")?;
+
+        Ok(row.get::<i64, _>(0) as u64)
+    }
+
+    /// Whether `root` was observed on `source`.
+    async fn is_root_observed(self, source: RootSource, root: U256) -> Result<bool, Error> {
+        let query = sqlx::query(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM roots WHERE source = $1 AND root = $2)
+            "#,
+        )
+        .bind(source)
+        .bind(format!("{root:#066x}"));
+        let row = self
+            .fetch_one(query)
+            .await
+            .context("check whether a root was observed")?;
+
+        Ok(row.get::<bool, _>(0))
+    }
+
+    /// The roots propagated on mainnet from `from_block` on that Scroll
+    /// hasn't added, oldest first. Only propagations after the last one
+    /// Scroll did add are considered, earlier ones were superseded on Scroll
+    /// anyway.
+    async fn get_outstanding_roots(self, from_block: u64) -> Result<Vec<U256>, Error> {
+        let query = sqlx::query(
+            r#"
+            SELECT propagated.root
+            FROM roots propagated
+            WHERE propagated.source = 'bridge'
+              AND propagated.block_number >= $1
+              AND propagated.block_number > COALESCE((
+                  SELECT MAX(delivered.block_number)
+                  FROM roots delivered
+                  WHERE delivered.source = 'bridge'
+                    AND EXISTS (
+                        SELECT 1 FROM roots added
+                        WHERE added.source = 'scroll' AND added.root = delivered.root
+                    )
+              ), -1)
+              AND NOT EXISTS (
+                  SELECT 1 FROM roots added
+                  WHERE added.source = 'scroll' AND added.root = propagated.root
+              )
+            GROUP BY propagated.root
+            ORDER BY MIN(propagated.block_number), MIN(propagated.log_index)
+            "#,
+        )
+        .bind(from_block as i64);
+        let rows = self
+            .fetch_all(query)
+            .await
+            .context("fetch the outstanding roots")?;
+
+        Ok(rows.iter().filter_map(parse_root).collect())
+    }
+
+    /// Marks the roots Scroll added at or below `finalized_block` finalized,
+    /// returning how many were.
+    async fn finalize_roots(self, finalized_block: u64) -> Result<u64, Error> {
+        let query = sqlx::query(
+            r#"
+            UPDATE roots
+            SET finalized_at = CURRENT_TIMESTAMP
+            WHERE source = 'scroll' AND finalized_at IS NULL AND block_number <= $1
+            "#,
+        )
+        .bind(finalized_block as i64);
+        let result = self.execute(query).await.context("mark roots finalized")?;
+        Ok(result.rows_affected())
+    }
+
+    /// Demotes the finalized roots above `finalized_block` back to added,
+    /// after the finalized block went back. Returns how many were.
+    async fn unfinalize_roots(self, finalized_block: u64) -> Result<u64, Error> {
+        let query = sqlx::query(
+            r#"
+            UPDATE roots
+            SET finalized_at = NULL
+            WHERE source = 'scroll' AND finalized_at IS NOT NULL AND block_number > $1
+            "#,
+        )
+        .bind(finalized_block as i64);
+        let result = self
+            .execute(query)
+            .await
+            .context("demote roots above the finalized block")?;
+        Ok(result.rows_affected())
+    }
+
+    /// Those of `roots` Scroll added in a finalized block.
+    async fn get_finalized_roots(self, roots: &[U256]) -> Result<HashSet<U256>, Error> {
+        let roots: Vec<String> = roots.iter().map(|root| format!("{root:#066x}")).collect();
+        let query = sqlx::query(
+            r#"
+            SELECT DISTINCT root
+            FROM roots
+            WHERE source = 'scroll' AND finalized_at IS NOT NULL AND root = ANY($1)
+            "#,
+        )
+        .bind(roots);
+        let rows = self
+            .fetch_all(query)
+            .await
+            .context("fetch the finalized roots")?;
+
+        Ok(rows.iter().filter_map(parse_root).collect())
+    }
+
+    /// Whether the latest root Scroll added is finalized, `None` before the
+    /// first one.
+    async fn is_latest_root_finalized(self) -> Result<Option<bool>, Error> {
+        let query = sqlx::query(
+            r#"
+            SELECT finalized_at IS NOT NULL
+            FROM roots
+            WHERE source = 'scroll'
+            ORDER BY block_number DESC, log_index DESC
+            LIMIT 1
+            "#,
+        );
+        let row = self
+            .fetch_optional(query)
+            .await
+            .context("check whether the latest root is finalized")?;
+
+        Ok(row.map(|row| row.get::<bool, _>(0)))
+    }
+
+    /// Deletes the roots observed at or after `block_number`, returning how
+    /// many were removed.
+    async fn delete_roots_from_block(
+        self,
+        source: RootSource,
+        block_number: u64,
+    ) -> Result<u64, Error> {
+        let query = sqlx::query(
+            r#"
+            DELETE FROM roots
+            WHERE source = $1 AND block_number >= $2
+            "#,
+        )
+        .bind(source)
+        .bind(block_number as i64);
+        let result = self
+            .execute(query)
+            .await
+            .context("delete the roots of a reorged range")?;
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes the root observed in `root`'s log, after the log was removed
+    /// by a reorg.
+    async fn delete_root(self, source: RootSource, root: &ObservedRoot) -> Result<(), Error> {
+        let query = sqlx::query(
+            r#"
+            DELETE FROM roots
+            WHERE source = $1 AND tx_hash = $2 AND log_index = $3
+            "#,
+        )
+        .bind(source)
+        .bind(format!("{:?}", root.tx_hash))
+        .bind(root.log_index as i64);
+        self.execute(query).await.context("delete a removed root")?;
+        Ok(())
+    }
+
+    /// Deletes up to `limit` roots observed before `cutoff`, returning how
+    /// many were removed. The latest root of each source and the roots of
+    /// pending transactions are kept whatever their age.
+    async fn prune_roots(self, cutoff: DateTime<Utc>, limit: u64) -> Result<u64, Error> {
+        let query = sqlx::query(
+            r#"
+            DELETE FROM roots
+            WHERE (source, tx_hash, log_index) IN (
+                SELECT old.source, old.tx_hash, old.log_index
+                FROM roots old
+                WHERE old.observed_at < $1
+                  AND (old.block_number, old.log_index) < (
+                      SELECT latest.block_number, latest.log_index
+                      FROM roots latest
+                      WHERE latest.source = old.source
+                      ORDER BY latest.block_number DESC, latest.log_index DESC
+                      LIMIT 1
+                  )
+                  AND NOT EXISTS (
+                      SELECT 1 FROM transactions
+                      WHERE transactions.status = $2 AND transactions.root = old.root
+                  )
+                LIMIT $3
+            )
+            "#,
+        )
+        .bind(cutoff)
+        .bind(TxStatus::Pending)
+        .bind(limit as i64);
+        let result = self.execute(query).await.context("prune old roots")?;
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes up to `limit` resolved transactions created before `cutoff`,
+    /// returning how many were removed. The latest transaction is kept
+    /// whatever its age, the status is read from it.
+    async fn prune_transactions(self, cutoff: DateTime<Utc>, limit: u64) -> Result<u64, Error> {
+        let query = sqlx::query(
+            r#"
+            DELETE FROM transactions
+            WHERE transaction_id IN (
+                SELECT transaction_id
+                FROM transactions
+                WHERE status <> $1
+                  AND created_at < $2
+                  AND created_at < (SELECT MAX(created_at) FROM transactions)
+                LIMIT $3
+            )
+            "#,
+        )
+        .bind(TxStatus::Pending)
+        .bind(cutoff)
+        .bind(limit as i64);
+        let result = self
+            .execute(query)
+            .await
+            .context("prune old transactions")?;
+        Ok(result.rows_affected())
+    }
+
+    /// The next block a backfill of `source` scans, if one was started.
+    async fn get_scan_progress(self, source: RootSource) -> Result<Option<u64>, Error> {
+        let query = sqlx::query(
+            r#"
+            SELECT next_block
+            FROM scan_progress
+            WHERE source = $1
+            "#,
+        )
+        .bind(source);
+        let row = self
+            .fetch_optional(query)
+            .await
+            .context("fetch the scan progress")?;
+
+        Ok(row.map(|r| r.get::<i64, _>(0) as u64))
+    }
+
+    async fn set_scan_progress(self, source: RootSource, next_block: u64) -> Result<(), Error> {
+        let query = sqlx::query(
+            r#"
+            INSERT INTO scan_progress (source, next_block)
+            VALUES ($1, $2)
+            ON CONFLICT (source) DO UPDATE
+            SET next_block = EXCLUDED.next_block,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(source)
+        .bind(next_block as i64);
+        self.execute(query)
+            .await
+            .context("record the scan progress")?;
+        Ok(())
+    }
+
+    /// Fails with [`Error::UnknownStatus`] if the row holds a status this
+    /// service doesn't know.
     async fn get_service_status(self) -> Result<Option<ServerStatus>, Error> {
-        Ok(sqlx::query_as::<_, ServerStatus>(
+        let query = sqlx::query(
             r#"
-            SELECT status, last_synced
+            SELECT status, status_reason, last_synced
             FROM service_status
             WHERE id = 1
-            "#
+            "#,
+        );
+        let row = self
+            .fetch_optional(query)
+            .await
+            .context("fetch the service status")?;
+
+        row.map(|row| {
+            Ok(ServerStatus {
+                status:      parse_status(row.get(0))?,
+                reason:      row.get(1),
+                last_synced: row.get(2),
+            })
+        })
+        .transpose()
+    }
+
+    /// Returns the current pause, if the service is paused.
+    async fn get_pause(self) -> Result<Option<PauseRecord>, Error> {
+        Ok(sqlx::query_as::<_, PauseRecord>(
+            r#"
+            SELECT pause_reason, paused_at, pause_expires_at
+            FROM service_status
+            WHERE id = 1 AND paused
+            "#,
+        )
+        .fetch_optional(self)
+        .await
+        .context("fetch the pause")?)
+    }
+
+    /// Records a pause, replacing any pause already in place.
+    async fn pause_service(self, pause: &PauseRecord) -> Result<(), Error> {
+        let query = sqlx::query(
+            r#"
+            UPDATE service_status
+            SET paused = TRUE,
+                pause_reason = $1,
+                paused_at = $2,
+                pause_expires_at = $3
+            WHERE id = 1
+            "#,
+        )
+        .bind(&pause.pause_reason)
+        .bind(pause.paused_at)
+        .bind(pause.pause_expires_at);
+        let result = self.execute(query).await.context("record a pause")?;
+        status_row_updated(result.rows_affected())
+    }
+
+    async fn resume_service(self, reason: &str) -> Result<(), Error> {
+        let query = sqlx::query(
+            r#"
+            UPDATE service_status
+            SET paused = FALSE,
+                pause_expires_at = NULL,
+                resume_reason = $1,
+                resumed_at = CURRENT_TIMESTAMP
+            WHERE id = 1
+            "#,
+        )
+        .bind(reason);
+        let result = self.execute(query).await.context("record a resume")?;
+        status_row_updated(result.rows_affected())
+    }
+
+    /// Takes the session advisory lock `key` if no other session holds it.
+    /// It is held until released or the session ends.
+    async fn try_advisory_lock(self, key: i64) -> Result<bool, Error> {
+        let query = sqlx::query("SELECT pg_try_advisory_lock($1)").bind(key);
+        let row = self
+            .fetch_one(query)
+            .await
+            .context("take the advisory lock")?;
+        Ok(row.get::<bool, _>(0))
+    }
+
+    /// Records `instance` as the leader, from now.
+    async fn record_leader(self, instance: &str) -> Result<(), Error> {
+        let query = sqlx::query(
+            r#"
+            INSERT INTO leader (id, instance, acquired_at, heartbeat_at)
+            VALUES (1, $1, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            ON CONFLICT (id) DO UPDATE
+            SET instance = EXCLUDED.instance,
+                acquired_at = EXCLUDED.acquired_at,
+                heartbeat_at = EXCLUDED.heartbeat_at
+            "#,
+        )
+        .bind(instance);
+        self.execute(query).await.context("record the leader")?;
+        Ok(())
+    }
+
+    async fn record_leader_heartbeat(self) -> Result<(), Error> {
+        let query = sqlx::query(
+            r#"
+            UPDATE leader
+            SET heartbeat_at = CURRENT_TIMESTAMP
+            WHERE id = 1
+            "#,
+        );
+        self.execute(query)
+            .await
+            .context("record a leader heartbeat")?;
+        Ok(())
+    }
+
+    async fn get_leader(self) -> Result<Option<LeaderRecord>, Error> {
+        Ok(sqlx::query_as::<_, LeaderRecord>(
+            r#"
+            SELECT instance, acquired_at, heartbeat_at
+            FROM leader
+            WHERE id = 1
+            "#,
         )
         .fetch_optional(self)
-        .await?)
+        .await
+        .context("fetch the leader")?)
     }
 
-    async fn get_db_status(self) -> Result<Option<String>, Error> {
+    /// Fails with [`Error::UnknownStatus`] if the row holds a status this
+    /// service doesn't know.
+    async fn get_db_status(self) -> Result<Option<BridgeStatus>, Error> {
         let query = sqlx::query(
             r#"
             SELECT status
             FROM service_status
             WHERE id = 1
-            "#
+            "#,
+        );
+        let row = self
+            .fetch_optional(query)
+            .await
+            .context("fetch the bridge status")?;
+        row.map(|r| parse_status(r.get::<String, _>(0))).transpose()
+    }
+
+    /// The status and `last_synced`, with the database's clock to age them
+    /// by. A row comes back even before the server was initialized. Fails
+    /// with [`Error::UnknownStatus`] like [`Self::get_service_status`].
+    #[cfg(feature = "server")]
+    async fn get_last_sync_timestamp(self) -> Result<LastSync, Error> {
+        let query = sqlx::query(
+            r#"
+            SELECT status.status, status.last_synced, CURRENT_TIMESTAMP AS now
+            FROM (SELECT 1) AS one
+            LEFT JOIN service_status status ON status.id = 1
+            "#,
         );
-        let row = self.fetch_optional(query).await?;
-        Ok(row.map(|r| r.get::<String, _>(0)))
-    }
-
-    // async fn get_last_sync_timestamp(self) -> Result<Option<DateTime<Utc>>, Error> {
-    //     let query = sqlx::query(
-    //         r#"
-    //         SELECT last_synced
-    //         FROM service_status
-    //         WHERE id = 1
-    //         "#
-    //     );
-    //     let row = self.fetch_optional(query).await?;
-    //     Ok(row.map(|r| r.get::<DateTime<Utc>, _>(0)))
-    // }
+        let row = self.fetch_one(query).await.context("fetch the last sync")?;
+
+        Ok(LastSync {
+            status:      row
+                .get::<Option<String>, _>(0)
+                .map(parse_status)
+                .transpose()?,
+            last_synced: row.get(1),
+            now:         row.get(2),
+        })
+    }
 }
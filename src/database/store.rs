@@ -0,0 +1,139 @@
+//! The status and transaction rows the sync check and the propagation task
+//! read and write, behind a trait so the tasks can run against an in-memory
+//! store in tests. [`Database`] is the store the service runs with.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use ethers::types::U256;
+
+use crate::{
+    database::{
+        query::DatabaseQuery,
+        types::{PendingPropagation, PropagationTimes, ServerStatus, Spend, TxStatus},
+        Database, Error,
+    },
+    processor::status::{BridgeStatus, UnsyncedReason},
+    utils::TransactionId,
+};
+
+#[async_trait]
+pub trait StatusStore: Send + Sync + 'static {
+    /// Creates the status row, unsynced, if it is missing. Returns `false`
+    /// if it already exists.
+    async fn initialize_server(&self) -> Result<bool, Error>;
+
+    async fn get_service_status(&self) -> Result<Option<ServerStatus>, Error>;
+
+    /// The bridge status alone, `None` until the server is initialized.
+    /// Fails with [`Error::UnknownStatus`] on a status this service doesn't
+    /// know.
+    async fn get_db_status(&self) -> Result<Option<BridgeStatus>, Error>;
+
+    /// Moves the status to unsynced, failing with
+    /// [`Error::InvalidStatusTransition`] if it can't move there.
+    async fn mark_status_as_unsynced(&self, reason: Option<UnsyncedReason>) -> Result<(), Error>;
+
+    /// See [`Self::mark_status_as_unsynced`].
+    async fn mark_status_as_pending(&self) -> Result<(), Error>;
+
+    /// See [`Self::mark_status_as_unsynced`].
+    async fn mark_status_as_synced(&self) -> Result<(), Error>;
+
+    async fn get_last_transaction_status(&self) -> Result<Option<TxStatus>, Error>;
+
+    async fn get_pending_propagation(&self) -> Result<Option<PendingPropagation>, Error>;
+
+    async fn insert_new_transaction(
+        &self,
+        transaction_id: &TransactionId,
+        root: U256,
+    ) -> Result<(), Error>;
+
+    /// Moves a pending transaction to a terminal status. Returns `false` if
+    /// it was already resolved.
+    async fn resolve_transaction(
+        &self,
+        transaction_id: &TransactionId,
+        tx_status: TxStatus,
+        tx_hash: Option<String>,
+        reason: Option<&str>,
+    ) -> Result<bool, Error>;
+
+    async fn set_mined_at(
+        &self,
+        transaction_id: &TransactionId,
+    ) -> Result<Option<PropagationTimes>, Error>;
+
+    async fn get_last_mined_propagation_at(&self) -> Result<Option<DateTime<Utc>>, Error>;
+
+    async fn get_spend_since(&self, cutoff: DateTime<Utc>) -> Result<Spend, Error>;
+}
+
+#[async_trait]
+impl StatusStore for Database {
+    async fn initialize_server(&self) -> Result<bool, Error> {
+        DatabaseQuery::initialize_server(&self.pool).await
+    }
+
+    async fn get_service_status(&self) -> Result<Option<ServerStatus>, Error> {
+        DatabaseQuery::get_service_status(&self.pool).await
+    }
+
+    async fn get_db_status(&self) -> Result<Option<BridgeStatus>, Error> {
+        DatabaseQuery::get_db_status(&self.pool).await
+    }
+
+    async fn mark_status_as_unsynced(&self, reason: Option<UnsyncedReason>) -> Result<(), Error> {
+        Self::mark_status_as_unsynced(self, reason).await
+    }
+
+    async fn mark_status_as_pending(&self) -> Result<(), Error> {
+        Self::mark_status_as_pending(self).await
+    }
+
+    async fn mark_status_as_synced(&self) -> Result<(), Error> {
+        Self::mark_status_as_synced(self).await
+    }
+
+    async fn get_last_transaction_status(&self) -> Result<Option<TxStatus>, Error> {
+        DatabaseQuery::get_last_transaction_status(&self.pool).await
+    }
+
+    async fn get_pending_propagation(&self) -> Result<Option<PendingPropagation>, Error> {
+        DatabaseQuery::get_pending_propagation(&self.pool).await
+    }
+
+    async fn insert_new_transaction(
+        &self,
+        transaction_id: &TransactionId,
+        root: U256,
+    ) -> Result<(), Error> {
+        DatabaseQuery::insert_new_transaction(&self.pool, transaction_id, root).await
+    }
+
+    async fn resolve_transaction(
+        &self,
+        transaction_id: &TransactionId,
+        tx_status: TxStatus,
+        tx_hash: Option<String>,
+        reason: Option<&str>,
+    ) -> Result<bool, Error> {
+        DatabaseQuery::resolve_transaction(&self.pool, transaction_id, tx_status, tx_hash, reason)
+            .await
+    }
+
+    async fn set_mined_at(
+        &self,
+        transaction_id: &TransactionId,
+    ) -> Result<Option<PropagationTimes>, Error> {
+        DatabaseQuery::set_mined_at(&self.pool, transaction_id).await
+    }
+
+    async fn get_last_mined_propagation_at(&self) -> Result<Option<DateTime<Utc>>, Error> {
+        DatabaseQuery::get_last_mined_propagation_at(&self.pool).await
+    }
+
+    async fn get_spend_since(&self, cutoff: DateTime<Utc>) -> Result<Spend, Error> {
+        DatabaseQuery::get_spend_since(&self.pool, cutoff).await
+    }
+}
@@ -1,32 +1,94 @@
+use async_trait::async_trait;
 use sqlx::{Postgres, Transaction};
-use tracing::instrument;
+use tracing::{instrument, warn};
 
-use crate::database::query::DatabaseQuery;
-use crate::database::{Database, Error};
-use crate::processor::status::BridgeStatus;
-use crate::retry_tx;
+use crate::{
+    database::{
+        query::DatabaseQuery,
+        types::{ObservedRoot, RootSource},
+        Database, Error,
+    },
+    processor::status::{BridgeStatus, UnsyncedReason},
+    retry_tx,
+    utils::persist_buffer::Flush,
+};
 
-pub async fn mark_status_as_pending(
-    tx: &mut Transaction<'_, Postgres>,
-) -> Result<(), Error> {
+pub async fn mark_status_as_pending(tx: &mut Transaction<'_, Postgres>) -> Result<(), Error> {
     let status = BridgeStatus::Pending;
     tx.update_server_status(status).await?;
+    tx.set_status_reason(None).await?;
     Ok(())
 }
 
 pub async fn mark_status_as_unsynced(
     tx: &mut Transaction<'_, Postgres>,
+    reason: Option<UnsyncedReason>,
 ) -> Result<(), Error> {
     let status = BridgeStatus::Unsynced;
     tx.update_server_status(status).await?;
+    tx.set_status_reason(reason.map(<&str>::from)).await?;
     Ok(())
 }
 
-pub async fn mark_status_as_synced(
-    tx: &mut Transaction<'_, Postgres>,
-) -> Result<(), Error> {
+pub async fn mark_status_as_synced(tx: &mut Transaction<'_, Postgres>) -> Result<(), Error> {
     let status = BridgeStatus::Synced;
     tx.update_server_status(status).await?;
+    tx.set_status_reason(None).await?;
+    Ok(())
+}
+
+/// Records the roots found by one scan. When the scan followed a reorg, the
+/// roots at or after `rewound_to` are deleted first, since the rescan emits
+/// every root in that range that is still canonical.
+pub async fn record_roots(
+    tx: &mut Transaction<'_, Postgres>,
+    source: RootSource,
+    rewound_to: Option<u64>,
+    roots: &[ObservedRoot],
+) -> Result<(), Error> {
+    if let Some(block_number) = rewound_to {
+        let removed = tx.delete_roots_from_block(source, block_number).await?;
+        if removed > 0 {
+            warn!(
+                ?source,
+                block_number, removed, "Removed roots invalidated by a reorg"
+            );
+        }
+    }
+
+    for root in roots {
+        tx.insert_root(source, root).await?;
+    }
+
+    Ok(())
+}
+
+/// Records a backfilled window together with how far the backfill got, so
+/// progress never runs ahead of the roots behind it.
+pub async fn record_backfill(
+    tx: &mut Transaction<'_, Postgres>,
+    source: RootSource,
+    rewound_to: Option<u64>,
+    roots: &[ObservedRoot],
+    next_block: u64,
+) -> Result<(), Error> {
+    record_roots(tx, source, rewound_to, roots).await?;
+    tx.set_scan_progress(source, next_block).await?;
+    Ok(())
+}
+
+/// Rows per statement when bulk inserting roots, keeping well under the
+/// Postgres limit of 65535 bind parameters.
+const ROOT_INSERT_CHUNK: usize = 1000;
+
+pub async fn insert_roots(
+    tx: &mut Transaction<'_, Postgres>,
+    rows: &[(RootSource, ObservedRoot)],
+) -> Result<(), Error> {
+    for chunk in rows.chunks(ROOT_INSERT_CHUNK) {
+        tx.insert_roots(chunk).await?;
+    }
+
     Ok(())
 }
 
@@ -44,9 +106,63 @@ impl Database {
         retry_tx!(self.pool, tx, mark_status_as_synced(&mut tx).await).await
     }
 
-    /// marks following server as unsynced
+    /// marks following server as unsynced, with why if it is known
     #[instrument(skip(self), level = "debug")]
-    pub async fn mark_status_as_unsynced(&self) -> Result<(), Error> {
-        retry_tx!(self.pool, tx, mark_status_as_unsynced(&mut tx).await).await
-    }   
+    pub async fn mark_status_as_unsynced(
+        &self,
+        reason: Option<UnsyncedReason>,
+    ) -> Result<(), Error> {
+        retry_tx!(
+            self.pool,
+            tx,
+            mark_status_as_unsynced(&mut tx, reason).await
+        )
+        .await
+    }
+
+    /// records the roots found by a scan, reconciling a reorged range
+    #[instrument(skip(self, roots), level = "debug")]
+    pub async fn record_roots(
+        &self,
+        source: RootSource,
+        rewound_to: Option<u64>,
+        roots: &[ObservedRoot],
+    ) -> Result<(), Error> {
+        retry_tx!(
+            self.pool,
+            tx,
+            record_roots(&mut tx, source, rewound_to, roots).await
+        )
+        .await
+    }
+
+    /// records a backfilled window and the block the backfill resumes from
+    #[instrument(skip(self, roots), level = "debug")]
+    pub async fn record_backfill(
+        &self,
+        source: RootSource,
+        rewound_to: Option<u64>,
+        roots: &[ObservedRoot],
+        next_block: u64,
+    ) -> Result<(), Error> {
+        retry_tx!(
+            self.pool,
+            tx,
+            record_backfill(&mut tx, source, rewound_to, roots, next_block).await
+        )
+        .await
+    }
+
+    /// inserts roots from any source in bulk
+    #[instrument(skip(self, rows), level = "debug")]
+    pub async fn insert_roots(&self, rows: &[(RootSource, ObservedRoot)]) -> Result<(), Error> {
+        retry_tx!(self.pool, tx, insert_roots(&mut tx, rows).await).await
+    }
+}
+
+#[async_trait]
+impl Flush<(RootSource, ObservedRoot)> for Database {
+    async fn flush(&self, rows: &[(RootSource, ObservedRoot)]) -> anyhow::Result<()> {
+        Ok(self.insert_roots(rows).await?)
+    }
 }
@@ -1,20 +1,196 @@
 use chrono::{DateTime, Utc};
-use sqlx::prelude::FromRow;
+use ethers::types::{H256, U256};
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+use sqlx::prelude::FromRow;
+#[cfg(feature = "server")]
+use std::fmt;
+use std::{str::FromStr, time::Duration};
 use thiserror::Error;
 
-#[derive(Debug, Clone, FromRow, Serialize, Deserialize, PartialEq)]
+use crate::{processor::status::BridgeStatus, utils::TransactionId};
+
+/// The `service_status` row. Read through [`DatabaseQuery`], which fails on
+/// a status this service doesn't know.
+///
+/// [`DatabaseQuery`]: crate::database::query::DatabaseQuery
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ServerStatus {
-    pub status: String,
+    pub status:      BridgeStatus,
+    /// Why the service is unsynced, e.g. `neverBridged`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason:      Option<String>,
+    pub last_synced: Option<DateTime<Utc>>,
+}
+
+/// `last_synced` read along with the database's clock, so an age taken from
+/// it doesn't depend on the clock of whoever reads it.
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LastSync {
+    /// `None` before the server was initialized
+    pub status:      Option<BridgeStatus>,
     pub last_synced: Option<DateTime<Utc>>,
+    /// `CURRENT_TIMESTAMP` on the database server
+    pub now:         DateTime<Utc>,
+}
+
+/// The replica that last took the leadership lock, as it recorded itself.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderRecord {
+    /// `service_name` of the leader
+    pub instance:     String,
+    pub acquired_at:  DateTime<Utc>,
+    /// Stops advancing once the leader is gone
+    pub heartbeat_at: DateTime<Utc>,
+}
+
+/// The latest pending propagation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingPropagation {
+    pub transaction_id: TransactionId,
+    /// The mainnet root it was sent for, unknown for transactions recorded
+    /// before roots were
+    pub root:           Option<U256>,
+}
+
+/// What propagations cost over some period.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Spend {
+    /// In wei
+    pub total:     U256,
+    /// Transactions whose cost was recorded
+    pub count:     u64,
+    /// Gas used by those, `0` for the ones recorded before it was
+    pub gas_used:  u64,
+    /// Of those, the ones priced at the transaction's `gasPrice`
+    pub estimated: u64,
+    /// Transactions that paid for gas but whose cost wasn't recorded
+    pub unknown:   u64,
+}
+
+/// When a propagation was submitted, seen mined on mainnet and seen added on
+/// Scroll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PropagationTimes {
+    pub created_at:   DateTime<Utc>,
+    pub mined_at:     Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
 }
 
+impl PropagationTimes {
+    /// From submission until mined, once mined.
+    #[must_use]
+    pub fn until_mined(&self) -> Option<Duration> {
+        self.mined_at
+            .map(|mined_at| elapsed(self.created_at, mined_at))
+    }
 
-#[derive(
-    Debug, Clone, Serialize, Deserialize, Copy, PartialEq, Eq, sqlx::Type
-)]
+    /// From mined until delivered, once both were seen.
+    #[must_use]
+    pub fn until_delivered(&self) -> Option<Duration> {
+        Some(elapsed(self.mined_at?, self.delivered_at?))
+    }
+}
+
+/// Zero when `to` was observed first, as a lagging monitor or scan can make
+/// it look.
+fn elapsed(from: DateTime<Utc>, to: DateTime<Utc>) -> Duration {
+    (to - from).to_std().unwrap_or_default()
+}
+
+/// A propagation and the Scroll side it led to, as `/propagations` lists
+/// them. Fields the scans or the monitor haven't seen yet are `null`.
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PropagationRecord {
+    pub transaction_id:     TransactionId,
+    /// The mainnet root it was sent for, `null` for transactions recorded
+    /// before roots were
+    pub root:               Option<String>,
+    pub submitted_at:       DateTime<Utc>,
+    /// The mainnet transaction, once the monitor read it
+    pub tx_hash:            Option<String>,
+    /// `gasUsed * effectiveGasPrice` in wei, as hex
+    pub cost:               Option<String>,
+    pub mined_at:           Option<DateTime<Utc>>,
+    /// When the Scroll scan first saw `RootAdded` for the root after the
+    /// submission, and in which transaction
+    pub root_added_at:      Option<DateTime<Utc>>,
+    pub root_added_tx_hash: Option<String>,
+    pub outcome:            TxStatus,
+    pub resolution_reason:  Option<String>,
+}
+
+#[cfg(feature = "server")]
+impl PropagationRecord {
+    /// Where the page after this record starts.
+    #[must_use]
+    pub fn cursor(&self) -> PropagationCursor {
+        PropagationCursor {
+            submitted_at:   self.submitted_at,
+            transaction_id: self.transaction_id.clone(),
+        }
+    }
+}
+
+/// A position in the propagations, newest first: the ones submitted before
+/// `submitted_at`, or at the same time with a smaller id. Written as
+/// `<unix microseconds>:<transaction id>`, the precision Postgres keeps.
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropagationCursor {
+    pub submitted_at:   DateTime<Utc>,
+    pub transaction_id: TransactionId,
+}
+
+#[cfg(feature = "server")]
+impl fmt::Display for PropagationCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}",
+            self.submitted_at.timestamp_micros(),
+            self.transaction_id
+        )
+    }
+}
+
+#[cfg(feature = "server")]
+#[derive(Debug, Error)]
+#[error("invalid cursor {0:?}")]
+pub struct InvalidCursor(String);
+
+#[cfg(feature = "server")]
+impl FromStr for PropagationCursor {
+    type Err = InvalidCursor;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidCursor(s.to_string());
+        let (micros, transaction_id) = s.split_once(':').ok_or_else(invalid)?;
+
+        Ok(Self {
+            submitted_at:   micros
+                .parse()
+                .ok()
+                .and_then(DateTime::from_timestamp_micros)
+                .ok_or_else(invalid)?,
+            transaction_id: transaction_id.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// A pause recorded in `service_status`.
+#[derive(Debug, Clone, FromRow, PartialEq, Eq)]
+pub struct PauseRecord {
+    pub pause_reason:     String,
+    pub paused_at:        DateTime<Utc>,
+    pub pause_expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Copy, PartialEq, Eq, sqlx::Type)]
 #[sqlx(rename_all = "camelCase")]
 #[sqlx(type_name = "tx_status")]
 #[serde(rename_all = "camelCase")]
@@ -22,7 +198,34 @@ pub enum TxStatus {
     Pending,
     Mined,
     Finalized,
-    Failed
+    Failed,
+    /// Never resolved on chain within the allowed time
+    Abandoned,
+    /// Its nonce was consumed by a different transaction
+    Superseded,
+    /// Replaced at its nonce on an operator's request
+    Cancelled,
+    /// Mined before the replacement an operator requested
+    #[sqlx(rename = "superseded_by_original")]
+    #[serde(rename = "superseded_by_original")]
+    SupersededByOriginal,
+}
+
+impl TxStatus {
+    /// Whether the transaction has reached a final outcome
+    #[must_use]
+    pub const fn is_terminal(self) -> bool {
+        !matches!(self, Self::Pending)
+    }
+
+    /// Whether the transaction mined and propagated its root
+    #[must_use]
+    pub const fn is_mined(self) -> bool {
+        matches!(
+            self,
+            Self::Mined | Self::Finalized | Self::SupersededByOriginal
+        )
+    }
 }
 
 #[derive(Debug, Error)]
@@ -38,26 +241,74 @@ impl FromStr for TxStatus {
             "pending" => Ok(Self::Pending),
             "mined" => Ok(Self::Mined),
             "finalized" => Ok(Self::Finalized),
+            "abandoned" => Ok(Self::Abandoned),
+            "superseded" => Ok(Self::Superseded),
+            "cancelled" => Ok(Self::Cancelled),
+            "superseded_by_original" => Ok(Self::SupersededByOriginal),
             _ => Err(UnknownStatus),
         }
     }
 }
 
 impl TryFrom<&str> for TxStatus {
-  type Error = UnknownStatus;
+    type Error = UnknownStatus;
 
-  fn try_from(s: &str) -> Result<Self, Self::Error> {
-    TxStatus::from_str(s)
-  }
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        TxStatus::from_str(s)
+    }
 }
 
 impl From<TxStatus> for &str {
-  fn from(scope: TxStatus) -> Self {
-      match scope {
-        TxStatus::Pending => "pending",
-        TxStatus::Mined => "mined",
-        TxStatus::Finalized => "finalized",
-        TxStatus::Failed => "failed"
-      }
-  }
+    fn from(scope: TxStatus) -> Self {
+        match scope {
+            TxStatus::Pending => "pending",
+            TxStatus::Mined => "mined",
+            TxStatus::Finalized => "finalized",
+            TxStatus::Failed => "failed",
+            TxStatus::Abandoned => "abandoned",
+            TxStatus::Superseded => "superseded",
+            TxStatus::Cancelled => "cancelled",
+            TxStatus::SupersededByOriginal => "superseded_by_original",
+        }
+    }
+}
+
+/// The contract a root was observed on.
+#[derive(Debug, Clone, Serialize, Deserialize, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(rename_all = "lowercase")]
+#[sqlx(type_name = "root_source")]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum RootSource {
+    /// `RootPropagated` on the L1 state bridge
+    Bridge,
+    /// `RootAdded` on the Scroll WorldID contract
+    Scroll,
+    /// `TreeChanged` on the mainnet WorldID contract, the canonical roots
+    #[sqlx(rename = "world_id")]
+    #[serde(rename = "world_id")]
+    WorldId,
+}
+
+impl RootSource {
+    pub const ALL: [Self; 3] = [Self::Bridge, Self::Scroll, Self::WorldId];
+}
+
+impl From<RootSource> for &str {
+    fn from(source: RootSource) -> Self {
+        match source {
+            RootSource::Bridge => "bridge",
+            RootSource::Scroll => "scroll",
+            RootSource::WorldId => "world_id",
+        }
+    }
+}
+
+/// A root decoded from a scanned log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObservedRoot {
+    pub root:         U256,
+    pub block_number: u64,
+    pub tx_hash:      H256,
+    pub log_index:    u64,
 }
@@ -1,87 +1,314 @@
-use std::sync::Arc;
+use std::{env, sync::Arc};
 
-use anyhow::bail;
-use ethers::types::transaction::eip2718::TypedTransaction;
-use ethers::types::Address;
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Utc};
+use ethers::{
+    providers::{Authorization, Middleware},
+    types::{transaction::eip2718::TypedTransaction, Address, H256, U256},
+};
 pub use read::ReadProvider;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
 use tracing::instrument;
-pub use write::TxError;
+pub use write::{GasCost, PendingState, PendingTransaction, TransactionState, TxError};
 
-use self::write_provider::WriteProvider;
-use crate::config::Config;
-pub type TransactionId = String;
+use self::{read::Endpoint, write_provider::WriteProvider};
+use crate::{
+    config::{Config, ProviderEndpoint, ProviderUrls},
+    exit,
+    utils::TransactionId,
+};
 
 pub mod read;
 pub mod write;
 
 mod write_provider;
 
+/// The mainnet and Scroll providers, and the relayer propagations are sent
+/// through.
 #[derive(Clone, Debug)]
 pub struct Ethereum {
-    l1_read_provider:   Arc<ReadProvider>,
-    l1_write_provider:  Arc<WriteProvider>,
-    l2_read_provider:   Arc<ReadProvider>,
+    l1_read_provider:  Arc<ReadProvider>,
+    /// `None` when read only
+    l1_write_provider: Option<Arc<WriteProvider>>,
+    l2_read_provider:  Arc<ReadProvider>,
 }
 
 impl Ethereum {
+    /// # Errors
+    ///
+    /// Will return `Err` if the providers or the relayer config are missing,
+    /// or a provider or the relayer can't be reached.
     #[instrument(name = "Ethereum::new", level = "debug", skip_all)]
     pub async fn new(config: &Config) -> anyhow::Result<Self> {
+        let Some(relayer_config) = &config.relayer else {
+            bail!("Relayer config is required for Ethereum.");
+        };
+
+        let mut ethereum = Self::read_only(config).await?;
+
+        // The raw signer signs for the chain id of the provider it is given,
+        // which was verified when it was connected
+        let l1_write_provider = WriteProvider::new(
+            ethereum.l1_read_provider.as_ref().clone(),
+            relayer_config,
+            &config.fees,
+        )
+        .await?;
+        ethereum.l1_write_provider = Some(Arc::new(l1_write_provider));
+
+        Ok(ethereum)
+    }
+
+    /// Only the read providers, for commands that never send a
+    /// transaction. The relayer isn't contacted.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the providers config is missing or a provider
+    /// can't be reached.
+    #[instrument(name = "Ethereum::read_only", level = "debug", skip_all)]
+    pub async fn read_only(config: &Config) -> anyhow::Result<Self> {
         let Some(providers_config) = &config.providers else {
             bail!("Providers config is required for Ethereum.");
         };
 
-        let Some(relayer_config) = &config.relayer else {
-            bail!("Relayer config is required for Ethereum.");
+        let read_provider = |path: &str, urls: &ProviderUrls| {
+            let endpoints = endpoints(path, urls);
+            async move {
+                ReadProvider::new(
+                    endpoints?,
+                    providers_config.failover_cooldown,
+                    providers_config.request_timeout,
+                )
+                .await
+            }
         };
 
-        let l1_read_provider =
-            ReadProvider::new(providers_config.l1_network_provider.clone().into()).await?;
-
-        let l2_read_provider = ReadProvider::new(providers_config.l2_network_provider.clone().into()).await?;
+        let l1_read_provider = read_provider(
+            "providers.l1_network_provider",
+            &providers_config.l1_network_provider,
+        )
+        .await?;
+        verify_chain_id(
+            "l1",
+            &l1_read_provider,
+            providers_config.l1_expected_chain_id,
+        )?;
 
-        let l1_write_provider: Arc<WriteProvider> =
-            Arc::new(WriteProvider::new(l1_read_provider.clone(), relayer_config).await?);
+        let l2_read_provider = read_provider(
+            "providers.l2_network_provider",
+            &providers_config.l2_network_provider,
+        )
+        .await?;
+        verify_chain_id(
+            "l2",
+            &l2_read_provider,
+            providers_config.l2_expected_chain_id,
+        )?;
 
         Ok(Self {
-            l1_read_provider: Arc::new(l1_read_provider),
-            l2_read_provider: Arc::new(l2_read_provider),
-            l1_write_provider,
+            l1_read_provider:  Arc::new(l1_read_provider),
+            l2_read_provider:  Arc::new(l2_read_provider),
+            l1_write_provider: None,
         })
     }
 
+    fn write_provider(&self) -> Result<&WriteProvider, TxError> {
+        self.l1_write_provider
+            .as_deref()
+            .ok_or_else(|| TxError::Other(anyhow!("no relayer, the providers are read only")))
+    }
+
     #[must_use]
     pub const fn l1_provider(&self) -> &Arc<ReadProvider> {
         &self.l1_read_provider
     }
 
     #[must_use]
-    pub const fn l2_provider(&self) -> &Arc<ReadProvider>{
+    pub const fn l2_provider(&self) -> &Arc<ReadProvider> {
         &self.l2_read_provider
     }
 
+    /// The address transactions are sent from, zero when read only.
     #[must_use]
     pub fn address(&self) -> Address {
-        self.l1_write_provider.address()
+        self.l1_write_provider
+            .as_ref()
+            .map_or_else(Address::zero, |provider| provider.address())
     }
 
-    pub async fn send_transaction(
+    #[instrument(level = "info", skip(self, tx))]
+    pub(crate) async fn send_transaction(
         &self,
         tx: TypedTransaction,
         only_once: bool,
     ) -> Result<TransactionId, TxError> {
         tracing::info!(?tx, "Sending transaction");
-        self.l1_write_provider.send_transaction(tx, only_once).await
+        self.write_provider()?.send_transaction(tx, only_once).await
+    }
+
+    /// What the relayer still has in flight.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the providers are read only or the relayer can't
+    /// be reached.
+    pub async fn fetch_pending_transactions(&self) -> Result<Vec<PendingTransaction>, TxError> {
+        self.write_provider()?.fetch_pending_transactions().await
+    }
+
+    pub(crate) async fn pending_states(
+        &self,
+        transaction_ids: Vec<TransactionId>,
+    ) -> Result<Vec<PendingTransaction>, TxError> {
+        self.write_provider()?.pending_states(transaction_ids).await
+    }
+
+    pub(crate) async fn cancel_transaction(&self, tx: TransactionId) -> Result<bool, TxError> {
+        self.write_provider()?.cancel_transaction(tx).await
+    }
+
+    pub(crate) async fn replace_transaction(
+        &self,
+        tx: &PendingTransaction,
+    ) -> Result<bool, TxError> {
+        self.write_provider()?.replace_transaction(tx).await
+    }
+
+    pub(crate) async fn cancel_in_flight(&self, tx: &PendingTransaction) -> Result<bool, TxError> {
+        self.write_provider()?.cancel_in_flight(tx).await
     }
 
-    pub async fn fetch_pending_transactions(&self) -> Result<Vec<TransactionId>, TxError> {
-        self.l1_write_provider.fetch_pending_transactions().await
+    pub(crate) async fn sent_to_self(&self, hash: H256) -> Result<bool, TxError> {
+        self.write_provider()?.sent_to_self(hash).await
     }
 
-    pub async fn fetch_mined_transactions(&self) -> Result<Vec<TransactionId>, TxError> {
-        self.l1_write_provider.fetch_mined_transactions().await
+    pub(crate) async fn fetch_mined_transactions(&self) -> Result<Vec<TransactionId>, TxError> {
+        self.write_provider()?.fetch_mined_transactions().await
     }
 
-    pub async fn mine_transaction(&self, tx: TransactionId) -> Result<bool, TxError> {
-        self.l1_write_provider.mine_transaction(tx).await
+    #[instrument(level = "info", skip_all, fields(%tx))]
+    pub(crate) async fn mine_transaction(&self, tx: TransactionId) -> Result<bool, TxError> {
+        self.write_provider()?.mine_transaction(tx).await
+    }
+
+    pub(crate) async fn transaction_state(
+        &self,
+        tx: TransactionId,
+    ) -> Result<TransactionState, TxError> {
+        self.write_provider()?.transaction_state(tx).await
+    }
+
+    /// When the mainnet transaction `hash` mined, the timestamp of its block.
+    /// `None` without a receipt.
+    pub(crate) async fn mined_at(&self, hash: H256) -> anyhow::Result<Option<DateTime<Utc>>> {
+        let receipt = self.l1_read_provider.get_transaction_receipt(hash).await?;
+        let Some(block_number) = receipt.and_then(|receipt| receipt.block_number) else {
+            return Ok(None);
+        };
+
+        let block = self.l1_read_provider.get_block(block_number).await?;
+        Ok(block.map(|block| block.time()).transpose()?)
+    }
+}
+
+/// The endpoints to connect to for the providers at `path`, with their
+/// headers and basic auth, whose password is read from `password_env`.
+fn endpoints(path: &str, urls: &ProviderUrls) -> Result<Vec<Endpoint>, exit::Error> {
+    urls.endpoints()
+        .iter()
+        .map(|endpoint| {
+            Ok(Endpoint {
+                url:             endpoint.url.clone().into(),
+                headers:         headers(path, endpoint)?,
+                request_timeout: endpoint.request_timeout,
+            })
+        })
+        .collect()
+}
+
+fn headers(path: &str, endpoint: &ProviderEndpoint) -> Result<HeaderMap, exit::Error> {
+    let sensitive = |value: &str| {
+        let mut value = HeaderValue::from_str(value).map_err(|_| {
+            exit::Error::Config(anyhow!("{path} has a header value that is invalid"))
+        })?;
+        value.set_sensitive(true);
+        Ok::<_, exit::Error>(value)
+    };
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in &endpoint.headers {
+        let name = HeaderName::from_bytes(name.as_bytes()).map_err(|err| {
+            exit::Error::Config(anyhow!("{path} has an invalid header name {name:?}: {err}"))
+        })?;
+        headers.insert(name, sensitive(value.expose())?);
+    }
+
+    if let Some(username) = &endpoint.username {
+        let password = match &endpoint.password_env {
+            Some(var) => env::var(var).map_err(|err| {
+                let path = format!("{path}.password_env");
+                exit::Error::Config(anyhow!("{path} names {var}, which is unreadable: {err}"))
+            })?,
+            None => String::new(),
+        };
+        let authorization = Authorization::basic(username, password).to_string();
+        headers.insert(AUTHORIZATION, sensitive(&authorization)?);
+    }
+
+    Ok(headers)
+}
+
+/// Fails startup when `provider` serves another chain than configured, which
+/// restarting won't fix.
+fn verify_chain_id(
+    network: &str,
+    provider: &ReadProvider,
+    expected: Option<u64>,
+) -> Result<(), exit::Error> {
+    match expected {
+        Some(expected) if provider.chain_id != U256::from(expected) => {
+            Err(exit::Error::Config(anyhow!(
+                "{network} provider is on chain {}, but {network}_expected_chain_id is {expected}",
+                provider.chain_id
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use ethers::core::utils::Anvil;
+
+    use super::*;
+
+    #[tokio::test]
+    #[ignore = "requires anvil"]
+    async fn provider_on_another_chain_is_a_config_error() -> anyhow::Result<()> {
+        let anvil = Anvil::new().spawn();
+        let provider = ReadProvider::new(
+            vec![anvil.endpoint().parse()?],
+            Duration::from_secs(30),
+            Duration::from_secs(30),
+        )
+        .await?;
+
+        verify_chain_id("l1", &provider, None)?;
+        verify_chain_id("l1", &provider, Some(anvil.chain_id()))?;
+
+        let err = verify_chain_id("l1", &provider, Some(1)).unwrap_err();
+        assert!(matches!(err, exit::Error::Config(_)));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "invalid configuration: l1 provider is on chain {}, but l1_expected_chain_id is 1",
+                anvil.chain_id()
+            )
+        );
+
+        Ok(())
     }
 }
@@ -0,0 +1,334 @@
+use std::{
+    fmt::{self, Debug},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use ::prometheus::{register_int_counter_vec, IntCounterVec};
+use async_trait::async_trait;
+use ethers::providers::{JsonRpcClient, RpcError};
+use once_cell::sync::Lazy;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+static SWITCHES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "eth_rpc_provider_switches",
+        "Number of times requests moved to another Ethereum provider.",
+        &["from", "to"]
+    )
+    .unwrap()
+});
+
+/// JSON-RPC error codes that mean the provider, not the request, is the
+/// problem.
+const RATE_LIMITED: [i64; 2] = [429, -32005];
+
+/// A transport that sends each request to the active provider and moves on to
+/// the next one when it is unreachable or rate limited.
+///
+/// A provider that fails is skipped until its cooldown has passed, unless
+/// every provider is cooling down.
+pub struct Failover<T> {
+    shared: Arc<Shared<T>>,
+}
+
+struct Shared<T> {
+    endpoints: Vec<Endpoint<T>>,
+    active:    AtomicUsize,
+    cooldown:  Duration,
+}
+
+struct Endpoint<T> {
+    /// Host of the provider url, safe to log
+    name:         String,
+    client:       T,
+    failed_until: Mutex<Option<Instant>>,
+}
+
+impl<T> Endpoint<T> {
+    fn is_cooling_down(&self, now: Instant) -> bool {
+        self.failed_until
+            .lock()
+            .unwrap()
+            .is_some_and(|until| now < until)
+    }
+}
+
+impl<T> Failover<T> {
+    /// # Panics
+    ///
+    /// Panics if `clients` is empty.
+    pub fn new(clients: Vec<(String, T)>, cooldown: Duration) -> Self {
+        assert!(!clients.is_empty(), "at least one provider is required");
+
+        let endpoints = clients
+            .into_iter()
+            .map(|(name, client)| Endpoint {
+                name,
+                client,
+                failed_until: Mutex::new(None),
+            })
+            .collect();
+
+        Self {
+            shared: Arc::new(Shared {
+                endpoints,
+                active: AtomicUsize::new(0),
+                cooldown,
+            }),
+        }
+    }
+
+    /// The providers to try, starting with the active one. Providers that are
+    /// cooling down go last.
+    fn order(&self) -> Vec<usize> {
+        let endpoints = &self.shared.endpoints;
+        let active = self.shared.active.load(Ordering::Relaxed);
+        let now = Instant::now();
+
+        let (healthy, cooling): (Vec<usize>, Vec<usize>) = (0..endpoints.len())
+            .map(|offset| (active + offset) % endpoints.len())
+            .partition(|&index| !endpoints[index].is_cooling_down(now));
+
+        healthy.into_iter().chain(cooling).collect()
+    }
+
+    fn mark_failed(&self, index: usize) {
+        let until = Instant::now() + self.shared.cooldown;
+        *self.shared.endpoints[index].failed_until.lock().unwrap() = Some(until);
+    }
+
+    fn mark_healthy(&self, index: usize) {
+        *self.shared.endpoints[index].failed_until.lock().unwrap() = None;
+
+        let previous = self.shared.active.swap(index, Ordering::Relaxed);
+        if previous != index {
+            let from = &self.shared.endpoints[previous].name;
+            let to = &self.shared.endpoints[index].name;
+            info!(%from, %to, "Switched Ethereum provider");
+            SWITCHES.with_label_values(&[from, to]).inc();
+        }
+    }
+}
+
+impl<T> Clone for Failover<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Debug for Failover<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self
+            .shared
+            .endpoints
+            .iter()
+            .map(|endpoint| endpoint.name.as_str())
+            .collect();
+
+        f.debug_struct("Failover")
+            .field("providers", &names)
+            .field("active", &self.shared.active.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+/// Whether the request should be retried on another provider.
+fn is_provider_failure<E: RpcError>(err: &E) -> bool {
+    match err.as_error_response() {
+        Some(response) => RATE_LIMITED.contains(&response.code),
+        // Connection errors, timeouts and non JSON-RPC bodies such as 5xx
+        // pages
+        None => true,
+    }
+}
+
+#[async_trait]
+impl<T> JsonRpcClient for Failover<T>
+where
+    T: JsonRpcClient + 'static,
+    <T as JsonRpcClient>::Error: Sync + Send + 'static,
+{
+    type Error = T::Error;
+
+    async fn request<P, R>(&self, method: &str, params: P) -> Result<R, Self::Error>
+    where
+        P: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let mut last_error = None;
+
+        for index in self.order() {
+            let endpoint = &self.shared.endpoints[index];
+
+            match endpoint.client.request(method, &params).await {
+                Ok(response) => {
+                    self.mark_healthy(index);
+                    return Ok(response);
+                }
+                Err(err) if is_provider_failure(&err) => {
+                    warn!(provider = %endpoint.name, method, %err, "Ethereum provider failed");
+                    self.mark_failed(index);
+                    last_error = Some(err);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Err(last_error.expect("there is at least one provider"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use ethers::providers::{JsonRpcError, MockError};
+    use serde_json::Value;
+
+    use super::*;
+
+    /// Answers every request with the next scripted outcome, and an
+    /// unreachable-style error once the script runs out.
+    #[derive(Debug, Default)]
+    struct Scripted {
+        outcomes: Mutex<VecDeque<Result<Value, MockError>>>,
+        calls:    AtomicUsize,
+    }
+
+    impl Scripted {
+        fn new(outcomes: impl IntoIterator<Item = Result<Value, MockError>>) -> Arc<Self> {
+            Arc::new(Self {
+                outcomes: Mutex::new(outcomes.into_iter().collect()),
+                calls:    AtomicUsize::new(0),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::Relaxed)
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct Client(Arc<Scripted>);
+
+    #[async_trait]
+    impl JsonRpcClient for Client {
+        type Error = MockError;
+
+        async fn request<P, R>(&self, _method: &str, _params: P) -> Result<R, Self::Error>
+        where
+            P: Debug + Serialize + Send + Sync,
+            R: DeserializeOwned + Send,
+        {
+            self.0.calls.fetch_add(1, Ordering::Relaxed);
+            let outcome = self
+                .0
+                .outcomes
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or(Err(MockError::EmptyResponses));
+
+            Ok(serde_json::from_value(outcome?)?)
+        }
+    }
+
+    fn rpc_error(code: i64) -> MockError {
+        MockError::JsonRpcError(JsonRpcError {
+            code,
+            message: "error".to_string(),
+            data: None,
+        })
+    }
+
+    fn failover(clients: &[&Arc<Scripted>]) -> Failover<Client> {
+        let clients = clients
+            .iter()
+            .enumerate()
+            .map(|(index, scripted)| (format!("provider-{index}"), Client((*scripted).clone())))
+            .collect();
+
+        Failover::new(clients, Duration::from_secs(30))
+    }
+
+    async fn block_number(failover: &Failover<Client>) -> Result<u64, MockError> {
+        failover.request("eth_blockNumber", ()).await
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unreachable_provider_fails_over_to_the_next() -> anyhow::Result<()> {
+        let primary = Scripted::new([]);
+        let secondary = Scripted::new([Ok(1.into()), Ok(2.into())]);
+        let failover = failover(&[&primary, &secondary]);
+
+        assert_eq!(block_number(&failover).await?, 1);
+
+        // The primary is cooling down, so it isn't tried again
+        assert_eq!(block_number(&failover).await?, 2);
+        assert_eq!(primary.calls(), 1);
+        assert_eq!(secondary.calls(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limited_provider_fails_over() -> anyhow::Result<()> {
+        let primary = Scripted::new([Err(rpc_error(429))]);
+        let secondary = Scripted::new([Ok(7.into())]);
+        let failover = failover(&[&primary, &secondary]);
+
+        assert_eq!(block_number(&failover).await?, 7);
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn request_errors_are_not_retried_elsewhere() {
+        let primary = Scripted::new([Err(rpc_error(3))]);
+        let secondary = Scripted::new([Ok(7.into())]);
+        let failover = failover(&[&primary, &secondary]);
+
+        let err = block_number(&failover).await.unwrap_err();
+
+        assert_eq!(err.as_error_response().map(|err| err.code), Some(3));
+        assert_eq!(secondary.calls(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_provider_is_retried_after_cooldown() -> anyhow::Result<()> {
+        let primary = Scripted::new([Err(MockError::EmptyResponses), Ok(3.into())]);
+        let secondary = Scripted::new([Ok(1.into()), Err(MockError::EmptyResponses)]);
+        let failover = failover(&[&primary, &secondary]);
+
+        assert_eq!(block_number(&failover).await?, 1);
+
+        tokio::time::advance(Duration::from_secs(31)).await;
+
+        // The secondary is active and fails, the primary is healthy again
+        assert_eq!(block_number(&failover).await?, 3);
+        assert_eq!(primary.calls(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn every_provider_is_tried_when_all_are_cooling_down() {
+        let primary = Scripted::new([]);
+        let secondary = Scripted::new([]);
+        let failover = failover(&[&primary, &secondary]);
+
+        assert!(block_number(&failover).await.is_err());
+        assert!(block_number(&failover).await.is_err());
+
+        assert_eq!(primary.calls(), 2);
+        assert_eq!(secondary.calls(), 2);
+    }
+}
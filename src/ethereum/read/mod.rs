@@ -1,18 +1,52 @@
+use std::{str::FromStr, time::Duration};
+
 use anyhow::anyhow;
 use chrono::{Duration as ChronoDuration, Utc};
-use ethers::abi::Error as AbiError;
-use ethers::providers::{Http, Middleware, Provider};
-use ethers::types::{BlockId, BlockNumber, Chain, U256};
+use ethers::{
+    providers::{Middleware, Provider},
+    types::{BlockId, BlockNumber, Chain, U256},
+};
 use futures::{try_join, FutureExt};
-use thiserror::Error;
+use reqwest::header::HeaderMap;
 use tracing::{error, info};
 use url::Url;
 
-use self::rpc_logger::RpcLogger;
+use self::{failover::Failover, rpc_logger::RpcLogger, transport::Transport};
 
+pub mod failover;
 pub mod rpc_logger;
+pub mod transport;
+
+type InnerProvider = Provider<RpcLogger<Failover<Transport>>>;
 
-type InnerProvider = Provider<RpcLogger<Http>>;
+/// A provider url, with what the http client sends along to it.
+#[derive(Clone)]
+pub struct Endpoint {
+    pub url:             Url,
+    /// Sent with every http request. Marked sensitive, so they are never
+    /// logged
+    pub headers:         HeaderMap,
+    /// Overrides the `request_timeout` of [`ReadProvider::new`]
+    pub request_timeout: Option<Duration>,
+}
+
+impl From<Url> for Endpoint {
+    fn from(url: Url) -> Self {
+        Self {
+            url,
+            headers: HeaderMap::new(),
+            request_timeout: None,
+        }
+    }
+}
+
+impl FromStr for Endpoint {
+    type Err = url::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Url::from_str(s).map(Self::from)
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct ReadProvider {
@@ -22,20 +56,39 @@ pub struct ReadProvider {
 }
 
 impl ReadProvider {
-    pub async fn new(url: Url) -> anyhow::Result<Self> {
+    /// Connects to the first reachable of `endpoints`. Later requests fail
+    /// over between them in order. Ws(s) urls are connected to up front and
+    /// send neither headers nor apply `request_timeout`.
+    pub async fn new(
+        endpoints: Vec<Endpoint>,
+        failover_cooldown: Duration,
+        request_timeout: Duration,
+    ) -> anyhow::Result<Self> {
         // Connect to the Ethereum provider
-        // TODO: Allow multiple providers with failover / broadcast.
         // TODO: Requests don't seem to process in parallel. Check if this is
         // a limitation client side or server side.
-        // TODO: Does the WebSocket impl handle dropped connections by
-        // reconnecting? What is the timeout on stalled connections? What is
-        // the retry policy?
         let (provider, chain_id, eip1559) = {
-            info!(
-                provider = %url,
-                "Connecting to provider"
-            );
-            let transport = Http::new(url);
+            let mut transports = Vec::with_capacity(endpoints.len());
+            for endpoint in endpoints {
+                let client = reqwest::Client::builder()
+                    .timeout(endpoint.request_timeout.unwrap_or(request_timeout))
+                    .default_headers(endpoint.headers)
+                    .build()?;
+
+                let url = endpoint.url;
+                let name = url.host_str().unwrap_or("unknown").to_string();
+                info!(provider = %name, scheme = url.scheme(), "Connecting to provider");
+                let transport = Transport::connect(url, &client)
+                    .await
+                    .map_err(|err| anyhow!("Failed to connect to provider {name}: {err}"))?;
+                transports.push((name, transport));
+            }
+
+            if transports.is_empty() {
+                return Err(anyhow!("At least one provider url is required"));
+            }
+
+            let transport = Failover::new(transports, failover_cooldown);
             let logger = RpcLogger::new(transport);
             let provider = Provider::new(logger);
 
@@ -97,9 +150,3 @@ impl Middleware for ReadProvider {
         &self.inner
     }
 }
-
-#[derive(Debug, Error)]
-pub enum EventError {
-    #[error("Error parsing log event: {0}")]
-    Parsing(#[from] AbiError),
-}
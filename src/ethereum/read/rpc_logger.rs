@@ -4,8 +4,7 @@ use ::prometheus::{register_histogram, register_int_counter_vec, Histogram, IntC
 use async_trait::async_trait;
 use ethers::providers::JsonRpcClient;
 use once_cell::sync::Lazy;
-use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
 use tracing::instrument;
 
 static REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
@@ -0,0 +1,86 @@
+use std::fmt::{self, Debug};
+
+use async_trait::async_trait;
+use ethers::providers::{
+    Http, HttpClientError, JsonRpcClient, JsonRpcError, ProviderError, RpcError, Ws, WsClientError,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+use url::Url;
+
+/// A provider connection, over http(s) or ws(s) depending on the url.
+#[derive(Clone)]
+pub enum Transport {
+    Http(Http),
+    Ws(Ws),
+}
+
+/// Without the url nor the client's headers, which may carry credentials.
+impl Debug for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Http(_) => f.write_str("Transport::Http"),
+            Self::Ws(_) => f.write_str("Transport::Ws"),
+        }
+    }
+}
+
+impl Transport {
+    /// Ws connections are opened here, http clients connect per request with
+    /// `client`.
+    pub async fn connect(url: Url, client: &reqwest::Client) -> Result<Self, TransportError> {
+        match url.scheme() {
+            "ws" | "wss" => Ok(Self::Ws(Ws::connect(url.as_str()).await?)),
+            _ => Ok(Self::Http(Http::new_with_client(url, client.clone()))),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TransportError {
+    #[error(transparent)]
+    Http(#[from] HttpClientError),
+    #[error(transparent)]
+    Ws(#[from] WsClientError),
+}
+
+impl RpcError for TransportError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            Self::Http(err) => err.as_error_response(),
+            Self::Ws(err) => err.as_error_response(),
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            Self::Http(err) => err.as_serde_error(),
+            Self::Ws(err) => err.as_serde_error(),
+        }
+    }
+}
+
+impl From<TransportError> for ProviderError {
+    fn from(err: TransportError) -> Self {
+        match err {
+            TransportError::Http(err) => err.into(),
+            TransportError::Ws(err) => err.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for Transport {
+    type Error = TransportError;
+
+    async fn request<P, R>(&self, method: &str, params: P) -> Result<R, Self::Error>
+    where
+        P: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            Self::Http(http) => Ok(http.request(method, params).await?),
+            Self::Ws(ws) => Ok(ws.request(method, params).await?),
+        }
+    }
+}
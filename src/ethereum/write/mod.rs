@@ -1,9 +1,95 @@
 use std::error::Error;
 
-use ethers::providers::ProviderError;
-use ethers::types::{TransactionReceipt, H256};
+use ethers::{
+    providers::ProviderError,
+    types::{TransactionReceipt, H256, U256},
+};
+use serde::Serialize;
 use thiserror::Error;
 
+use crate::utils::{InvalidTransactionId, TransactionId};
+
+/// What the relayer and the chain report about a submitted transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TransactionState {
+    /// A receipt exists on chain. `cost` is unknown when the receipt lacks
+    /// `gasUsed`, or a price can't be found.
+    Mined {
+        hash:      H256,
+        succeeded: bool,
+        cost:      Option<GasCost>,
+    },
+    /// The relayer still considers the transaction in flight
+    Pending,
+    /// The relayer gave up on the transaction
+    Failed,
+    /// The transaction's nonce was used by a different transaction
+    NonceConsumed { nonce: u64 },
+    /// The relayer has no record of the transaction
+    Unknown,
+}
+
+/// What a mined transaction paid for gas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasCost {
+    pub gas_used:  U256,
+    /// The receipt's `effectiveGasPrice`, or the transaction's `gasPrice`
+    /// on providers whose receipts lack it
+    pub gas_price: U256,
+    /// The price was taken from the transaction, which for EIP-1559
+    /// transactions may be above what was paid
+    pub estimated: bool,
+}
+
+impl GasCost {
+    /// `gas_used * gas_price`, in wei.
+    #[must_use]
+    pub fn wei(&self) -> U256 {
+        self.gas_used.saturating_mul(self.gas_price)
+    }
+}
+
+/// Where a transaction the relayer still has in flight stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum PendingState {
+    /// Accepted by the relayer but not broadcast yet, it can be cancelled
+    Queued,
+    /// In the mempool, only a replacement at the same nonce stops it
+    Broadcast,
+    /// Mined but not yet confirmed by the relayer
+    MinedUnconfirmed,
+    /// The relayer can't tell
+    Unknown,
+}
+
+/// A transaction from
+/// [`fetch_pending_transactions`](crate::ethereum::Ethereum::fetch_pending_transactions).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingTransaction {
+    pub transaction_id: TransactionId,
+    pub state:          PendingState,
+    /// Set when the relayer has assigned one
+    pub nonce:          Option<u64>,
+}
+
+impl PendingTransaction {
+    #[must_use]
+    pub const fn new(
+        transaction_id: TransactionId,
+        state: PendingState,
+        nonce: Option<u64>,
+    ) -> Self {
+        Self {
+            transaction_id,
+            state,
+            nonce,
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 #[allow(dead_code)] // Unused variants
 pub enum TxError {
@@ -34,6 +120,30 @@ pub enum TxError {
     #[error("Error parsing transaction id: {0}")]
     Parse(Box<dyn Error + Send + Sync + 'static>),
 
+    #[error("Gas estimation reverted: {reason}")]
+    EstimationReverted { reason: String },
+
+    #[error("Estimated {estimate} gas, above max_gas_limit of {max_gas_limit}")]
+    GasLimitExceeded {
+        estimate:      U256,
+        max_gas_limit: u64,
+    },
+
+    #[error("Nonce {nonce} is already used")]
+    NonceTooLow { nonce: u64 },
+
+    #[error("A transaction with nonce {nonce} is already pending at higher fees")]
+    ReplacementUnderpriced { nonce: u64 },
+
+    #[error(
+        "{count} pending transactions from nonce {first} aren't monitored, waiting for them to \
+         mine"
+    )]
+    NonceGap { first: u64, count: usize },
+
+    #[error("Relayer returned an invalid transaction id: {0}")]
+    InvalidTransactionId(#[from] InvalidTransactionId),
+
     #[error("{0:?}")]
     Other(anyhow::Error),
 }
@@ -0,0 +1,375 @@
+use ethers::{
+    contract::EthError,
+    providers::{Middleware, ProviderError, RpcError},
+    types::{
+        transaction::eip2718::TypedTransaction, Address, Eip1559TransactionRequest,
+        TransactionRequest, U256,
+    },
+};
+use tracing::info;
+
+use crate::{
+    config::FeesConfig,
+    ethereum::{ReadProvider, TxError},
+    metrics,
+};
+
+/// The fee parameters resolved for a single transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fees {
+    Legacy {
+        gas_price: U256,
+    },
+    Eip1559 {
+        max_fee_per_gas:          U256,
+        max_priority_fee_per_gas: U256,
+    },
+}
+
+impl Fees {
+    /// Every fee raised by `percent`, e.g. to replace a pending transaction.
+    /// Rounds up, so even a tip of a few wei is raised.
+    #[must_use]
+    pub fn bumped(self, percent: u64) -> Self {
+        let bump = |fee: U256| (fee.saturating_mul(U256::from(100 + percent)) + 99) / 100;
+
+        match self {
+            Self::Legacy { gas_price } => Self::Legacy {
+                gas_price: bump(gas_price),
+            },
+            Self::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => Self::Eip1559 {
+                max_fee_per_gas:          bump(max_fee_per_gas),
+                max_priority_fee_per_gas: bump(max_priority_fee_per_gas),
+            },
+        }
+    }
+}
+
+/// Resolves the fees for the next transaction, using the configured values
+/// where present and estimating the rest from the provider.
+pub async fn resolve_fees(config: &FeesConfig, provider: &ReadProvider) -> Result<Fees, TxError> {
+    if config.force_legacy || provider.legacy {
+        let estimate = provider
+            .get_gas_price()
+            .await
+            .map_err(|err| TxError::Fill(err.into()))?;
+
+        return Ok(Fees::Legacy {
+            gas_price: legacy_gas_price(config, estimate),
+        });
+    }
+
+    let configured = (
+        config.max_fee_per_gas.map(U256::from),
+        config.max_priority_fee_per_gas.map(U256::from),
+    );
+
+    let (max_fee_per_gas, max_priority_fee_per_gas) = match configured {
+        (Some(max_fee), Some(priority_fee)) => (max_fee, priority_fee),
+        (max_fee, priority_fee) => {
+            let (estimated_max_fee, estimated_priority_fee) = provider
+                .estimate_eip1559_fees(None)
+                .await
+                .map_err(|err| TxError::Fill(err.into()))?;
+
+            (
+                max_fee.unwrap_or(estimated_max_fee),
+                priority_fee.unwrap_or(estimated_priority_fee),
+            )
+        }
+    };
+
+    // An estimated tip can exceed a configured cap, clamp it so the transaction
+    // stays valid.
+    let max_priority_fee_per_gas = max_priority_fee_per_gas.min(max_fee_per_gas);
+
+    Ok(Fees::Eip1559 {
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+    })
+}
+
+/// The node's gas price, capped to `max_fee_per_gas` like the total fee of
+/// an EIP-1559 transaction.
+fn legacy_gas_price(config: &FeesConfig, estimate: U256) -> U256 {
+    config
+        .max_fee_per_gas
+        .map_or(estimate, |max_fee| estimate.min(max_fee.into()))
+}
+
+/// Converts `tx` into the transaction type matching `fees` and sets the fee
+/// fields. Every other field is carried over unchanged.
+#[must_use]
+pub fn apply_fees(tx: TypedTransaction, fees: Fees) -> TypedTransaction {
+    info!(?fees, "Applying transaction fees");
+
+    match fees {
+        Fees::Legacy { gas_price } => TypedTransaction::Legacy(TransactionRequest {
+            from: tx.from().copied(),
+            to: tx.to().cloned(),
+            gas: tx.gas().copied(),
+            gas_price: Some(gas_price),
+            value: tx.value().copied(),
+            data: tx.data().cloned(),
+            nonce: tx.nonce().copied(),
+            chain_id: tx.chain_id(),
+            ..Default::default()
+        }),
+        Fees::Eip1559 {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        } => TypedTransaction::Eip1559(Eip1559TransactionRequest {
+            from: tx.from().copied(),
+            to: tx.to().cloned(),
+            gas: tx.gas().copied(),
+            value: tx.value().copied(),
+            data: tx.data().cloned(),
+            nonce: tx.nonce().copied(),
+            access_list: tx.access_list().cloned().unwrap_or_default(),
+            max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
+            max_fee_per_gas: Some(max_fee_per_gas),
+            chain_id: tx.chain_id(),
+        }),
+    }
+}
+
+/// Estimates the gas `tx` needs when sent from `from` and scales it into the
+/// limit to send it with.
+///
+/// # Errors
+///
+/// Will return `Err` if estimation reverts, naming the reason, or if the
+/// estimate is above `max_gas_limit`.
+pub async fn estimate_gas_limit(
+    config: &FeesConfig,
+    provider: &ReadProvider,
+    tx: &TypedTransaction,
+    from: Address,
+) -> Result<U256, TxError> {
+    let mut request = tx.clone();
+    request.set_from(from);
+
+    let estimate =
+        provider
+            .estimate_gas(&request, None)
+            .await
+            .map_err(|err| match revert_reason(&err) {
+                Some(reason) => TxError::EstimationReverted { reason },
+                None => TxError::Fill(err.into()),
+            })?;
+
+    let gauge = u64::try_from(estimate)
+        .ok()
+        .and_then(|gas| i64::try_from(gas).ok());
+    metrics::GAS_ESTIMATE.set(gauge.unwrap_or(i64::MAX));
+
+    let gas_limit = gas_limit(config, estimate)?;
+    info!(%estimate, %gas_limit, "Estimated the gas limit");
+    Ok(gas_limit)
+}
+
+/// `estimate` scaled by `gas_limit_multiplier_percent` and capped to
+/// `max_gas_limit`, which only the estimate itself may not exceed.
+fn gas_limit(config: &FeesConfig, estimate: U256) -> Result<U256, TxError> {
+    let scaled = estimate.saturating_mul(config.gas_limit_multiplier_percent.into()) / 100;
+
+    let Some(max_gas_limit) = config.max_gas_limit else {
+        return Ok(scaled);
+    };
+
+    if estimate > U256::from(max_gas_limit) {
+        return Err(TxError::GasLimitExceeded {
+            estimate,
+            max_gas_limit,
+        });
+    }
+
+    Ok(scaled.min(max_gas_limit.into()))
+}
+
+/// The decoded reason of a reverting `eth_estimateGas`, `None` if it failed
+/// otherwise.
+fn revert_reason(err: &ProviderError) -> Option<String> {
+    let response = err.as_error_response()?;
+
+    if let Some(data) = response.as_revert_data() {
+        return Some(String::decode_with_selector(&data).unwrap_or_else(|| format!("{data}")));
+    }
+
+    // Some providers report a revert without any data
+    response
+        .message
+        .contains("revert")
+        .then(|| response.message.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use ethers::{
+        core::utils::Anvil,
+        middleware::SignerMiddleware,
+        signers::{LocalWallet, Signer},
+        types::{Address, Bytes, U64},
+    };
+
+    use super::*;
+
+    fn legacy_tx() -> TypedTransaction {
+        TransactionRequest::new()
+            .to(Address::repeat_byte(0x11))
+            .value(7u64)
+            .gas(21_000u64)
+            .data(Bytes::from(vec![0xde, 0xad]))
+            .gas_price(1u64)
+            .into()
+    }
+
+    #[test]
+    fn converts_legacy_to_eip1559() {
+        let tx = apply_fees(legacy_tx(), Fees::Eip1559 {
+            max_fee_per_gas:          U256::from(100),
+            max_priority_fee_per_gas: U256::from(2),
+        });
+
+        let TypedTransaction::Eip1559(request) = tx else {
+            panic!("expected an EIP-1559 transaction");
+        };
+
+        assert_eq!(request.max_fee_per_gas, Some(U256::from(100)));
+        assert_eq!(request.max_priority_fee_per_gas, Some(U256::from(2)));
+        assert_eq!(request.value, Some(U256::from(7)));
+        assert_eq!(request.gas, Some(U256::from(21_000)));
+        assert_eq!(request.data, Some(Bytes::from(vec![0xde, 0xad])));
+    }
+
+    #[test]
+    fn gas_limit_is_scaled_and_capped() {
+        let config = FeesConfig {
+            max_gas_limit: Some(110_000),
+            ..FeesConfig::default()
+        };
+
+        let unbounded = gas_limit(&FeesConfig::default(), U256::from(100_000)).unwrap();
+        assert_eq!(unbounded, U256::from(120_000));
+
+        let capped = gas_limit(&config, U256::from(100_000)).unwrap();
+        assert_eq!(capped, U256::from(110_000));
+
+        let err = gas_limit(&config, U256::from(110_001)).unwrap_err();
+        assert!(matches!(
+            err,
+            TxError::GasLimitExceeded { estimate, max_gas_limit: 110_000 }
+                if estimate == U256::from(110_001)
+        ));
+    }
+
+    #[test]
+    fn legacy_gas_price_is_capped_not_fixed() {
+        let config = FeesConfig {
+            max_fee_per_gas: Some(30),
+            force_legacy: true,
+            ..FeesConfig::default()
+        };
+
+        assert_eq!(legacy_gas_price(&config, U256::from(20)), U256::from(20));
+        assert_eq!(legacy_gas_price(&config, U256::from(40)), U256::from(30));
+        assert_eq!(
+            legacy_gas_price(&FeesConfig::default(), U256::from(40)),
+            U256::from(40)
+        );
+    }
+
+    #[test]
+    fn converts_eip1559_to_legacy() {
+        let tx = apply_fees(legacy_tx(), Fees::Eip1559 {
+            max_fee_per_gas:          U256::from(100),
+            max_priority_fee_per_gas: U256::from(2),
+        });
+
+        let tx = apply_fees(tx, Fees::Legacy {
+            gas_price: U256::from(50),
+        });
+
+        let TypedTransaction::Legacy(request) = tx else {
+            panic!("expected a legacy transaction");
+        };
+
+        assert_eq!(request.gas_price, Some(U256::from(50)));
+        assert_eq!(request.value, Some(U256::from(7)));
+    }
+
+    #[test]
+    fn bumped_fees_round_up() {
+        let fees = Fees::Eip1559 {
+            max_fee_per_gas:          U256::from(105),
+            max_priority_fee_per_gas: U256::from(3),
+        };
+
+        assert_eq!(fees.bumped(20), Fees::Eip1559 {
+            max_fee_per_gas:          U256::from(126),
+            max_priority_fee_per_gas: U256::from(4),
+        });
+        assert_eq!(
+            Fees::Legacy {
+                gas_price: U256::from(50),
+            }
+            .bumped(10),
+            Fees::Legacy {
+                gas_price: U256::from(55),
+            }
+        );
+    }
+
+    async fn send_with_fees(config: FeesConfig) -> anyhow::Result<Option<U64>> {
+        let anvil = Anvil::new().spawn();
+        let provider = ReadProvider::new(
+            vec![anvil.endpoint().parse()?],
+            Duration::from_secs(30),
+            Duration::from_secs(30),
+        )
+        .await?;
+
+        let fees = resolve_fees(&config, &provider).await?;
+        let tx = apply_fees(legacy_tx(), fees);
+
+        let wallet = LocalWallet::from(anvil.keys()[0].clone()).with_chain_id(anvil.chain_id());
+        let client = SignerMiddleware::new(provider, wallet);
+
+        let receipt = client
+            .send_transaction(tx, None)
+            .await?
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("missing receipt"))?;
+
+        Ok(receipt.transaction_type)
+    }
+
+    #[tokio::test]
+    #[ignore = "requires anvil"]
+    async fn anvil_receives_eip1559_transaction() -> anyhow::Result<()> {
+        let tx_type = send_with_fees(FeesConfig::default()).await?;
+
+        assert_eq!(tx_type, Some(U64::from(2)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore = "requires anvil"]
+    async fn anvil_receives_legacy_transaction_when_forced() -> anyhow::Result<()> {
+        let tx_type = send_with_fees(FeesConfig {
+            force_legacy: true,
+            ..FeesConfig::default()
+        })
+        .await?;
+
+        assert_eq!(tx_type, Some(U64::from(0)));
+
+        Ok(())
+    }
+}
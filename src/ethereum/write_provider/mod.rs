@@ -1,60 +1,131 @@
-use std::fmt;
-use std::sync::Arc;
+use std::{fmt, sync::Arc};
 
-use ethers::providers::Middleware;
-use ethers::types::transaction::eip2718::TypedTransaction;
-use ethers::types::{Address, U64};
+use ethers::{
+    providers::Middleware,
+    types::{
+        transaction::eip2718::TypedTransaction, Address, BlockNumber, TransactionReceipt,
+        TransactionRequest, H256, U256, U64,
+    },
+};
 use tracing::{info, warn};
 
-use self::inner::Inner;
-use self::openzeppelin::OzRelay;
-use self::tx_sitter::TxSitter;
-use super::{ReadProvider, TxError};
-use crate::config::RelayerConfig;
-use crate::utils::TransactionId;
-
+use self::{
+    fees::{apply_fees, estimate_gas_limit, resolve_fees, Fees},
+    nonce::{classify_send_error, NonceManager, Reconciled},
+    openzeppelin::OzRelay,
+    raw_signer::RawSigner,
+    relayer::{Relayer, RelayerState},
+    signing_key::LoadedKey,
+    tx_sitter::TxSitter,
+};
+use super::{GasCost, PendingState, PendingTransaction, ReadProvider, TransactionState, TxError};
+use crate::{
+    config::{FeesConfig, NonceGapRecovery, RelayerConfig},
+    utils::TransactionId,
+};
 
 mod error;
-mod inner;
+mod fees;
+mod nonce;
 mod openzeppelin;
+mod raw_signer;
+mod relayer;
+mod signing_key;
 mod tx_sitter;
 
 pub struct WriteProvider {
     read_provider: ReadProvider,
-    inner:         Arc<dyn Inner>,
+    relayer:       Arc<dyn Relayer>,
     address:       Address,
+    fees:          FeesConfig,
+    /// Set for the raw signer, the other relayers assign their own nonces
+    nonces:        Option<NonceManager>,
+    /// `signer_cancel_fee_bump_percent`
+    cancel_bump:   u64,
 }
 
+/// A self-send needs no more
+const CANCEL_GAS: u64 = 21_000;
+
 impl fmt::Debug for WriteProvider {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("WriteProvider")
             .field("read_provider", &self.read_provider)
-            .field("inner", &"<REDACTED>")
+            .field("relayer", &"<REDACTED>")
             .field("address", &self.address)
+            .field("fees", &self.fees)
+            .field("nonces", &self.nonces)
             .finish()
     }
 }
 
 impl WriteProvider {
-    pub async fn new(read_provider: ReadProvider, config: &RelayerConfig) -> anyhow::Result<Self> {
-        let address = config.address();
-
-        let inner: Arc<dyn Inner> = match config {
+    pub async fn new(
+        read_provider: ReadProvider,
+        config: &RelayerConfig,
+        fees: &FeesConfig,
+    ) -> anyhow::Result<Self> {
+        let mut nonces = None;
+        let mut cancel_bump = 0;
+        let (relayer, address): (Arc<dyn Relayer>, Address) = match config {
             RelayerConfig::OzDefender(oz_config) => {
                 tracing::info!("Initializing OZ Relayer");
-                Arc::new(OzRelay::new(oz_config).await?)
+                (
+                    Arc::new(OzRelay::new(oz_config).await?),
+                    oz_config.oz_address,
+                )
             }
             RelayerConfig::TxSitter(tx_sitter_config) => {
                 tracing::info!("Initializing TxSitter");
-                Arc::new(TxSitter::new(tx_sitter_config))
+                (
+                    Arc::new(TxSitter::new(tx_sitter_config)),
+                    tx_sitter_config.tx_sitter_address,
+                )
+            }
+            RelayerConfig::RawSigner(signer_config) => {
+                tracing::info!("Initializing raw signer");
+                nonces = Some(NonceManager::new(signer_config.signer_nonce_gap));
+                cancel_bump = signer_config.signer_cancel_fee_bump_percent;
+                let chain_id = read_provider.chain_id.as_u64();
+                let provider = read_provider.clone();
+
+                match signing_key::load(&signer_config.signing_key, chain_id).await? {
+                    LoadedKey::Local(wallet) => {
+                        let signer = RawSigner::new(provider, wallet, signer_config);
+                        let address = signer.address();
+                        (Arc::new(signer), address)
+                    }
+                    #[cfg(feature = "aws-kms")]
+                    LoadedKey::AwsKms(kms) => {
+                        let signer = RawSigner::new(provider, kms, signer_config);
+                        let address = signer.address();
+                        (Arc::new(signer), address)
+                    }
+                }
             }
         };
 
-        Ok(Self {
+        let write_provider = Self {
             read_provider,
-            inner,
+            relayer,
             address,
-        })
+            fees: fees.clone(),
+            nonces,
+            cancel_bump,
+        };
+
+        if let Some(nonces) = &write_provider.nonces {
+            let reconciled = write_provider.reconcile_nonce().await?;
+            if reconciled.gap.is_empty() {
+                *nonces.lock().await = Some(reconciled.next);
+            } else {
+                // Possibly a propagation the monitor resumes, only a send
+                // deals with the gap
+                warn!(gap = ?reconciled.gap, "Transactions from before the restart are pending");
+            }
+        }
+
+        Ok(write_provider)
     }
 
     pub async fn send_transaction(
@@ -62,32 +133,236 @@ impl WriteProvider {
         tx: TypedTransaction,
         only_once: bool,
     ) -> Result<TransactionId, TxError> {
-        self.inner.send_transaction(tx, only_once).await
+        let fees = resolve_fees(&self.fees, &self.read_provider).await?;
+        let mut tx = apply_fees(tx, fees);
+
+        // A gas limit configured for the relayer still replaces this one
+        let gas_limit =
+            estimate_gas_limit(&self.fees, &self.read_provider, &tx, self.address).await?;
+        tx.set_gas(gas_limit);
+
+        let Some(nonces) = &self.nonces else {
+            return self.relayer.send_transaction(tx, only_once).await;
+        };
+
+        let mut next = nonces.lock().await;
+        let nonce = match *next {
+            Some(nonce) => nonce,
+            None => self.recover_nonce(nonces.recovery(), fees).await?,
+        };
+        tx.set_nonce(nonce);
+
+        // A transaction still in flight is reused, leaving the nonce unused
+        let in_flight = if only_once {
+            self.relayer.fetch_pending_transactions().await?
+        } else {
+            vec![]
+        };
+
+        match self.relayer.send_transaction(tx, only_once).await {
+            Ok(tx_id) => {
+                if !in_flight
+                    .iter()
+                    .any(|pending| pending.transaction_id == tx_id)
+                {
+                    *next = Some(nonce + 1);
+                }
+                Ok(tx_id)
+            }
+            Err(err) => {
+                *next = None;
+                Err(classify_send_error(err, nonce))
+            }
+        }
+    }
+
+    /// Reads the node's transaction counts and the nonces of the
+    /// transactions the signer still tracks.
+    async fn reconcile_nonce(&self) -> Result<Reconciled, TxError> {
+        let (latest, pending) = futures::try_join!(
+            self.transaction_count(BlockNumber::Latest),
+            self.transaction_count(BlockNumber::Pending),
+        )?;
+
+        let mut in_flight = vec![];
+        for tx in self.relayer.fetch_pending_transactions().await? {
+            let state = self.relayer.relayer_state(tx.transaction_id).await?;
+            if let RelayerState::Pending {
+                nonce: Some(nonce), ..
+            } = state
+            {
+                in_flight.push(nonce);
+            }
+        }
+
+        let reconciled = Reconciled::new(latest, pending, &in_flight);
+        info!(
+            latest,
+            pending,
+            next = reconciled.next,
+            "Reconciled the nonce"
+        );
+        Ok(reconciled)
+    }
+
+    /// Reconciles the nonce and, if pending transactions nobody tracks hold
+    /// it up, waits for them or cancels them as `signer_nonce_gap` says.
+    async fn recover_nonce(&self, recovery: NonceGapRecovery, fees: Fees) -> Result<u64, TxError> {
+        let Reconciled { next, gap } = self.reconcile_nonce().await?;
+        let Some(&first) = gap.first() else {
+            return Ok(next);
+        };
+
+        match recovery {
+            NonceGapRecovery::Wait => Err(TxError::NonceGap {
+                first,
+                count: gap.len(),
+            }),
+            NonceGapRecovery::Cancel => {
+                let fees = fees.bumped(self.cancel_bump);
+                for nonce in gap {
+                    self.cancel(nonce, fees).await?;
+                }
+                Ok(next)
+            }
+        }
+    }
+
+    /// Replaces whatever is pending at `nonce` with a zero-value self-send.
+    async fn cancel(&self, nonce: u64, fees: Fees) -> Result<(), TxError> {
+        let tx = TransactionRequest::new()
+            .to(self.address)
+            .value(0u64)
+            .gas(CANCEL_GAS)
+            .nonce(nonce);
+
+        let tx_id = self
+            .relayer
+            .send_transaction(apply_fees(tx.into(), fees), false)
+            .await
+            .map_err(|err| classify_send_error(err, nonce))?;
+
+        warn!(nonce, %tx_id, "Cancelled a pending transaction with a self-send");
+        Ok(())
+    }
+
+    async fn transaction_count(&self, block: BlockNumber) -> Result<u64, TxError> {
+        let count = self
+            .read_provider
+            .get_transaction_count(self.address, Some(block.into()))
+            .await
+            .map_err(|err| TxError::Fetch(err.into()))?;
+
+        Ok(count.as_u64())
+    }
+
+    pub async fn fetch_pending_transactions(&self) -> Result<Vec<PendingTransaction>, TxError> {
+        self.relayer.fetch_pending_transactions().await
+    }
+
+    /// Where each of `transaction_ids` stands on the relayer. Ones it doesn't
+    /// list as pending, such as the raw signer's from before a restart, are
+    /// looked up one by one, and are `Unknown` if that fails.
+    pub async fn pending_states(
+        &self,
+        transaction_ids: Vec<TransactionId>,
+    ) -> Result<Vec<PendingTransaction>, TxError> {
+        let listed = self.relayer.fetch_pending_transactions().await?;
+
+        let mut pending = Vec::with_capacity(transaction_ids.len());
+        for transaction_id in transaction_ids {
+            if let Some(tx) = listed.iter().find(|tx| tx.transaction_id == transaction_id) {
+                pending.push(tx.clone());
+                continue;
+            }
+
+            let (state, nonce) = match self.relayer.relayer_state(transaction_id.clone()).await {
+                Ok(RelayerState::Pending {
+                    nonce,
+                    hash: Some(_),
+                }) => (PendingState::Broadcast, nonce),
+                Ok(RelayerState::Pending { nonce, hash: None }) => (PendingState::Queued, nonce),
+                Ok(RelayerState::Mined { .. }) => (PendingState::MinedUnconfirmed, None),
+                Ok(RelayerState::Failed | RelayerState::Unknown) => (PendingState::Unknown, None),
+                Err(err) => {
+                    warn!(%transaction_id, %err, "Failed to look up a pending transaction");
+                    (PendingState::Unknown, None)
+                }
+            };
+            pending.push(PendingTransaction::new(transaction_id, state, nonce));
+        }
+
+        Ok(pending)
     }
 
-    pub async fn fetch_pending_transactions(&self) -> Result<Vec<TransactionId>, TxError> {
-        self.inner.fetch_pending_transactions().await
+    /// Drops a transaction the relayer hasn't broadcast yet. `false` if it
+    /// can't.
+    pub async fn cancel_transaction(&self, tx: TransactionId) -> Result<bool, TxError> {
+        self.relayer.cancel_transaction(tx).await
+    }
+
+    /// Replaces a broadcast transaction with a self-send at its nonce, at
+    /// current fees bumped by `signer_cancel_fee_bump_percent`. `false` unless
+    /// this provider assigns the nonces and the transaction's is known.
+    pub async fn replace_transaction(&self, tx: &PendingTransaction) -> Result<bool, TxError> {
+        let (Some(nonces), Some(nonce)) = (&self.nonces, tx.nonce) else {
+            return Ok(false);
+        };
+
+        // No send picks a nonce meanwhile
+        let _next = nonces.lock().await;
+        let fees = resolve_fees(&self.fees, &self.read_provider).await?;
+        self.cancel(nonce, fees.bumped(self.cancel_bump)).await?;
+
+        Ok(true)
+    }
+
+    /// Stops a transaction that isn't mined yet: through the relayer's own
+    /// cancellation where it has one, otherwise with
+    /// [`Self::replace_transaction`]. `false` if neither can.
+    pub async fn cancel_in_flight(&self, tx: &PendingTransaction) -> Result<bool, TxError> {
+        if self
+            .relayer
+            .cancel_in_flight(tx.transaction_id.clone())
+            .await?
+        {
+            return Ok(true);
+        }
+
+        self.replace_transaction(tx).await
+    }
+
+    /// Whether the mined transaction `hash` was sent to ourselves, as a
+    /// cancelling self-send is.
+    pub async fn sent_to_self(&self, hash: H256) -> Result<bool, TxError> {
+        let tx = self
+            .read_provider
+            .get_transaction(hash)
+            .await
+            .map_err(|err| TxError::Fetch(err.into()))?;
+
+        Ok(tx.is_some_and(|tx| tx.from == self.address && tx.to == Some(self.address)))
     }
 
     pub async fn fetch_mined_transactions(&self) -> Result<Vec<TransactionId>, TxError> {
-        self.inner.fetch_mined_transactions().await
+        self.relayer.fetch_mined_transactions().await
     }
 
     pub async fn mine_transaction(&self, tx: TransactionId) -> Result<bool, TxError> {
-        let oz_transaction_result = self.inner.mine_transaction(tx.clone()).await;
+        let relayer_result = self.relayer.mine_transaction(tx.clone()).await;
 
-        if let Err(TxError::Failed(_)) = oz_transaction_result {
-            warn!(?tx, "Transaction failed in OZ Relayer");
+        if let Err(TxError::Failed(_)) = relayer_result {
+            warn!(?tx, "Transaction failed in the relayer");
 
             return Ok(false);
         }
 
-        let oz_transaction = oz_transaction_result?;
+        let relayer_transaction = relayer_result?;
 
-        let tx_hash = oz_transaction.hash.ok_or_else(|| {
+        let tx_hash = relayer_transaction.hash.ok_or_else(|| {
             TxError::Fetch(From::from(format!(
                 "Failed to get tx hash for transaction id {}",
-                oz_transaction.transaction_id
+                relayer_transaction.transaction_id
             )))
         })?;
 
@@ -102,7 +377,7 @@ impl WriteProvider {
         let tx = tx.ok_or_else(|| {
             TxError::Fetch(From::from(format!(
                 "Failed to get transaction receipt for transaction id {}",
-                oz_transaction.transaction_id
+                relayer_transaction.transaction_id
             )))
         })?;
 
@@ -115,6 +390,76 @@ impl WriteProvider {
         }
     }
 
+    /// Resolves the current state of a transaction without waiting for it to
+    /// be mined.
+    pub async fn transaction_state(&self, tx: TransactionId) -> Result<TransactionState, TxError> {
+        let (nonce, hash) = match self.relayer.relayer_state(tx).await? {
+            RelayerState::Unknown => return Ok(TransactionState::Unknown),
+            RelayerState::Failed => return Ok(TransactionState::Failed),
+            RelayerState::Mined { hash } => (None, Some(hash)),
+            RelayerState::Pending { nonce, hash } => (nonce, hash),
+        };
+
+        if let Some(hash) = hash {
+            let receipt = self
+                .read_provider
+                .get_transaction_receipt(hash)
+                .await
+                .map_err(|err| TxError::Fetch(err.into()))?;
+
+            if let Some(receipt) = receipt {
+                return Ok(TransactionState::Mined {
+                    hash,
+                    succeeded: receipt.status == Some(U64::from(1u64)),
+                    cost: self.gas_cost(&receipt).await?,
+                });
+            }
+        }
+
+        if let Some(nonce) = nonce {
+            let latest_nonce = self
+                .read_provider
+                .get_transaction_count(self.address, Some(BlockNumber::Latest.into()))
+                .await
+                .map_err(|err| TxError::Fetch(err.into()))?;
+
+            if latest_nonce > U256::from(nonce) {
+                return Ok(TransactionState::NonceConsumed { nonce });
+            }
+        }
+
+        Ok(TransactionState::Pending)
+    }
+
+    /// What the receipt's transaction paid. Some providers leave
+    /// `effectiveGasPrice` out of receipts, the transaction's `gasPrice` is
+    /// used then, marked estimated.
+    async fn gas_cost(&self, receipt: &TransactionReceipt) -> Result<Option<GasCost>, TxError> {
+        let Some(gas_used) = receipt.gas_used else {
+            return Ok(None);
+        };
+        if let Some(gas_price) = receipt.effective_gas_price {
+            return Ok(Some(GasCost {
+                gas_used,
+                gas_price,
+                estimated: false,
+            }));
+        }
+
+        let transaction = self
+            .read_provider
+            .get_transaction(receipt.transaction_hash)
+            .await
+            .map_err(|err| TxError::Fetch(err.into()))?;
+        Ok(transaction
+            .and_then(|transaction| transaction.gas_price)
+            .map(|gas_price| GasCost {
+                gas_used,
+                gas_price,
+                estimated: true,
+            }))
+    }
+
     pub fn address(&self) -> Address {
         self.address
     }
@@ -0,0 +1,142 @@
+//! Nonces for the raw signer, the one relayer that leaves them to us. OZ
+//! Defender and TxSitter assign their own.
+
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::{config::NonceGapRecovery, ethereum::TxError};
+
+/// The next nonce, tracked locally between reconciliations with the node.
+#[derive(Debug)]
+pub struct NonceManager {
+    recovery: NonceGapRecovery,
+    /// `None` until reconciled, and again after a send fails
+    next:     Mutex<Option<u64>>,
+}
+
+impl NonceManager {
+    pub fn new(recovery: NonceGapRecovery) -> Self {
+        Self {
+            recovery,
+            next: Mutex::new(None),
+        }
+    }
+
+    pub const fn recovery(&self) -> NonceGapRecovery {
+        self.recovery
+    }
+
+    /// Held while a transaction is sent, so two sends never share a nonce.
+    pub async fn lock(&self) -> MutexGuard<'_, Option<u64>> {
+        self.next.lock().await
+    }
+}
+
+/// Where the nonces of the sending address stand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reconciled {
+    /// The nonce to send the next transaction with
+    pub next: u64,
+    /// Nonces below `next` held by pending transactions the signer doesn't
+    /// track, e.g. sent before a restart. Nothing sent after them mines
+    /// until they do.
+    pub gap:  Vec<u64>,
+}
+
+impl Reconciled {
+    /// `latest` and `pending` are the node's transaction counts, `in_flight`
+    /// the nonces of the transactions the signer still tracks. The node's
+    /// pending count can lag behind what it was just sent, e.g. after a
+    /// failover, so it never takes precedence over them.
+    pub fn new(latest: u64, pending: u64, in_flight: &[u64]) -> Self {
+        let next = in_flight
+            .iter()
+            .map(|nonce| nonce + 1)
+            .fold(pending, u64::max);
+        let gap = (latest..next)
+            .filter(|nonce| !in_flight.contains(nonce))
+            .collect();
+
+        Self { next, gap }
+    }
+}
+
+/// Tells nonce conflicts apart from other send errors. Nodes only report
+/// them in the error message.
+pub fn classify_send_error(err: TxError, nonce: u64) -> TxError {
+    let TxError::Send(inner) = &err else {
+        return err;
+    };
+
+    let message = format!("{inner:#}").to_lowercase();
+    if message.contains("nonce too low") {
+        TxError::NonceTooLow { nonce }
+    } else if message.contains("replacement transaction underpriced") {
+        TxError::ReplacementUnderpriced { nonce }
+    } else {
+        err
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[test]
+    fn untracked_pending_transactions_are_a_gap() {
+        assert_eq!(Reconciled::new(5, 5, &[]), Reconciled {
+            next: 5,
+            gap:  vec![],
+        });
+
+        // After a restart nothing is tracked
+        assert_eq!(Reconciled::new(5, 8, &[]), Reconciled {
+            next: 8,
+            gap:  vec![5, 6, 7],
+        });
+
+        assert_eq!(Reconciled::new(5, 8, &[6, 7]), Reconciled {
+            next: 8,
+            gap:  vec![5],
+        });
+    }
+
+    #[test]
+    fn a_lagging_pending_count_doesnt_reuse_a_tracked_nonce() {
+        assert_eq!(Reconciled::new(5, 5, &[5, 6]), Reconciled {
+            next: 7,
+            gap:  vec![],
+        });
+
+        // Mined, the node just hasn't served the receipt yet
+        assert_eq!(Reconciled::new(6, 6, &[5]), Reconciled {
+            next: 6,
+            gap:  vec![],
+        });
+    }
+
+    #[test]
+    fn nonce_conflicts_are_told_apart() {
+        let send = |message: &str| {
+            TxError::Send(anyhow!("(code: -32000, message: {message}, data: None)"))
+        };
+
+        assert!(matches!(
+            classify_send_error(send("nonce too low"), 4),
+            TxError::NonceTooLow { nonce: 4 }
+        ));
+        assert!(matches!(
+            classify_send_error(send("replacement transaction underpriced"), 4),
+            TxError::ReplacementUnderpriced { nonce: 4 }
+        ));
+        assert!(matches!(
+            classify_send_error(send("insufficient funds for gas * price + value"), 4),
+            TxError::Send(_)
+        ));
+        assert!(matches!(
+            classify_send_error(TxError::SendTimeout, 4),
+            TxError::SendTimeout
+        ));
+    }
+}
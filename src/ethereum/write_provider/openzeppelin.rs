@@ -1,20 +1,24 @@
-use std::fmt::Debug;
-use std::time::Duration;
+use std::{fmt::Debug, time::Duration};
 
 use ethers::types::transaction::eip2718::TypedTransaction;
 use once_cell::sync::Lazy;
-use oz_api::data::transactions::{RelayerTransactionBase, SendBaseTransactionRequest, Status};
-use oz_api::OzApi;
+use oz_api::{
+    data::transactions::{RelayerTransactionBase, SendBaseTransactionRequest, Status},
+    OzApi,
+};
 use prometheus::{register_int_counter_vec, IntCounterVec};
 use tokio::time::timeout;
 use tracing::{error, info, info_span, Instrument};
 
-use super::error::Error;
-use super::inner::{Inner, TransactionResult};
-use crate::config::OzDefenderConfig;
-use crate::ethereum::TxError;
-use crate::utils::TransactionId;
-
+use super::{
+    error::Error,
+    relayer::{Relayer, RelayerState, TransactionResult},
+};
+use crate::{
+    config::OzDefenderConfig,
+    ethereum::{PendingState, PendingTransaction, TxError},
+    utils::TransactionId,
+};
 
 static TX_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!("eth_tx_count", "The transaction count by bytes4.", &[
@@ -44,8 +48,8 @@ impl OzRelay {
         } else {
             OzApi::new(
                 &options.oz_api_url,
-                &options.oz_api_key,
-                &options.oz_api_secret,
+                options.oz_api_key.expose(),
+                options.oz_api_secret.expose(),
             )
             .await?
         };
@@ -158,10 +162,11 @@ impl OzRelay {
             if let Some(existing_transaction) = existing_transaction {
                 info!(only_once, "mining previously submitted transaction");
 
-                let transaction_id = existing_transaction.transaction_id.clone();
-                info!(?transaction_id);
+                let transaction_id =
+                    TransactionId::job(existing_transaction.transaction_id.clone())?;
+                info!(%transaction_id);
 
-                self.mine_transaction_id(&transaction_id).await?;
+                self.mine_transaction_id(transaction_id.as_str()).await?;
 
                 return Ok(transaction_id);
             }
@@ -191,7 +196,7 @@ impl OzRelay {
 
         info!(?tx_id, "Transaction submitted to OZ Relay");
 
-        Ok(tx_id)
+        Ok(TransactionId::job(tx_id)?)
     }
 
     pub async fn mine_transaction(
@@ -201,20 +206,91 @@ impl OzRelay {
         self.mine_transaction_id(tx_id.as_str()).await
     }
 
-    pub async fn fetch_pending_transactions(&self) -> Result<Vec<TransactionId>, TxError> {
-        let recent_pending_txs = self
+    /// The recent transactions not yet confirmed or failed. Defender doesn't
+    /// report nonces.
+    pub async fn fetch_pending_transactions(&self) -> Result<Vec<PendingTransaction>, TxError> {
+        let recent_txs = self
             .list_recent_transactions()
             .await
             .map_err(|err| TxError::Fetch(Box::new(err)))?;
 
-        let pending_txs = recent_pending_txs
-            .into_iter()
-            .map(|tx| tx.transaction_id)
-            .collect();
+        let mut pending_txs = vec![];
+        for tx in recent_txs {
+            let state = match tx.status {
+                Status::Pending => PendingState::Queued,
+                Status::Sent | Status::Submitted | Status::Inmempool => PendingState::Broadcast,
+                Status::Mined => PendingState::MinedUnconfirmed,
+                Status::Confirmed | Status::Failed => continue,
+            };
+            pending_txs.push(PendingTransaction::new(
+                TransactionId::job(tx.transaction_id)?,
+                state,
+                None,
+            ));
+        }
 
         Ok(pending_txs)
     }
 
+    /// Only cancels transactions Defender hasn't sent yet, a sent one would
+    /// be replaced at fees we don't control.
+    pub async fn cancel_transaction(&self, tx_id: TransactionId) -> Result<bool, TxError> {
+        let transaction = self
+            .query(tx_id.as_str())
+            .await
+            .map_err(|err| TxError::Fetch(Box::new(err)))?;
+        if transaction.status != Status::Pending {
+            return Ok(false);
+        }
+
+        self.oz_api
+            .cancel_transaction(tx_id.as_str())
+            .await
+            .map_err(|err| TxError::Send(Error::from(err).into()))?;
+
+        info!(%tx_id, "Cancelled a queued transaction");
+        Ok(true)
+    }
+
+    /// Has Defender replace a transaction it hasn't mined yet with a no-op
+    /// at the same nonce. The job keeps its id and takes the no-op's hash.
+    pub async fn cancel_in_flight(&self, tx_id: TransactionId) -> Result<bool, TxError> {
+        let transaction = self
+            .query(tx_id.as_str())
+            .await
+            .map_err(|err| TxError::Fetch(Box::new(err)))?;
+        if matches!(
+            transaction.status,
+            Status::Mined | Status::Confirmed | Status::Failed
+        ) {
+            return Ok(false);
+        }
+
+        self.oz_api
+            .cancel_transaction(tx_id.as_str())
+            .await
+            .map_err(|err| TxError::Send(Error::from(err).into()))?;
+
+        info!(%tx_id, "Had Defender replace a transaction with a no-op");
+        Ok(true)
+    }
+
+    pub async fn relayer_state(&self, tx_id: TransactionId) -> Result<RelayerState, TxError> {
+        let transaction = match self.oz_api.query_transaction(tx_id.as_str()).await {
+            Ok(transaction) => transaction,
+            Err(oz_api::Error::InvalidResponse(status)) if status.as_u16() == 404 => {
+                return Ok(RelayerState::Unknown);
+            }
+            Err(err) => return Err(TxError::Fetch(Box::new(Error::from(err)))),
+        };
+
+        Ok(match (transaction.status, transaction.hash) {
+            (Status::Failed, _) => RelayerState::Failed,
+            (Status::Mined | Status::Confirmed, Some(hash)) => RelayerState::Mined { hash },
+            (_, hash) => RelayerState::Pending { nonce: None, hash },
+        })
+    }
+
     pub async fn fetch_mined_transactions(&self) -> Result<Vec<TransactionId>, TxError> {
         let recent_pending_txs = self
             .list_recent_transactions()
@@ -223,15 +299,15 @@ impl OzRelay {
 
         let pending_txs = recent_pending_txs
             .into_iter()
-            .map(|tx| tx.transaction_id)
-            .collect();
+            .map(|tx| TransactionId::job(tx.transaction_id))
+            .collect::<Result<_, _>>()?;
 
         Ok(pending_txs)
     }
 }
 
 #[async_trait::async_trait]
-impl Inner for OzRelay {
+impl Relayer for OzRelay {
     async fn send_transaction(
         &self,
         tx: TypedTransaction,
@@ -240,7 +316,7 @@ impl Inner for OzRelay {
         self.send_transaction(tx, only_once).await
     }
 
-    async fn fetch_pending_transactions(&self) -> Result<Vec<TransactionId>, TxError> {
+    async fn fetch_pending_transactions(&self) -> Result<Vec<PendingTransaction>, TxError> {
         self.fetch_pending_transactions().await
     }
 
@@ -248,7 +324,7 @@ impl Inner for OzRelay {
         let transaction = self.mine_transaction(tx).await?;
 
         Ok(TransactionResult {
-            transaction_id: transaction.transaction_id,
+            transaction_id: TransactionId::job(transaction.transaction_id)?,
             hash:           transaction.hash,
         })
     }
@@ -256,4 +332,16 @@ impl Inner for OzRelay {
     async fn fetch_mined_transactions(&self) -> Result<Vec<TransactionId>, TxError> {
         self.fetch_mined_transactions().await
     }
+
+    async fn relayer_state(&self, tx: TransactionId) -> Result<RelayerState, TxError> {
+        self.relayer_state(tx).await
+    }
+
+    async fn cancel_transaction(&self, tx: TransactionId) -> Result<bool, TxError> {
+        self.cancel_transaction(tx).await
+    }
+
+    async fn cancel_in_flight(&self, tx: TransactionId) -> Result<bool, TxError> {
+        self.cancel_in_flight(tx).await
+    }
 }
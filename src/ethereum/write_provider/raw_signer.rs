@@ -0,0 +1,271 @@
+use std::{sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::Middleware,
+    signers::Signer,
+    types::{transaction::eip2718::TypedTransaction, Bytes, NameOrAddress, H256, U256},
+};
+use tokio::time::timeout;
+use tracing::info;
+
+use super::relayer::{Relayer, RelayerState, TransactionResult};
+use crate::{
+    config::RawSignerConfig,
+    ethereum::{PendingState, PendingTransaction, ReadProvider, TxError},
+    utils::TransactionId,
+};
+
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Signs transactions with the configured key and sends them straight to the
+/// node. Transaction ids are the transaction hashes.
+///
+/// Only transactions sent by this process are tracked, so `only_once` and
+/// the pending list don't see transactions from before a restart. The write
+/// provider sets the nonces, the node's pending count is only a fallback.
+pub struct RawSigner<S> {
+    client:       SignerMiddleware<ReadProvider, S>,
+    gas_limit:    Option<u64>,
+    mine_timeout: Duration,
+    sent:         Mutex<Vec<SentTransaction>>,
+}
+
+struct SentTransaction {
+    hash:  H256,
+    to:    Option<NameOrAddress>,
+    data:  Option<Bytes>,
+    nonce: Option<u64>,
+}
+
+impl<S: Signer + 'static> RawSigner<S> {
+    /// `signer` must be bound to the provider's chain id.
+    pub fn new(provider: ReadProvider, signer: S, config: &RawSignerConfig) -> Self {
+        Self {
+            client:       SignerMiddleware::new(provider, signer),
+            gas_limit:    config.signer_gas_limit,
+            mine_timeout: config.signer_mine_timeout,
+            sent:         Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn address(&self) -> ethers::types::Address {
+        self.client.address()
+    }
+
+    /// Sent transactions, split by whether they have a receipt.
+    async fn sent_by_receipt(&self) -> Result<(Vec<H256>, Vec<H256>), TxError> {
+        let hashes: Vec<H256> = self.sent.lock().unwrap().iter().map(|tx| tx.hash).collect();
+
+        let mut mined = vec![];
+        let mut pending = vec![];
+        for hash in hashes {
+            if self.receipt(hash).await?.is_some() {
+                mined.push(hash);
+            } else {
+                pending.push(hash);
+            }
+        }
+
+        Ok((mined, pending))
+    }
+
+    async fn receipt(
+        &self,
+        hash: H256,
+    ) -> Result<Option<ethers::types::TransactionReceipt>, TxError> {
+        self.client
+            .get_transaction_receipt(hash)
+            .await
+            .map_err(|err| TxError::Fetch(err.into()))
+    }
+
+    async fn mine_transaction_unchecked(&self, hash: H256) -> Result<TransactionResult, TxError> {
+        loop {
+            if self.receipt(hash).await?.is_some() {
+                return Ok(TransactionResult {
+                    transaction_id: TransactionId::from_hash(hash),
+                    hash:           Some(hash),
+                });
+            }
+
+            let known = self
+                .client
+                .get_transaction(hash)
+                .await
+                .map_err(|err| TxError::Fetch(err.into()))?;
+            if known.is_none() {
+                return Err(TxError::Dropped(hash));
+            }
+
+            tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Signer + 'static> Relayer for RawSigner<S> {
+    async fn send_transaction(
+        &self,
+        mut tx: TypedTransaction,
+        only_once: bool,
+    ) -> Result<TransactionId, TxError> {
+        if let Some(gas_limit) = self.gas_limit {
+            tx.set_gas(gas_limit);
+        }
+
+        if only_once {
+            let (_, pending) = self.sent_by_receipt().await?;
+            let existing = self
+                .sent
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|sent| {
+                    pending.contains(&sent.hash)
+                        && sent.to.as_ref() == tx.to()
+                        && sent.data.as_ref() == tx.data()
+                })
+                .map(|sent| sent.hash);
+
+            if let Some(hash) = existing {
+                info!(?hash, "Reusing in-flight transaction");
+                return Ok(TransactionId::from_hash(hash));
+            }
+        }
+
+        let pending = self
+            .client
+            .send_transaction(tx.clone(), None)
+            .await
+            .map_err(|err| TxError::Send(err.into()))?;
+        let hash = pending.tx_hash();
+
+        info!(?hash, "Transaction sent");
+        self.sent.lock().unwrap().push(SentTransaction {
+            hash,
+            to: tx.to().cloned(),
+            data: tx.data().cloned(),
+            nonce: tx.nonce().map(U256::as_u64),
+        });
+
+        Ok(TransactionId::from_hash(hash))
+    }
+
+    /// Everything sent goes straight to the mempool, nothing is ever queued.
+    async fn fetch_pending_transactions(&self) -> Result<Vec<PendingTransaction>, TxError> {
+        let (_, pending) = self.sent_by_receipt().await?;
+        let sent = self.sent.lock().unwrap();
+
+        Ok(pending
+            .into_iter()
+            .map(|hash| {
+                let nonce = sent
+                    .iter()
+                    .find(|tx| tx.hash == hash)
+                    .and_then(|tx| tx.nonce);
+                PendingTransaction::new(
+                    TransactionId::from_hash(hash),
+                    PendingState::Broadcast,
+                    nonce,
+                )
+            })
+            .collect())
+    }
+
+    async fn mine_transaction(&self, tx: TransactionId) -> Result<TransactionResult, TxError> {
+        let hash = tx.to_hash()?;
+
+        timeout(self.mine_timeout, self.mine_transaction_unchecked(hash))
+            .await
+            .map_err(|_| TxError::ConfirmationTimeout)?
+    }
+
+    async fn fetch_mined_transactions(&self) -> Result<Vec<TransactionId>, TxError> {
+        let (mined, _) = self.sent_by_receipt().await?;
+
+        Ok(mined.into_iter().map(TransactionId::from_hash).collect())
+    }
+
+    async fn relayer_state(&self, tx: TransactionId) -> Result<RelayerState, TxError> {
+        let hash = tx.to_hash()?;
+
+        if self.receipt(hash).await?.is_some() {
+            return Ok(RelayerState::Mined { hash });
+        }
+
+        let transaction = self
+            .client
+            .get_transaction(hash)
+            .await
+            .map_err(|err| TxError::Fetch(err.into()))?;
+
+        Ok(match transaction {
+            Some(transaction) => RelayerState::Pending {
+                nonce: Some(transaction.nonce.as_u64()),
+                hash:  Some(hash),
+            },
+            None => RelayerState::Unknown,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{
+        core::utils::Anvil,
+        signers::LocalWallet,
+        types::{Address, TransactionRequest},
+    };
+
+    use super::*;
+    use crate::{
+        config::{NonceGapRecovery, SigningKey},
+        utils::secret::SecretString,
+    };
+
+    #[tokio::test]
+    #[ignore = "requires anvil"]
+    async fn anvil_mines_transaction_by_hash() -> anyhow::Result<()> {
+        let anvil = Anvil::new().spawn();
+        let provider = ReadProvider::new(
+            vec![anvil.endpoint().parse()?],
+            Duration::from_secs(30),
+            Duration::from_secs(30),
+        )
+        .await?;
+
+        let wallet = LocalWallet::from(anvil.keys()[0].clone()).with_chain_id(anvil.chain_id());
+        let signer = RawSigner::new(provider, wallet, &RawSignerConfig {
+            signing_key:                    SigningKey::Inline(SecretString::from(String::new())),
+            signer_mine_timeout:            Duration::from_secs(30),
+            signer_gas_limit:               Some(21_000),
+            signer_nonce_gap:               NonceGapRecovery::Wait,
+            signer_cancel_fee_bump_percent: 20,
+        });
+
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(Address::repeat_byte(0x11))
+            .value(1u64)
+            .into();
+
+        let tx_id = signer.send_transaction(tx.clone(), true).await?;
+        let mined = signer.mine_transaction(tx_id.clone()).await?;
+
+        assert_eq!(mined.hash, Some(tx_id.to_hash()?));
+        assert!(matches!(
+            signer.relayer_state(tx_id.clone()).await?,
+            RelayerState::Mined { .. }
+        ));
+        assert_eq!(
+            signer.fetch_mined_transactions().await?,
+            vec![tx_id.clone()]
+        );
+
+        // Once mined, the same transaction is sent again
+        assert_ne!(signer.send_transaction(tx, true).await?, tx_id);
+
+        Ok(())
+    }
+}
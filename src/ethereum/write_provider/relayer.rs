@@ -0,0 +1,65 @@
+use ethers::types::{transaction::eip2718::TypedTransaction, H256};
+
+use crate::{
+    ethereum::{PendingTransaction, TxError},
+    utils::TransactionId,
+};
+
+/// A backend that submits transactions on our behalf, selected by the
+/// `relayer` config section.
+///
+/// What a [`TransactionId`] is depends on the backend: a job id for OZ
+/// Defender and TxSitter, the transaction hash for the raw signer.
+#[async_trait::async_trait]
+pub trait Relayer: Send + Sync + 'static {
+    /// Submits `tx`. With `only_once`, a matching transaction that is still in
+    /// flight is returned instead of sending a new one.
+    async fn send_transaction(
+        &self,
+        tx: TypedTransaction,
+        only_once: bool,
+    ) -> Result<TransactionId, TxError>;
+
+    /// Transactions in flight, with how far along each is. Backends that
+    /// can't tell report
+    /// [`PendingState::Unknown`](crate::ethereum::PendingState::Unknown).
+    async fn fetch_pending_transactions(&self) -> Result<Vec<PendingTransaction>, TxError>;
+
+    /// Waits until the transaction is mined and returns its hash.
+    async fn mine_transaction(&self, tx: TransactionId) -> Result<TransactionResult, TxError>;
+
+    async fn fetch_mined_transactions(&self) -> Result<Vec<TransactionId>, TxError>;
+
+    /// Looks up the transaction on the relayer without waiting for it.
+    async fn relayer_state(&self, tx: TransactionId) -> Result<RelayerState, TxError>;
+
+    /// Drops a queued transaction before it is broadcast. `false` when the
+    /// backend can't, or the transaction is already out.
+    async fn cancel_transaction(&self, _tx: TransactionId) -> Result<bool, TxError> {
+        Ok(false)
+    }
+
+    /// Has the backend replace a transaction that isn't mined yet with a
+    /// no-op at its nonce, at fees it picks. `false` when it can't.
+    async fn cancel_in_flight(&self, _tx: TransactionId) -> Result<bool, TxError> {
+        Ok(false)
+    }
+}
+
+/// The relayer's view of a transaction.
+pub enum RelayerState {
+    Pending {
+        nonce: Option<u64>,
+        hash:  Option<H256>,
+    },
+    Mined {
+        hash: H256,
+    },
+    Failed,
+    Unknown,
+}
+
+pub struct TransactionResult {
+    pub transaction_id: TransactionId,
+    pub hash:           Option<H256>,
+}
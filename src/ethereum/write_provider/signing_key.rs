@@ -0,0 +1,187 @@
+//! Loads the raw signer's key from wherever the config says it lives.
+
+use std::path::Path;
+
+use anyhow::Context;
+use ethers::signers::{LocalWallet, Signer};
+use tracing::{info, warn};
+use zeroize::Zeroizing;
+
+use crate::config::{parse_private_key, SigningKey, SigningKeySource};
+
+/// A loaded signing key.
+pub enum LoadedKey {
+    Local(LocalWallet),
+    #[cfg(feature = "aws-kms")]
+    AwsKms(ethers::signers::AwsSigner),
+}
+
+/// # Errors
+///
+/// Will return `Err` if the key can't be read or doesn't parse, or the KMS key
+/// can't be used.
+pub async fn load(key: &SigningKey, chain_id: u64) -> anyhow::Result<LoadedKey> {
+    let wallet = match key {
+        SigningKey::Inline(key) => parse_private_key(key.expose())?,
+        SigningKey::Source(SigningKeySource::File { path }) => {
+            warn_if_world_readable(path);
+
+            let key = Zeroizing::new(
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("failed to read signing key {}", path.display()))?,
+            );
+            parse_private_key(&key).with_context(|| format!("signing key {}", path.display()))?
+        }
+        SigningKey::Source(SigningKeySource::Env { var }) => {
+            let key = Zeroizing::new(
+                std::env::var(var)
+                    .with_context(|| format!("signing key variable {var} is not set"))?,
+            );
+            parse_private_key(&key).with_context(|| format!("signing key variable {var}"))?
+        }
+        SigningKey::Source(SigningKeySource::AwsKms { key_arn }) => {
+            return aws_kms(key_arn, chain_id).await;
+        }
+    };
+
+    let wallet = wallet.with_chain_id(chain_id);
+    info!(address = ?wallet.address(), "Loaded signing key");
+
+    Ok(LoadedKey::Local(wallet))
+}
+
+#[cfg(feature = "aws-kms")]
+async fn aws_kms(key_arn: &str, chain_id: u64) -> anyhow::Result<LoadedKey> {
+    use std::str::FromStr;
+
+    use ethers::signers::AwsSigner;
+    use rusoto_core::Region;
+    use rusoto_kms::KmsClient;
+
+    let region = crate::config::kms_region(key_arn)
+        .with_context(|| format!("{key_arn} is not a KMS key ARN"))?;
+    let client = KmsClient::new(Region::from_str(region)?);
+
+    let signer = AwsSigner::new(client, key_arn, chain_id)
+        .await
+        .with_context(|| format!("failed to load KMS key {key_arn}"))?;
+    info!(address = ?signer.address(), key_arn, "Loaded KMS signing key");
+
+    Ok(LoadedKey::AwsKms(signer))
+}
+
+#[cfg(not(feature = "aws-kms"))]
+#[allow(clippy::unused_async)]
+async fn aws_kms(_key_arn: &str, _chain_id: u64) -> anyhow::Result<LoadedKey> {
+    anyhow::bail!("KMS signing keys require building with the aws-kms feature")
+}
+
+#[cfg(unix)]
+fn warn_if_world_readable(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+
+    if let Ok(metadata) = std::fs::metadata(path) {
+        if is_world_readable(metadata.permissions().mode()) {
+            warn!(
+                path = %path.display(),
+                "Signing key file is readable by every user, chmod 600 it"
+            );
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn warn_if_world_readable(_path: &Path) {}
+
+#[cfg(unix)]
+const fn is_world_readable(mode: u32) -> bool {
+    mode & 0o004 != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use ethers::types::Address;
+
+    use super::*;
+    use crate::utils::secret::SecretString;
+
+    const KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    fn address() -> Address {
+        "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+            .parse()
+            .unwrap()
+    }
+
+    fn local(key: LoadedKey) -> LocalWallet {
+        match key {
+            LoadedKey::Local(wallet) => wallet,
+            #[cfg(feature = "aws-kms")]
+            LoadedKey::AwsKms(_) => panic!("expected a local key"),
+        }
+    }
+
+    #[tokio::test]
+    async fn every_local_source_derives_the_same_address() -> anyhow::Result<()> {
+        let mut file = tempfile::NamedTempFile::new()?;
+        writeln!(file, "{KEY}")?;
+        std::env::set_var("TEST_SIGNING_KEY", KEY);
+
+        for key in [
+            SigningKey::Inline(SecretString::from(KEY.to_string())),
+            SigningKey::Source(SigningKeySource::File {
+                path: file.path().to_path_buf(),
+            }),
+            SigningKey::Source(SigningKeySource::Env {
+                var: "TEST_SIGNING_KEY".to_string(),
+            }),
+        ] {
+            let wallet = local(load(&key, 5).await?);
+
+            assert_eq!(wallet.address(), address());
+            assert_eq!(wallet.chain_id(), 5);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn missing_sources_are_reported_without_the_key() {
+        let missing = SigningKey::Source(SigningKeySource::Env {
+            var: "TEST_SIGNING_KEY_UNSET".to_string(),
+        });
+        let err = load(&missing, 1).await.err().unwrap();
+        assert!(err.to_string().contains("TEST_SIGNING_KEY_UNSET"));
+
+        let invalid = SigningKey::Inline(SecretString::from("0xnot-a-key".to_string()));
+        let err = load(&invalid, 1).await.err().unwrap();
+        assert!(!format!("{err:?}").contains("not-a-key"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn world_readable_modes() {
+        assert!(is_world_readable(0o100_644));
+        assert!(!is_world_readable(0o100_600));
+        assert!(!is_world_readable(0o100_640));
+    }
+
+    /// Needs AWS credentials and a secp256k1 KMS key in `TEST_KMS_KEY_ARN`.
+    #[cfg(feature = "aws-kms")]
+    #[tokio::test]
+    #[ignore = "requires an AWS KMS key"]
+    async fn kms_key_reports_its_address() -> anyhow::Result<()> {
+        let key_arn = std::env::var("TEST_KMS_KEY_ARN")?;
+        let key = SigningKey::Source(SigningKeySource::AwsKms { key_arn });
+
+        let LoadedKey::AwsKms(signer) = load(&key, 5).await? else {
+            panic!("expected a KMS key");
+        };
+        assert!(!signer.address().is_zero());
+        assert_eq!(signer.chain_id(), 5);
+
+        Ok(())
+    }
+}
@@ -2,15 +2,19 @@ use std::time::Duration;
 
 use anyhow::Context;
 use async_trait::async_trait;
-use ethers::types::transaction::eip2718::TypedTransaction;
-use ethers::types::U256;
-use tx_sitter_client::data::{SendTxRequest, TransactionPriority, TxStatus};
-use tx_sitter_client::TxSitterClient;
-
-use super::inner::{Inner, TransactionResult};
-use crate::config::TxSitterConfig;
-use crate::ethereum::TxError;
-use crate::utils::TransactionId;
+use ethers::types::{transaction::eip2718::TypedTransaction, U256};
+use tracing::info;
+use tx_sitter_client::{
+    data::{GetTxResponse, SendTxRequest, TransactionPriority, TxStatus},
+    TxSitterClient,
+};
+
+use super::relayer::{Relayer, RelayerState, TransactionResult};
+use crate::{
+    config::TxSitterConfig,
+    ethereum::{PendingState, PendingTransaction, TxError},
+    utils::TransactionId,
+};
 
 const MINING_TIMEOUT: Duration = Duration::from_secs(60);
 
@@ -22,11 +26,30 @@ pub struct TxSitter {
 impl TxSitter {
     pub fn new(config: &TxSitterConfig) -> Self {
         Self {
-            client:    TxSitterClient::new(&config.tx_sitter_url),
+            client:    TxSitterClient::new(config.tx_sitter_url.expose()),
             gas_limit: config.tx_sitter_gas_limit,
         }
     }
 
+    /// Transactions that are unsent or sent but not yet mined.
+    async fn in_flight(&self) -> Result<Vec<GetTxResponse>, TxError> {
+        let unsent_txs = self
+            .client
+            .get_unsent_txs()
+            .await
+            .context("Error fetching unsent transactions")
+            .map_err(TxError::Send)?;
+
+        let pending_txs = self
+            .client
+            .get_txs_by_status(TxStatus::Pending)
+            .await
+            .context("Error fetching pending transactions")
+            .map_err(TxError::Send)?;
+
+        Ok(unsent_txs.into_iter().chain(pending_txs).collect())
+    }
+
     pub async fn mine_transaction_inner(
         &self,
         tx_id: TransactionId,
@@ -34,14 +57,14 @@ impl TxSitter {
         loop {
             let tx = self
                 .client
-                .get_tx(&tx_id)
+                .get_tx(tx_id.as_str())
                 .await
                 .context("Error fetching tx")
                 .map_err(TxError::Send)?;
 
             if tx.status == Some(TxStatus::Mined) || tx.status == Some(TxStatus::Finalized) {
                 return Ok(TransactionResult {
-                    transaction_id: tx.tx_id,
+                    transaction_id: TransactionId::job(tx.tx_id)?,
                     hash:           Some(
                         tx.tx_hash
                             .context("Missing hash on a mined tx")
@@ -56,17 +79,29 @@ impl TxSitter {
 }
 
 #[async_trait]
-impl Inner for TxSitter {
+impl Relayer for TxSitter {
+    /// With `only_once`, an in-flight transaction to the same address with the
+    /// same calldata is reused, its tx id acting as the idempotency key.
     async fn send_transaction(
         &self,
         mut tx: TypedTransaction,
-        _only_once: bool,
+        only_once: bool,
     ) -> Result<TransactionId, TxError> {
         if let Some(gas_limit) = self.gas_limit {
             tx.set_gas(gas_limit);
         }
 
-        // TODO: Handle only_once
+        if only_once {
+            let existing = self.in_flight().await?.into_iter().find(|existing| {
+                tx.to_addr() == Some(&existing.to) && tx.data() == existing.data.as_ref()
+            });
+
+            if let Some(existing) = existing {
+                info!(tx_id = %existing.tx_id, "Reusing in-flight transaction");
+                return Ok(TransactionId::job(existing.tx_id)?);
+            }
+        }
+
         let tx = self
             .client
             .send_tx(&SendTxRequest {
@@ -87,34 +122,30 @@ impl Inner for TxSitter {
             .context("Error sending transaction")
             .map_err(TxError::Send)?;
 
-        Ok(tx.tx_id)
+        Ok(TransactionId::job(tx.tx_id)?)
     }
 
-    async fn fetch_pending_transactions(&self) -> Result<Vec<TransactionId>, TxError> {
-        let unsent_txs = self
-            .client
-            .get_unsent_txs()
-            .await
-            .context("Error fetching unsent transactions")
-            .map_err(TxError::Send)?;
-
-        let pending_txs = self
-            .client
-            .get_txs_by_status(TxStatus::Pending)
-            .await
-            .context("Error fetching pending transactions")
-            .map_err(TxError::Send)?;
-
-        let mut tx_ids = vec![];
-
-        for tx in unsent_txs.into_iter().chain(pending_txs) {
-            tx_ids.push(tx.tx_id);
-        }
-
-        Ok(tx_ids)
+    /// Unsent transactions have no hash yet. TxSitter has no way to cancel
+    /// them.
+    async fn fetch_pending_transactions(&self) -> Result<Vec<PendingTransaction>, TxError> {
+        self.in_flight()
+            .await?
+            .into_iter()
+            .map(|tx| {
+                let state = if tx.tx_hash.is_some() {
+                    PendingState::Broadcast
+                } else {
+                    PendingState::Queued
+                };
+                Ok(PendingTransaction::new(
+                    TransactionId::job(tx.tx_id)?,
+                    state,
+                    Some(tx.nonce),
+                ))
+            })
+            .collect()
     }
 
-
     async fn fetch_mined_transactions(&self) -> Result<Vec<TransactionId>, TxError> {
         let mined_txs = self
             .client
@@ -126,7 +157,7 @@ impl Inner for TxSitter {
         let mut tx_ids = vec![];
 
         for tx in mined_txs {
-            tx_ids.push(tx.tx_id);
+            tx_ids.push(TransactionId::job(tx.tx_id)?);
         }
 
         Ok(tx_ids)
@@ -137,4 +168,27 @@ impl Inner for TxSitter {
             .await
             .map_err(|_| TxError::ConfirmationTimeout)?
     }
+
+    async fn relayer_state(&self, tx_id: TransactionId) -> Result<RelayerState, TxError> {
+        let tx = self
+            .client
+            .find_tx(tx_id.as_str())
+            .await
+            .context("Error fetching tx")
+            .map_err(|err| TxError::Fetch(err.into()))?;
+
+        let Some(tx) = tx else {
+            return Ok(RelayerState::Unknown);
+        };
+
+        Ok(match (tx.status, tx.tx_hash) {
+            (Some(TxStatus::Mined | TxStatus::Finalized), Some(hash)) => {
+                RelayerState::Mined { hash }
+            }
+            (_, hash) => RelayerState::Pending {
+                nonce: Some(tx.nonce),
+                hash,
+            },
+        })
+    }
 }
@@ -0,0 +1,216 @@
+//! Exit codes and panic reporting for the binary.
+//!
+//! The process supervisor decides whether to restart the service from the
+//! exit code alone, so every way the process can end maps to one of a small,
+//! stable set of codes. The values follow `sysexits.h`.
+
+use std::{
+    future::Future,
+    io::Write,
+    panic::PanicHookInfo,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use serde::Serialize;
+use thiserror::Error;
+
+/// The exit codes of the binary. These are part of the operational contract
+/// with the supervisor and must not be renumbered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ExitCode {
+    /// Clean shutdown
+    Ok          = 0,
+    /// A one-shot command's answer is no: `check-sync` found the roots
+    /// differ, or `propagate-once` didn't mine
+    Failed      = 1,
+    /// Invalid config or command line, restarting won't help
+    Config      = 64,
+    /// A dependency could not be reached at startup, even after retries
+    Unavailable = 69,
+    /// A bug, including panics
+    Internal    = 70,
+    /// A transient failure, restart with backoff
+    TempFail    = 75,
+}
+
+impl From<ExitCode> for std::process::ExitCode {
+    fn from(code: ExitCode) -> Self {
+        Self::from(code as u8)
+    }
+}
+
+/// The errors that end the process, each tied to an exit code.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid configuration: {0:#}")]
+    Config(#[source] anyhow::Error),
+
+    #[error("dependency unavailable: {0:#}")]
+    Unavailable(#[source] anyhow::Error),
+
+    #[error("internal error: {0:#}")]
+    Internal(#[source] anyhow::Error),
+
+    #[error("temporary failure: {0:#}")]
+    TempFail(#[source] anyhow::Error),
+}
+
+impl Error {
+    #[must_use]
+    pub const fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::Config(_) => ExitCode::Config,
+            Self::Unavailable(_) => ExitCode::Unavailable,
+            Self::Internal(_) => ExitCode::Internal,
+            Self::TempFail(_) => ExitCode::TempFail,
+        }
+    }
+}
+
+/// The lifecycle phase the process is in, reported with panics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum Phase {
+    Startup  = 0,
+    Running  = 1,
+    Shutdown = 2,
+}
+
+impl Phase {
+    const fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Running,
+            2 => Self::Shutdown,
+            _ => Self::Startup,
+        }
+    }
+}
+
+static PHASE: AtomicU8 = AtomicU8::new(Phase::Startup as u8);
+
+pub fn set_phase(phase: Phase) {
+    PHASE.store(phase as u8, Ordering::SeqCst);
+}
+
+#[must_use]
+pub fn phase() -> Phase {
+    Phase::from_u8(PHASE.load(Ordering::SeqCst))
+}
+
+tokio::task_local! {
+    /// The supervised task being polled, see [`supervised`].
+    static SUPERVISED_TASK: &'static str;
+}
+
+/// Runs `future` as the supervised task `name`. A panic while it is polled
+/// is reported without exiting, the supervisor restarts the task. Tasks it
+/// spawns aren't covered.
+pub async fn supervised<F: Future>(name: &'static str, future: F) -> F::Output {
+    SUPERVISED_TASK.scope(name, future).await
+}
+
+/// The single-line JSON document written to stderr when the process panics.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PanicReport {
+    pub event:    &'static str,
+    pub message:  String,
+    pub location: Option<String>,
+    pub version:  &'static str,
+    pub phase:    Phase,
+    /// The supervised task that panicked, which is restarted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task:     Option<&'static str>,
+}
+
+impl PanicReport {
+    #[must_use]
+    pub fn new(
+        info: &PanicHookInfo<'_>,
+        version: &'static str,
+        phase: Phase,
+        task: Option<&'static str>,
+    ) -> Self {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(ToString::to_string)
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+
+        Self {
+            event: "panic",
+            message,
+            location: info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            version,
+            phase,
+            task,
+        }
+    }
+}
+
+/// Replaces the default panic hook with one that writes a [`PanicReport`] to
+/// stderr and, unless the panic is in a [`supervised`] task, exits with
+/// [`ExitCode::Internal`].
+///
+/// A supervised task is restarted by its supervisor, without taking down
+/// its siblings. A panic anywhere else takes down the whole process, instead
+/// of leaving it running with a dead task.
+pub fn install_panic_hook(version: &'static str) {
+    std::panic::set_hook(Box::new(move |info| {
+        let task = SUPERVISED_TASK.try_with(|task| *task).ok();
+        let report = PanicReport::new(info, version, phase(), task);
+
+        let mut stderr = std::io::stderr().lock();
+        if let Ok(line) = serde_json::to_string(&report) {
+            _ = writeln!(stderr, "{line}");
+        }
+        _ = stderr.flush();
+
+        if task.is_none() {
+            std::process::exit(i32::from(ExitCode::Internal as u8));
+        }
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_codes_are_stable() {
+        assert_eq!(ExitCode::Ok as u8, 0);
+        assert_eq!(ExitCode::Failed as u8, 1);
+        assert_eq!(ExitCode::Config as u8, 64);
+        assert_eq!(ExitCode::Unavailable as u8, 69);
+        assert_eq!(ExitCode::Internal as u8, 70);
+        assert_eq!(ExitCode::TempFail as u8, 75);
+    }
+
+    #[test]
+    fn errors_map_to_exit_codes() {
+        let cases = [
+            (Error::Config(anyhow::anyhow!("bad")), ExitCode::Config),
+            (
+                Error::Unavailable(anyhow::anyhow!("refused")),
+                ExitCode::Unavailable,
+            ),
+            (Error::Internal(anyhow::anyhow!("bug")), ExitCode::Internal),
+            (Error::TempFail(anyhow::anyhow!("busy")), ExitCode::TempFail),
+        ];
+
+        for (error, code) in cases {
+            assert_eq!(error.exit_code(), code);
+        }
+    }
+
+    #[test]
+    fn phase_round_trips() {
+        for phase in [Phase::Startup, Phase::Running, Phase::Shutdown] {
+            assert_eq!(Phase::from_u8(phase as u8), phase);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Whether the synced status rests on a root Scroll has finalized, as
+//! reported on `/serviceStatus` with `[finality]` enabled.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::FinalityConfig,
+    database::{self, query::DatabaseQuery, types::ServerStatus, Database},
+    processor::status::BridgeStatus,
+};
+
+/// Why a synced status is reported as pending with
+/// `synced_requires_finality`.
+pub const AWAITING_FINALITY: &str = "awaitingFinality";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Finality {
+    /// Scroll added the latest root in a block not finalized yet
+    Unfinalized,
+    Finalized,
+}
+
+impl Finality {
+    /// Reads the finality of a synced `status` from the roots table, `None`
+    /// without `[finality]`. See [`Self::apply`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the roots can't be read.
+    pub async fn read(
+        database: &Database,
+        config: &FinalityConfig,
+        status: &mut ServerStatus,
+    ) -> Result<Option<Self>, database::Error> {
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let latest_root_finalized = database.is_latest_root_finalized().await?;
+        Ok(Self::apply(
+            status,
+            latest_root_finalized,
+            config.synced_requires_finality,
+        ))
+    }
+
+    /// The finality of a synced `status`, given whether the latest root Scroll
+    /// added is finalized. With `synced_requires_finality`, a synced status
+    /// still waiting on it is turned into a pending one.
+    pub fn apply(
+        status: &mut ServerStatus,
+        latest_root_finalized: Option<bool>,
+        synced_requires_finality: bool,
+    ) -> Option<Self> {
+        if status.status != BridgeStatus::Synced {
+            return None;
+        }
+
+        let finality = if latest_root_finalized? {
+            Self::Finalized
+        } else {
+            Self::Unfinalized
+        };
+
+        if finality == Self::Unfinalized && synced_requires_finality {
+            status.status = BridgeStatus::Pending;
+            status.reason = Some(AWAITING_FINALITY.to_string());
+        }
+
+        Some(finality)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(status: BridgeStatus) -> ServerStatus {
+        ServerStatus {
+            status,
+            reason: None,
+            last_synced: None,
+        }
+    }
+
+    #[test]
+    fn only_a_synced_status_has_a_finality() {
+        let mut unsynced = status(BridgeStatus::Unsynced);
+        assert_eq!(Finality::apply(&mut unsynced, Some(false), true), None);
+        assert_eq!(unsynced, status(BridgeStatus::Unsynced));
+
+        let mut synced = status(BridgeStatus::Synced);
+        assert_eq!(Finality::apply(&mut synced, None, true), None);
+        assert_eq!(
+            Finality::apply(&mut synced, Some(true), true),
+            Some(Finality::Finalized)
+        );
+        assert_eq!(
+            Finality::apply(&mut synced, Some(false), false),
+            Some(Finality::Unfinalized)
+        );
+        assert_eq!(synced, status(BridgeStatus::Synced));
+    }
+
+    #[test]
+    fn an_unfinalized_sync_can_be_held_pending() {
+        let mut synced = status(BridgeStatus::Synced);
+        assert_eq!(
+            Finality::apply(&mut synced, Some(false), true),
+            Some(Finality::Unfinalized)
+        );
+        assert_eq!(synced.status, BridgeStatus::Pending);
+        assert_eq!(synced.reason.as_deref(), Some(AWAITING_FINALITY));
+    }
+}
@@ -0,0 +1,89 @@
+//! Whether this replica leads. Only the leader propagates and monitors
+//! transactions, the others run everything else. Without `[leadership]`
+//! every replica leads.
+
+use tokio::sync::watch;
+
+/// Kept up to date by the election task.
+#[derive(Debug)]
+pub struct Leadership {
+    leading: watch::Sender<bool>,
+}
+
+impl Leadership {
+    /// Follows until elected when `elected`, leads from the start otherwise.
+    #[must_use]
+    pub fn new(elected: bool) -> Self {
+        Self {
+            leading: watch::Sender::new(!elected),
+        }
+    }
+
+    #[must_use]
+    pub fn is_leader(&self) -> bool {
+        *self.leading.borrow()
+    }
+
+    /// Returns whether it changed.
+    pub fn set(&self, leading: bool) -> bool {
+        self.leading
+            .send_if_modified(|current| std::mem::replace(current, leading) != leading)
+    }
+
+    /// Waits until this replica leads.
+    pub async fn acquired(&self) {
+        self.wait_for(true).await;
+    }
+
+    /// Waits until this replica no longer leads.
+    pub async fn lost(&self) {
+        self.wait_for(false).await;
+    }
+
+    async fn wait_for(&self, leading: bool) {
+        let mut receiver = self.leading.subscribe();
+        // Can't fail, the sender lives as long as `self`
+        _ = receiver.wait_for(|current| *current == leading).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn without_an_election_every_replica_leads() {
+        assert!(Leadership::new(false).is_leader());
+
+        let leadership = Leadership::new(true);
+        assert!(!leadership.is_leader());
+        assert!(leadership.set(true));
+        assert!(!leadership.set(true));
+        assert!(leadership.is_leader());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn losing_leadership_wakes_the_waiters() {
+        let leadership = Arc::new(Leadership::new(true));
+        leadership.set(true);
+
+        let lost = tokio::spawn({
+            let leadership = leadership.clone();
+            async move { leadership.lost().await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!lost.is_finished());
+
+        leadership.set(false);
+        tokio::time::timeout(Duration::from_secs(1), lost)
+            .await
+            .unwrap()
+            .unwrap();
+        // Already lost, returns right away
+        tokio::time::timeout(Duration::from_secs(1), leadership.lost())
+            .await
+            .unwrap();
+    }
+}
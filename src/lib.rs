@@ -2,14 +2,37 @@
 #![warn(clippy::all, clippy::pedantic, clippy::cargo, clippy::nursery)]
 #![allow(clippy::multiple_crate_versions, clippy::too_many_arguments)]
 
-
-
+pub mod app;
+mod balance;
+pub mod build_info;
+pub mod commands;
+pub mod config;
 mod contracts;
+mod database;
 mod ethereum;
+pub mod exit;
+mod finality;
+mod leadership;
+mod metrics;
+mod notifier;
+mod pause;
 mod processor;
-mod database;
-pub mod config;
-pub mod task_monitor;
-pub mod app;
+#[cfg(feature = "server")]
 pub mod server;
+pub mod task_monitor;
+mod unsynced;
 pub mod utils;
+
+// What an embedder needs to drive the processor without the service around
+// it, see `examples/processor.rs`
+pub use self::{
+    contracts::{
+        BridgeAddresses, RootLookup, RootStatus, ScrollBridge, SimulationError, StateBridge,
+    },
+    database::types::RootSource,
+    ethereum::{Ethereum, GasCost, PendingState, PendingTransaction, TransactionState},
+    processor::{
+        status::{BridgeStatus, SyncState, UnsyncedReason},
+        BridgeProcessor, LatestRoots, Processor, Propagation, ScannerStatus, ScannersStatus,
+    },
+};
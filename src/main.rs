@@ -5,79 +5,381 @@
     clippy::wildcard_imports,
     clippy::multiple_crate_versions
 )]
+// The startup reconciliation makes `main`'s future too deep for the default
+#![recursion_limit = "256"]
 
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::{
+    fs::OpenOptions,
+    io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use clap::Parser;
-use scroll_service::app::App;
-use scroll_service::config::{load_config, ServiceConfig};
-use scroll_service::server;
-use scroll_service::utils::shutdown::{watch_shutdown_signals, Shutdown};
-use scroll_service::task_monitor::TaskMonitor;
-use telemetry_batteries::tracing::datadog::DatadogBattery;
-use telemetry_batteries::tracing::stdout::StdoutBattery;
-use telemetry_batteries::tracing::TracingShutdownHandle;
+use clap::{Parser, Subcommand};
+use opentelemetry::{global, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    runtime,
+    trace::{self, BatchConfig, Sampler, Tracer},
+    Resource,
+};
+use serde::Serialize;
+use telemetry_batteries::tracing::{datadog::DatadogBattery, TracingShutdownHandle};
+use tracing_subscriber::{
+    fmt, fmt::writer::BoxMakeWriter, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter,
+    Layer,
+};
+
+use scroll_service::{
+    app::App,
+    commands,
+    config::{
+        load_config_with_sources, Config, ConfigSource, ConfigSources, LogFormat, LoggingConfig,
+        ObservabilityConfig, OtlpConfig,
+    },
+    exit::{self, ExitCode, Phase},
+    server,
+    utils::shutdown::{watch_shutdown_signals, Shutdown},
+};
+
+/// How many times the app tries to reach its dependencies before giving up.
+const STARTUP_ATTEMPTS: usize = 3;
+const STARTUP_RETRY_DELAY: Duration = Duration::from_secs(1);
 
 #[derive(Debug, Clone, Parser)]
 struct Args {
-    /// Path to the optional config file
+    /// Path to the optional config file, read from `SCROLL_SVC_CONFIG` when
+    /// unset
     config: Option<PathBuf>,
+
+    /// The config file, in place of the positional path. Given both, the
+    /// flag wins
+    #[clap(long = "config", value_name = "PATH", global = true)]
+    config_flag: Option<PathBuf>,
+
+    /// Simulate propagations against the current chain state without
+    /// sending them
+    #[clap(long, global = true)]
+    dry_run: bool,
+
+    /// Start without reconciling the recorded status with the chains, for a
+    /// quicker local start
+    #[clap(long, global = true)]
+    skip_reconciliation: bool,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Subcommand)]
+enum Command {
+    /// Run the tasks and the HTTP server, the default
+    #[default]
+    Serve,
+    /// Simulate and send one propagation of the latest root and wait for it
+    /// to mine, exiting with 1 if it doesn't
+    PropagateOnce,
+    /// Print the latest mainnet and Scroll roots, exiting with 1 if they
+    /// differ. Only the providers are used
+    CheckSync,
+    /// Print the service status recorded in the database
+    Status,
 }
 
 #[tokio::main]
-async fn main() -> eyre::Result<()> {
-    let args = Args::parse();
-    // telemetry_batteries::init()?;
-    scroll_service(args)
-        .await
-        .map_err(|e| eyre::eyre!("{:?}", e))
+async fn main() -> std::process::ExitCode {
+    let version = env!("GIT_VERSION");
+    exit::install_panic_hook(version);
+
+    let args = match Args::try_parse() {
+        Ok(args) => args,
+        Err(err) => {
+            _ = err.print();
+            // --help and --version are reported as errors by clap
+            return if err.use_stderr() {
+                ExitCode::Config.into()
+            } else {
+                ExitCode::Ok.into()
+            };
+        }
+    };
+
+    match scroll_service(args, version).await {
+        Ok(code) => code.into(),
+        Err(err) => {
+            tracing::error!(%err, "Exiting");
+            eprintln!("Error: {err}");
+            err.exit_code().into()
+        }
+    }
 }
 
-async fn scroll_service(args: Args) -> anyhow::Result<()> {
+async fn scroll_service(args: Args, version: &'static str) -> Result<ExitCode, exit::Error> {
+    let path = args.config_flag.as_deref().or(args.config.as_deref());
+    let (mut config, mut sources) = load_config_with_sources(path).map_err(exit::Error::Config)?;
 
-    let config = load_config(args.config.as_deref())?;
+    if args.dry_run {
+        config.app.propagation.dry_run = true;
+        sources.set("app.dry_run", ConfigSource::Cli);
+    }
+
+    if args.skip_reconciliation {
+        config.maintenance.startup_reconciliation = false;
+        sources.set("maintenance.startup_reconciliation", ConfigSource::Cli);
+    }
 
-    let _tracing_shutdown_handle = init_telemetry(&config.service)?;
+    let _telemetry =
+        init_telemetry(&config.service, &config.logging).map_err(exit::Error::Internal)?;
 
+    match args.command.unwrap_or_default() {
+        Command::Serve => serve(config, &sources, version)
+            .await
+            .map(|()| ExitCode::Ok),
+        Command::PropagateOnce => {
+            let propagation = commands::propagate_once(config)
+                .await
+                .map_err(one_shot_error)?;
+            print_json(&propagation)?;
+            Ok(if propagation.failed() {
+                ExitCode::Failed
+            } else {
+                ExitCode::Ok
+            })
+        }
+        Command::CheckSync => {
+            let check = commands::check_sync(&config)
+                .await
+                .map_err(one_shot_error)?;
+            print_json(&check)?;
+            Ok(if check.synced {
+                ExitCode::Ok
+            } else {
+                ExitCode::Failed
+            })
+        }
+        Command::Status => {
+            let status = commands::service_status(&config)
+                .await
+                .map_err(one_shot_error)?;
+            print_json(&status)?;
+            Ok(ExitCode::Ok)
+        }
+    }
+}
+
+/// Errors that carry an exit code keep it, anything else may pass when run
+/// again.
+fn one_shot_error(err: anyhow::Error) -> exit::Error {
+    err.downcast::<exit::Error>()
+        .unwrap_or_else(exit::Error::TempFail)
+}
+
+/// The result of a one-shot command, on stdout for scripts.
+fn print_json(value: &impl Serialize) -> Result<(), exit::Error> {
+    let json =
+        serde_json::to_string_pretty(value).map_err(|err| exit::Error::Internal(err.into()))?;
+    println!("{json}");
+    Ok(())
+}
+
+async fn serve(
+    config: Config,
+    sources: &ConfigSources,
+    version: &'static str,
+) -> Result<(), exit::Error> {
     let shutdown = Arc::new(Shutdown::new());
 
     watch_shutdown_signals(shutdown.clone());
 
-    let version = env!("GIT_VERSION");
-
-    tracing::info!(?config, version, "Starting the app");
+    tracing::info!(version, "Starting the app");
 
     let server_config = config.server.clone();
 
     // Create App struct
-    let app = App::new(config).await?;
+    let app = start_app(config).await?;
 
-    app.initialize_server().await?;
+    // Once the chains are verified, so the event names them
+    app.log_effective_config(sources)
+        .map_err(exit::Error::Internal)?;
 
-    let task_monitor = TaskMonitor::new(app.clone(), shutdown.clone());
+    exit::set_phase(Phase::Running);
 
     // Process to push new identities to Ethereum
-    task_monitor.start().await;
+    let tasks = app.spawn_tasks(shutdown.clone()).await;
 
     // Start server (will stop on shutdown signal)
-    server::run(app, server_config, shutdown.clone()).await?;
+    let served = server::run(app.clone(), server_config, shutdown.clone()).await;
+
+    exit::set_phase(Phase::Shutdown);
 
     tracing::info!("Stopping the app");
-    task_monitor.shutdown().await?;
+    tasks.shutdown().await.map_err(exit::Error::Internal)?;
+
+    // Only once no task can buffer more roots
+    app.shutdown().await.map_err(exit::Error::Internal)?;
+
+    served.map_err(exit::Error::TempFail)?;
 
     Ok(())
 }
 
-fn init_telemetry(service: &ServiceConfig) -> anyhow::Result<TracingShutdownHandle> {
+/// Connects to the database and the chains, retrying a few times so a
+/// dependency that comes up slightly after us doesn't fail the deployment.
+/// Errors that already carry an exit code are not retried.
+async fn start_app(config: Config) -> Result<Arc<App>, exit::Error> {
+    let mut attempt = 1;
+
+    loop {
+        let result = async {
+            let app = App::new(config.clone()).await?;
+            app.initialize_server().await?;
+            app.reconcile().await?;
+            app.await_clean_slate().await?;
+            anyhow::Ok(app)
+        }
+        .await;
+
+        let err = match result {
+            Ok(app) => return Ok(app),
+            Err(err) => match err.downcast::<exit::Error>() {
+                Ok(err) => return Err(err),
+                Err(err) => err,
+            },
+        };
+
+        if attempt >= STARTUP_ATTEMPTS {
+            return Err(exit::Error::Unavailable(err));
+        }
+
+        tracing::warn!(%err, attempt, "Failed to start the app, retrying");
+        attempt += 1;
+        tokio::time::sleep(STARTUP_RETRY_DELAY).await;
+    }
+}
+
+/// Held until exit. Spans still queued for OTLP export are flushed when it's
+/// dropped.
+struct Telemetry {
+    _datadog: Option<TracingShutdownHandle>,
+    otlp:     bool,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if self.otlp {
+            global::shutdown_tracer_provider();
+        }
+    }
+}
+
+fn init_telemetry(
+    service: &ObservabilityConfig,
+    logging: &LoggingConfig,
+) -> anyhow::Result<Telemetry> {
     if let Some(ref datadog) = service.datadog {
-        Ok(DatadogBattery::init(
+        let handle = DatadogBattery::init(
             datadog.traces_endpoint.as_deref(),
             &service.service_name,
             None,
             true,
-        ))
-    } else {
-        Ok(StdoutBattery::init())
+        );
+
+        return Ok(Telemetry {
+            _datadog: Some(handle),
+            otlp:     false,
+        });
     }
+
+    let tracer = service
+        .otlp
+        .as_ref()
+        .map(|otlp| otlp_tracer(otlp, &service.service_name))
+        .transpose()?;
+    let otlp = tracer.is_some();
+    init_logging(logging, tracer)?;
+
+    Ok(Telemetry {
+        _datadog: None,
+        otlp,
+    })
+}
+
+/// Exports spans in batches from a bounded queue, so an unreachable
+/// collector drops spans instead of holding up the tasks. Requests continue
+/// the trace in their `traceparent` header, and responses carry it back.
+fn otlp_tracer(config: &OtlpConfig, service_name: &str) -> anyhow::Result<Tracer> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let service_name = config
+        .service_name
+        .clone()
+        .unwrap_or_else(|| service_name.to_string());
+
+    let exporter = opentelemetry_otlp::new_exporter()
+        .tonic()
+        .with_endpoint(config.endpoint.as_str())
+        .with_timeout(config.export_timeout);
+
+    // A trace continued from a request keeps the caller's sampling decision
+    let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+        config.sampling_ratio.get(),
+    )));
+
+    let batch = BatchConfig::default()
+        .with_max_queue_size(config.max_queue_size)
+        .with_max_export_timeout(config.export_timeout);
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(exporter)
+        .with_trace_config(
+            trace::config()
+                .with_sampler(sampler)
+                .with_resource(Resource::new([KeyValue::new("service.name", service_name)])),
+        )
+        .with_batch_config(batch)
+        .install_batch(runtime::Tokio)?;
+
+    Ok(tracer)
+}
+
+/// Installs the subscriber described by `[logging]`, exporting spans to
+/// `tracer` when given. JSON events carry the fields of their spans, e.g. a
+/// transaction id, as fields of their own.
+fn init_logging(config: &LoggingConfig, tracer: Option<Tracer>) -> anyhow::Result<()> {
+    let filter = match std::env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(directives) if !directives.trim().is_empty() => EnvFilter::try_new(directives)?,
+        _ => EnvFilter::try_new(config.directives())?,
+    };
+
+    let writer = match &config.file {
+        Some(path) => {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            BoxMakeWriter::new(Mutex::new(file))
+        }
+        None => BoxMakeWriter::new(io::stdout),
+    };
+
+    let layer = fmt::layer()
+        .with_writer(writer)
+        .with_ansi(config.file.is_none());
+    let layer = match config.format {
+        LogFormat::Pretty => layer.pretty().boxed(),
+        LogFormat::Compact => layer.compact().boxed(),
+        LogFormat::Json => layer
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .boxed(),
+    };
+
+    tracing_subscriber::registry()
+        .with(layer)
+        .with(tracer.map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer)))
+        .with(filter)
+        .try_init()?;
+
+    Ok(())
 }
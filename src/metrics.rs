@@ -0,0 +1,736 @@
+//! Bridge-specific Prometheus metrics, exported on `/metrics`.
+//!
+//! Everything here is registered by [`register`] when the app starts, so the
+//! series are present, at zero, before anything has happened.
+
+use std::{
+    sync::atomic::{AtomicI64, Ordering},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use ethers::types::{Address, U256};
+use once_cell::sync::Lazy;
+use prometheus::{
+    register_counter, register_gauge, register_histogram, register_int_counter,
+    register_int_counter_vec, register_int_gauge, register_int_gauge_vec, Counter, Gauge,
+    Histogram, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+};
+
+use crate::{database::types::RootSource, ethereum::GasCost};
+
+/// Unset until the first sync check has read `last_synced`.
+const NEVER: i64 = i64::MIN;
+
+static LAST_SYNCED: AtomicI64 = AtomicI64::new(NEVER);
+
+static SECONDS_SINCE_LAST_SYNC: Lazy<Gauge> = Lazy::new(|| {
+    register_gauge!(
+        "seconds_since_last_sync",
+        "seconds since the roots were last seen in sync, -1 if never"
+    )
+    .unwrap()
+});
+
+static LEADER: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!("leader", "1 while this replica holds the leadership").unwrap()
+});
+
+static SCROLL_FINALIZED_BLOCK: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "scroll_finalized_block",
+        "the Scroll block the added roots were last compared against for finality"
+    )
+    .unwrap()
+});
+
+static UNSYNCED_SECONDS: Lazy<Gauge> = Lazy::new(|| {
+    register_gauge!(
+        "unsynced_seconds",
+        "seconds the status has been unsynced or pending since the last sync, 0 while synced"
+    )
+    .unwrap()
+});
+
+pub static SYNCED_STATE: Lazy<Gauge> =
+    Lazy::new(|| register_gauge!("synced_state", "current scroll bridge sync status").unwrap());
+
+static ROOTS_OUTSTANDING: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "roots_outstanding",
+        "roots propagated on mainnet that the scans haven't seen Scroll add yet"
+    )
+    .unwrap()
+});
+
+pub static PROPAGATION_ATTEMPTS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "propagation_attempts",
+        "propagateRoot calls started, including simulations"
+    )
+    .unwrap()
+});
+
+pub static PROPAGATION_RESULTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "propagation_results",
+        "propagateRoot outcomes, successes once mined",
+        &["result"]
+    )
+    .unwrap()
+});
+
+pub static PAUSED: Lazy<Gauge> = Lazy::new(|| {
+    register_gauge!("paused", "whether propagation is paused by an operator").unwrap()
+});
+
+static PROPAGATIONS_SKIPPED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "propagations_skipped",
+        "wake-ups of the propagation task that didn't propagate, by what held it back",
+        &["reason"]
+    )
+    .unwrap()
+});
+
+static MONITORED_TXS_QUEUED: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "monitored_txs_queued",
+        "submitted transactions waiting for the transaction monitor"
+    )
+    .unwrap()
+});
+
+static MONITORED_TXS_DROPPED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "monitored_txs_dropped",
+        "transactions not queued for the monitor, by why",
+        &["reason"]
+    )
+    .unwrap()
+});
+
+pub static PAUSE_REMINDERS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "pause_reminders",
+        "reminders that a pause without a duration is still in place"
+    )
+    .unwrap()
+});
+
+pub static LOW_BALANCE: Lazy<Gauge> = Lazy::new(|| {
+    register_gauge!(
+        "low_balance",
+        "whether propagation is stopped by a critically low wallet balance"
+    )
+    .unwrap()
+});
+
+static WALLET_BALANCE: Lazy<Gauge> = Lazy::new(|| {
+    register_gauge!(
+        "wallet_balance_ether",
+        "balance of the relayer wallet on mainnet"
+    )
+    .unwrap()
+});
+
+static PROPAGATION_SPEND: Lazy<Gauge> = Lazy::new(|| {
+    register_gauge!(
+        "propagation_spend_ether",
+        "gas spent by propagations over the trailing 24 hours"
+    )
+    .unwrap()
+});
+
+static PROPAGATION_GAS_USED: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "propagation_gas_used_total",
+        "gas used by the propagations that made it on chain, reverted ones included"
+    )
+    .unwrap()
+});
+
+static PROPAGATION_COST_WEI: Lazy<Counter> = Lazy::new(|| {
+    register_counter!(
+        "propagation_cost_wei_total",
+        "wei the propagations that made it on chain paid for gas"
+    )
+    .unwrap()
+});
+
+static PROPAGATION_COST: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "propagation_cost_ether",
+        "what a single propagation paid for gas",
+        vec![0.0001, 0.0005, 0.001, 0.002, 0.005, 0.01, 0.02, 0.05, 0.1]
+    )
+    .unwrap()
+});
+
+pub static SPEND_LIMIT_REACHED: Lazy<Gauge> = Lazy::new(|| {
+    register_gauge!(
+        "spend_limit_reached",
+        "whether propagation is stopped by the daily spend limit"
+    )
+    .unwrap()
+});
+
+pub static CONSECUTIVE_FAILURES: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "consecutive_failed_propagations",
+        "propagations failed since the last one that mined or the last resume"
+    )
+    .unwrap()
+});
+
+pub static GAS_ESTIMATE: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "gas_estimate",
+        "eth_estimateGas result for the latest transaction, before the multiplier"
+    )
+    .unwrap()
+});
+
+static DATABASE_CONNECTIONS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "database_connections",
+        "connections of the database pool, by whether they are idle or in use",
+        &["state"]
+    )
+    .unwrap()
+});
+
+static PRUNED_ROWS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "pruned_rows",
+        "rows deleted by the retention task, per table",
+        &["table"]
+    )
+    .unwrap()
+});
+
+static SCANNED_LOGS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!("scanned_logs", "logs scanned per contract", &["contract"]).unwrap()
+});
+
+static LOG_RESUBSCRIPTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "log_resubscriptions",
+        "log subscriptions made again after one dropped or failed, per contract",
+        &["contract"]
+    )
+    .unwrap()
+});
+
+static OWNERSHIP_TRANSFERS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "ownership_transfers",
+        "OwnershipTransferred events scanned, per contract",
+        &["contract"]
+    )
+    .unwrap()
+});
+
+static SCANNING_WINDOW: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "scanning_window_blocks",
+        "blocks per log query, below scanning_window_size while the provider rejects ranges",
+        &["contract"]
+    )
+    .unwrap()
+});
+
+static SCANNER_LAG: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "scanner_lag_blocks",
+        "blocks the scanner has left to scan up to the chain head less the offset",
+        &["contract"]
+    )
+    .unwrap()
+});
+
+static SCANNER_HEALTHY: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "scanner_healthy",
+        "0 once unhealthy_scan_failures scans of the contract failed in a row, 1 otherwise",
+        &["contract"]
+    )
+    .unwrap()
+});
+
+static SCAN_FAILURES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!("scan_failures", "failed scans per contract", &["contract"]).unwrap()
+});
+
+pub static MINE_TRANSACTION_WAIT: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "mine_transaction_wait_seconds",
+        "time spent waiting for a propagation to be mined",
+        vec![1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0]
+    )
+    .unwrap()
+});
+
+pub static PROPAGATION_MINED: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "propagation_mined_seconds",
+        "time from submitting a propagation until it was seen mined on mainnet",
+        vec![15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0]
+    )
+    .unwrap()
+});
+
+pub static PROPAGATION_DELIVERED: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "propagation_delivered_seconds",
+        "time from a propagation mining on mainnet until Scroll was seen adding its root",
+        vec![60.0, 120.0, 300.0, 600.0, 900.0, 1800.0, 3600.0, 7200.0]
+    )
+    .unwrap()
+});
+
+pub static PROPAGATION_TIMEOUTS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "propagation_timeout_total",
+        "propagations whose root Scroll didn't add within delivery_deadline"
+    )
+    .unwrap()
+});
+
+static INFO: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "scroll_service_info",
+        "deployment this service runs in, always 1",
+        &[
+            "environment",
+            "l1_chain_id",
+            "l2_chain_id",
+            "bridge_address",
+            "scroll_world_id_address",
+            "signer_address",
+            "version",
+            "deployment",
+        ]
+    )
+    .unwrap()
+});
+
+/// Labels of [`PROPAGATION_RESULTS`].
+pub const SUCCESS: &str = "success";
+pub const FAILURE: &str = "failure";
+
+/// What held a propagation back, the label of `propagations_skipped`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    Paused,
+    LowBalance,
+    MinInterval,
+    SpendLimit,
+    NotLeader,
+}
+
+impl SkipReason {
+    const ALL: [Self; 5] = [
+        Self::Paused,
+        Self::LowBalance,
+        Self::MinInterval,
+        Self::SpendLimit,
+        Self::NotLeader,
+    ];
+
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Paused => "paused",
+            Self::LowBalance => "low_balance",
+            Self::MinInterval => "min_propagation_interval",
+            Self::SpendLimit => "daily_spend_limit",
+            Self::NotLeader => "not_leader",
+        }
+    }
+}
+
+/// Why a transaction wasn't queued for the monitor, the label of
+/// `monitored_txs_dropped`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropReason {
+    /// Already queued
+    Duplicate,
+    /// The queue was full, the monitor picks it up from the db instead
+    Full,
+}
+
+impl DropReason {
+    const ALL: [Self; 2] = [Self::Duplicate, Self::Full];
+
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Duplicate => "duplicate",
+            Self::Full => "full",
+        }
+    }
+}
+
+/// A table the retention task prunes, the label of `pruned_rows`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrunedTable {
+    Roots,
+    Transactions,
+}
+
+impl PrunedTable {
+    const ALL: [Self; 2] = [Self::Roots, Self::Transactions];
+
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Roots => "roots",
+            Self::Transactions => "transactions",
+        }
+    }
+}
+
+/// Registers every metric and the known label values.
+pub fn register() {
+    Lazy::force(&SYNCED_STATE);
+    Lazy::force(&ROOTS_OUTSTANDING);
+    Lazy::force(&PROPAGATION_ATTEMPTS);
+    Lazy::force(&MINE_TRANSACTION_WAIT);
+    Lazy::force(&PROPAGATION_MINED);
+    Lazy::force(&PROPAGATION_DELIVERED);
+    Lazy::force(&PROPAGATION_TIMEOUTS);
+    Lazy::force(&PAUSED);
+    Lazy::force(&MONITORED_TXS_QUEUED);
+    Lazy::force(&PAUSE_REMINDERS);
+    Lazy::force(&LOW_BALANCE);
+    Lazy::force(&WALLET_BALANCE);
+    Lazy::force(&CONSECUTIVE_FAILURES);
+    Lazy::force(&PROPAGATION_SPEND);
+    Lazy::force(&PROPAGATION_GAS_USED);
+    Lazy::force(&PROPAGATION_COST_WEI);
+    Lazy::force(&PROPAGATION_COST);
+    Lazy::force(&SPEND_LIMIT_REACHED);
+    Lazy::force(&GAS_ESTIMATE);
+
+    for result in [SUCCESS, FAILURE] {
+        PROPAGATION_RESULTS.with_label_values(&[result]);
+    }
+
+    for reason in SkipReason::ALL {
+        PROPAGATIONS_SKIPPED.with_label_values(&[reason.label()]);
+    }
+
+    for reason in DropReason::ALL {
+        MONITORED_TXS_DROPPED.with_label_values(&[reason.label()]);
+    }
+
+    for table in PrunedTable::ALL {
+        PRUNED_ROWS.with_label_values(&[table.label()]);
+    }
+
+    for source in RootSource::ALL {
+        SCANNED_LOGS.with_label_values(&[contract(source)]);
+        LOG_RESUBSCRIPTIONS.with_label_values(&[contract(source)]);
+        SCAN_FAILURES.with_label_values(&[contract(source)]);
+        SCANNER_HEALTHY
+            .with_label_values(&[contract(source)])
+            .set(1);
+    }
+    OWNERSHIP_TRANSFERS.with_label_values(&[contract(RootSource::Bridge)]);
+
+    record_database_connections(0, 0);
+
+    refresh();
+}
+
+/// The contract, named as in the `/contracts` report, that a source is
+/// scanned from.
+const fn contract(source: RootSource) -> &'static str {
+    match source {
+        RootSource::Bridge => "scroll_state_bridge",
+        RootSource::Scroll => "scroll_world_id",
+        RootSource::WorldId => "world_id",
+    }
+}
+
+/// What `scroll_service_info` reports, from the validated config and the
+/// contracts.
+#[derive(Debug, Clone)]
+pub struct ServiceInfo {
+    pub environment:             String,
+    /// Chain of the bridge and the world id contract
+    pub l1_chain_id:             U256,
+    /// Chain of the scroll world id contract
+    pub l2_chain_id:             U256,
+    pub bridge_address:          Address,
+    pub scroll_world_id_address: Address,
+    pub signer_address:          Address,
+}
+
+/// Exports `info` once, after the contracts are connected. Values are
+/// normalized so a restart produces the same series.
+pub fn record_info(info: &ServiceInfo) {
+    // Debug renders the full lowercase address, unlike the checksummed or
+    // abbreviated forms
+    let environment = info.environment.trim().to_lowercase();
+    let l1_chain_id = info.l1_chain_id.to_string();
+    let l2_chain_id = info.l2_chain_id.to_string();
+    let bridge_address = format!("{:?}", info.bridge_address);
+    let scroll_world_id_address = format!("{:?}", info.scroll_world_id_address);
+    let signer_address = format!("{:?}", info.signer_address);
+
+    let mut deployments = vec!["stable"];
+    if cfg!(feature = "canary") {
+        deployments.push("canary");
+    }
+
+    for deployment in deployments {
+        INFO.with_label_values(&[
+            &environment,
+            &l1_chain_id,
+            &l2_chain_id,
+            &bridge_address,
+            &scroll_world_id_address,
+            &signer_address,
+            env!("CARGO_PKG_VERSION"),
+            deployment,
+        ])
+        .set(1);
+    }
+}
+
+#[allow(clippy::cast_possible_wrap)]
+pub fn record_outstanding_roots(count: usize) {
+    ROOTS_OUTSTANDING.set(count as i64);
+}
+
+pub fn record_skipped_propagation(reason: SkipReason) {
+    PROPAGATIONS_SKIPPED
+        .with_label_values(&[reason.label()])
+        .inc();
+}
+
+#[allow(clippy::cast_possible_wrap)]
+pub fn record_monitored_txs_queued(depth: usize) {
+    MONITORED_TXS_QUEUED.set(depth as i64);
+}
+
+pub fn record_dropped_monitored_tx(reason: DropReason) {
+    MONITORED_TXS_DROPPED
+        .with_label_values(&[reason.label()])
+        .inc();
+}
+
+/// `size` counts every open connection, `idle` those not in use.
+#[allow(clippy::cast_possible_wrap)]
+pub fn record_database_connections(size: u32, idle: usize) {
+    let idle = idle.min(size as usize);
+    DATABASE_CONNECTIONS
+        .with_label_values(&["idle"])
+        .set(idle as i64);
+    DATABASE_CONNECTIONS
+        .with_label_values(&["active"])
+        .set(i64::from(size) - idle as i64);
+}
+
+pub fn record_pruned_rows(table: PrunedTable, count: u64) {
+    PRUNED_ROWS
+        .with_label_values(&[table.label()])
+        .inc_by(count);
+}
+
+pub fn record_scanned_logs(source: RootSource, count: usize) {
+    SCANNED_LOGS
+        .with_label_values(&[contract(source)])
+        .inc_by(count as u64);
+}
+
+pub fn record_resubscription(source: RootSource) {
+    LOG_RESUBSCRIPTIONS
+        .with_label_values(&[contract(source)])
+        .inc();
+}
+
+pub fn record_ownership_transfer(source: RootSource) {
+    OWNERSHIP_TRANSFERS
+        .with_label_values(&[contract(source)])
+        .inc();
+}
+
+#[allow(clippy::cast_possible_wrap)]
+pub fn record_scanning_window(source: RootSource, window_size: u64) {
+    SCANNING_WINDOW
+        .with_label_values(&[contract(source)])
+        .set(window_size as i64);
+}
+
+#[allow(clippy::cast_possible_wrap)]
+pub fn record_scanner_lag(source: RootSource, lag: u64) {
+    SCANNER_LAG
+        .with_label_values(&[contract(source)])
+        .set(lag as i64);
+}
+
+pub fn record_scan_failure(source: RootSource, healthy: bool) {
+    SCAN_FAILURES.with_label_values(&[contract(source)]).inc();
+    record_scanner_health(source, healthy);
+}
+
+pub fn record_scanner_health(source: RootSource, healthy: bool) {
+    SCANNER_HEALTHY
+        .with_label_values(&[contract(source)])
+        .set(i64::from(healthy));
+}
+
+/// Precise to the gwei, well past what an alert needs.
+#[allow(clippy::cast_precision_loss)]
+fn ether(wei: U256) -> f64 {
+    let gwei = wei / U256::exp10(9);
+    let gwei = if gwei > U256::from(u128::MAX) {
+        u128::MAX
+    } else {
+        gwei.as_u128()
+    };
+    gwei as f64 / 1e9
+}
+
+pub fn record_wallet_balance(balance: U256) {
+    WALLET_BALANCE.set(ether(balance));
+}
+
+pub fn record_propagation_spend(spend: U256) {
+    PROPAGATION_SPEND.set(ether(spend));
+}
+
+/// Counts a propagation's cost, once when it is first recorded.
+#[allow(clippy::cast_precision_loss)]
+pub fn record_propagation_cost(cost: &GasCost) {
+    let gas_used = if cost.gas_used > U256::from(u64::MAX) {
+        u64::MAX
+    } else {
+        cost.gas_used.as_u64()
+    };
+    let wei = cost.wei();
+    let wei = if wei > U256::from(u128::MAX) {
+        u128::MAX
+    } else {
+        wei.as_u128()
+    };
+
+    PROPAGATION_GAS_USED.inc_by(gas_used);
+    PROPAGATION_COST_WEI.inc_by(wei as f64);
+    PROPAGATION_COST.observe(ether(cost.wei()));
+}
+
+pub fn record_leader(leading: bool) {
+    LEADER.set(i64::from(leading));
+}
+
+pub fn record_scroll_finalized_block(block_number: u64) {
+    SCROLL_FINALIZED_BLOCK.set(i64::try_from(block_number).unwrap_or(i64::MAX));
+}
+
+pub fn record_unsynced_for(unsynced_for: Option<Duration>) {
+    UNSYNCED_SECONDS.set(unsynced_for.map_or(0.0, |duration| duration.as_secs_f64()));
+}
+
+pub fn record_last_synced(last_synced: DateTime<Utc>) {
+    LAST_SYNCED.store(last_synced.timestamp(), Ordering::Relaxed);
+}
+
+/// Updates the gauges derived from the current time. Called before every
+/// scrape.
+#[allow(clippy::cast_precision_loss)]
+pub fn refresh() {
+    let seconds = match LAST_SYNCED.load(Ordering::Relaxed) {
+        NEVER => -1.0,
+        last_synced => (Utc::now().timestamp() - last_synced).max(0) as f64,
+    };
+
+    SECONDS_SINCE_LAST_SYNC.set(seconds);
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus::Encoder;
+
+    use super::*;
+
+    #[test]
+    fn registered_metrics_are_exported_before_any_activity() {
+        register();
+
+        let families = prometheus::gather();
+        let family = |name: &str| {
+            families
+                .iter()
+                .find(|family| family.get_name() == name)
+                .unwrap_or_else(|| panic!("{name} is not registered"))
+        };
+
+        assert_eq!(family("scanned_logs").get_metric().len(), 3);
+        assert_eq!(family("log_resubscriptions").get_metric().len(), 3);
+        assert_eq!(family("scan_failures").get_metric().len(), 3);
+        assert_eq!(family("scanner_healthy").get_metric().len(), 3);
+        assert_eq!(family("ownership_transfers").get_metric().len(), 1);
+        assert_eq!(family("propagation_results").get_metric().len(), 2);
+        assert_eq!(family("propagations_skipped").get_metric().len(), 5);
+        assert_eq!(family("database_connections").get_metric().len(), 2);
+        assert_eq!(family("monitored_txs_dropped").get_metric().len(), 2);
+        assert_eq!(family("pruned_rows").get_metric().len(), 2);
+        assert_eq!(family("monitored_txs_queued").get_metric().len(), 1);
+        assert_eq!(
+            family("mine_transaction_wait_seconds").get_metric().len(),
+            1
+        );
+        assert_eq!(
+            family("propagation_delivered_seconds").get_metric().len(),
+            1
+        );
+        assert_eq!(family("propagation_timeout_total").get_metric().len(), 1);
+
+        let never_synced = family("seconds_since_last_sync").get_metric()[0].get_gauge();
+        assert!(never_synced.get_value() < 0.0);
+    }
+
+    #[test]
+    fn info_metric_is_scraped_with_normalized_labels() {
+        record_info(&ServiceInfo {
+            environment:             " Staging ".to_string(),
+            l1_chain_id:             U256::from(1),
+            l2_chain_id:             U256::from(534_352),
+            bridge_address:          "0xAbCdEf0000000000000000000000000000000001"
+                .parse()
+                .unwrap(),
+            scroll_world_id_address: Address::repeat_byte(0xab),
+            signer_address:          Address::repeat_byte(0x11),
+        });
+
+        let mut scrape = Vec::new();
+        prometheus::TextEncoder::new()
+            .encode(&prometheus::gather(), &mut scrape)
+            .unwrap();
+        let scrape = String::from_utf8(scrape).unwrap();
+
+        let series = scrape
+            .lines()
+            .find(|line| {
+                line.starts_with("scroll_service_info{") && line.contains(r#"deployment="stable""#)
+            })
+            .expect("scroll_service_info is exported");
+
+        for label in [
+            r#"environment="staging""#,
+            r#"l1_chain_id="1""#,
+            r#"l2_chain_id="534352""#,
+            r#"bridge_address="0xabcdef0000000000000000000000000000000001""#,
+            r#"scroll_world_id_address="0xabababababababababababababababababababab""#,
+            r#"signer_address="0x1111111111111111111111111111111111111111""#,
+            &format!(r#"version="{}""#, env!("CARGO_PKG_VERSION")),
+        ] {
+            assert!(series.contains(label), "{label} missing from {series}");
+        }
+        assert!(series.ends_with(" 1"));
+
+        let canary = scrape.contains(r#"deployment="canary""#);
+        assert_eq!(canary, cfg!(feature = "canary"));
+    }
+}
@@ -0,0 +1,323 @@
+//! Webhook notifications of what the service does, e.g. into a Slack channel.
+//!
+//! Every delivery runs in the background and is retried a bounded number of
+//! times, so an unreachable webhook never holds up the task that raised the
+//! event.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use ethers::types::U256;
+use serde::{Deserialize, Serialize};
+use tokio::time;
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::{config::NotificationsConfig, utils::TransactionId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// The bridge status moved, e.g. from pending to synced
+    StatusChanged,
+    /// The roots have differed for longer than `unsynced_after`
+    Unsynced,
+    PropagationSubmitted,
+    /// A propagation reverted or was dropped
+    PropagationFailed,
+    /// A propagation mined but Scroll didn't add its root within
+    /// `delivery_deadline`
+    PropagationTimedOut,
+    /// The status stayed unsynced or pending past `unsynced_deadline`
+    UnsyncedDeadline,
+    /// The wallet fell below the critical threshold and propagation stopped
+    LowBalance,
+    /// The trailing day's gas spend exceeded `daily_spend_limit` and
+    /// propagation stopped
+    SpendLimitReached,
+    /// A task failed or panicked and is restarted
+    TaskRestarted,
+    /// The state bridge changed owners
+    OwnershipTransferred,
+}
+
+/// Something worth telling the operators about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub kind:           EventKind,
+    pub message:        String,
+    pub root:           Option<U256>,
+    pub transaction_id: Option<TransactionId>,
+    /// Why a propagation reverted, decoded from its replay
+    pub revert_reason:  Option<String>,
+}
+
+impl Event {
+    pub fn new(kind: EventKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            root: None,
+            transaction_id: None,
+            revert_reason: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_root(mut self, root: U256) -> Self {
+        self.root = Some(root);
+        self
+    }
+
+    #[must_use]
+    pub fn with_transaction(mut self, transaction_id: TransactionId) -> Self {
+        self.transaction_id = Some(transaction_id);
+        self
+    }
+
+    #[must_use]
+    pub fn with_revert_reason(mut self, revert_reason: String) -> Self {
+        self.revert_reason = Some(revert_reason);
+        self
+    }
+}
+
+/// What a webhook receives. `text` repeats the message for Slack, which only
+/// renders that field.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Payload<'a> {
+    event:          EventKind,
+    timestamp:      DateTime<Utc>,
+    instance:       &'a str,
+    message:        &'a str,
+    text:           String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    root:           Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transaction_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    revert_reason:  Option<&'a str>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    config:   NotificationsConfig,
+    /// `service_name` under `[service]`
+    instance: String,
+    client:   reqwest::Client,
+}
+
+impl Notifier {
+    #[must_use]
+    pub fn new(config: NotificationsConfig, instance: String) -> Self {
+        Self {
+            config,
+            instance,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    #[must_use]
+    pub const fn unsynced_after(&self) -> Duration {
+        self.config.unsynced_after
+    }
+
+    fn wants(&self, kind: EventKind) -> bool {
+        !self.config.webhooks.is_empty()
+            && (self.config.events.is_empty() || self.config.events.contains(&kind))
+    }
+
+    /// Sends `event` to every webhook in the background, unless it is
+    /// filtered out.
+    pub fn notify(&self, event: Event) {
+        if !self.wants(event.kind) {
+            return;
+        }
+
+        let payload = Payload {
+            event:          event.kind,
+            timestamp:      Utc::now(),
+            instance:       &self.instance,
+            message:        &event.message,
+            text:           format!("[{}] {}", self.instance, event.message),
+            root:           event.root,
+            transaction_id: event.transaction_id.as_ref().map(TransactionId::as_str),
+            revert_reason:  event.revert_reason.as_deref(),
+        };
+        let payload = match serde_json::to_value(&payload) {
+            Ok(payload) => payload,
+            Err(err) => {
+                warn!(%err, "Failed to serialize a notification");
+                return;
+            }
+        };
+
+        for webhook in &self.config.webhooks {
+            tokio::spawn(deliver(
+                self.client.clone(),
+                webhook.clone().into(),
+                payload.clone(),
+                self.config.clone(),
+            ));
+        }
+    }
+}
+
+async fn deliver(
+    client: reqwest::Client,
+    webhook: Url,
+    payload: serde_json::Value,
+    config: NotificationsConfig,
+) {
+    // Webhook URLs carry their credentials in the path
+    let host = webhook.host_str().unwrap_or_default().to_string();
+    let mut backoff = config.retry_backoff;
+
+    for attempt in 0..=config.retries {
+        let result = client
+            .post(webhook.clone())
+            .timeout(config.timeout)
+            .json(&payload)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status);
+
+        match result {
+            Ok(_) => return,
+            Err(err) if attempt == config.retries => {
+                warn!(
+                    webhook = host,
+                    %err,
+                    event = %payload["event"],
+                    "Giving up on a notification"
+                );
+            }
+            Err(err) => {
+                debug!(webhook = host, %err, attempt, "Notification failed, retrying");
+                time::sleep(backoff).await;
+                backoff *= 2;
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use std::{
+        convert::Infallible,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use hyper::{
+        service::{make_service_fn, service_fn},
+        Body, Request, Response, Server, StatusCode,
+    };
+    use serde_json::Value;
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    /// A webhook that answers `500` to its first `failures` requests and
+    /// forwards every payload it receives.
+    fn webhook(failures: usize) -> (Url, mpsc::UnboundedReceiver<Value>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let make_service = make_service_fn(move |_| {
+            let sender = sender.clone();
+            let requests = requests.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                    let sender = sender.clone();
+                    let requests = requests.clone();
+                    async move {
+                        let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+                        sender.send(serde_json::from_slice(&body).unwrap()).unwrap();
+
+                        let status = if requests.fetch_add(1, Ordering::SeqCst) < failures {
+                            StatusCode::INTERNAL_SERVER_ERROR
+                        } else {
+                            StatusCode::OK
+                        };
+                        Ok::<_, Infallible>(
+                            Response::builder()
+                                .status(status)
+                                .body(Body::empty())
+                                .unwrap(),
+                        )
+                    }
+                }))
+            }
+        });
+
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let url = format!("http://{}/hook", server.local_addr())
+            .parse()
+            .unwrap();
+        tokio::spawn(server);
+
+        (url, receiver)
+    }
+
+    fn notifier(webhook: Url, events: Vec<EventKind>) -> Notifier {
+        Notifier::new(
+            NotificationsConfig {
+                webhooks: vec![webhook.into()],
+                events,
+                retry_backoff: Duration::from_millis(10),
+                ..NotificationsConfig::default()
+            },
+            "scroll-service-staging".to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn payload_is_delivered_after_a_failed_attempt() {
+        let (url, mut received) = webhook(1);
+
+        notifier(url, vec![]).notify(
+            Event::new(EventKind::PropagationFailed, "Propagation reverted")
+                .with_root(U256::from(0xabc))
+                .with_transaction(TransactionId::job("tx-1").unwrap())
+                .with_revert_reason("GasLimitZero".to_string()),
+        );
+
+        let first = received.recv().await.unwrap();
+        let retried = received.recv().await.unwrap();
+        assert_eq!(first, retried);
+
+        assert_eq!(retried["event"], "propagation_failed");
+        assert_eq!(retried["instance"], "scroll-service-staging");
+        assert_eq!(retried["message"], "Propagation reverted");
+        assert_eq!(
+            retried["text"],
+            "[scroll-service-staging] Propagation reverted"
+        );
+        assert_eq!(retried["root"], "0xabc");
+        assert_eq!(retried["transactionId"], "tx-1");
+        assert_eq!(retried["revertReason"], "GasLimitZero");
+        assert!(retried["timestamp"]
+            .as_str()
+            .unwrap()
+            .parse::<DateTime<Utc>>()
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn events_outside_the_filter_are_not_sent() {
+        let (url, mut received) = webhook(0);
+        let notifier = notifier(url, vec![EventKind::Unsynced]);
+
+        notifier.notify(Event::new(EventKind::TaskRestarted, "scan_roots restarted"));
+        notifier.notify(Event::new(EventKind::Unsynced, "Unsynced for 10m"));
+
+        let payload = received.recv().await.unwrap();
+        assert_eq!(payload["event"], "unsynced");
+        assert!(payload.get("root").is_none());
+        assert!(received.try_recv().is_err());
+    }
+}
@@ -0,0 +1,324 @@
+//! Operator pauses, which stop propagation while the rest of the service keeps
+//! running.
+//!
+//! A pause either expires after a duration or lasts until it is lifted, in
+//! which case [`PauseWatcher`] keeps reminding that the service is still
+//! paused.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::watch,
+    time::{self, Instant},
+};
+
+use crate::database::types::PauseRecord;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pause {
+    pub reason:     String,
+    pub paused_at:  DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// When `expires_at` passes, on the runtime clock
+    deadline:       Option<Instant>,
+}
+
+impl Pause {
+    /// # Errors
+    ///
+    /// Will return `Err` if `duration` is too long to represent as a date.
+    pub fn new(reason: String, duration: Option<Duration>) -> anyhow::Result<Self> {
+        let paused_at = Utc::now();
+        let expires_at = duration
+            .map(|duration| {
+                chrono::Duration::from_std(duration)
+                    .ok()
+                    .and_then(|duration| paused_at.checked_add_signed(duration))
+                    .ok_or_else(|| anyhow::anyhow!("pause duration is too long"))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            reason,
+            paused_at,
+            expires_at,
+            deadline: duration.map(|duration| Instant::now() + duration),
+        })
+    }
+
+    /// Rebuilds a pause persisted by an earlier run. A pause that expired in
+    /// the meantime expires right away.
+    #[must_use]
+    pub fn restore(record: PauseRecord) -> Self {
+        let deadline = record.pause_expires_at.map(|expires_at| {
+            Instant::now() + (expires_at - Utc::now()).to_std().unwrap_or_default()
+        });
+
+        Self {
+            reason: record.pause_reason,
+            paused_at: record.paused_at,
+            expires_at: record.pause_expires_at,
+            deadline,
+        }
+    }
+
+    #[must_use]
+    pub fn record(&self) -> PauseRecord {
+        PauseRecord {
+            pause_reason:     self.reason.clone(),
+            paused_at:        self.paused_at,
+            pause_expires_at: self.expires_at,
+        }
+    }
+
+    /// Time left until the pause expires.
+    #[must_use]
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+}
+
+/// How a pause is reported on the status endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PauseStatus {
+    pub reason:            String,
+    pub paused_at:         DateTime<Utc>,
+    pub expires_at:        Option<DateTime<Utc>>,
+    /// Countdown to `expires_at`
+    pub remaining_seconds: Option<u64>,
+}
+
+impl From<&Pause> for PauseStatus {
+    fn from(pause: &Pause) -> Self {
+        Self {
+            reason:            pause.reason.clone(),
+            paused_at:         pause.paused_at,
+            expires_at:        pause.expires_at,
+            remaining_seconds: pause.remaining().map(|remaining| remaining.as_secs()),
+        }
+    }
+}
+
+/// The in-memory pause state, shared by the server and the tasks.
+#[derive(Debug)]
+pub struct PauseControl {
+    state: watch::Sender<Option<Pause>>,
+}
+
+impl PauseControl {
+    #[must_use]
+    pub fn new(pause: Option<Pause>) -> Self {
+        Self {
+            state: watch::Sender::new(pause),
+        }
+    }
+
+    #[must_use]
+    pub fn current(&self) -> Option<Pause> {
+        self.state.borrow().clone()
+    }
+
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.state.borrow().is_some()
+    }
+
+    /// Pauses, or replaces the current pause, which is how a pause is
+    /// extended.
+    pub fn pause(&self, pause: Pause) {
+        self.state.send_replace(Some(pause));
+    }
+
+    /// Returns `false` if the service wasn't paused.
+    pub fn resume(&self) -> bool {
+        self.state.send_replace(None).is_some()
+    }
+
+    /// Lifts `pause` unless it has been replaced in the meantime.
+    pub fn resume_if_current(&self, pause: &Pause) -> bool {
+        self.state.send_if_modified(|state| {
+            if state.as_ref() == Some(pause) {
+                *state = None;
+                true
+            } else {
+                false
+            }
+        })
+    }
+
+    #[must_use]
+    pub fn watcher(&self, reminder_interval: Duration) -> PauseWatcher {
+        let receiver = self.state.subscribe();
+        let paused = receiver.borrow().is_some();
+
+        PauseWatcher {
+            receiver,
+            reminder_interval,
+            next_reminder: None,
+            paused,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PauseEvent {
+    /// The pause reached its expiry without being replaced
+    Expired(Pause),
+    /// A pause without expiry has lasted another reminder interval
+    StillPaused(Pause),
+    /// The pause was lifted
+    Resumed,
+}
+
+pub struct PauseWatcher {
+    receiver:          watch::Receiver<Option<Pause>>,
+    reminder_interval: Duration,
+    next_reminder:     Option<Instant>,
+    /// Whether the last state seen was paused
+    paused:            bool,
+}
+
+impl PauseWatcher {
+    /// Waits for the next event. Cancel safe: dropping the future keeps the
+    /// reminder schedule.
+    pub async fn next_event(&mut self) -> PauseEvent {
+        loop {
+            let pause = self.receiver.borrow_and_update().clone();
+
+            let Some(pause) = pause else {
+                self.next_reminder = None;
+                if std::mem::take(&mut self.paused) {
+                    return PauseEvent::Resumed;
+                }
+                self.changed().await;
+                continue;
+            };
+            self.paused = true;
+
+            let (deadline, event) = match pause.deadline {
+                Some(deadline) => (deadline, PauseEvent::Expired(pause)),
+                None => {
+                    let interval = self.reminder_interval;
+                    let due = *self
+                        .next_reminder
+                        .get_or_insert_with(|| Instant::now() + interval);
+                    (due, PauseEvent::StillPaused(pause))
+                }
+            };
+
+            let elapsed = tokio::select! {
+                () = self.changed() => false,
+                () = time::sleep_until(deadline) => true,
+            };
+
+            if elapsed {
+                if matches!(event, PauseEvent::StillPaused(_)) {
+                    self.next_reminder = Some(deadline + self.reminder_interval);
+                }
+                return event;
+            }
+        }
+    }
+
+    async fn changed(&mut self) {
+        if self.receiver.changed().await.is_err() {
+            // Nothing can change once the control is gone
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::timeout;
+
+    use super::*;
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    fn pause(duration: Option<Duration>) -> Pause {
+        Pause::new("upgrade".to_string(), duration).unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pause_expires_after_its_duration() {
+        let control = PauseControl::new(None);
+        let mut watcher = control.watcher(HOUR);
+        let start = Instant::now();
+
+        let paused = pause(Some(Duration::from_secs(60)));
+        control.pause(paused.clone());
+
+        assert_eq!(
+            watcher.next_event().await,
+            PauseEvent::Expired(paused.clone())
+        );
+        assert_eq!(start.elapsed(), Duration::from_secs(60));
+
+        assert!(control.resume_if_current(&paused));
+        assert_eq!(watcher.next_event().await, PauseEvent::Resumed);
+        assert!(!control.is_paused());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn extended_pause_expires_later() {
+        let control = PauseControl::new(None);
+        let mut watcher = control.watcher(HOUR);
+        let start = Instant::now();
+
+        let first = pause(Some(Duration::from_secs(60)));
+        control.pause(first.clone());
+        assert!(timeout(Duration::from_secs(30), watcher.next_event())
+            .await
+            .is_err());
+
+        let extended = pause(Some(Duration::from_secs(120)));
+        control.pause(extended.clone());
+
+        assert_eq!(watcher.next_event().await, PauseEvent::Expired(extended));
+        assert_eq!(start.elapsed(), Duration::from_secs(150));
+
+        // An expiry observed just before the extension doesn't lift it
+        assert!(!control.resume_if_current(&first));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pause_without_duration_reminds_every_interval() {
+        let control = PauseControl::new(None);
+        let mut watcher = control.watcher(HOUR);
+        let start = Instant::now();
+
+        let paused = pause(None);
+        control.pause(paused.clone());
+
+        for reminder in 1..=3 {
+            assert_eq!(
+                watcher.next_event().await,
+                PauseEvent::StillPaused(paused.clone())
+            );
+            assert_eq!(start.elapsed(), HOUR * reminder);
+        }
+
+        // Interrupting the wait doesn't push the next reminder back
+        assert!(timeout(HOUR / 2, watcher.next_event()).await.is_err());
+        assert_eq!(watcher.next_event().await, PauseEvent::StillPaused(paused));
+        assert_eq!(start.elapsed(), HOUR * 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn restored_pause_that_already_expired_expires_immediately() {
+        let mut record = pause(None).record();
+        record.pause_expires_at = Some(Utc::now() - chrono::Duration::minutes(5));
+
+        let control = PauseControl::new(Some(Pause::restore(record)));
+        let mut watcher = control.watcher(HOUR);
+        let start = Instant::now();
+
+        assert!(matches!(watcher.next_event().await, PauseEvent::Expired(_)));
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+}
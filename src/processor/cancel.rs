@@ -0,0 +1,80 @@
+//! Cancels a stuck propagation on an operator's request: the transaction is
+//! replaced at its nonce, and whichever of the two mines decides the outcome.
+
+use ethers::types::H256;
+use serde::Serialize;
+
+use crate::{ethereum::TransactionState, utils::TransactionId};
+
+/// How a cancel ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CancelOutcome {
+    /// The replacement mined, or the relayer dropped the original
+    Cancelled,
+    /// The original mined before its replacement
+    SupersededByOriginal,
+    /// Neither mined within `cancel_timeout`, the monitor settles it
+    Pending,
+}
+
+/// Where the race between the original and its replacement stands, from the
+/// original's state. `sent_to_self` tells whether what mined at its nonce
+/// is the self-send replacing it. `None` while neither mined.
+#[must_use]
+pub const fn outcome(state: &TransactionState, sent_to_self: bool) -> Option<CancelOutcome> {
+    match state {
+        TransactionState::Mined { .. } if sent_to_self => Some(CancelOutcome::Cancelled),
+        TransactionState::Mined { .. } => Some(CancelOutcome::SupersededByOriginal),
+        // The raw signer tracks the original, whose nonce the replacement took
+        TransactionState::NonceConsumed { .. } | TransactionState::Failed => {
+            Some(CancelOutcome::Cancelled)
+        }
+        TransactionState::Pending | TransactionState::Unknown => None,
+    }
+}
+
+/// A cancel of a pending propagation, as `POST /transactions/{id}/cancel`
+/// answers it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cancel {
+    pub transaction_id: TransactionId,
+    pub outcome:        CancelOutcome,
+    /// The transaction that mined at the nonce, when the relayer reports it
+    pub tx_hash:        Option<H256>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINED: TransactionState = TransactionState::Mined {
+        hash:      H256::zero(),
+        succeeded: true,
+        cost:      None,
+    };
+
+    #[test]
+    fn the_transaction_that_mined_decides_the_outcome() {
+        assert_eq!(outcome(&MINED, true), Some(CancelOutcome::Cancelled));
+        assert_eq!(
+            outcome(&MINED, false),
+            Some(CancelOutcome::SupersededByOriginal)
+        );
+        assert_eq!(
+            outcome(&TransactionState::NonceConsumed { nonce: 7 }, false),
+            Some(CancelOutcome::Cancelled)
+        );
+        assert_eq!(
+            outcome(&TransactionState::Failed, false),
+            Some(CancelOutcome::Cancelled)
+        );
+    }
+
+    #[test]
+    fn nothing_is_decided_before_either_mines() {
+        assert_eq!(outcome(&TransactionState::Pending, false), None);
+        assert_eq!(outcome(&TransactionState::Unknown, false), None);
+    }
+}
@@ -0,0 +1,286 @@
+//! Settles the transactions a previous run left pending, before propagation
+//! starts again.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    future::Future,
+    time::Duration,
+};
+
+use futures::StreamExt;
+use serde::Serialize;
+use tokio::time::{self, Instant};
+
+use crate::{config::CleanSlatePolicy, ethereum::PendingState, utils::TransactionId};
+
+/// How many transactions are waited for at once.
+const CONCURRENCY: usize = 16;
+
+/// How many transactions ended each way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Counts {
+    pub mined:     usize,
+    pub failed:    usize,
+    pub timed_out: usize,
+    pub cancelled: usize,
+    pub replaced:  usize,
+}
+
+impl Counts {
+    fn add(&mut self, outcome: &Outcome) {
+        match outcome {
+            Outcome::Mined => self.mined += 1,
+            Outcome::Failed(_) => self.failed += 1,
+            Outcome::TimedOut => self.timed_out += 1,
+            Outcome::Cancelled => self.cancelled += 1,
+            Outcome::Replaced => self.replaced += 1,
+        }
+    }
+}
+
+/// How the pending transactions found at startup ended, overall and by the
+/// state the relayer had each in.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CleanSlate {
+    #[serde(flatten)]
+    pub total:    Counts,
+    pub by_state: BTreeMap<PendingState, Counts>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Mined,
+    Failed(String),
+    TimedOut,
+    /// Dropped by the relayer before it was broadcast
+    Cancelled,
+    /// A fee-bumped self-send took its nonce
+    Replaced,
+}
+
+impl CleanSlate {
+    #[must_use]
+    pub fn from_outcomes<'a>(
+        outcomes: impl IntoIterator<Item = (PendingState, &'a Outcome)>,
+    ) -> Self {
+        outcomes
+            .into_iter()
+            .fold(Self::default(), |mut summary, (state, outcome)| {
+                summary.total.add(outcome);
+                summary.by_state.entry(state).or_default().add(outcome);
+                summary
+            })
+    }
+}
+
+/// What startup does with a pending transaction before waiting for the rest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Wait,
+    Cancel,
+    Replace,
+}
+
+impl Action {
+    /// Mined transactions are only waited for, as is whatever the relayer
+    /// can't place.
+    #[must_use]
+    pub const fn new(policy: CleanSlatePolicy, state: PendingState) -> Self {
+        match (policy, state) {
+            (
+                CleanSlatePolicy::CancelQueued | CleanSlatePolicy::ReplaceBroadcast,
+                PendingState::Queued,
+            ) => Self::Cancel,
+            (CleanSlatePolicy::ReplaceBroadcast, PendingState::Broadcast) => Self::Replace,
+            _ => Self::Wait,
+        }
+    }
+}
+
+/// Waits for every transaction concurrently, each for at most `per_transaction`
+/// and all of them for at most `overall`. Transactions still unresolved when
+/// `overall` passes, including ones never started, time out.
+pub async fn settle<M, F>(
+    transaction_ids: Vec<TransactionId>,
+    per_transaction: Duration,
+    overall: Duration,
+    mine: M,
+) -> Vec<(TransactionId, Outcome)>
+where
+    M: Fn(TransactionId) -> F,
+    F: Future<Output = anyhow::Result<bool>>,
+{
+    let deadline = Instant::now() + overall;
+    let mut outstanding: BTreeSet<TransactionId> = transaction_ids.iter().cloned().collect();
+    let mut outcomes = Vec::with_capacity(transaction_ids.len());
+
+    let mut mining = futures::stream::iter(transaction_ids)
+        .map(|transaction_id| {
+            let mined = mine(transaction_id.clone());
+            async move {
+                let outcome = match time::timeout(per_transaction, mined).await {
+                    Ok(Ok(true)) => Outcome::Mined,
+                    Ok(Ok(false)) => Outcome::Failed("failed in the relayer".to_string()),
+                    Ok(Err(err)) => Outcome::Failed(format!("{err:#}")),
+                    Err(_) => Outcome::TimedOut,
+                };
+                (transaction_id, outcome)
+            }
+        })
+        .buffer_unordered(CONCURRENCY);
+
+    while let Ok(Some((transaction_id, outcome))) = time::timeout_at(deadline, mining.next()).await
+    {
+        outstanding.remove(&transaction_id);
+        outcomes.push((transaction_id, outcome));
+    }
+
+    outcomes.extend(
+        outstanding
+            .into_iter()
+            .map(|transaction_id| (transaction_id, Outcome::TimedOut)),
+    );
+
+    outcomes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn transactions_are_mined_concurrently_within_the_timeouts() {
+        let ids = ["mined", "failed", "reverted", "slow", "stuck"]
+            .map(|id| TransactionId::job(id).unwrap());
+        let start = Instant::now();
+
+        let outcomes = settle(
+            ids.to_vec(),
+            Duration::from_secs(60),
+            Duration::from_secs(90),
+            |transaction_id| async move {
+                match transaction_id.as_str() {
+                    "mined" => {
+                        time::sleep(Duration::from_secs(30)).await;
+                        Ok(true)
+                    }
+                    "failed" => Err(anyhow::anyhow!("relayer unreachable")),
+                    "reverted" => Ok(false),
+                    _ => std::future::pending().await,
+                }
+            },
+        )
+        .await;
+
+        // Everything ran at once, bounded by the per transaction timeout
+        assert_eq!(start.elapsed(), Duration::from_secs(60));
+
+        let outcomes: BTreeMap<_, _> = outcomes.into_iter().collect();
+        assert_eq!(outcomes["mined"], Outcome::Mined);
+        assert_eq!(
+            outcomes["failed"],
+            Outcome::Failed("relayer unreachable".to_string())
+        );
+        assert_eq!(
+            outcomes["reverted"],
+            Outcome::Failed("failed in the relayer".to_string())
+        );
+        assert_eq!(outcomes["slow"], Outcome::TimedOut);
+        assert_eq!(outcomes["stuck"], Outcome::TimedOut);
+
+        let summary = CleanSlate::from_outcomes(
+            outcomes
+                .values()
+                .map(|outcome| (PendingState::Unknown, outcome)),
+        );
+        assert_eq!(summary.total, Counts {
+            mined: 1,
+            failed: 2,
+            timed_out: 2,
+            ..Counts::default()
+        });
+        assert_eq!(summary.by_state.keys().collect::<Vec<_>>(), [
+            &PendingState::Unknown
+        ]);
+    }
+
+    #[test]
+    fn policies_only_touch_the_states_they_name() {
+        use CleanSlatePolicy::{CancelQueued, ReplaceBroadcast, Wait};
+        use PendingState::{Broadcast, MinedUnconfirmed, Queued, Unknown};
+
+        for state in [Queued, Broadcast, MinedUnconfirmed, Unknown] {
+            assert_eq!(Action::new(Wait, state), Action::Wait);
+        }
+
+        assert_eq!(Action::new(CancelQueued, Queued), Action::Cancel);
+        assert_eq!(Action::new(CancelQueued, Broadcast), Action::Wait);
+
+        assert_eq!(Action::new(ReplaceBroadcast, Queued), Action::Cancel);
+        assert_eq!(Action::new(ReplaceBroadcast, Broadcast), Action::Replace);
+        assert_eq!(
+            Action::new(ReplaceBroadcast, MinedUnconfirmed),
+            Action::Wait
+        );
+        assert_eq!(Action::new(ReplaceBroadcast, Unknown), Action::Wait);
+    }
+
+    #[test]
+    fn summary_breaks_counts_down_by_state() {
+        let outcomes = [
+            (PendingState::Queued, Outcome::Cancelled),
+            (PendingState::Queued, Outcome::Cancelled),
+            (PendingState::Broadcast, Outcome::Replaced),
+            (PendingState::Broadcast, Outcome::Mined),
+            (PendingState::Unknown, Outcome::TimedOut),
+        ];
+
+        let summary =
+            CleanSlate::from_outcomes(outcomes.iter().map(|(state, outcome)| (*state, outcome)));
+
+        assert_eq!(summary.total, Counts {
+            mined:     1,
+            failed:    0,
+            timed_out: 1,
+            cancelled: 2,
+            replaced:  1,
+        });
+        assert_eq!(summary.by_state[&PendingState::Queued].cancelled, 2);
+        assert_eq!(summary.by_state[&PendingState::Broadcast], Counts {
+            mined: 1,
+            replaced: 1,
+            ..Counts::default()
+        });
+        assert!(!summary
+            .by_state
+            .contains_key(&PendingState::MinedUnconfirmed));
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["cancelled"], 2);
+        assert_eq!(json["by_state"]["broadcast"]["replaced"], 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn overall_timeout_gives_up_on_the_rest() {
+        let ids: Vec<_> = (0..CONCURRENCY * 2)
+            .map(|index| TransactionId::job(index.to_string()).unwrap())
+            .collect();
+        let start = Instant::now();
+
+        let outcomes = settle(
+            ids,
+            Duration::from_secs(60),
+            Duration::from_secs(10),
+            |_| std::future::pending(),
+        )
+        .await;
+
+        assert_eq!(start.elapsed(), Duration::from_secs(10));
+        assert_eq!(outcomes.len(), CONCURRENCY * 2);
+        assert!(outcomes
+            .iter()
+            .all(|(_, outcome)| *outcome == Outcome::TimedOut));
+    }
+}
@@ -0,0 +1,236 @@
+//! The events each scanned contract is watched for. One `eth_getLogs` per
+//! contract asks for all of them, and each log is told apart by its topic0:
+//! roots go on to be recorded, an ownership change is raised to the
+//! operators.
+
+use ethers::{
+    contract::{parse_log, EthEvent},
+    types::{Address, Log, Topic, H256, U256},
+};
+
+use crate::{
+    contracts::{
+        abi::{
+            OwnershipTransferredFilter, RootAddedFilter, RootPropagatedFilter, TreeChangedFilter,
+        },
+        scanner,
+    },
+    database::types::RootSource,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchedEvent {
+    /// The state bridge sent a root
+    RootPropagated,
+    /// The target's WorldID received a root
+    RootAdded,
+    /// WorldID on mainnet moved to a new root
+    TreeChanged,
+    /// The state bridge changed owners, which the owner check at startup
+    /// wouldn't notice until the next restart
+    OwnershipTransferred,
+}
+
+impl WatchedEvent {
+    /// The events the contract `source` is scanned on is watched for.
+    #[must_use]
+    pub const fn of(source: RootSource) -> &'static [Self] {
+        match source {
+            RootSource::Bridge => &[Self::RootPropagated, Self::OwnershipTransferred],
+            RootSource::Scroll => &[Self::RootAdded],
+            RootSource::WorldId => &[Self::TreeChanged],
+        }
+    }
+
+    #[must_use]
+    pub fn topic0(self) -> H256 {
+        match self {
+            Self::RootPropagated => RootPropagatedFilter::signature(),
+            Self::RootAdded => RootAddedFilter::signature(),
+            Self::TreeChanged => TreeChangedFilter::signature(),
+            Self::OwnershipTransferred => OwnershipTransferredFilter::signature(),
+        }
+    }
+
+    /// The event of `source` whose topic0 is `topic0`, if it is watched for.
+    #[must_use]
+    pub fn from_topic0(source: RootSource, topic0: H256) -> Option<Self> {
+        Self::of(source)
+            .iter()
+            .copied()
+            .find(|event| event.topic0() == topic0)
+    }
+}
+
+/// What a log of a watched event carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decoded {
+    /// A root of [`WatchedEvent::RootPropagated`], [`WatchedEvent::RootAdded`]
+    /// or [`WatchedEvent::TreeChanged`]
+    Root(U256),
+    OwnershipTransferred {
+        previous_owner: Address,
+        new_owner:      Address,
+    },
+}
+
+/// The topics of a filter matching every event `source` is watched for.
+#[must_use]
+pub fn topics(source: RootSource) -> [Option<Topic>; 4] {
+    let topics: Vec<H256> = WatchedEvent::of(source)
+        .iter()
+        .map(|event| event.topic0())
+        .collect();
+    scanner::event_topics(&topics)
+}
+
+/// Decodes `log` as the event of `source` its topic0 names, `None` when
+/// `source` isn't watched for it.
+///
+/// # Errors
+///
+/// Will return `Err` if the log doesn't decode as the event its topic0 names.
+pub fn decode(source: RootSource, log: &Log) -> anyhow::Result<Option<Decoded>> {
+    let Some(event) = log
+        .topics
+        .first()
+        .and_then(|topic0| WatchedEvent::from_topic0(source, *topic0))
+    else {
+        return Ok(None);
+    };
+
+    let decoded = match event {
+        WatchedEvent::RootPropagated => {
+            Decoded::Root(parse_log::<RootPropagatedFilter>(log.clone())?.root)
+        }
+        WatchedEvent::RootAdded => Decoded::Root(parse_log::<RootAddedFilter>(log.clone())?.root),
+        WatchedEvent::TreeChanged => {
+            Decoded::Root(parse_log::<TreeChangedFilter>(log.clone())?.post_root)
+        }
+        WatchedEvent::OwnershipTransferred => {
+            let transfer = parse_log::<OwnershipTransferredFilter>(log.clone())?;
+            Decoded::OwnershipTransferred {
+                previous_owner: transfer.previous_owner,
+                new_owner:      transfer.new_owner,
+            }
+        }
+    };
+
+    Ok(Some(decoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{
+        abi::{self, Token},
+        types::ValueOrArray,
+    };
+
+    use super::*;
+
+    fn log(topics: Vec<H256>, data: &[Token]) -> Log {
+        Log {
+            topics,
+            data: abi::encode(data).into(),
+            ..Log::default()
+        }
+    }
+
+    fn word(value: u64) -> H256 {
+        H256::from_low_u64_be(value)
+    }
+
+    #[test]
+    fn every_watched_event_decodes_from_its_log() -> anyhow::Result<()> {
+        let propagated = log(vec![RootPropagatedFilter::signature()], &[Token::Uint(
+            7.into(),
+        )]);
+        assert_eq!(
+            decode(RootSource::Bridge, &propagated)?,
+            Some(Decoded::Root(7.into()))
+        );
+
+        let added = log(vec![RootAddedFilter::signature()], &[
+            Token::Uint(8.into()),
+            Token::Uint(1_700_000_000.into()),
+        ]);
+        assert_eq!(
+            decode(RootSource::Scroll, &added)?,
+            Some(Decoded::Root(8.into()))
+        );
+
+        let changed = log(
+            vec![TreeChangedFilter::signature(), word(8), word(0), word(9)],
+            &[],
+        );
+        assert_eq!(
+            decode(RootSource::WorldId, &changed)?,
+            Some(Decoded::Root(9.into()))
+        );
+
+        let previous_owner = Address::repeat_byte(0x11);
+        let new_owner = Address::repeat_byte(0x22);
+        let transferred = log(
+            vec![
+                OwnershipTransferredFilter::signature(),
+                previous_owner.into(),
+                new_owner.into(),
+            ],
+            &[],
+        );
+        assert_eq!(
+            decode(RootSource::Bridge, &transferred)?,
+            Some(Decoded::OwnershipTransferred {
+                previous_owner,
+                new_owner,
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn events_of_other_contracts_and_unknown_topics_are_ignored() -> anyhow::Result<()> {
+        let added = log(vec![RootAddedFilter::signature()], &[
+            Token::Uint(8.into()),
+            Token::Uint(1_700_000_000.into()),
+        ]);
+        assert_eq!(decode(RootSource::Bridge, &added)?, None);
+        assert_eq!(
+            decode(RootSource::Bridge, &log(vec![word(0xdead)], &[]))?,
+            None
+        );
+        assert_eq!(decode(RootSource::Bridge, &log(Vec::new(), &[]))?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn a_malformed_log_fails_to_decode() {
+        // RootPropagated without its root
+        let truncated = log(vec![RootPropagatedFilter::signature()], &[]);
+        assert!(decode(RootSource::Bridge, &truncated).is_err());
+
+        // OwnershipTransferred without its indexed owners
+        let unindexed = log(vec![OwnershipTransferredFilter::signature()], &[]);
+        assert!(decode(RootSource::Bridge, &unindexed).is_err());
+    }
+
+    #[test]
+    fn one_filter_asks_for_every_event_of_a_contract() {
+        let [topic0, ..] = topics(RootSource::Bridge);
+        assert_eq!(
+            topic0,
+            Some(ValueOrArray::Array(vec![
+                Some(RootPropagatedFilter::signature()),
+                Some(OwnershipTransferredFilter::signature()),
+            ]))
+        );
+
+        let [topic0, ..] = topics(RootSource::Scroll);
+        assert_eq!(
+            topic0,
+            Some(ValueOrArray::Value(Some(RootAddedFilter::signature())))
+        );
+    }
+}
@@ -1,40 +1,307 @@
-use std::sync::Arc;
+use std::{
+    sync::{
+        self,
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
+use anyhow::{anyhow, Context};
 use async_trait::async_trait;
+use ethers::types::{Address, Filter, Log, ValueOrArray, H256, U256};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::{Mutex, OnceCell},
+    time,
+};
+use tracing::{debug, error, info, instrument, warn};
 
-use tracing::{error, info, instrument};
-
+pub mod cancel;
+pub mod clean_slate;
+pub mod events;
+pub mod reconcile;
+pub mod root_cache;
+pub mod startup;
 pub mod status;
 
-use crate::contracts::ScrollBridge;
-use crate::ethereum::Ethereum;
+use self::{
+    events::Decoded,
+    reconcile::{Disagreement, ScannedRoots},
+    root_cache::RootCache,
+    status::{SyncState, UnsyncedReason},
+};
+use crate::{
+    config::{Config, LogMode, PropagationConfig, ProvidersConfig, RootCheckConfig, ScannerConfig},
+    contracts::{
+        self,
+        scanner::{BlockScanner, ScanResult, ScannedLog},
+        RootLookup, RootStatus, SimulationError, StateBridge,
+    },
+    database::{
+        query::DatabaseQuery,
+        types::{ObservedRoot, RootSource},
+        Database,
+    },
+    ethereum::{Ethereum, PendingTransaction, ReadProvider, TransactionState},
+    exit,
+    metrics::{self, MINE_TRANSACTION_WAIT, PROPAGATION_ATTEMPTS, PROPAGATION_RESULTS},
+    notifier::{Event, EventKind, Notifier},
+    utils::{persist_buffer::PersistBuffer, secret::SecretUrl, TransactionId},
+};
+
+/// Observed roots waiting to be written, shared by the bridge and Scroll scans.
+pub type RootBuffer = PersistBuffer<(RootSource, ObservedRoot)>;
 
-pub type TransactionId = String;
+/// The result of a propagation that wasn't rejected by the simulation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Propagation {
+    /// The transaction was handed to the relayer
+    Submitted {
+        transaction_id: TransactionId,
+        /// The latest mainnet root when it was sent
+        root:           U256,
+    },
+    /// The latest root is younger than `min_root_age`, it is looked at again
+    /// on the next sync check
+    Deferred { root: U256, age: Duration },
+    /// Dry-run mode: the simulation succeeded and nothing was sent
+    Simulated,
+    /// Scroll already had the mainnet root when it was about to be sent,
+    /// e.g. another replica propagated it. Nothing was sent.
+    AlreadySynced { root: U256 },
+}
 
+/// Everything the service does with the bridge: scanning the roots,
+/// checking the sync and propagating. [`BridgeProcessor`] implements it on
+/// chain.
 #[async_trait]
 pub trait Processor: Send + Sync + 'static {
-    async fn propagate_root(&self) -> anyhow::Result<TransactionId>;
-    async fn check_sync_state(&self) -> anyhow::Result<bool>;
+    /// Simulates `propagateRoot` and sends it if the simulation succeeds and
+    /// Scroll still lacks the mainnet root. A simulated revert is returned as
+    /// a [`SimulationError`].
+    async fn propagate_root(&self) -> anyhow::Result<Propagation>;
+    /// Whether Scroll has the mainnet root, going by the scanned roots and
+    /// falling back to the Scroll root on chain.
+    async fn sync_state(&self) -> anyhow::Result<SyncState>;
+    /// The latest mainnet and Scroll roots. The Scroll root is `None` until
+    /// the first propagation lands.
+    async fn latest_roots(&self) -> anyhow::Result<LatestRoots>;
+    /// Whether `root` is known on Scroll, as the latest root or in its
+    /// history.
+    async fn is_root_mined(&self, root: U256) -> anyhow::Result<bool>;
+    /// Where `root` stands on mainnet and on Scroll.
+    async fn lookup_root(&self, root: U256) -> anyhow::Result<RootLookup>;
+    /// [`Self::lookup_root`] for every root in `roots`, in the same order,
+    /// batched where the bridge can.
+    async fn lookup_roots(&self, roots: &[U256]) -> anyhow::Result<Vec<RootLookup>>;
     async fn get_mined_transactions(&self) -> anyhow::Result<Vec<TransactionId>>;
     async fn mine_transaction(&self, transaction_id: TransactionId) -> anyhow::Result<bool>;
+    /// Looks up a submitted transaction without waiting for it to mine.
+    async fn transaction_state(
+        &self,
+        transaction_id: TransactionId,
+    ) -> anyhow::Result<TransactionState>;
+    /// How far along each of `transaction_ids` is on the relayer.
+    async fn pending_transactions(
+        &self,
+        transaction_ids: Vec<TransactionId>,
+    ) -> anyhow::Result<Vec<PendingTransaction>>;
+    /// Drops a queued transaction, `false` if the relayer can't.
+    async fn cancel_transaction(&self, transaction_id: TransactionId) -> anyhow::Result<bool>;
+    /// Replaces a broadcast transaction with a fee-bumped self-send at its
+    /// nonce, `false` if the relayer can't.
+    async fn replace_transaction(&self, transaction: &PendingTransaction) -> anyhow::Result<bool>;
+    /// Stops a transaction that isn't mined yet, through the relayer's own
+    /// cancellation or a fee-bumped self-send at its nonce. `false` if the
+    /// relayer can't.
+    async fn cancel_in_flight(&self, transaction: &PendingTransaction) -> anyhow::Result<bool>;
+    /// Whether the mined transaction `hash` was a self-send, as a cancel is.
+    async fn sent_to_self(&self, hash: H256) -> anyhow::Result<bool>;
+    /// Replays the reverted transaction `hash` and decodes why it reverted,
+    /// `None` if the replay doesn't revert.
+    async fn revert_reason(&self, hash: H256) -> anyhow::Result<Option<String>>;
+    /// Scans the next window of bridge, Scroll and mainnet WorldID logs and
+    /// reconciles what they carry. Returns how many roots WorldID moved to.
+    /// Every scan runs even when another fails.
+    async fn scan_roots(&self) -> anyhow::Result<usize>;
+    /// Scans the next window of `source` and buffers the roots it carries,
+    /// returning how many. A failure counts against the scanner's health and
+    /// leaves its cursor where it was. A subscribed source isn't scanned.
+    async fn scan_source(&self, source: RootSource) -> anyhow::Result<usize>;
+    /// Writes the buffered roots, persists the cursors the scanners resume
+    /// from and counts the outstanding roots.
+    async fn reconcile_scans(&self) -> anyhow::Result<()>;
+    /// Scans `source` from its cursor up to the head, after a subscription
+    /// to its logs was made.
+    async fn catch_up(&self, source: RootSource) -> anyhow::Result<()>;
+    /// The filter to subscribe to the logs of `source` with.
+    fn log_filter(&self, source: RootSource) -> Filter;
+    /// Records the root of a log from a subscription.
+    async fn record_subscribed_log(&self, source: RootSource, log: &Log) -> anyhow::Result<()>;
+    /// Where the scanners stand, as of their last scan. Doesn't wait for a
+    /// scan in progress.
+    fn scanner_status(&self) -> ScannersStatus;
+}
+
+/// Where a chain's scanner stands, as of its last recorded window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct ScannerStatus {
+    /// The next block to scan
+    pub next_block:           u64,
+    /// The latest block the last scan saw, `None` before the first
+    pub chain_head:           Option<u64>,
+    /// Blocks left to scan up to the head less `scanning_chain_head_offset`
+    pub lag_blocks:           Option<u64>,
+    /// Scans that failed since the last one that succeeded
+    pub consecutive_failures: u32,
+    /// `false` once `unhealthy_scan_failures` scans failed in a row
+    pub healthy:              bool,
+}
+
+impl Default for ScannerStatus {
+    fn default() -> Self {
+        Self {
+            next_block:           0,
+            chain_head:           None,
+            lag_blocks:           None,
+            consecutive_failures: 0,
+            healthy:              true,
+        }
+    }
+}
+
+impl ScannerStatus {
+    fn of(scanner: &BlockScanner<Arc<ReadProvider>>) -> Self {
+        Self {
+            next_block: scanner.current_block(),
+            chain_head: scanner.chain_head(),
+            lag_blocks: scanner.lag(),
+            ..Self::default()
+        }
+    }
+
+    /// `self` after one more scan failed. The scanner stays where it was.
+    #[must_use]
+    pub const fn failed(self, unhealthy_scan_failures: u32) -> Self {
+        let consecutive_failures = self.consecutive_failures.saturating_add(1);
+        Self {
+            consecutive_failures,
+            healthy: consecutive_failures < unhealthy_scan_failures,
+            ..self
+        }
+    }
+}
+
+/// Where each scanner stands, as [`Processor::scanner_status`] reports it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ScannersStatus {
+    /// `RootPropagated` on the state bridge
+    pub bridge:            ScannerStatus,
+    /// `RootAdded` on the Scroll WorldID
+    pub scroll:            ScannerStatus,
+    /// `TreeChanged` on the mainnet WorldID
+    #[serde(default, rename = "worldId")]
+    pub world_id:          ScannerStatus,
+    /// Propagated roots Scroll hasn't added, as of the last count, `None`
+    /// before the first
+    #[serde(
+        default,
+        rename = "rootsOutstanding",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub roots_outstanding: Option<u64>,
 }
 
+impl ScannersStatus {
+    fn scanner_mut(&mut self, source: RootSource) -> &mut ScannerStatus {
+        match source {
+            RootSource::Bridge => &mut self.bridge,
+            RootSource::Scroll => &mut self.scroll,
+            RootSource::WorldId => &mut self.world_id,
+        }
+    }
+}
+
+/// The latest root on each side of the bridge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatestRoots {
+    pub mainnet: U256,
+    pub scroll:  Option<U256>,
+}
+
+/// The [`Processor`] of the deployed bridge, reading it through the providers
+/// and sending through the relayer. Scanned roots are kept in the database.
 pub struct BridgeProcessor {
-    ethereum:           Ethereum,
-    scroll_bridge:      Arc<ScrollBridge>
+    ethereum:            Ethereum,
+    database:            Arc<Database>,
+    root_buffer:         Arc<RootBuffer>,
+    bridge:              Arc<dyn StateBridge>,
+    scanner_config:      ScannerConfig,
+    propagation_config:  PropagationConfig,
+    bridge_scanner:      Mutex<BlockScanner<Arc<ReadProvider>>>,
+    scroll_scanner:      Mutex<BlockScanner<Arc<ReadProvider>>>,
+    world_id_scanner:    Mutex<BlockScanner<Arc<ReadProvider>>>,
+    /// Statuses of single roots. Never consulted for the latest roots
+    root_cache:          RootCache,
+    /// Completed [`Processor::scan_roots`] calls
+    scan_cycles:         AtomicU64,
+    disagreement:        Disagreement,
+    /// Sources read from a subscription rather than polled
+    subscribed:          Vec<RootSource>,
+    /// Updated as the scanners move, readable while they are locked
+    scanner_status:      sync::Mutex<ScannersStatus>,
+    /// Read from WorldID on mainnet once needed
+    root_history_expiry: OnceCell<Duration>,
+    /// Told about ownership changes of the bridge
+    notifier:            Option<Arc<Notifier>>,
+    /// Sources that start behind the head, from a start block or a
+    /// persisted cursor, and backfill up to it
+    backfilled:          Vec<RootSource>,
+    /// The blocks the scanners resume from after a restart, persisted once
+    /// the roots scanned before them are written
+    cursors:             sync::Mutex<Vec<(RootSource, u64)>>,
 }
 
 #[async_trait]
 impl Processor for BridgeProcessor {
-    async fn propagate_root(&self) -> anyhow::Result<TransactionId> {
+    async fn propagate_root(&self) -> anyhow::Result<Propagation> {
         self.propagate_root().await
     }
 
-    async fn check_sync_state(&self) -> anyhow::Result<bool> {
-        self.check_sync_state().await
+    async fn sync_state(&self) -> anyhow::Result<SyncState> {
+        self.sync_state().await
+    }
+
+    async fn latest_roots(&self) -> anyhow::Result<LatestRoots> {
+        let (mainnet, scroll) = futures::try_join!(
+            self.bridge.get_world_id_latest_root(),
+            self.bridge.get_target_latest_root(),
+        )?;
+
+        Ok(LatestRoots {
+            mainnet,
+            scroll: (!scroll.is_zero()).then_some(scroll),
+        })
+    }
+
+    async fn is_root_mined(&self, root: U256) -> anyhow::Result<bool> {
+        Ok(self.root_status(root).await?.is_mined())
+    }
+
+    async fn lookup_root(&self, root: U256) -> anyhow::Result<RootLookup> {
+        self.bridge.lookup_root(root).await
+    }
+
+    async fn lookup_roots(&self, roots: &[U256]) -> anyhow::Result<Vec<RootLookup>> {
+        self.bridge.lookup_roots(roots).await
     }
 
-    async fn get_mined_transactions(&self) -> anyhow::Result<Vec<TransactionId>>{
+    async fn get_mined_transactions(&self) -> anyhow::Result<Vec<TransactionId>> {
         // Await for all mined transactions
         let mined_transactions = self.fetch_mined_transactions().await?;
         Ok(mined_transactions)
@@ -42,66 +309,1212 @@ impl Processor for BridgeProcessor {
 
     #[instrument(level = "debug", skip(self))]
     async fn mine_transaction(&self, transaction_id: TransactionId) -> anyhow::Result<bool> {
-        let result = self.ethereum.mine_transaction(transaction_id).await?;
-        Ok(result)
+        let timer = MINE_TRANSACTION_WAIT.start_timer();
+        let result = self.ethereum.mine_transaction(transaction_id).await;
+        timer.observe_duration();
+
+        let outcome = if matches!(result, Ok(true)) {
+            metrics::SUCCESS
+        } else {
+            metrics::FAILURE
+        };
+        PROPAGATION_RESULTS.with_label_values(&[outcome]).inc();
+
+        Ok(result?)
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn transaction_state(
+        &self,
+        transaction_id: TransactionId,
+    ) -> anyhow::Result<TransactionState> {
+        let state = self.ethereum.transaction_state(transaction_id).await?;
+        Ok(state)
+    }
+
+    async fn pending_transactions(
+        &self,
+        transaction_ids: Vec<TransactionId>,
+    ) -> anyhow::Result<Vec<PendingTransaction>> {
+        Ok(self.ethereum.pending_states(transaction_ids).await?)
+    }
+
+    async fn cancel_transaction(&self, transaction_id: TransactionId) -> anyhow::Result<bool> {
+        Ok(self.ethereum.cancel_transaction(transaction_id).await?)
+    }
+
+    async fn replace_transaction(&self, transaction: &PendingTransaction) -> anyhow::Result<bool> {
+        Ok(self.ethereum.replace_transaction(transaction).await?)
+    }
+
+    async fn cancel_in_flight(&self, transaction: &PendingTransaction) -> anyhow::Result<bool> {
+        Ok(self.ethereum.cancel_in_flight(transaction).await?)
+    }
+
+    async fn sent_to_self(&self, hash: H256) -> anyhow::Result<bool> {
+        Ok(self.ethereum.sent_to_self(hash).await?)
+    }
+
+    async fn revert_reason(&self, hash: H256) -> anyhow::Result<Option<String>> {
+        contracts::replay_revert(self.ethereum.l1_provider(), hash).await
+    }
+
+    async fn scan_roots(&self) -> anyhow::Result<usize> {
+        let (bridge, scroll, world_id) = futures::join!(
+            async {
+                self.scan_source(RootSource::Bridge)
+                    .await
+                    .context("scan the bridge logs")
+            },
+            async {
+                self.scan_source(RootSource::Scroll)
+                    .await
+                    .context("scan the Scroll logs")
+            },
+            async {
+                self.scan_source(RootSource::WorldId)
+                    .await
+                    .context("scan the WorldID logs")
+            },
+        );
+        // What the other scans found is kept when one failed
+        self.reconcile_scans().await?;
+
+        match (bridge, scroll, world_id) {
+            (Ok(_), Ok(_), Ok(world_id)) => Ok(world_id),
+            (bridge, scroll, world_id) => {
+                let mut errors = [bridge.err(), scroll.err(), world_id.err()]
+                    .into_iter()
+                    .flatten();
+                let err = errors.next().expect("one of the scans failed");
+                for other in errors {
+                    warn!(err = ?other, "More than one scan failed");
+                }
+                Err(err)
+            }
+        }
+    }
+
+    async fn scan_source(&self, source: RootSource) -> anyhow::Result<usize> {
+        if self.subscribed.contains(&source) {
+            return Ok(0);
+        }
+
+        match self.scan_chain(source, false).await {
+            Ok(roots) => Ok(roots.len()),
+            Err(err) => {
+                self.scan_failed(source);
+                Err(err)
+            }
+        }
+    }
+
+    async fn reconcile_scans(&self) -> anyhow::Result<()> {
+        self.scan_cycles.fetch_add(1, Ordering::Relaxed);
+
+        // Taken before the roots are written, the roots scanned after a
+        // cursor was taken may still be buffered
+        let cursors = std::mem::take(&mut *self.cursors.lock().unwrap());
+        if let Err(err) = self.persist_cursors(&cursors).await {
+            let mut staged = self.cursors.lock().unwrap();
+            for (source, next_block) in cursors {
+                if !staged.iter().any(|(staged, _)| *staged == source) {
+                    staged.push((source, next_block));
+                }
+            }
+            return Err(err);
+        }
+
+        if let Err(err) = self.outstanding_roots().await {
+            warn!(%err, "Failed to count the outstanding roots");
+        }
+
+        Ok(())
+    }
+
+    async fn catch_up(&self, source: RootSource) -> anyhow::Result<()> {
+        self.catch_up(source).await
+    }
+
+    fn log_filter(&self, source: RootSource) -> Filter {
+        self.log_filter(source)
+    }
+
+    async fn record_subscribed_log(&self, source: RootSource, log: &Log) -> anyhow::Result<()> {
+        self.record_subscribed_log(source, log).await
+    }
+
+    fn scanner_status(&self) -> ScannersStatus {
+        *self.scanner_status.lock().unwrap()
     }
 }
 
 impl BridgeProcessor {
-    pub async fn new(
+    /// Connects to everything `config` names, without the HTTP server or the
+    /// tasks of [`App`](crate::app::App). The caller drives the scans and
+    /// propagations through [`Processor`]. The database is migrated if
+    /// `migrate` is set under `[database]`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the config is invalid, or the database, a
+    /// provider, the relayer or the contracts can't be reached.
+    #[instrument(name = "BridgeProcessor::new", level = "debug", skip_all)]
+    pub async fn new(config: &Config) -> anyhow::Result<Self> {
+        config
+            .validate()
+            .map_err(|errors| exit::Error::Config(errors.into()))?;
+
+        let database = Arc::new(Database::new(&config.database).await?);
+        let ethereum = Ethereum::new(config).await?;
+        let bridge = contracts::connect(config, ethereum.clone()).await?;
+        let root_buffer = Arc::new(RootBuffer::spawn(
+            "roots",
+            &config.persistence,
+            database.clone(),
+        ));
+        let notifier = Notifier::new(
+            config.notifications.clone(),
+            config.service.service_name.clone(),
+        );
+
+        let processor = Self::from_parts(
+            ethereum,
+            database,
+            root_buffer,
+            bridge,
+            config.app.scanner.clone(),
+            config.app.propagation.clone(),
+            &config.root_check,
+        )
+        .await?;
+        Ok(processor.with_notifier(Arc::new(notifier)))
+    }
+
+    /// [`Self::new`] over connections the caller shares with others.
+    pub(crate) async fn from_parts(
         ethereum: Ethereum,
-        scroll_bridge: Arc<ScrollBridge>
+        database: Arc<Database>,
+        root_buffer: Arc<RootBuffer>,
+        bridge: Arc<dyn StateBridge>,
+        scanner_config: ScannerConfig,
+        propagation_config: PropagationConfig,
+        root_check: &RootCheckConfig,
     ) -> anyhow::Result<Self> {
+        let (bridge_scanner, bridge_behind) = Self::scanner(
+            ethereum.l1_provider().clone(),
+            &scanner_config,
+            &database,
+            RootSource::Bridge,
+            scanner_config.bridge_start_block,
+        )
+        .await?;
+        let (scroll_scanner, scroll_behind) = Self::scanner(
+            ethereum.l2_provider().clone(),
+            &scanner_config,
+            &database,
+            RootSource::Scroll,
+            scanner_config.scroll_world_id_start_block,
+        )
+        .await?;
+        // The mainnet provider is often paid for, so WorldID has its own
+        // window and offset
+        let (world_id_scanner, world_id_behind) = Self::scanner(
+            ethereum.l1_provider().clone(),
+            &scanner_config,
+            &database,
+            RootSource::WorldId,
+            scanner_config.world_id_start_block,
+        )
+        .await?;
+        let scanner_status = ScannersStatus {
+            bridge:            ScannerStatus::of(&bridge_scanner),
+            scroll:            ScannerStatus::of(&scroll_scanner),
+            world_id:          ScannerStatus::of(&world_id_scanner),
+            roots_outstanding: None,
+        };
+        let backfilled = [
+            (RootSource::Bridge, bridge_behind),
+            (RootSource::Scroll, scroll_behind),
+            (RootSource::WorldId, world_id_behind),
+        ]
+        .into_iter()
+        .filter_map(|(source, behind)| behind.then_some(source))
+        .collect();
+
         Ok(Self {
             ethereum,
-            scroll_bridge
+            database,
+            root_buffer,
+            bridge,
+            scanner_config,
+            propagation_config,
+            bridge_scanner: Mutex::new(bridge_scanner),
+            scroll_scanner: Mutex::new(scroll_scanner),
+            world_id_scanner: Mutex::new(world_id_scanner),
+            root_cache: RootCache::new(root_check.cache_ttl),
+            scan_cycles: AtomicU64::new(0),
+            disagreement: Disagreement::default(),
+            subscribed: Vec::new(),
+            scanner_status: sync::Mutex::new(scanner_status),
+            root_history_expiry: OnceCell::new(),
+            notifier: None,
+            backfilled,
+            cursors: sync::Mutex::new(Vec::new()),
         })
     }
 
+    /// Where `root` stands, reusing a status read within the cache TTL.
+    async fn root_status(&self, root: U256) -> anyhow::Result<RootStatus> {
+        if let Some(status) = self.root_cache.get(root) {
+            return Ok(status);
+        }
+
+        let status = self.bridge.root_status(root).await?;
+        self.root_cache.insert(root, status);
+        Ok(status)
+    }
+
+    /// Starts where the cursor of `source` was persisted, or at the head
+    /// without one. With a `start_block` it starts there unless the cursor
+    /// is past it. Also returns whether it starts behind the head.
+    async fn scanner(
+        provider: Arc<ReadProvider>,
+        config: &ScannerConfig,
+        database: &Database,
+        source: RootSource,
+        start_block: Option<u64>,
+    ) -> anyhow::Result<(BlockScanner<Arc<ReadProvider>>, bool)> {
+        let (window_size, offset) = window(config, source);
+        let progress = database.get_scan_progress(source).await?;
+        let scanner = match (start_block, progress) {
+            (Some(start_block), progress) => {
+                let from_block = progress.map_or(start_block, |next| next.max(start_block));
+                if from_block > start_block {
+                    info!(?source, start_block, from_block, "Resuming backfill");
+                }
+
+                BlockScanner::new(provider, from_block, window_size)
+            }
+            // Whatever happened while the service was down is backfilled
+            (None, Some(from_block)) => {
+                info!(?source, from_block, "Resuming from the persisted cursor");
+                BlockScanner::new(provider, from_block, window_size)
+            }
+            (None, None) => BlockScanner::new_latest(provider, window_size).await?,
+        };
+        let behind = start_block.is_some() || progress.is_some();
+
+        let confirmations = match source {
+            RootSource::Bridge => config.bridge_confirmations,
+            RootSource::Scroll => config.scroll_world_id_confirmations,
+            RootSource::WorldId => config.world_id_confirmations,
+        };
+
+        let scanner = scanner
+            .with_offset(offset)
+            .with_rewind_depth(config.scanning_reorg_rewind_depth)
+            .with_confirmations(confirmations);
+        Ok((scanner, behind))
+    }
 
     #[instrument(level = "info", skip_all)]
-    async fn propagate_root(
-        &self,
-    ) -> anyhow::Result<TransactionId> {
+    async fn propagate_root(&self) -> anyhow::Result<Propagation> {
+        // propagateRoot sends whatever the latest root is. The root whose age
+        // was checked is the one recorded, it isn't read again before sending.
+        let (root, young) =
+            latest_root_age(self.bridge.as_ref(), self.propagation_config.min_root_age).await?;
+        if let Some(age) = young {
+            info!(
+                ?root,
+                age = %humantime::format_duration(age),
+                min_root_age = %humantime::format_duration(self.propagation_config.min_root_age),
+                "Latest root is too young, not propagating yet"
+            );
+            return Ok(Propagation::Deferred { root, age });
+        }
 
-        info!("Creating propagate root txn");
+        let value = self.bridge.propagation_value().await;
 
-        let transaction_id = self
-            .scroll_bridge
-            .propagate_root()
-            .await
-            .map_err(|e| {
-                error!(?e, "Failed to propagate root");
-                e
-            })?;
+        PROPAGATION_ATTEMPTS.inc();
 
-        info!(
-            ?transaction_id,
-            "Progation root txn submitted"
+        if let Err(err) = self.bridge.simulate_propagate_root(value).await {
+            if let SimulationError::WouldRevert { reason } = &err {
+                warn!(%reason, "Skipping propagation, simulation reverted");
+            }
+            PROPAGATION_RESULTS
+                .with_label_values(&[metrics::FAILURE])
+                .inc();
+            return Err(err.into());
+        }
+
+        if self.propagation_config.dry_run {
+            info!(%value, "Dry run: propagateRoot simulation succeeded, not sending");
+            return Ok(Propagation::Simulated);
+        }
+
+        // The sync check that woke us may be stale by now, another replica
+        // can have propagated the root since
+        if let Some(root) = already_synced(self.bridge.as_ref()).await? {
+            info!(?root, "Scroll already has the mainnet root, not sending");
+            return Ok(Propagation::AlreadySynced { root });
+        }
+
+        info!(?root, "Creating propagate root txn");
+
+        let transaction_id = self.bridge.propagate_root(value).await.map_err(|e| {
+            error!(?e, "Failed to propagate root");
+            PROPAGATION_RESULTS
+                .with_label_values(&[metrics::FAILURE])
+                .inc();
+            e
+        })?;
+
+        info!(?transaction_id, ?root, "Progation root txn submitted");
+
+        Ok(Propagation::Submitted {
+            transaction_id,
+            root,
+        })
+    }
+
+    /// Goes by the scanned roots once a `RootAdded` was scanned, with the
+    /// Scroll root on chain as a cross-check. A Scroll provider that fails
+    /// then only costs the cross-check.
+    #[instrument(level = "debug", skip_all)]
+    async fn sync_state(&self) -> anyhow::Result<SyncState> {
+        let bridge = self.bridge.as_ref();
+        let mainnet_root = bridge.get_world_id_latest_root().await.map_err(|e| {
+            error!(
+                ?e,
+                chain = bridge.chain(),
+                "Failed to fetch the latest mainnet root"
+            );
+            e
+        })?;
+
+        let (scanned, on_chain) = futures::join!(
+            self.scanned_roots(mainnet_root),
+            target_sync_state(bridge, mainnet_root),
         );
+        let scanned = scanned?;
+
+        let max_outstanding_roots = self.scanner_config.max_outstanding_roots;
+        if scanned.too_many_outstanding(max_outstanding_roots) {
+            warn!(
+                outstanding = scanned.outstanding.len(),
+                max_outstanding_roots,
+                ?mainnet_root,
+                "More roots are outstanding than max_outstanding_roots, reporting unsynced"
+            );
+            return Ok(SyncState::Unsynced(UnsyncedReason::RootsOutstanding));
+        }
+
+        let Some(scanned_state) = scanned.sync_state() else {
+            return on_chain;
+        };
+
+        match on_chain {
+            Ok(on_chain) => {
+                let cycles = self.disagreement.observe(
+                    on_chain == scanned_state,
+                    self.scan_cycles.load(Ordering::Relaxed),
+                );
+                if cycles > 1 {
+                    warn!(
+                        ?scanned_state,
+                        ?on_chain,
+                        outstanding = ?scanned.outstanding,
+                        ?mainnet_root,
+                        cycles,
+                        "Scanned roots and the target's latest root disagree on the sync status"
+                    );
+                }
+            }
+            Err(err) => {
+                warn!(
+                    %err,
+                    ?scanned_state,
+                    "Failed to read the target's roots, going by the scanned roots"
+                );
+            }
+        }
+
+        Ok(scanned_state)
+    }
+
+    /// Reads the scanned roots, including those still buffered.
+    async fn scanned_roots(&self, mainnet_root: U256) -> anyhow::Result<ScannedRoots> {
+        self.root_buffer.flush().await?;
+
+        let outstanding = self.outstanding_roots().await?;
+        let (added, mainnet_root_added) = futures::try_join!(
+            self.database.count_roots(RootSource::Scroll),
+            self.database
+                .is_root_observed(RootSource::Scroll, mainnet_root),
+        )?;
+
+        Ok(ScannedRoots {
+            outstanding,
+            any_added: added > 0,
+            mainnet_root_added,
+        })
+    }
+
+    /// The recorded roots Scroll hasn't added, leaving out those propagated
+    /// longer ago than mainnet's root history expiry. Refreshes
+    /// `roots_outstanding` and the scanners' status.
+    async fn outstanding_roots(&self) -> anyhow::Result<Vec<U256>> {
+        let expiry = *self
+            .root_history_expiry
+            .get_or_try_init(|| self.bridge.get_root_history_expiry())
+            .await?;
+        let chain_head = self.scanner_status.lock().unwrap().bridge.chain_head;
+
+        let outstanding = self
+            .database
+            .get_outstanding_roots(reconcile::outstanding_from_block(chain_head, expiry))
+            .await?;
+
+        metrics::record_outstanding_roots(outstanding.len());
+        self.scanner_status.lock().unwrap().roots_outstanding = Some(outstanding.len() as u64);
+        Ok(outstanding)
+    }
+
+    /// Polled scans skip the sources in `subscribed`, whose logs are read
+    /// from a subscription instead.
+    #[must_use]
+    pub(crate) fn with_subscribed(mut self, subscribed: Vec<RootSource>) -> Self {
+        self.subscribed = subscribed;
+        self
+    }
+
+    /// Ownership changes of the bridge are sent to `notifier` as well as
+    /// logged.
+    #[must_use]
+    pub(crate) fn with_notifier(mut self, notifier: Arc<Notifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    async fn catch_up(&self, source: RootSource) -> anyhow::Result<()> {
+        self.scan_chain(source, true).await?;
+        Ok(())
+    }
+
+    fn log_filter(&self, source: RootSource) -> Filter {
+        Filter {
+            address: Some(ValueOrArray::Value(self.contract(source))),
+            topics: events::topics(source),
+            ..Filter::default()
+        }
+    }
+
+    /// Records a log from a subscription. A log removed by a reorg deletes
+    /// its root again, the buffer is flushed first in case the root is still
+    /// in it.
+    async fn record_subscribed_log(&self, source: RootSource, log: &Log) -> anyhow::Result<()> {
+        metrics::record_scanned_logs(source, 1);
+
+        if log.removed == Some(true) {
+            let Some(Decoded::Root(root)) = events::decode(source, log)? else {
+                return Ok(());
+            };
+            let root = observed_root(log, root)?;
+            warn!(?source, ?root, "Subscribed log was removed by a reorg");
+            self.root_buffer.flush().await?;
+            self.database.delete_root(source, &root).await?;
+            return Ok(());
+        }
+
+        let scanned = ScannedLog {
+            log:       log.clone(),
+            rescanned: false,
+        };
+        let roots = self.dispatch(source, &[scanned])?;
+        if roots.is_empty() {
+            return Ok(());
+        }
+
+        info!(?source, count = roots.len(), "Observed roots");
+        self.root_buffer
+            .push(roots.into_iter().map(|root| (source, root)).collect())
+            .await?;
+        Ok(())
+    }
+
+    /// The contract `source` is scanned on.
+    fn contract(&self, source: RootSource) -> Address {
+        let addresses = self.bridge.addresses();
+        match source {
+            RootSource::Bridge => addresses.bridge,
+            RootSource::Scroll => addresses.target_world_id,
+            RootSource::WorldId => addresses.world_id,
+        }
+    }
+
+    /// Routes each log by the event it is of: the roots are returned to be
+    /// recorded and ownership changes are raised. Logs of events `source`
+    /// isn't watched for are skipped.
+    fn dispatch(
+        &self,
+        source: RootSource,
+        logs: &[ScannedLog],
+    ) -> anyhow::Result<Vec<ObservedRoot>> {
+        let mut roots = Vec::new();
+        for scanned in logs {
+            match events::decode(source, &scanned.log)? {
+                Some(Decoded::Root(root)) => roots.push(observed_root(&scanned.log, root)?),
+                // Raised once, not again when a reorg has its block rescanned
+                Some(Decoded::OwnershipTransferred { .. }) if scanned.rescanned => {}
+                Some(Decoded::OwnershipTransferred {
+                    previous_owner,
+                    new_owner,
+                }) => self.ownership_transferred(source, &scanned.log, previous_owner, new_owner),
+                None => debug!(
+                    ?source,
+                    topic0 = ?scanned.log.topics.first(),
+                    "Skipping a log of an event that isn't watched for"
+                ),
+            }
+        }
 
-        Ok(transaction_id)
+        Ok(roots)
     }
 
-    async fn check_sync_state(
+    /// Nothing stops propagating here, but the relayer may no longer be
+    /// allowed to once the new owner reconfigures the bridge.
+    fn ownership_transferred(
         &self,
-    ) -> anyhow::Result<bool> {
-
-        let result = self
-            .scroll_bridge.check_sync_state()
-            .await
-            .map_err(|e| {
-                error!(?e, "Failed to check sync");
-                e
-            })?;
+        source: RootSource,
+        log: &Log,
+        previous_owner: Address,
+        new_owner: Address,
+    ) {
+        metrics::record_ownership_transfer(source);
+        warn!(
+            ?source,
+            ?previous_owner,
+            ?new_owner,
+            tx_hash = ?log.transaction_hash,
+            "The state bridge changed owners"
+        );
+
+        if let Some(notifier) = &self.notifier {
+            notifier.notify(Event::new(
+                EventKind::OwnershipTransferred,
+                format!("State bridge ownership moved from {previous_owner:?} to {new_owner:?}"),
+            ));
+        }
+    }
+
+    /// Scans the next window of `source`, after backfilling when a start
+    /// block is set or `catch_up` is.
+    #[instrument(level = "debug", skip(self))]
+    async fn scan_chain(
+        &self,
+        source: RootSource,
+        catch_up: bool,
+    ) -> anyhow::Result<Vec<ObservedRoot>> {
+        let scanner = match source {
+            RootSource::Bridge => &self.bridge_scanner,
+            RootSource::Scroll => &self.scroll_scanner,
+            RootSource::WorldId => &self.world_id_scanner,
+        };
+        let address = self.contract(source);
+
+        let mut scanner = scanner.lock().await;
+        if catch_up || self.backfilled.contains(&source) {
+            self.backfill(source, &mut scanner, address).await?;
+        }
+
+        // Scanned on a clone, the cursor only moves once the roots are in
+        let mut window = scanner.clone();
+        let result = Self::scan(source, &mut window, address).await?;
+        let roots = self.record_roots(source, &result).await?;
+        *scanner = window;
+        self.publish_status(source, &scanner);
+        self.stage_cursor(source, scanner.resume_block());
+
+        Ok(roots)
+    }
+
+    async fn scan(
+        source: RootSource,
+        scanner: &mut BlockScanner<Arc<ReadProvider>>,
+        address: Address,
+    ) -> anyhow::Result<ScanResult> {
+        let result = scanner
+            .next(Some(ValueOrArray::Value(address)), events::topics(source))
+            .await?;
+
+        metrics::record_scanning_window(source, scanner.window_size());
+        debug!(
+            ?address,
+            next_block = scanner.current_block(),
+            window_size = scanner.window_size(),
+            "Scanned logs"
+        );
+
         Ok(result)
     }
 
+    /// Publishes where the scanner of `source` stands after a scan that
+    /// succeeded.
+    fn publish_status(&self, source: RootSource, scanner: &BlockScanner<Arc<ReadProvider>>) {
+        let status = ScannerStatus::of(scanner);
+        if let Some(lag) = status.lag_blocks {
+            metrics::record_scanner_lag(source, lag);
+        }
+
+        let mut scanners = self.scanner_status.lock().unwrap();
+        let previous = scanners.scanner_mut(source);
+        if previous.consecutive_failures > 0 {
+            info!(
+                ?source,
+                failures = previous.consecutive_failures,
+                next_block = status.next_block,
+                "Scanning again after failed scans"
+            );
+            metrics::record_scanner_health(source, true);
+        }
+        *previous = status;
+    }
+
+    /// Counts a failed scan of `source` against its health. The other
+    /// scanners aren't affected.
+    fn scan_failed(&self, source: RootSource) {
+        let unhealthy_scan_failures = self.scanner_config.unhealthy_scan_failures;
+        let mut scanners = self.scanner_status.lock().unwrap();
+        let status = scanners.scanner_mut(source);
+        *status = status.failed(unhealthy_scan_failures);
+
+        metrics::record_scan_failure(source, status.healthy);
+        if status.consecutive_failures == unhealthy_scan_failures {
+            warn!(
+                ?source,
+                failures = status.consecutive_failures,
+                next_block = status.next_block,
+                "Scanner is unhealthy, it resumes from its cursor once a scan succeeds"
+            );
+        }
+    }
+
+    /// `next_block` is persisted as the cursor of `source` on the next
+    /// [`Processor::reconcile_scans`].
+    fn stage_cursor(&self, source: RootSource, next_block: u64) {
+        let mut cursors = self.cursors.lock().unwrap();
+        cursors.retain(|(staged, _)| *staged != source);
+        cursors.push((source, next_block));
+    }
+
+    /// Writes the buffered roots, then `cursors`, so a cursor is never
+    /// persisted past a root that isn't.
+    async fn persist_cursors(&self, cursors: &[(RootSource, u64)]) -> anyhow::Result<()> {
+        self.root_buffer.flush().await?;
+        for (source, next_block) in cursors {
+            self.database
+                .set_scan_progress(*source, *next_block)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Scans window after window while the scanner is at least a window
+    /// behind the head. Each window's roots are written together with the
+    /// block the backfill resumes from, skipping the buffer. Logs still held
+    /// back for confirmations are scanned again after a restart.
+    async fn backfill(
+        &self,
+        source: RootSource,
+        scanner: &mut BlockScanner<Arc<ReadProvider>>,
+        address: Address,
+    ) -> anyhow::Result<()> {
+        let (window_size, _) = window(&self.scanner_config, source);
+        let from_block = scanner.current_block();
+        let mut windows: u64 = 0;
+
+        loop {
+            let head = scanner.head().await?;
+            if head
+                .saturating_add(1)
+                .saturating_sub(scanner.current_block())
+                < window_size
+            {
+                break;
+            }
+
+            let mut window = scanner.clone();
+            let result = Self::scan(source, &mut window, address).await?;
+            metrics::record_scanned_logs(source, result.logs.len());
+            let roots = self.dispatch(source, &result.logs)?;
+
+            let rewound_to = result.reorg.map(|reorg| reorg.rewound_to);
+            if rewound_to.is_some() {
+                // Buffered roots may fall in the rewound range
+                self.root_buffer.flush().await?;
+            }
+            self.database
+                .record_backfill(source, rewound_to, &roots, window.resume_block())
+                .await?;
+            *scanner = window;
+            self.publish_status(source, scanner);
+
+            windows += 1;
+            if windows % self.scanner_config.backfill_progress_interval == 0 {
+                info!(
+                    ?source,
+                    from_block,
+                    next_block = scanner.current_block(),
+                    head,
+                    windows,
+                    "Backfilling roots"
+                );
+            }
+
+            time::sleep(self.scanner_config.backfill_window_delay).await;
+        }
+
+        if windows > 0 {
+            info!(
+                ?source,
+                from_block,
+                next_block = scanner.current_block(),
+                windows,
+                "Backfill caught up with the chain head"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Dispatches the scanned logs and buffers their roots, which are
+    /// returned. After a reorg the buffer is flushed and the rewound range is
+    /// replaced by whatever the rescan found.
+    async fn record_roots(
+        &self,
+        source: RootSource,
+        result: &ScanResult,
+    ) -> anyhow::Result<Vec<ObservedRoot>> {
+        metrics::record_scanned_logs(source, result.logs.len());
+
+        if let Some(reorg) = result.reorg {
+            warn!(?source, ?reorg, "Reconciling roots after a reorg");
+        }
+
+        if result.logs.is_empty() && result.reorg.is_none() {
+            return Ok(Vec::new());
+        }
+
+        let roots = self.dispatch(source, &result.logs)?;
+
+        let rescanned = result
+            .logs
+            .iter()
+            .filter(|scanned| scanned.rescanned)
+            .count();
+        info!(?source, count = roots.len(), rescanned, "Observed roots");
+
+        match result.reorg {
+            Some(reorg) => {
+                // Buffered roots may fall in the rewound range, so they have
+                // to land before it is deleted.
+                self.root_buffer.flush().await?;
+                self.database
+                    .record_roots(source, Some(reorg.rewound_to), &roots)
+                    .await?;
+            }
+            None => {
+                self.root_buffer
+                    .push(roots.iter().map(|root| (source, *root)).collect())
+                    .await?;
+            }
+        }
+
+        Ok(roots)
+    }
+
     #[instrument(level = "debug", skip_all)]
     async fn fetch_mined_transactions(&self) -> anyhow::Result<Vec<TransactionId>> {
         let pending_transactions = self.ethereum.fetch_mined_transactions().await?;
         Ok(pending_transactions)
     }
+}
+
+/// The sources whose logs are read from a subscription rather than polled,
+/// with the url each one subscribes over.
+#[must_use]
+pub fn subscriptions(providers: &ProvidersConfig) -> Vec<(RootSource, SecretUrl)> {
+    [
+        (
+            RootSource::Bridge,
+            providers.l1_log_mode,
+            &providers.l1_network_provider,
+        ),
+        (
+            RootSource::Scroll,
+            providers.l2_log_mode,
+            &providers.l2_network_provider,
+        ),
+    ]
+    .into_iter()
+    .filter(|(_, mode, _)| *mode == LogMode::Subscribe)
+    .filter_map(|(source, _, urls)| Some((source, urls.ws_url()?.clone())))
+    .collect()
+}
+
+/// The window `source` is scanned in and how far behind the head it stays.
+fn window(config: &ScannerConfig, source: RootSource) -> (u64, u64) {
+    match source {
+        RootSource::Bridge | RootSource::Scroll => (
+            config.scanning_window_size,
+            config.scanning_chain_head_offset,
+        ),
+        RootSource::WorldId => (
+            config
+                .world_id_scanning_window_size
+                .unwrap_or(config.scanning_window_size),
+            config
+                .world_id_chain_head_offset
+                .unwrap_or(config.scanning_chain_head_offset),
+        ),
+    }
+}
+
+/// The latest mainnet root, with its age if it is younger than
+/// `min_root_age`.
+async fn latest_root_age(
+    bridge: &dyn StateBridge,
+    min_root_age: Duration,
+) -> anyhow::Result<(U256, Option<Duration>)> {
+    let root = bridge.get_world_id_latest_root().await?;
+    if min_root_age.is_zero() {
+        return Ok((root, None));
+    }
+
+    let age = bridge.get_world_id_root_age(root, min_root_age).await?;
+    Ok((root, age.filter(|age| *age < min_root_age)))
+}
 
+/// The latest mainnet root if the target has it, as its latest root or in
+/// its history. Read past the root cache, a status cached before another
+/// replica's propagation landed would be stale.
+async fn already_synced(bridge: &dyn StateBridge) -> anyhow::Result<Option<U256>> {
+    let (mainnet_root, target_root) = futures::try_join!(
+        bridge.get_world_id_latest_root(),
+        bridge.get_target_latest_root(),
+    )?;
+
+    let synced = mainnet_root == target_root || bridge.is_root_mined(mainnet_root).await?;
+    Ok(synced.then_some(mainnet_root))
 }
 
+/// Compares `mainnet_root` with the target's latest root, going by what
+/// `bridge` reports.
+async fn target_sync_state(
+    bridge: &dyn StateBridge,
+    mainnet_root: U256,
+) -> anyhow::Result<SyncState> {
+    let scroll_root = bridge.get_target_latest_root().await.map_err(|e| {
+        error!(
+            ?e,
+            chain = bridge.chain(),
+            "Failed to fetch the target's latest root"
+        );
+        e
+    })?;
+
+    if scroll_root.is_zero() {
+        info!(
+            ?mainnet_root,
+            chain = bridge.chain(),
+            "The target has never received a root"
+        );
+        return Ok(SyncState::Unsynced(UnsyncedReason::NeverBridged));
+    }
+
+    if mainnet_root == scroll_root {
+        return Ok(SyncState::Synced);
+    }
+
+    // The roots differ, but the mainnet root may already have been
+    // delivered, in which case propagating it again only burns gas.
+    let mined = bridge.is_root_mined(mainnet_root).await?;
+    if mined {
+        warn!(
+            ?mainnet_root,
+            ?scroll_root,
+            "Latest roots differ but the mainnet root is already known on the target"
+        );
+    } else {
+        info!(?mainnet_root, ?scroll_root, "Latest roots differ");
+    }
+
+    Ok(SyncState::from_roots(mainnet_root, scroll_root, mined))
+}
+
+fn observed_root(log: &Log, root: U256) -> anyhow::Result<ObservedRoot> {
+    let missing = |field: &str| anyhow!("scanned log is missing its {field}");
+
+    Ok(ObservedRoot {
+        root,
+        block_number: log
+            .block_number
+            .ok_or_else(|| missing("block number"))?
+            .as_u64(),
+        tx_hash: log
+            .transaction_hash
+            .ok_or_else(|| missing("transaction hash"))?,
+        log_index: log.log_index.ok_or_else(|| missing("log index"))?.as_u64(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::contracts::BridgeAddresses;
+
+    /// A bridge to no chain in particular, backed by maps.
+    #[derive(Debug, Default)]
+    struct FakeBridge {
+        mainnet_root:  U256,
+        mainnet_roots: Vec<U256>,
+        target_root:   U256,
+        /// When the target received each root
+        target_roots:  BTreeMap<U256, u128>,
+        /// The target's provider never answers
+        target_hangs:  bool,
+        /// How long ago the mainnet root was inserted
+        mainnet_age:   Option<Duration>,
+    }
+
+    #[async_trait]
+    impl StateBridge for FakeBridge {
+        fn chain(&self) -> &'static str {
+            "fake"
+        }
+
+        fn addresses(&self) -> BridgeAddresses {
+            BridgeAddresses {
+                bridge:          Address::repeat_byte(1),
+                target_world_id: Address::repeat_byte(2),
+                world_id:        Address::repeat_byte(3),
+            }
+        }
+
+        async fn simulate_propagate_root(&self, _: U256) -> Result<(), SimulationError> {
+            Ok(())
+        }
+
+        async fn propagate_root(&self, _: U256) -> anyhow::Result<TransactionId> {
+            Ok(TransactionId::job("fake-tx")?)
+        }
+
+        async fn get_world_id_latest_root(&self) -> anyhow::Result<U256> {
+            Ok(self.mainnet_root)
+        }
+
+        async fn is_root_on_mainnet(&self, root: U256) -> anyhow::Result<bool> {
+            Ok(self.mainnet_roots.contains(&root))
+        }
+
+        async fn get_world_id_root_age(
+            &self,
+            root: U256,
+            within: Duration,
+        ) -> anyhow::Result<Option<Duration>> {
+            assert_eq!(root, self.mainnet_root);
+            Ok(self.mainnet_age.filter(|age| *age <= within))
+        }
+
+        async fn get_root_history_expiry(&self) -> anyhow::Result<Duration> {
+            Ok(Duration::from_secs(7 * 24 * 60 * 60))
+        }
+
+        async fn get_target_latest_root(&self) -> anyhow::Result<U256> {
+            if self.target_hangs {
+                std::future::pending::<()>().await;
+            }
+            Ok(self.target_root)
+        }
+
+        async fn get_target_root_timestamp(&self, root: U256) -> anyhow::Result<u128> {
+            if self.target_hangs {
+                std::future::pending::<()>().await;
+            }
+            Ok(self.target_roots.get(&root).copied().unwrap_or_default())
+        }
+    }
+
+    fn bridge(mainnet_root: u64, target_root: u64, delivered: &[u64]) -> FakeBridge {
+        FakeBridge {
+            mainnet_root:  mainnet_root.into(),
+            mainnet_roots: [mainnet_root, target_root].map(U256::from).to_vec(),
+            target_root:   target_root.into(),
+            target_roots:  delivered
+                .iter()
+                .map(|root| (U256::from(*root), 1_700_000_000))
+                .collect(),
+            target_hangs:  false,
+            mainnet_age:   None,
+        }
+    }
+
+    #[tokio::test]
+    async fn target_sync_state_only_depends_on_the_bridge_trait() -> anyhow::Result<()> {
+        let sync_state = |bridge: FakeBridge| async move {
+            target_sync_state(&bridge, bridge.get_world_id_latest_root().await?).await
+        };
+
+        assert_eq!(
+            sync_state(bridge(1, 0, &[])).await?,
+            SyncState::Unsynced(UnsyncedReason::NeverBridged)
+        );
+        assert_eq!(sync_state(bridge(1, 1, &[1])).await?, SyncState::Synced);
+        assert_eq!(
+            sync_state(bridge(2, 1, &[1])).await?,
+            SyncState::Unsynced(UnsyncedReason::RootsDiffer)
+        );
+        // Already delivered, the target just moved on from it
+        assert_eq!(sync_state(bridge(2, 3, &[2, 3])).await?, SyncState::Synced);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn a_root_the_target_already_has_isnt_sent_again() -> anyhow::Result<()> {
+        assert_eq!(already_synced(&bridge(2, 1, &[1])).await?, None);
+        assert_eq!(
+            already_synced(&bridge(2, 2, &[2])).await?,
+            Some(U256::from(2))
+        );
+        // Delivered, and the target moved on before mainnet did
+        assert_eq!(
+            already_synced(&bridge(2, 3, &[2, 3])).await?,
+            Some(U256::from(2))
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn root_status_and_lookup_are_derived_from_the_primitives() -> anyhow::Result<()> {
+        let bridge = bridge(2, 3, &[2, 3]);
+
+        assert_eq!(bridge.root_status(U256::from(3)).await?, RootStatus::Latest);
+        assert_eq!(
+            bridge.root_status(U256::from(2)).await?,
+            RootStatus::Superseded
+        );
+        assert_eq!(
+            bridge.root_status(U256::from(9)).await?,
+            RootStatus::NotOnMainnet
+        );
+        assert!(bridge.is_root_mined(U256::from(2)).await?);
+
+        let lookup = bridge.lookup_root(U256::from(2)).await?;
+        assert_eq!(lookup.status, RootStatus::Superseded);
+        assert_eq!(lookup.scroll_timestamp, Some(1_700_000_000));
+
+        let roots = [U256::from(3), U256::from(9), U256::from(2)];
+        let lookups = bridge.lookup_roots(&roots).await?;
+        assert_eq!(
+            lookups
+                .iter()
+                .map(|lookup| lookup.status)
+                .collect::<Vec<_>>(),
+            vec![
+                RootStatus::Latest,
+                RootStatus::NotOnMainnet,
+                RootStatus::Superseded
+            ]
+        );
+        assert_eq!(bridge.are_roots_mined(&roots).await?, vec![
+            true, false, true
+        ]);
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn root_unknown_on_mainnet_does_not_wait_for_the_target() -> anyhow::Result<()> {
+        let bridge = FakeBridge {
+            target_hangs: true,
+            ..bridge(2, 3, &[2, 3])
+        };
+
+        let status =
+            time::timeout(Duration::from_secs(1), bridge.root_status(U256::from(9))).await?;
+        assert_eq!(status?, RootStatus::NotOnMainnet);
+
+        // Known on mainnet, the target has to answer
+        let status = time::timeout(Duration::from_secs(1), bridge.root_status(U256::from(2))).await;
+        assert!(status.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn a_scanner_is_unhealthy_after_enough_failures_in_a_row() -> anyhow::Result<()> {
+        let once = ScannerStatus::default().failed(3);
+        assert_eq!((once.consecutive_failures, once.healthy), (1, true));
+
+        let thrice = once.failed(3).failed(3);
+        assert_eq!((thrice.consecutive_failures, thrice.healthy), (3, false));
+        assert_eq!(thrice.next_block, once.next_block);
+
+        // Reported before scanners had a health
+        let status: ScannerStatus = serde_json::from_str(r#"{"nextBlock": 7}"#)?;
+        assert!(status.healthy);
+        assert_eq!(status.next_block, 7);
+
+        Ok(())
+    }
+
+    #[test]
+    fn the_world_id_scan_can_have_its_own_window() {
+        let config = ScannerConfig::default().with_window(1000, 2);
+        assert_eq!(window(&config, RootSource::Bridge), (1000, 2));
+        assert_eq!(window(&config, RootSource::WorldId), (1000, 2));
+
+        let config = config.with_world_id_window(100, 0);
+        assert_eq!(window(&config, RootSource::Scroll), (1000, 2));
+        assert_eq!(window(&config, RootSource::WorldId), (100, 0));
+    }
+
+    #[tokio::test]
+    async fn young_roots_are_held_back_until_min_root_age() -> anyhow::Result<()> {
+        let hour = PropagationConfig::default()
+            .with_min_root_age(Duration::from_secs(3600))
+            .min_root_age;
+        let with_age = |age| FakeBridge {
+            mainnet_age: age,
+            ..bridge(2, 1, &[1])
+        };
+
+        let young = with_age(Some(Duration::from_secs(600)));
+        assert_eq!(
+            latest_root_age(&young, hour).await?,
+            (U256::from(2), Some(Duration::from_secs(600)))
+        );
+        // Without a minimum age the root isn't looked up
+        assert_eq!(
+            latest_root_age(&young, Duration::ZERO).await?,
+            (U256::from(2), None)
+        );
+
+        assert_eq!(
+            latest_root_age(&with_age(Some(hour)), hour).await?,
+            (U256::from(2), None)
+        );
+        // Not found within the window, so older than it
+        assert_eq!(
+            latest_root_age(&with_age(None), hour).await?,
+            (U256::from(2), None)
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+//! The sync status as the scanned logs tell it: every `RootPropagated` on the
+//! mainnet state bridge should be followed by a `RootAdded` of the same root
+//! on the target.
+//!
+//! Only the mainnet root is read from a contract, so a flaky target provider
+//! doesn't make the bridge look unsynced. The target's latest root is still
+//! read, as a cross-check.
+
+use std::{sync::Mutex, time::Duration};
+
+use ethers::types::U256;
+
+use super::status::{SyncState, UnsyncedReason};
+use crate::contracts::state_bridge::SLOT;
+
+/// What the `roots` table says about the bridge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannedRoots {
+    /// Propagated roots the target hasn't added, oldest first, leaving out
+    /// those older than the root history expiry
+    pub outstanding:        Vec<U256>,
+    /// Whether any `RootAdded` was scanned at all
+    pub any_added:          bool,
+    /// Whether the target added the latest mainnet root
+    pub mainnet_root_added: bool,
+}
+
+impl ScannedRoots {
+    /// `None` until a `RootAdded` was scanned, e.g. on a fresh deployment or
+    /// while the target's scan starts after the last delivery. The target's
+    /// own view is all there is then.
+    #[must_use]
+    pub const fn sync_state(&self) -> Option<SyncState> {
+        if !self.any_added {
+            return None;
+        }
+
+        Some(if self.mainnet_root_added {
+            SyncState::Synced
+        } else {
+            SyncState::Unsynced(UnsyncedReason::RootsDiffer)
+        })
+    }
+
+    /// Whether more roots are outstanding than `max_outstanding_roots`
+    /// allows, which is unsynced even while the latest roots match.
+    #[must_use]
+    pub fn too_many_outstanding(&self, max_outstanding_roots: Option<u64>) -> bool {
+        max_outstanding_roots.is_some_and(|max| self.outstanding.len() as u64 > max)
+    }
+}
+
+/// The first bridge block whose propagations still count as outstanding:
+/// older roots expired on mainnet, and delivering them would be pointless.
+/// Blocks are at least a slot apart, so this reaches back at least
+/// `expiry`. Everything counts before the first scan saw the head.
+#[must_use]
+pub const fn outstanding_from_block(chain_head: Option<u64>, expiry: Duration) -> u64 {
+    match chain_head {
+        Some(head) => head.saturating_sub(expiry.as_secs() / SLOT.as_secs()),
+        None => 0,
+    }
+}
+
+/// Since which scan cycle the scanned and the on-chain views disagree.
+/// The scans trail the chain head, so a cycle of disagreement is expected
+/// whenever a root lands.
+#[derive(Debug, Default)]
+pub struct Disagreement {
+    since: Mutex<Option<u64>>,
+}
+
+impl Disagreement {
+    /// Records whether the views agree as of scan cycle `cycle`, returning
+    /// for how many cycles they've disagreed.
+    pub fn observe(&self, agree: bool, cycle: u64) -> u64 {
+        let mut since = self.since.lock().unwrap();
+        if agree {
+            *since = None;
+            return 0;
+        }
+
+        cycle.saturating_sub(*since.get_or_insert(cycle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scanned_roots_decide_once_a_delivery_was_scanned() {
+        let scanned = |any_added, mainnet_root_added| ScannedRoots {
+            outstanding: Vec::new(),
+            any_added,
+            mainnet_root_added,
+        };
+
+        assert_eq!(scanned(false, false).sync_state(), None);
+        assert_eq!(scanned(true, true).sync_state(), Some(SyncState::Synced));
+        assert_eq!(
+            scanned(true, false).sync_state(),
+            Some(SyncState::Unsynced(UnsyncedReason::RootsDiffer))
+        );
+    }
+
+    #[test]
+    fn roots_past_the_expiry_are_not_outstanding() {
+        let week = Duration::from_secs(7 * 24 * 60 * 60);
+
+        // A week of 12 second slots back from the head
+        assert_eq!(
+            outstanding_from_block(Some(1_000_000), week),
+            1_000_000 - 50_400
+        );
+        // A chain younger than the expiry keeps all of its roots
+        assert_eq!(outstanding_from_block(Some(1_000), week), 0);
+        assert_eq!(outstanding_from_block(None, week), 0);
+    }
+
+    #[test]
+    fn a_backlog_above_the_threshold_is_too_many() {
+        let scanned = ScannedRoots {
+            outstanding:        [1, 2, 3].map(U256::from).to_vec(),
+            any_added:          true,
+            mainnet_root_added: true,
+        };
+
+        assert!(!scanned.too_many_outstanding(None));
+        assert!(!scanned.too_many_outstanding(Some(3)));
+        assert!(scanned.too_many_outstanding(Some(2)));
+    }
+
+    #[test]
+    fn disagreement_is_counted_in_scan_cycles_until_the_views_agree() {
+        let disagreement = Disagreement::default();
+
+        assert_eq!(disagreement.observe(false, 4), 0);
+        assert_eq!(disagreement.observe(false, 4), 0);
+        assert_eq!(disagreement.observe(false, 5), 1);
+        assert_eq!(disagreement.observe(false, 7), 3);
+
+        assert_eq!(disagreement.observe(true, 8), 0);
+        assert_eq!(disagreement.observe(false, 9), 0);
+    }
+}
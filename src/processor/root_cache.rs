@@ -0,0 +1,72 @@
+//! Root statuses read recently, so checking the same root again within a few
+//! seconds doesn't reach the providers.
+
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use ethers::types::U256;
+use tokio::time::Instant;
+
+use crate::contracts::RootStatus;
+
+#[derive(Debug)]
+pub struct RootCache {
+    ttl:     Duration,
+    entries: Mutex<HashMap<U256, (Instant, RootStatus)>>,
+}
+
+impl RootCache {
+    /// A zero `ttl` caches nothing.
+    #[must_use]
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    #[must_use]
+    pub fn get(&self, root: U256) -> Option<RootStatus> {
+        let entries = self.entries.lock().unwrap();
+        let (read_at, status) = entries.get(&root)?;
+        (read_at.elapsed() < self.ttl).then_some(*status)
+    }
+
+    /// Records a status just read, dropping the ones that expired.
+    pub fn insert(&self, root: U256, status: RootStatus) {
+        if self.ttl.is_zero() {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (read_at, _)| read_at.elapsed() < self.ttl);
+        entries.insert(root, (Instant::now(), status));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn statuses_expire_after_the_ttl() {
+        let cache = RootCache::new(Duration::from_secs(5));
+        cache.insert(U256::one(), RootStatus::Latest);
+
+        time::sleep(Duration::from_secs(4)).await;
+        assert_eq!(cache.get(U256::one()), Some(RootStatus::Latest));
+        assert_eq!(cache.get(U256::from(2)), None);
+
+        time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(cache.get(U256::one()), None);
+    }
+
+    #[test]
+    fn zero_ttl_caches_nothing() {
+        let cache = RootCache::new(Duration::ZERO);
+        cache.insert(U256::one(), RootStatus::Latest);
+
+        assert_eq!(cache.get(U256::one()), None);
+    }
+}
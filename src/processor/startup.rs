@@ -0,0 +1,135 @@
+//! Reconciles the recorded status with the chains before the tasks start,
+//! e.g. after the database was restored from a backup and no longer matches
+//! what happened on chain.
+
+use ethers::types::U256;
+
+use super::status::{BridgeStatus, SyncState, UnsyncedReason};
+
+/// A status the recorded one is moved to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Correction {
+    Unsynced(Option<UnsyncedReason>),
+    Pending,
+    Synced,
+}
+
+impl Correction {
+    #[must_use]
+    pub const fn status(self) -> BridgeStatus {
+        match self {
+            Self::Unsynced(_) => BridgeStatus::Unsynced,
+            Self::Pending => BridgeStatus::Pending,
+            Self::Synced => BridgeStatus::Synced,
+        }
+    }
+}
+
+/// The statuses `recorded` moves through to match the chains, in order,
+/// since synced can't move to pending directly. Empty when it already
+/// matches.
+///
+/// A pending propagation keeps the status pending while the roots differ.
+/// Roots that match only count as synced once a propagation of ours mined,
+/// as on every sync check.
+#[must_use]
+pub fn corrections(
+    recorded: BridgeStatus,
+    sync_state: SyncState,
+    propagation_pending: bool,
+    last_mined: bool,
+) -> Vec<Correction> {
+    match (sync_state, propagation_pending, recorded) {
+        (SyncState::Synced, false, BridgeStatus::Pending) => vec![Correction::Synced],
+        (SyncState::Synced, false, BridgeStatus::Unsynced) if last_mined => {
+            vec![Correction::Synced]
+        }
+        (SyncState::Synced, ..)
+        | (SyncState::Unsynced(_), true, BridgeStatus::Pending)
+        | (SyncState::Unsynced(_), false, BridgeStatus::Unsynced) => Vec::new(),
+        (SyncState::Unsynced(_), true, BridgeStatus::Unsynced) => vec![Correction::Pending],
+        (SyncState::Unsynced(reason), true, BridgeStatus::Synced) => {
+            vec![Correction::Unsynced(Some(reason)), Correction::Pending]
+        }
+        (SyncState::Unsynced(reason), false, _) => vec![Correction::Unsynced(Some(reason))],
+    }
+}
+
+/// What startup found and fixed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reconciliation {
+    pub mainnet_root:        U256,
+    /// `None` when the Scroll root couldn't be read
+    pub scroll_root:         Option<U256>,
+    pub sync_state:          SyncState,
+    /// The status as the database had it
+    pub recorded:            BridgeStatus,
+    /// The status once corrected, `recorded` when nothing was
+    pub status:              BridgeStatus,
+    /// Pending transactions the relayer or the chain had already settled
+    pub resolved:            usize,
+    /// Of those, the mined ones whose `mined_at` was taken from their block
+    pub mined_at_backfilled: usize,
+    /// Transactions still pending, left to the clean slate
+    pub pending:             usize,
+}
+
+impl Reconciliation {
+    #[must_use]
+    pub fn corrected(&self) -> bool {
+        self.recorded != self.status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFFER: SyncState = SyncState::Unsynced(UnsyncedReason::RootsDiffer);
+
+    #[test]
+    fn a_synced_status_the_chains_drifted_from_is_corrected() {
+        assert_eq!(
+            corrections(BridgeStatus::Synced, DIFFER, false, true),
+            vec![Correction::Unsynced(Some(UnsyncedReason::RootsDiffer))]
+        );
+        assert_eq!(
+            corrections(BridgeStatus::Synced, DIFFER, true, false),
+            vec![
+                Correction::Unsynced(Some(UnsyncedReason::RootsDiffer)),
+                Correction::Pending,
+            ]
+        );
+        assert_eq!(
+            corrections(BridgeStatus::Unsynced, DIFFER, true, false),
+            vec![Correction::Pending]
+        );
+    }
+
+    #[test]
+    fn a_pending_status_whose_propagation_landed_is_synced() {
+        assert_eq!(
+            corrections(BridgeStatus::Pending, SyncState::Synced, false, true),
+            vec![Correction::Synced]
+        );
+        assert_eq!(
+            corrections(BridgeStatus::Pending, DIFFER, false, false),
+            vec![Correction::Unsynced(Some(UnsyncedReason::RootsDiffer))]
+        );
+
+        // Still in flight, the monitor settles it
+        assert!(corrections(BridgeStatus::Pending, SyncState::Synced, true, false).is_empty());
+        assert!(corrections(BridgeStatus::Pending, DIFFER, true, false).is_empty());
+    }
+
+    #[test]
+    fn matching_roots_only_sync_after_a_propagation_of_ours() {
+        assert!(corrections(BridgeStatus::Unsynced, SyncState::Synced, false, false).is_empty());
+        assert_eq!(
+            corrections(BridgeStatus::Unsynced, SyncState::Synced, false, true),
+            vec![Correction::Synced]
+        );
+        assert!(corrections(BridgeStatus::Synced, SyncState::Synced, false, false).is_empty());
+        assert!(corrections(BridgeStatus::Unsynced, DIFFER, false, false).is_empty());
+    }
+}
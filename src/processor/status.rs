@@ -1,10 +1,10 @@
-use std::str::FromStr;
+use ethers::types::U256;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use thiserror::Error;
 
-#[derive(
-  Debug, Clone, Serialize, Deserialize, Copy, PartialEq, Eq, sqlx::Type
-)]
+/// The status the service records, as `/status` reports it.
+#[derive(Debug, Clone, Serialize, Deserialize, Copy, PartialEq, Eq, sqlx::Type)]
 #[sqlx(rename_all = "camelCase")]
 #[sqlx(type_name = "tx_status")]
 #[serde(rename_all = "camelCase")]
@@ -14,6 +14,28 @@ pub enum BridgeStatus {
     Synced,
 }
 
+impl BridgeStatus {
+    /// The statuses the service may move to `self` from. A propagation takes
+    /// the service from unsynced through pending to synced, and it falls
+    /// back to unsynced when the roots drift apart or the propagation fails.
+    /// Unsynced goes straight to synced when a propagation finds Scroll
+    /// already has the root, e.g. sent by another replica. Unsynced may be
+    /// recorded again, with a new reason.
+    #[must_use]
+    pub const fn predecessors(self) -> &'static [Self] {
+        match self {
+            Self::Unsynced => &[Self::Unsynced, Self::Pending, Self::Synced],
+            Self::Pending => &[Self::Unsynced],
+            Self::Synced => &[Self::Unsynced, Self::Pending],
+        }
+    }
+
+    #[must_use]
+    pub fn can_transition_to(self, to: Self) -> bool {
+        to.predecessors().contains(&self)
+    }
+}
+
 #[derive(Debug, Error)]
 #[error("unknown status")]
 pub struct UnknownStatus;
@@ -22,7 +44,7 @@ impl FromStr for BridgeStatus {
     type Err = UnknownStatus;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
+        match s.to_lowercase().as_str() {
             "unsynced" => Ok(Self::Unsynced),
             "pending" => Ok(Self::Pending),
             "synced" => Ok(Self::Synced),
@@ -32,19 +54,136 @@ impl FromStr for BridgeStatus {
 }
 
 impl TryFrom<&str> for BridgeStatus {
-  type Error = UnknownStatus;
+    type Error = UnknownStatus;
 
-  fn try_from(s: &str) -> Result<Self, Self::Error> {
-    BridgeStatus::from_str(s)
-  }
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        BridgeStatus::from_str(s)
+    }
 }
 
 impl From<BridgeStatus> for &str {
-  fn from(scope: BridgeStatus) -> Self {
-      match scope {
-        BridgeStatus::Pending => "pending",
-        BridgeStatus::Unsynced => "unsynced",
-        BridgeStatus::Synced => "Synced",
-      }
-  }
+    fn from(scope: BridgeStatus) -> Self {
+        match scope {
+            BridgeStatus::Pending => "pending",
+            BridgeStatus::Unsynced => "unsynced",
+            BridgeStatus::Synced => "synced",
+        }
+    }
+}
+
+/// Why the roots are considered out of sync.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum UnsyncedReason {
+    /// The Scroll root differs from the mainnet root
+    RootsDiffer,
+    /// Scroll reports a zero root: it has never received one
+    NeverBridged,
+    /// The last propagation reverted or was dropped
+    PropagationFailed,
+    /// More propagated roots wait for Scroll than `max_outstanding_roots`
+    RootsOutstanding,
+}
+
+impl From<UnsyncedReason> for &str {
+    fn from(reason: UnsyncedReason) -> Self {
+        match reason {
+            UnsyncedReason::RootsDiffer => "rootsDiffer",
+            UnsyncedReason::NeverBridged => "neverBridged",
+            UnsyncedReason::PropagationFailed => "propagationFailed",
+            UnsyncedReason::RootsOutstanding => "rootsOutstanding",
+        }
+    }
+}
+
+/// The outcome of comparing the roots on chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncState {
+    Synced,
+    Unsynced(UnsyncedReason),
+}
+
+impl SyncState {
+    /// `mainnet_root_mined` tells whether Scroll already knows the mainnet
+    /// root, and is only consulted when the latest roots differ.
+    #[must_use]
+    pub fn from_roots(mainnet_root: U256, scroll_root: U256, mainnet_root_mined: bool) -> Self {
+        // A fresh deployment reports zero until the first propagation, which
+        // must not read as being in sync with an equally fresh mainnet
+        if scroll_root.is_zero() {
+            return Self::Unsynced(UnsyncedReason::NeverBridged);
+        }
+
+        if mainnet_root == scroll_root || mainnet_root_mined {
+            Self::Synced
+        } else {
+            Self::Unsynced(UnsyncedReason::RootsDiffer)
+        }
+    }
+
+    #[must_use]
+    pub const fn is_synced(self) -> bool {
+        matches!(self, Self::Synced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synced_is_never_recorded_twice() {
+        use BridgeStatus::{Pending, Synced, Unsynced};
+
+        let legal = [
+            (Unsynced, Unsynced),
+            (Unsynced, Pending),
+            (Unsynced, Synced),
+            (Pending, Synced),
+            (Pending, Unsynced),
+            (Synced, Unsynced),
+        ];
+        for from in [Unsynced, Pending, Synced] {
+            for to in [Unsynced, Pending, Synced] {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    legal.contains(&(from, to)),
+                    "{from:?} -> {to:?}"
+                );
+            }
+        }
+
+        // Written by older versions
+        assert_eq!("Synced".parse::<BridgeStatus>().unwrap(), Synced);
+    }
+
+    #[test]
+    fn zero_scroll_root_is_never_bridged_until_the_first_root_lands() {
+        let mainnet_root = U256::from(0xabc);
+
+        // Fresh on both sides, then a root on mainnet, then the first RootAdded
+        assert_eq!(
+            SyncState::from_roots(U256::zero(), U256::zero(), false),
+            SyncState::Unsynced(UnsyncedReason::NeverBridged)
+        );
+        assert_eq!(
+            SyncState::from_roots(mainnet_root, U256::zero(), false),
+            SyncState::Unsynced(UnsyncedReason::NeverBridged)
+        );
+        assert_eq!(
+            SyncState::from_roots(mainnet_root, mainnet_root, false),
+            SyncState::Synced
+        );
+
+        // Later roots go back to an ordinary difference
+        assert_eq!(
+            SyncState::from_roots(U256::from(0xdef), mainnet_root, false),
+            SyncState::Unsynced(UnsyncedReason::RootsDiffer)
+        );
+        assert_eq!(
+            SyncState::from_roots(U256::from(0xdef), mainnet_root, true),
+            SyncState::Synced
+        );
+    }
 }
@@ -0,0 +1,275 @@
+//! API keys under `[server]`. Routes that change anything require one, and
+//! so do read routes with `protect_read_routes`, and always `/config`.
+
+use std::{hint::black_box, net::SocketAddr, sync::Arc};
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{
+        header::{AUTHORIZATION, WWW_AUTHENTICATE},
+        HeaderMap, HeaderValue, Method, Request, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+
+use crate::{config::ServerConfig, utils::secret::SecretString};
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Probes keep working without a key.
+const OPEN_ROUTES: [&str; 2] = ["/health", "/ready"];
+
+/// Read routes that show more than the service's state.
+const ADMIN_READ_ROUTES: [&str; 1] = ["/config"];
+
+/// Reads that take a body, so are `POST`s.
+const READ_POST_ROUTES: [&str; 1] = ["/roots/batch"];
+
+const BEARER: &[u8] = b"bearer ";
+
+#[derive(Debug, Clone)]
+pub struct ApiKeys {
+    keys:                Vec<SecretString>,
+    protect_read_routes: bool,
+}
+
+impl ApiKeys {
+    #[must_use]
+    pub fn new(config: &ServerConfig) -> Self {
+        Self {
+            keys:                config.api_keys.clone(),
+            protect_read_routes: config.protect_read_routes,
+        }
+    }
+
+    fn required<B>(&self, request: &Request<B>) -> bool {
+        if self.keys.is_empty() {
+            return false;
+        }
+
+        let path = request.uri().path();
+        let read = matches!(
+            *request.method(),
+            Method::GET | Method::HEAD | Method::OPTIONS
+        ) || (request.method() == Method::POST && READ_POST_ROUTES.contains(&path));
+        !read
+            || ADMIN_READ_ROUTES.contains(&path)
+            || (self.protect_read_routes && !OPEN_ROUTES.contains(&path))
+    }
+
+    /// Whether `headers` carry one of the keys. Every key is compared, so the
+    /// time taken doesn't tell which one came closest.
+    fn accepts(&self, headers: &HeaderMap) -> bool {
+        let Some(presented) = presented_key(headers) else {
+            return false;
+        };
+
+        self.keys.iter().fold(false, |accepted, key| {
+            accepted | constant_time_eq(key.expose().as_bytes(), presented)
+        })
+    }
+}
+
+/// The key from `Authorization: Bearer`, or else from `X-Api-Key`.
+fn presented_key(headers: &HeaderMap) -> Option<&[u8]> {
+    let bearer = headers.get(AUTHORIZATION).and_then(|value| {
+        let value = value.as_bytes();
+        // The scheme is case insensitive
+        let scheme = value.get(..BEARER.len())?;
+        scheme
+            .eq_ignore_ascii_case(BEARER)
+            .then(|| &value[BEARER.len()..])
+    });
+
+    bearer.or_else(|| headers.get(API_KEY_HEADER).map(HeaderValue::as_bytes))
+}
+
+/// Looks at every byte whatever the first mismatch, only the length shows.
+fn constant_time_eq(expected: &[u8], presented: &[u8]) -> bool {
+    if expected.len() != presented.len() {
+        return false;
+    }
+
+    let diff = expected
+        .iter()
+        .zip(presented)
+        .fold(0, |diff, (a, b)| black_box(diff | (a ^ b)));
+    diff == 0
+}
+
+/// Answers `401` to a request that needs a key and has none that matches.
+/// The rejection is logged with the client's address, never with the key.
+pub async fn middleware<B>(
+    State(keys): State<Arc<ApiKeys>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, StatusCode> {
+    if !keys.required(&request) || keys.accepts(request.headers()) {
+        return Ok(next.run(request).await);
+    }
+
+    let source = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip());
+    warn!(
+        ?source,
+        method = %request.method(),
+        path = request.uri().path(),
+        "Rejected a request without a valid API key"
+    );
+
+    Ok((StatusCode::UNAUTHORIZED, [(WWW_AUTHENTICATE, "Bearer")]).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use axum::{
+        middleware,
+        routing::{get, post},
+        Router,
+    };
+
+    use super::*;
+
+    /// Serves `/health`, `/roots/latest`, `/roots/batch`, `/config` and
+    /// `/admin/pause` behind the layer.
+    fn serve(api_keys: &[&str], protect_read_routes: bool) -> String {
+        let keys = Arc::new(ApiKeys::new(&ServerConfig {
+            api_keys: api_keys
+                .iter()
+                .map(|key| SecretString::from((*key).to_string()))
+                .collect(),
+            protect_read_routes,
+            ..ServerConfig::default()
+        }));
+
+        let router = Router::new()
+            .route("/health", get(|| async {}))
+            .route("/roots/latest", get(|| async {}))
+            .route("/roots/batch", post(|| async {}))
+            .route("/config", get(|| async {}))
+            .route("/admin/pause", post(|| async {}))
+            .layer(middleware::from_fn_with_state(keys, super::middleware));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(router.into_make_service_with_connect_info::<SocketAddr>());
+        tokio::spawn(server);
+
+        url
+    }
+
+    async fn status(url: &str, method: Method, path: &str, header: Option<(&str, &str)>) -> u16 {
+        let mut request = reqwest::Client::new().request(method, format!("{url}{path}"));
+        if let Some((name, value)) = header {
+            request = request.header(name, value);
+        }
+        request.send().await.unwrap().status().as_u16()
+    }
+
+    #[tokio::test]
+    async fn mutating_routes_need_a_valid_key() {
+        let url = serve(&["first-key", "second-key"], false);
+
+        assert_eq!(status(&url, Method::POST, "/admin/pause", None).await, 401);
+        assert_eq!(
+            status(
+                &url,
+                Method::POST,
+                "/admin/pause",
+                Some(("Authorization", "Bearer wrong-key"))
+            )
+            .await,
+            401
+        );
+        assert_eq!(
+            status(
+                &url,
+                Method::POST,
+                "/admin/pause",
+                Some(("X-Api-Key", "first-key-but-longer"))
+            )
+            .await,
+            401
+        );
+        assert_eq!(
+            status(
+                &url,
+                Method::POST,
+                "/admin/pause",
+                Some(("Authorization", "Basic second-key"))
+            )
+            .await,
+            401
+        );
+
+        assert_eq!(
+            status(
+                &url,
+                Method::POST,
+                "/admin/pause",
+                Some(("Authorization", "Bearer second-key"))
+            )
+            .await,
+            200
+        );
+        assert_eq!(
+            status(
+                &url,
+                Method::POST,
+                "/admin/pause",
+                Some(("X-Api-Key", "first-key"))
+            )
+            .await,
+            200
+        );
+
+        assert_eq!(status(&url, Method::GET, "/roots/latest", None).await, 200);
+        assert_eq!(status(&url, Method::POST, "/roots/batch", None).await, 200);
+    }
+
+    #[tokio::test]
+    async fn config_needs_a_key_even_with_open_read_routes() {
+        let url = serve(&["key"], false);
+
+        assert_eq!(status(&url, Method::GET, "/config", None).await, 401);
+        assert_eq!(
+            status(&url, Method::GET, "/config", Some(("X-Api-Key", "key"))).await,
+            200
+        );
+    }
+
+    #[tokio::test]
+    async fn read_routes_can_be_protected_except_probes() {
+        let url = serve(&["key"], true);
+
+        assert_eq!(status(&url, Method::GET, "/roots/latest", None).await, 401);
+        assert_eq!(status(&url, Method::POST, "/roots/batch", None).await, 401);
+        assert_eq!(
+            status(
+                &url,
+                Method::GET,
+                "/roots/latest",
+                Some(("Authorization", "bearer key"))
+            )
+            .await,
+            200
+        );
+        assert_eq!(status(&url, Method::GET, "/health", None).await, 200);
+    }
+
+    #[tokio::test]
+    async fn no_keys_leave_every_route_open() {
+        let url = serve(&[], false);
+
+        assert_eq!(status(&url, Method::POST, "/admin/pause", None).await, 200);
+        assert_eq!(status(&url, Method::GET, "/config", None).await, 200);
+    }
+}
@@ -0,0 +1,36 @@
+//! Caps how many root lookups run at once, so a burst of them can't exhaust
+//! the RPC providers. The rest wait for a turn, within their timeout.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{MatchedPath, State},
+    http::Request,
+    middleware::Next,
+    response::Response,
+};
+use tokio::sync::Semaphore;
+
+/// The routes that call the contracts.
+const LIMITED_ROUTES: [&str; 3] = ["/roots/latest", "/roots/:root", "/roots/batch"];
+
+pub async fn middleware<B>(
+    State(lookups): State<Arc<Semaphore>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let limited = request
+        .extensions()
+        .get::<MatchedPath>()
+        .is_some_and(|path| LIMITED_ROUTES.contains(&path.as_str()));
+    if !limited {
+        return next.run(request).await;
+    }
+
+    // The semaphore is never closed
+    let _permit = lookups
+        .acquire()
+        .await
+        .expect("root lookup semaphore closed");
+    next.run(request).await
+}
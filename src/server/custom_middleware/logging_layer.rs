@@ -1,30 +1,47 @@
 #![allow(clippy::cast_possible_truncation)]
 
-use axum::http::{Request, StatusCode};
-use axum::middleware::Next;
-use axum::response::Response;
+use std::time::Instant;
+
+use axum::{
+    extract::State,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::Response,
+};
 use bytes::Bytes;
-use hyper::body::HttpBody;
-use hyper::{Body, Method};
+use hyper::{body::HttpBody, Body, Method};
 use telemetry_batteries::tracing::{trace_from_headers, trace_to_headers};
 use tracing::{error, info, info_span, warn, Instrument};
 
-// 1 MiB
-const MAX_REQUEST_BODY_SIZE: u64 = 1024 * 1024;
+use super::request_id_layer::REQUEST_ID_HEADER;
 
-pub async fn middleware<B>(request: Request<B>, next: Next<Body>) -> Result<Response, StatusCode>
+/// Logs every request and its response with the latency, as request ids
+/// are set. Bodies larger than `max_body_size` under `[server]` are a `413`.
+pub async fn middleware<B>(
+    State(max_body_size): State<usize>,
+    request: Request<B>,
+    next: Next<Body>,
+) -> Result<Response, StatusCode>
 where
     B: HttpBody,
     <B as HttpBody>::Error: std::error::Error,
 {
+    let started = Instant::now();
     let (parts, body) = request.into_parts();
 
+    let request_id = parts
+        .headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|request_id| request_id.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
     let uri_path = parts.uri.path().to_string();
     let request_method = parts.method.clone();
     let request_query = parts.uri.query().map(ToString::to_string);
 
     if let Method::GET = request_method {
-        let span = info_span!("request", ?uri_path, ?request_method, ?request_query);
+        let span = info_span!("request", %request_id, ?uri_path, ?request_method, ?request_query);
 
         async {
             trace_from_headers(&parts.headers);
@@ -46,6 +63,8 @@ where
                 &request_method,
                 request_query.as_deref(),
                 response,
+                started,
+                max_body_size,
             )
             .await?;
 
@@ -56,9 +75,16 @@ where
         .instrument(span)
         .await
     } else {
-        let body = body_to_string(body).await?;
-
-        let span = info_span!("request", ?uri_path, ?request_method, ?request_query, ?body);
+        let body = body_to_string(body, max_body_size).await?;
+
+        let span = info_span!(
+            "request",
+            %request_id,
+            ?uri_path,
+            ?request_method,
+            ?request_query,
+            ?body
+        );
 
         async {
             trace_from_headers(&parts.headers);
@@ -81,6 +107,8 @@ where
                 &request_method,
                 request_query.as_deref(),
                 response,
+                started,
+                max_body_size,
             )
             .await?;
 
@@ -98,13 +126,15 @@ async fn handle_response(
     request_method: &Method,
     request_query: Option<&str>,
     response: Response,
+    started: Instant,
+    max_body_size: usize,
 ) -> Result<Response, StatusCode> {
     let (parts, body) = response.into_parts();
 
     let response_status = parts.status;
 
     let response = if response_status.is_client_error() || response_status.is_server_error() {
-        let response_body = body_to_string(body).await?;
+        let response_body = body_to_string(body, max_body_size).await?;
 
         if response_status.is_client_error() {
             warn!(
@@ -138,6 +168,7 @@ async fn handle_response(
         ?request_method,
         ?request_query,
         ?response_status,
+        latency_ms = started.elapsed().as_millis() as u64,
         "Finished processing request"
     );
 
@@ -145,8 +176,8 @@ async fn handle_response(
 }
 
 /// Reads the body of a request into a `Bytes` object chunk by chunk
-/// and returns an error if the body is larger than `MAX_REQUEST_BODY_SIZE`.
-async fn body_to_bytes_safe<B>(body: B) -> Result<Bytes, StatusCode>
+/// and returns an error if the body is larger than `max_body_size`.
+async fn body_to_bytes_safe<B>(body: B, max_body_size: usize) -> Result<Bytes, StatusCode>
 where
     B: HttpBody,
     <B as HttpBody>::Error: std::error::Error,
@@ -158,10 +189,10 @@ where
         .upper()
         .unwrap_or_else(|| body.size_hint().lower());
 
-    if size_hint > MAX_REQUEST_BODY_SIZE {
+    if size_hint > max_body_size as u64 {
         error!(
             "Request body too large: {} bytes (max: {} bytes)",
-            size_hint, MAX_REQUEST_BODY_SIZE
+            size_hint, max_body_size
         );
 
         return Err(StatusCode::PAYLOAD_TOO_LARGE);
@@ -179,11 +210,11 @@ where
 
         body_bytes.put(chunk);
 
-        if body_bytes.len() > MAX_REQUEST_BODY_SIZE as usize {
+        if body_bytes.len() > max_body_size {
             error!(
                 "Request body too large: {} bytes (max: {} bytes)",
                 body_bytes.len(),
-                MAX_REQUEST_BODY_SIZE
+                max_body_size
             );
 
             return Err(StatusCode::PAYLOAD_TOO_LARGE);
@@ -193,12 +224,12 @@ where
     Ok(body_bytes.into())
 }
 
-async fn body_to_string<B>(body: B) -> Result<String, StatusCode>
+async fn body_to_string<B>(body: B, max_body_size: usize) -> Result<String, StatusCode>
 where
     B: HttpBody,
     <B as HttpBody>::Error: std::error::Error,
 {
-    let body_bytes = body_to_bytes_safe(body).await?;
+    let body_bytes = body_to_bytes_safe(body, max_body_size).await?;
 
     let s = match String::from_utf8(body_bytes.to_vec()) {
         Ok(s) => s,
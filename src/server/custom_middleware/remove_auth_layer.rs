@@ -1,10 +1,14 @@
-use axum::http::header::AUTHORIZATION;
-use axum::http::{Request, StatusCode};
-use axum::middleware::Next;
-use axum::response::Response;
+use axum::{
+    http::{header::AUTHORIZATION, Request, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+use super::auth_layer::API_KEY_HEADER;
 
 pub async fn middleware<B>(mut request: Request<B>, next: Next<B>) -> Result<Response, StatusCode> {
     request.headers_mut().remove(AUTHORIZATION);
+    request.headers_mut().remove(API_KEY_HEADER);
 
     let response = next.run(request).await;
     Ok(response)
@@ -0,0 +1,76 @@
+//! Gives every request an `X-Request-Id`, the client's own if it sent one,
+//! and echoes it on the response so a report can be matched to the logs.
+
+use axum::{
+    http::{HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+pub async fn middleware<B>(mut request: Request<B>, next: Next<B>) -> Response {
+    let request_id = match request.headers().get(REQUEST_ID_HEADER) {
+        Some(request_id) if !request_id.is_empty() => request_id.clone(),
+        _ => {
+            let generated = format!("{:032x}", ethers::core::rand::random::<u128>());
+            let generated = HeaderValue::from_str(&generated).expect("hex is a valid header value");
+            request
+                .headers_mut()
+                .insert(REQUEST_ID_HEADER, generated.clone());
+            generated
+        }
+    };
+
+    let mut response = next.run(request).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use axum::{http::HeaderMap, middleware, routing::get, Router};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn request_ids_are_echoed_or_generated() {
+        // The handler sees the id the client gets back
+        let router = Router::new()
+            .route(
+                "/health",
+                get(|headers: HeaderMap| async move {
+                    headers[REQUEST_ID_HEADER].to_str().unwrap().to_string()
+                }),
+            )
+            .layer(middleware::from_fn(super::middleware));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/health", listener.local_addr().unwrap());
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(router.into_make_service()),
+        );
+
+        let client = reqwest::Client::new();
+        let response = client
+            .get(&url)
+            .header(REQUEST_ID_HEADER, "abc-123")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "abc-123");
+        assert_eq!(response.text().await.unwrap(), "abc-123");
+
+        let response = client.get(&url).send().await.unwrap();
+        let generated = response.headers()[REQUEST_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(generated.len(), 32);
+        assert_eq!(response.text().await.unwrap(), generated);
+    }
+}
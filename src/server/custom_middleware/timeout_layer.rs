@@ -1,17 +1,110 @@
-use std::time::Duration;
+//! Bounds how long a request may take: `serve_timeout` under `[server]`,
+//! unless `route_timeouts` sets another for its route.
 
-use axum::extract::State;
-use axum::http::{Request, StatusCode};
-use axum::middleware::Next;
-use axum::response::Response;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
+use axum::{
+    extract::{MatchedPath, State},
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use tracing::warn;
+
+use crate::config::ServerConfig;
+
+#[derive(Debug, Clone)]
+pub struct Timeouts {
+    default: Duration,
+    /// By route as registered, e.g. `/roots/:root`
+    routes:  HashMap<String, Duration>,
+}
+
+impl Timeouts {
+    #[must_use]
+    pub fn new(config: &ServerConfig) -> Self {
+        Self {
+            default: config.serve_timeout,
+            routes:  config
+                .route_timeouts
+                .iter()
+                .map(|route| (route.route.clone(), route.timeout))
+                .collect(),
+        }
+    }
+
+    fn of(&self, route: &str) -> Duration {
+        self.routes.get(route).copied().unwrap_or(self.default)
+    }
+}
+
+/// Answers `504` with a JSON error once the request's timeout passes.
 pub async fn middleware<B>(
-    State(timeout_duration): State<Duration>,
+    State(timeouts): State<Arc<Timeouts>>,
     request: Request<B>,
     next: Next<B>,
-) -> Result<Response, StatusCode> {
-    match tokio::time::timeout(timeout_duration, next.run(request)).await {
-        Ok(response) => Ok(response),
-        Err(_elapsed) => Err(StatusCode::REQUEST_TIMEOUT),
+) -> Response {
+    let route = request.extensions().get::<MatchedPath>().map_or_else(
+        || request.uri().path().to_string(),
+        |path| path.as_str().to_string(),
+    );
+    let timeout = timeouts.of(&route);
+
+    match tokio::time::timeout(timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_elapsed) => {
+            let timeout = humantime::format_duration(timeout).to_string();
+            warn!(route, timeout, "Request timed out");
+
+            let body = json!({ "error": format!("request timed out after {timeout}") });
+            (StatusCode::GATEWAY_TIMEOUT, Json(body)).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use axum::{middleware, routing::get, Router};
+    use serde_json::Value;
+
+    use super::*;
+    use crate::config::RouteTimeout;
+
+    #[tokio::test]
+    async fn slow_routes_time_out_with_a_json_error() {
+        let timeouts = Arc::new(Timeouts::new(&ServerConfig {
+            serve_timeout: Duration::from_millis(50),
+            route_timeouts: vec![RouteTimeout {
+                route:   "/roots/:root".to_string(),
+                timeout: Duration::from_secs(5),
+            }],
+            ..ServerConfig::default()
+        }));
+        let slow = || async { tokio::time::sleep(Duration::from_millis(200)).await };
+        let router = Router::new()
+            .route("/roots/latest", get(slow))
+            .route("/roots/:root", get(slow))
+            .layer(middleware::from_fn_with_state(timeouts, super::middleware));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(router.into_make_service()),
+        );
+
+        let response = reqwest::get(format!("{url}/roots/latest")).await.unwrap();
+        assert_eq!(response.status(), 504);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["error"], "request timed out after 50ms");
+
+        // Its own, longer timeout
+        let response = reqwest::get(format!("{url}/roots/0xabc")).await.unwrap();
+        assert_eq!(response.status(), 200);
     }
 }
@@ -1,23 +1,196 @@
+use std::{collections::HashSet, time::Duration};
+
+use crate::{
+    app::{LatestRoots, RootLookup, RootStatus, ServiceStatus},
+    database::types::{LastSync, PropagationRecord, Spend},
+    processor::status::BridgeStatus,
+};
+use chrono::{DateTime, Utc};
+use ethers::types::U256;
 use hyper::StatusCode;
 use serde::{Deserialize, Serialize};
-use crate::database::types::ServerStatus;
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
-pub struct ServerStatusResponse(pub ServerStatus);
+impl ToResponseCode for ServiceStatus {
+    fn to_response_code(&self) -> StatusCode {
+        StatusCode::OK
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LatestRootsResponse {
+    pub mainnet_root: String,
+    /// `null` until Scroll has received its first root
+    pub scroll_root:  Option<String>,
+}
+
+impl From<LatestRoots> for LatestRootsResponse {
+    fn from(roots: LatestRoots) -> Self {
+        Self {
+            mainnet_root: format!("{:#066x}", roots.mainnet),
+            scroll_root:  roots.scroll.map(|root| format!("{root:#066x}")),
+        }
+    }
+}
+
+/// What `/admin/trigger` found.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerResponse {
+    /// The roots already match, propagation wasn't woken
+    pub already_synced: bool,
+}
 
+/// A root that mainnet doesn't know is still a `200`, so callers can tell it
+/// apart from a missing route.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RootLookupResponse {
+    pub root:             String,
+    pub on_mainnet:       bool,
+    /// Whether Scroll received the root, as its latest root or since
+    /// superseded
+    pub mined:            bool,
+    pub status:           RootStatus,
+    /// Unix seconds when Scroll received the root, `null` if it hasn't
+    pub scroll_timestamp: Option<u64>,
+    /// Whether Scroll added the root in a finalized block, absent without
+    /// `[finality]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finalized:        Option<bool>,
+}
+
+impl RootLookupResponse {
+    #[must_use]
+    pub fn new(root: U256, lookup: RootLookup) -> Self {
+        Self {
+            root:             format!("{root:#066x}"),
+            on_mainnet:       lookup.status != RootStatus::NotOnMainnet,
+            mined:            lookup.status.is_mined(),
+            status:           lookup.status,
+            scroll_timestamp: lookup
+                .scroll_timestamp
+                .map(|timestamp| u64::try_from(timestamp).unwrap_or(u64::MAX)),
+            finalized:        None,
+        }
+    }
 
-impl From<ServerStatus> for ServerStatusResponse {
-    fn from(value: ServerStatus) -> Self {
-        Self(value)
+    /// Sets `finalized` from the roots finalized on Scroll, if they are
+    /// tracked.
+    #[must_use]
+    pub fn with_finality(mut self, root: U256, finalized_roots: Option<&HashSet<U256>>) -> Self {
+        self.finalized = finalized_roots.map(|finalized| finalized.contains(&root));
+        self
     }
 }
 
-impl ToResponseCode for ServerStatusResponse {
+/// A page of `/propagations`, newest first.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PropagationsResponse {
+    pub propagations: Vec<PropagationRecord>,
+    /// `before` for the next page, `null` on the last one
+    pub next_cursor:  Option<String>,
+}
+
+impl PropagationsResponse {
+    /// A short page is the last one.
+    #[must_use]
+    pub fn new(propagations: Vec<PropagationRecord>, limit: u64) -> Self {
+        let full = u64::try_from(propagations.len()).unwrap_or(u64::MAX) >= limit;
+        let next_cursor = propagations
+            .last()
+            .filter(|_| full)
+            .map(|last| last.cursor().to_string());
+
+        Self {
+            propagations,
+            next_cursor,
+        }
+    }
+}
+
+/// Whether the Scroll root is at most `max_age` behind mainnet, by the
+/// database's clock. A synced bridge isn't behind at all, otherwise it is as
+/// far behind as its last sync. `409` when it is further behind.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FreshnessResponse {
+    pub fresh:         bool,
+    /// The roots matched at the last sync check
+    pub synced:        bool,
+    /// Seconds behind mainnet, `null` when never synced, which is stale at
+    /// any `maxAge`
+    pub age:           Option<u64>,
+    pub max_age:       u64,
+    pub last_synced:   Option<DateTime<Utc>>,
+    /// The database's clock the age was taken by
+    pub database_time: DateTime<Utc>,
+}
+
+impl FreshnessResponse {
+    #[must_use]
+    pub fn new(last_sync: LastSync, max_age: Duration) -> Self {
+        let synced = last_sync.status == Some(BridgeStatus::Synced);
+        let age = if synced {
+            Some(Duration::ZERO)
+        } else {
+            last_sync
+                .last_synced
+                .map(|last_synced| (last_sync.now - last_synced).to_std().unwrap_or_default())
+        };
+
+        Self {
+            fresh: age.is_some_and(|age| age <= max_age),
+            synced,
+            age: age.map(|age| age.as_secs()),
+            max_age: max_age.as_secs(),
+            last_synced: last_sync.last_synced,
+            database_time: last_sync.now,
+        }
+    }
+}
+
+impl ToResponseCode for FreshnessResponse {
     fn to_response_code(&self) -> StatusCode {
-        StatusCode::OK
+        if self.fresh {
+            StatusCode::OK
+        } else {
+            StatusCode::CONFLICT
+        }
     }
 }
 
+/// What the propagations created since `since` cost.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CostsResponse {
+    pub since:        DateTime<Utc>,
+    /// In wei, as hex like a propagation's `cost`
+    pub total_cost:   String,
+    /// Propagations whose cost was recorded
+    pub transactions: u64,
+    pub gas_used:     u64,
+    /// Of those, the ones priced at `gasPrice` as their receipt had no
+    /// `effectiveGasPrice`
+    pub estimated:    u64,
+    /// Propagations that paid for gas but whose cost is unknown
+    pub unknown:      u64,
+}
+
+impl CostsResponse {
+    #[must_use]
+    pub fn new(since: DateTime<Utc>, spend: Spend) -> Self {
+        Self {
+            since,
+            total_cost: format!("{:#x}", spend.total),
+            transactions: spend.count,
+            gas_used: spend.gas_used,
+            estimated: spend.estimated,
+            unknown: spend.unknown,
+        }
+    }
+}
 
 pub trait ToResponseCode {
     fn to_response_code(&self) -> StatusCode;
@@ -28,3 +201,185 @@ impl ToResponseCode for () {
         StatusCode::OK
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::database::types::PropagationCursor;
+
+    #[test]
+    fn freshness_is_aged_by_the_database_clock() {
+        let now: DateTime<Utc> = "2026-01-01T00:10:00Z".parse().unwrap();
+        let last_sync = |status: BridgeStatus, minutes_ago: Option<i64>| LastSync {
+            status: Some(status),
+            last_synced: minutes_ago.map(|minutes| now - chrono::Duration::minutes(minutes)),
+            now,
+        };
+        let max_age = Duration::from_secs(300);
+
+        let behind = FreshnessResponse::new(last_sync(BridgeStatus::Pending, Some(4)), max_age);
+        assert!(behind.fresh);
+        assert_eq!(behind.age, Some(240));
+        assert_eq!(behind.database_time, now);
+
+        let stale = FreshnessResponse::new(last_sync(BridgeStatus::Unsynced, Some(6)), max_age);
+        assert_eq!((stale.fresh, stale.age), (false, Some(360)));
+        assert_eq!(stale.to_response_code(), StatusCode::CONFLICT);
+
+        // Matching roots aren't behind, however long ago they started to
+        let synced = FreshnessResponse::new(last_sync(BridgeStatus::Synced, Some(60)), max_age);
+        assert_eq!((synced.fresh, synced.age), (true, Some(0)));
+        assert_eq!(synced.to_response_code(), StatusCode::OK);
+    }
+
+    #[test]
+    fn never_synced_is_stale_at_any_age() {
+        let now = Utc::now();
+        let response = FreshnessResponse::new(
+            LastSync {
+                status: None,
+                last_synced: None,
+                now,
+            },
+            Duration::from_secs(u64::from(u32::MAX)),
+        );
+
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            json!({
+                "fresh": false,
+                "synced": false,
+                "age": null,
+                "maxAge": u32::MAX,
+                "lastSynced": null,
+                "databaseTime": now,
+            })
+        );
+        assert_eq!(response.to_response_code(), StatusCode::CONFLICT);
+    }
+
+    #[test]
+    fn costs_are_reported_in_hex_wei() {
+        let since = "2026-01-01T00:00:00Z".parse().unwrap();
+        let response = CostsResponse::new(since, Spend {
+            total:     U256::from(350),
+            count:     2,
+            gas_used:  35,
+            estimated: 1,
+            unknown:   1,
+        });
+
+        assert_eq!(
+            serde_json::to_value(response).unwrap(),
+            json!({
+                "since": "2026-01-01T00:00:00Z",
+                "totalCost": "0x15e",
+                "transactions": 2,
+                "gasUsed": 35,
+                "estimated": 1,
+                "unknown": 1,
+            })
+        );
+    }
+
+    #[test]
+    fn never_bridged_scroll_root_is_null() {
+        let response = LatestRootsResponse::from(LatestRoots {
+            mainnet: U256::from(0xabc),
+            scroll:  None,
+        });
+
+        assert_eq!(
+            serde_json::to_value(response).unwrap(),
+            json!({
+                "mainnetRoot": format!("0x{:064x}", 0xabc),
+                "scrollRoot": null,
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_root_is_reported_not_on_mainnet() {
+        let response = RootLookupResponse::new(U256::from(0xabc), RootLookup {
+            status:           RootStatus::NotOnMainnet,
+            scroll_timestamp: None,
+        });
+
+        assert_eq!(
+            serde_json::to_value(response).unwrap(),
+            json!({
+                "root": format!("0x{:064x}", 0xabc),
+                "onMainnet": false,
+                "mined": false,
+                "status": "notOnMainnet",
+                "scrollTimestamp": null,
+            })
+        );
+    }
+
+    #[test]
+    fn only_a_full_page_has_a_next_cursor() {
+        let submitted_at = chrono::DateTime::from_timestamp_micros(1_700_000_000_123_456).unwrap();
+        let record = PropagationRecord {
+            transaction_id: crate::utils::TransactionId::job("tx-1").unwrap(),
+            root: Some(format!("{:#066x}", 0xabc)),
+            submitted_at,
+            tx_hash: None,
+            cost: None,
+            mined_at: None,
+            root_added_at: None,
+            root_added_tx_hash: None,
+            outcome: crate::database::types::TxStatus::Pending,
+            resolution_reason: None,
+        };
+
+        let full = PropagationsResponse::new(vec![record.clone()], 1);
+        assert_eq!(full.next_cursor.as_deref(), Some("1700000000123456:tx-1"));
+        assert_eq!(
+            full.next_cursor
+                .unwrap()
+                .parse::<PropagationCursor>()
+                .unwrap(),
+            record.cursor()
+        );
+
+        let last = serde_json::to_value(PropagationsResponse::new(vec![record], 20)).unwrap();
+        assert_eq!(last["nextCursor"], json!(null));
+        assert_eq!(last["propagations"][0]["outcome"], "pending");
+        assert_eq!(last["propagations"][0]["rootAddedAt"], json!(null));
+
+        for invalid in [
+            "",
+            "1700000000",
+            "abc:tx-1",
+            "1700000000:",
+            "1700000000:0x12",
+        ] {
+            assert!(invalid.parse::<PropagationCursor>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn finality_is_only_reported_when_tracked() {
+        let root = U256::from(0xabc);
+        let lookup = RootLookup {
+            status:           RootStatus::Latest,
+            scroll_timestamp: Some(1_700_000_000),
+        };
+
+        let untracked = RootLookupResponse::new(root, lookup).with_finality(root, None);
+        assert!(serde_json::to_value(untracked)
+            .unwrap()
+            .get("finalized")
+            .is_none());
+
+        let finalized = HashSet::from([root]);
+        let response = RootLookupResponse::new(root, lookup).with_finality(root, Some(&finalized));
+        assert_eq!(response.finalized, Some(true));
+        let response =
+            RootLookupResponse::new(root, lookup).with_finality(root, Some(&HashSet::new()));
+        assert_eq!(response.finalized, Some(false));
+    }
+}
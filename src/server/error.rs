@@ -1,9 +1,10 @@
 use anyhow::Error as EyreError;
 use axum::response::IntoResponse;
+use ethers::types::U256;
 use hyper::StatusCode;
 use thiserror::Error;
 
-use crate::database;
+use crate::{app, contracts::CallTimeout, database, utils::InvalidTransactionId};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -23,10 +24,20 @@ pub enum Error {
     InvalidPath,
     #[error("invalid content type")]
     InvalidContentType,
+    #[error("invalid root: {0}")]
+    InvalidRoot(String),
+    #[error("{size} roots is more than max_root_batch_size ({max})")]
+    BatchTooLarge { size: usize, max: usize },
+    #[error("no lookup came back for root {0:#066x}")]
+    MissingLookup(U256),
+    #[error("invalid query: {0}")]
+    InvalidQuery(String),
+    #[error(transparent)]
+    InvalidTransactionId(#[from] InvalidTransactionId),
     #[error("Root mismatch between world-id and scroll-world-id.")]
     RootMismatch,
-    #[error("service is not initialized")]
-    UNITIALIZED,
+    #[error(transparent)]
+    App(#[from] app::Error),
     #[error(transparent)]
     Database(#[from] database::Error),
     #[error(transparent)]
@@ -39,9 +50,18 @@ impl Error {
             Self::InvalidMethod => StatusCode::METHOD_NOT_ALLOWED,
             Self::InvalidPath => StatusCode::NOT_FOUND,
             Self::InvalidContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
-            Self::InvalidSerialization(_) => {
-                StatusCode::BAD_REQUEST
+            Self::InvalidSerialization(_)
+            | Self::InvalidRoot(_)
+            | Self::BatchTooLarge { .. }
+            | Self::InvalidQuery(_)
+            | Self::InvalidTransactionId(_)
+            | Self::App(app::Error::InvalidPause(_)) => StatusCode::BAD_REQUEST,
+            Self::App(app::Error::NotPending(_)) => StatusCode::NOT_FOUND,
+            Self::Elapsed(_) => StatusCode::GATEWAY_TIMEOUT,
+            Self::Other(err) if err.chain().any(|cause| cause.is::<CallTimeout>()) => {
+                StatusCode::GATEWAY_TIMEOUT
             }
+            Self::App(app::Error::Paused | app::Error::CannotCancel(_)) => StatusCode::CONFLICT,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -1,40 +1,309 @@
 pub mod error;
 
-use std::net::TcpListener;
-use std::sync::Arc;
-use std::time::Duration;
-
-use axum::extract::State;
-use axum::response::Response;
-use axum::routing::get;
-use axum::{middleware, Json, Router};
+use std::{
+    collections::{HashMap, HashSet},
+    net::{SocketAddr, TcpListener},
+    sync::Arc,
+    time::Duration,
+};
+
+use axum::{
+    extract::{Path, Query, State},
+    middleware,
+    response::Response,
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
 use error::Error;
-use hyper::header::CONTENT_TYPE;
-use hyper::{Body, StatusCode};
+use ethers::types::U256;
+use hyper::{header::CONTENT_TYPE, Body, StatusCode};
 use prometheus::{Encoder, TextEncoder};
+use serde::Deserialize;
+use tokio::sync::Semaphore;
 use tracing::info;
 
-use crate::app::App;
-use crate::config::ServerConfig;
-use crate::utils::shutdown::Shutdown;
-use self::data::{ToResponseCode, ServerStatusResponse};
+use self::{
+    custom_middleware::{auth_layer::ApiKeys, timeout_layer::Timeouts},
+    data::{
+        CostsResponse, FreshnessResponse, LatestRootsResponse, PropagationsResponse,
+        RootLookupResponse, ToResponseCode, TriggerResponse,
+    },
+};
+use crate::{
+    app::{App, Cancel, PauseStatus, ServiceStatus},
+    build_info::BuildInfo,
+    config::ServerConfig,
+    contracts::{abi_check::ContractAbiReport, RootLookup},
+    database::{query::DatabaseQuery, types::PropagationCursor},
+    processor::{cancel::CancelOutcome, status::SyncState},
+    utils::{secret::Redacted, shutdown::Shutdown},
+};
 
 mod custom_middleware;
 pub mod data;
-
+mod readiness;
 
 async fn fetch_service_status(
-    State(app): State<Arc<App>>
-) -> Result<(StatusCode, Json<ServerStatusResponse>), Error> {
-    let result: ServerStatusResponse  = app.get_service_status().await?;
+    State(app): State<Arc<App>>,
+) -> Result<(StatusCode, Json<ServiceStatus>), Error> {
+    let result = app.status().await?;
     Ok((result.to_response_code(), Json(result)))
 }
 
+async fn latest_roots(State(app): State<Arc<App>>) -> Result<Json<LatestRootsResponse>, Error> {
+    let roots = app.latest_roots().await?;
+    Ok(Json(roots.into()))
+}
+
+fn parse_root(root: &str) -> Result<U256, Error> {
+    let digits = root
+        .strip_prefix("0x")
+        .or_else(|| root.strip_prefix("0X"))
+        .unwrap_or(root);
+    if digits.is_empty() || digits.len() > 64 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::InvalidRoot(format!(
+            "{root:?} is not a 32 byte hex value"
+        )));
+    }
+
+    U256::from_str_radix(digits, 16).map_err(|err| Error::InvalidRoot(err.to_string()))
+}
+
+async fn lookup_root(
+    State(app): State<Arc<App>>,
+    Path(root): Path<String>,
+) -> Result<Json<RootLookupResponse>, Error> {
+    let root = parse_root(&root)?;
+    let timeout = app.config.server.root_lookup_timeout;
+    let lookup = tokio::time::timeout(timeout, app.lookup_root(root)).await??;
+    let finalized_roots = app.finalized_roots(&[root]).await?;
+    Ok(Json(
+        RootLookupResponse::new(root, lookup).with_finality(root, finalized_roots.as_ref()),
+    ))
+}
+
+/// Looks a JSON array of hex roots up at once, answering in the same order.
+/// Repeated roots are looked up once.
+async fn lookup_roots(
+    State(app): State<Arc<App>>,
+    Json(roots): Json<Vec<String>>,
+) -> Result<Json<Vec<RootLookupResponse>>, Error> {
+    let max = app.config.server.max_root_batch_size;
+    if roots.len() > max {
+        return Err(Error::BatchTooLarge {
+            size: roots.len(),
+            max,
+        });
+    }
+
+    let roots = roots
+        .iter()
+        .map(|root| parse_root(root))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut unique = roots.clone();
+    unique.sort_unstable();
+    unique.dedup();
+
+    let timeout = app.config.server.root_lookup_timeout;
+    let lookups = tokio::time::timeout(timeout, app.lookup_roots(&unique)).await??;
+    let finalized_roots = app.finalized_roots(&unique).await?;
+    let lookups: HashMap<_, _> = unique.into_iter().zip(lookups).collect();
+
+    Ok(Json(in_request_order(
+        roots,
+        &lookups,
+        finalized_roots.as_ref(),
+    )?))
+}
+
+/// The lookup of every root in `roots`, failing rather than panicking when
+/// one didn't come back.
+fn in_request_order(
+    roots: Vec<U256>,
+    lookups: &HashMap<U256, RootLookup>,
+    finalized_roots: Option<&HashSet<U256>>,
+) -> Result<Vec<RootLookupResponse>, Error> {
+    roots
+        .into_iter()
+        .map(|root| {
+            let lookup = lookups.get(&root).ok_or(Error::MissingLookup(root))?;
+            Ok(RootLookupResponse::new(root, *lookup).with_finality(root, finalized_roots))
+        })
+        .collect()
+}
+
+/// Propagations per page unless `limit` says otherwise.
+const DEFAULT_PROPAGATIONS_LIMIT: u64 = 20;
+const MAX_PROPAGATIONS_LIMIT: u64 = 100;
+
+#[derive(Debug, Deserialize)]
+struct PropagationsQuery {
+    limit:  Option<u64>,
+    /// `nextCursor` of the previous page
+    before: Option<String>,
+}
+
+async fn propagations(
+    State(app): State<Arc<App>>,
+    Query(query): Query<PropagationsQuery>,
+) -> Result<Json<PropagationsResponse>, Error> {
+    let limit = query.limit.unwrap_or(DEFAULT_PROPAGATIONS_LIMIT);
+    if !(1..=MAX_PROPAGATIONS_LIMIT).contains(&limit) {
+        return Err(Error::InvalidQuery(format!(
+            "limit must be between 1 and {MAX_PROPAGATIONS_LIMIT}"
+        )));
+    }
+
+    let before = query
+        .before
+        .map(|before| before.parse::<PropagationCursor>())
+        .transpose()
+        .map_err(|err| Error::InvalidQuery(err.to_string()))?;
+
+    let propagations = app
+        .database
+        .get_propagations(before.as_ref(), limit)
+        .await?;
+    Ok(Json(PropagationsResponse::new(propagations, limit)))
+}
+
+/// The largest `max_age` accepted, well past any useful freshness bound.
+const MAX_FRESHNESS_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+#[derive(Debug, Deserialize)]
+struct FreshnessQuery {
+    /// Seconds the Scroll root may be behind mainnet
+    max_age: u64,
+}
+
+/// `200` when the Scroll root is at most `max_age` behind mainnet, `409`
+/// with its age otherwise.
+async fn freshness(
+    State(app): State<Arc<App>>,
+    Query(query): Query<FreshnessQuery>,
+) -> Result<(StatusCode, Json<FreshnessResponse>), Error> {
+    let max_age = Duration::from_secs(query.max_age);
+    if max_age > MAX_FRESHNESS_AGE {
+        return Err(Error::InvalidQuery(format!(
+            "max_age must be at most {} seconds",
+            MAX_FRESHNESS_AGE.as_secs()
+        )));
+    }
+
+    let last_sync = app.database.get_last_sync_timestamp().await?;
+    let response = FreshnessResponse::new(last_sync, max_age);
+    Ok((response.to_response_code(), Json(response)))
+}
+
+#[derive(Debug, Deserialize)]
+struct CostsQuery {
+    /// RFC 3339, e.g. `2026-01-01T00:00:00Z`
+    since: DateTime<Utc>,
+}
+
+/// The cost of the propagations created since `since`, e.g. for a monthly
+/// report.
+async fn costs(
+    State(app): State<Arc<App>>,
+    Query(query): Query<CostsQuery>,
+) -> Result<Json<CostsResponse>, Error> {
+    let spend = app.database.get_spend_since(query.since).await?;
+    Ok(Json(CostsResponse::new(query.since, spend)))
+}
+
+async fn contracts(State(app): State<Arc<App>>) -> Json<Vec<ContractAbiReport>> {
+    Json(app.contract_abis.clone())
+}
+
+async fn version() -> Json<BuildInfo> {
+    Json(BuildInfo::get())
+}
+
+/// The config the service runs with, secrets redacted.
+async fn redacted_config(State(app): State<Arc<App>>) -> Result<Json<serde_json::Value>, Error> {
+    let config = serde_json::to_value(Redacted(&app.config)).map_err(|e| Error::Other(e.into()))?;
+    Ok(Json(config))
+}
+
+#[derive(Debug, Deserialize)]
+struct PauseRequest {
+    reason:   String,
+    /// Lifts the pause on its own once passed, e.g. `"2h"`
+    #[serde(default, with = "humantime_serde")]
+    duration: Option<Duration>,
+}
+
+async fn pause(
+    State(app): State<Arc<App>>,
+    Json(request): Json<PauseRequest>,
+) -> Result<Json<PauseStatus>, Error> {
+    Ok(Json(app.pause(request.reason, request.duration).await?))
+}
+
+async fn resume(State(app): State<Arc<App>>) -> Result<StatusCode, Error> {
+    if app.resume("resumed by operator").await? {
+        Ok(StatusCode::OK)
+    } else {
+        Ok(StatusCode::CONFLICT)
+    }
+}
+
+/// `202` when the roots differ and propagation was woken, `200` when they
+/// match.
+async fn trigger(
+    State(app): State<Arc<App>>,
+) -> Result<(StatusCode, Json<TriggerResponse>), Error> {
+    let already_synced = match app.trigger_propagation().await? {
+        SyncState::Synced => true,
+        SyncState::Unsynced(_) => false,
+    };
+    let status = if already_synced {
+        StatusCode::OK
+    } else {
+        StatusCode::ACCEPTED
+    };
+
+    Ok((status, Json(TriggerResponse { already_synced })))
+}
+
+/// `200` once the cancel or the original mined, `202` while neither has,
+/// which the monitor settles.
+async fn cancel_transaction(
+    State(app): State<Arc<App>>,
+    Path(transaction_id): Path<String>,
+) -> Result<(StatusCode, Json<Cancel>), Error> {
+    let cancel = app.cancel_propagation(transaction_id.parse()?).await?;
+    let status = if cancel.outcome == CancelOutcome::Pending {
+        StatusCode::ACCEPTED
+    } else {
+        StatusCode::OK
+    };
+
+    Ok((status, Json(cancel)))
+}
+
+/// Liveness: answering at all means the process and the runtime are alive.
 async fn health() -> Result<(), Error> {
     Ok(())
 }
 
-async fn metrics() -> Result<Response<Body>, Error> {
+/// Readiness: the database, both providers and the tasks are healthy.
+async fn ready(State(app): State<Arc<App>>) -> (StatusCode, Json<readiness::ReadinessReport>) {
+    let report = readiness::check(&app).await;
+    let status = if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(report))
+}
+
+async fn metrics(State(app): State<Arc<App>>) -> Result<Response<Body>, Error> {
+    crate::metrics::refresh();
+    app.database.record_pool_metrics();
+
     let encoder = TextEncoder::new();
 
     let metric_families = prometheus::gather();
@@ -64,7 +333,7 @@ pub async fn run(
     info!("Will listen on {}", config.address);
     let listener = TcpListener::bind(config.address)?;
 
-    bind_from_listener(app, config.serve_timeout, listener, shutdown).await?;
+    bind_from_listener(app, &config, listener, shutdown).await?;
 
     Ok(())
 }
@@ -75,7 +344,7 @@ pub async fn run(
 /// if the server fails to bind to the given address.
 pub async fn bind_from_listener(
     app: Arc<App>,
-    serve_timeout: Duration,
+    config: &ServerConfig,
     listener: TcpListener,
     shutdown: Arc<Shutdown>,
 ) -> anyhow::Result<()> {
@@ -84,27 +353,103 @@ pub async fn bind_from_listener(
         .route("/serviceStatus", get(fetch_service_status))
         // Health check, return 200 OK
         .route("/health", get(health))
+        // Dependencies reachable and tasks running, 503 otherwise
+        .route("/ready", get(ready))
         .route("/metrics", get(metrics))
+        // Crate version, git sha and build time
+        .route("/version", get(version))
+        // Effective config, secrets redacted and a key required
+        .route("/config", get(redacted_config))
+        // Event signature check against the deployed contracts
+        .route("/contracts", get(contracts))
+        // Latest mainnet and Scroll roots
+        .route("/roots/latest", get(latest_roots))
+        // Where a single root stands on mainnet and Scroll
+        .route("/roots/:root", get(lookup_root))
+        // The same for a JSON array of roots
+        .route("/roots/batch", post(lookup_roots))
+        // Recent propagations with their Scroll side, paged by cursor
+        .route("/propagations", get(propagations))
+        // Whether the Scroll root is at most `max_age` seconds behind
+        .route("/freshness", get(freshness))
+        // Gas spent by the propagations created since `since`
+        .route("/costs", get(costs))
+        // Operator pause, an `{ "reason", "duration" }` body
+        .route("/admin/pause", post(pause))
+        .route("/admin/resume", post(resume))
+        // Sync check now, propagating if the roots differ
+        .route("/admin/trigger", post(trigger))
+        // Replace a stuck propagation at its nonce, waiting for either to mine
+        .route("/transactions/:id/cancel", post(cancel_transaction))
         .layer(middleware::from_fn(
             custom_middleware::api_metrics_layer::middleware,
         ))
+        // Inside the timeout, so waiting for a turn counts against it
         .layer(middleware::from_fn_with_state(
-            serve_timeout,
+            Arc::new(Semaphore::new(config.max_concurrent_root_lookups)),
+            custom_middleware::concurrency_layer::middleware,
+        ))
+        .layer(middleware::from_fn_with_state(
+            Arc::new(Timeouts::new(config)),
             custom_middleware::timeout_layer::middleware,
         ))
-        .layer(middleware::from_fn(
+        .layer(middleware::from_fn_with_state(
+            config.max_body_size,
             custom_middleware::logging_layer::middleware,
         ))
         .layer(middleware::from_fn(
             custom_middleware::remove_auth_layer::middleware,
         ))
+        // Before the keys are removed from the request
+        .layer(middleware::from_fn_with_state(
+            Arc::new(ApiKeys::new(config)),
+            custom_middleware::auth_layer::middleware,
+        ))
+        // Outermost, so rejected requests get an id too
+        .layer(middleware::from_fn(
+            custom_middleware::request_id_layer::middleware,
+        ))
         .with_state(app.clone());
 
     let server = axum::Server::from_tcp(listener)?
-        .serve(router.into_make_service())
+        .serve(router.into_make_service_with_connect_info::<SocketAddr>())
         .with_graceful_shutdown(shutdown.await_shutdown());
 
     server.await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roots_are_parsed_as_hex() {
+        assert_eq!(parse_root("0xabc").unwrap(), U256::from(0xabc));
+        assert_eq!(parse_root("ABC").unwrap(), U256::from(0xabc));
+        assert_eq!(
+            parse_root(&format!("0x{}", "f".repeat(64))).unwrap(),
+            U256::MAX
+        );
+
+        for invalid in ["", "0x", "0xxyz", "12.5", &format!("0x{}", "f".repeat(65))] {
+            assert!(
+                matches!(parse_root(invalid), Err(Error::InvalidRoot(_))),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn a_root_the_lookup_left_out_is_an_error() {
+        let lookup = RootLookup::new(U256::from(1), true, U256::from(1), 100);
+        let lookups = HashMap::from([(U256::from(1), lookup)]);
+
+        let responses = in_request_order(vec![U256::from(1), U256::from(1)], &lookups, None);
+        assert_eq!(responses.unwrap().len(), 2);
+
+        let err = in_request_order(vec![U256::from(1), U256::from(2)], &lookups, None).unwrap_err();
+        assert!(matches!(err, Error::MissingLookup(root) if root == U256::from(2)));
+    }
+}
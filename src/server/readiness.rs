@@ -0,0 +1,177 @@
+//! The `/ready` probe: every dependency is checked concurrently, each under
+//! its own timeout so one hung provider can't hang the probe.
+
+use std::{future::Future, time::Duration};
+
+use ethers::providers::Middleware;
+use serde::Serialize;
+
+use crate::{app::App, task_monitor::health::TaskState, unsynced::Overdue};
+
+/// How long a single dependency check may take.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReadinessReport {
+    pub ready:  bool,
+    pub checks: Vec<DependencyCheck>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DependencyCheck {
+    pub name:  String,
+    pub ok:    bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl DependencyCheck {
+    fn result(name: impl Into<String>, result: anyhow::Result<()>) -> Self {
+        Self {
+            name:  name.into(),
+            ok:    result.is_ok(),
+            error: result.err().map(|err| format!("{err:#}")),
+        }
+    }
+}
+
+async fn probe<F>(name: &str, timeout: Duration, check: F) -> DependencyCheck
+where
+    F: Future<Output = anyhow::Result<()>>,
+{
+    let result = match tokio::time::timeout(timeout, check).await {
+        Ok(result) => result,
+        Err(_) => Err(anyhow::anyhow!("timed out after {timeout:?}")),
+    };
+
+    DependencyCheck::result(name, result)
+}
+
+pub async fn check(app: &App) -> ReadinessReport {
+    let (database, l1_provider, l2_provider) = futures::join!(
+        probe("database", CHECK_TIMEOUT, async {
+            sqlx::query("SELECT 1").execute(&app.database.pool).await?;
+            Ok(())
+        }),
+        probe("l1_provider", CHECK_TIMEOUT, async {
+            app.ethereum.l1_provider().get_chainid().await?;
+            Ok(())
+        }),
+        probe("l2_provider", CHECK_TIMEOUT, async {
+            app.ethereum.l2_provider().get_chainid().await?;
+            Ok(())
+        }),
+    );
+
+    let mut checks = vec![database, l1_provider, l2_provider];
+    checks.push(tasks(
+        &app.task_health.snapshot(),
+        &app.task_health.tripped(),
+    ));
+    if app.config.maintenance.unsynced_fails_ready {
+        checks.push(sync_deadline(app.unsynced.overdue()));
+    }
+
+    ReadinessReport {
+        ready: checks.iter().all(|check| check.ok),
+        checks,
+    }
+}
+
+/// Every task must be running, and none restarting too often.
+fn tasks(
+    tasks: &std::collections::BTreeMap<&'static str, TaskState>,
+    tripped: &[&'static str],
+) -> DependencyCheck {
+    if tasks.is_empty() {
+        return DependencyCheck::result("tasks", Err(anyhow::anyhow!("not started")));
+    }
+
+    let failing: Vec<String> = tasks
+        .iter()
+        .filter_map(|(name, state)| match state {
+            TaskState::Running if tripped.contains(name) => {
+                Some(format!("{name}: restarting too often"))
+            }
+            TaskState::Running => None,
+            TaskState::Failed { error } => Some(format!("{name}: {error}")),
+            TaskState::Stopped => Some(format!("{name}: stopped")),
+        })
+        .collect();
+
+    let result = if failing.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("{}", failing.join(", ")))
+    };
+
+    DependencyCheck::result("tasks", result)
+}
+
+/// The status must not have been unsynced past `unsynced_deadline`.
+fn sync_deadline(overdue: Option<Overdue>) -> DependencyCheck {
+    let result = match overdue {
+        None => Ok(()),
+        Some(overdue) => Err(anyhow::anyhow!(
+            "unsynced for {}s, since {}",
+            overdue.unsynced_for.as_secs(),
+            overdue.last_synced.to_rfc3339()
+        )),
+    };
+
+    DependencyCheck::result("sync_deadline", result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn hung_dependency_times_out() {
+        let check = probe("l1_provider", CHECK_TIMEOUT, std::future::pending()).await;
+
+        assert!(!check.ok);
+        assert_eq!(check.error.as_deref(), Some("timed out after 2s"));
+    }
+
+    #[test]
+    fn failed_tasks_are_named() {
+        let mut states = BTreeMap::new();
+        assert_eq!(tasks(&states, &[]).error.as_deref(), Some("not started"));
+
+        states.insert("scan_roots", TaskState::Running);
+        assert!(tasks(&states, &[]).ok);
+
+        states.insert("check_sync", TaskState::Failed {
+            error: "rpc down".to_string(),
+        });
+        let check = tasks(&states, &[]);
+        assert!(!check.ok);
+        assert_eq!(check.error.as_deref(), Some("check_sync: rpc down"));
+
+        // Running right now, but crashed too often recently
+        let check = tasks(&states, &["scan_roots"]);
+        assert_eq!(
+            check.error.as_deref(),
+            Some("check_sync: rpc down, scan_roots: restarting too often")
+        );
+    }
+
+    #[test]
+    fn sync_deadline_fails_while_overdue() {
+        assert!(sync_deadline(None).ok);
+
+        let last_synced = "2026-01-01T00:00:00Z".parse().unwrap();
+        let check = sync_deadline(Some(Overdue {
+            last_synced,
+            unsynced_for: Duration::from_secs(7300),
+        }));
+        assert!(!check.ok);
+        assert_eq!(
+            check.error.as_deref(),
+            Some("unsynced for 7300s, since 2026-01-01T00:00:00+00:00")
+        );
+    }
+}
@@ -0,0 +1,153 @@
+//! What the task monitor's tasks are doing, for the readiness probe.
+
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+use tokio::time::Instant;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "state")]
+pub enum TaskState {
+    Running,
+    /// The last run failed, the task is waiting to be restarted
+    Failed {
+        error: String,
+    },
+    /// The task returned or was cancelled by a shutdown
+    Stopped,
+}
+
+/// The state of every task the monitor started. Empty until the monitor
+/// starts.
+#[derive(Debug, Default)]
+pub struct TaskHealth {
+    tasks:    Mutex<BTreeMap<&'static str, TaskState>>,
+    /// Tasks restarting too often, and until when they count as unhealthy
+    restarts: Mutex<BTreeMap<&'static str, Instant>>,
+}
+
+impl TaskHealth {
+    #[must_use]
+    pub fn snapshot(&self) -> BTreeMap<&'static str, TaskState> {
+        self.tasks.lock().unwrap().clone()
+    }
+
+    /// The tasks whose circuit breaker is open, i.e. that restarted too often
+    /// recently, even if they are running right now.
+    #[must_use]
+    pub fn tripped(&self) -> Vec<&'static str> {
+        let now = Instant::now();
+        self.restarts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, until)| now < **until)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    pub(crate) fn trip(&self, name: &'static str, until: Instant) {
+        self.restarts.lock().unwrap().insert(name, until);
+    }
+
+    fn set(&self, name: &'static str, state: TaskState) {
+        self.tasks.lock().unwrap().insert(name, state);
+    }
+
+    /// Runs one attempt of a task, recording how it ends.
+    ///
+    /// # Errors
+    ///
+    /// Returns the task's own error.
+    pub async fn track<F>(self: Arc<Self>, name: &'static str, task: F) -> anyhow::Result<()>
+    where
+        F: Future<Output = anyhow::Result<()>>,
+    {
+        self.set(name, TaskState::Running);
+
+        let mut guard = Guard {
+            health: &self,
+            name,
+            armed: true,
+        };
+        let result = task.await;
+        guard.armed = false;
+
+        self.set(name, match &result {
+            Ok(()) => TaskState::Stopped,
+            Err(err) => TaskState::Failed {
+                error: err.to_string(),
+            },
+        });
+
+        result
+    }
+}
+
+/// Records a panic, or a cancellation, if the task never returned.
+struct Guard<'a> {
+    health: &'a TaskHealth,
+    name:   &'static str,
+    armed:  bool,
+}
+
+impl Drop for Guard<'_> {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+
+        let state = if std::thread::panicking() {
+            TaskState::Failed {
+                error: "panicked".to_string(),
+            }
+        } else {
+            TaskState::Stopped
+        };
+        self.health.set(self.name, state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn records_how_each_attempt_ends() {
+        let health = Arc::new(TaskHealth::default());
+
+        let failed = health
+            .clone()
+            .track("failing", async { Err(anyhow::anyhow!("rpc down")) })
+            .await;
+        assert!(failed.is_err());
+
+        let panicked = std::panic::AssertUnwindSafe(
+            health.clone().track("panicking", async { panic!("boom") }),
+        )
+        .catch_unwind()
+        .await;
+        assert!(panicked.is_err());
+
+        let mut running = Box::pin(health.clone().track("running", std::future::pending()));
+        assert!(futures::poll!(running.as_mut()).is_pending());
+        assert_eq!(health.snapshot()["running"], TaskState::Running);
+        drop(running);
+
+        let snapshot = health.snapshot();
+        assert_eq!(snapshot["failing"], TaskState::Failed {
+            error: "rpc down".to_string(),
+        });
+        assert_eq!(snapshot["panicking"], TaskState::Failed {
+            error: "panicked".to_string(),
+        });
+        // Dropping the pending future is a cancellation
+        assert_eq!(snapshot["running"], TaskState::Stopped);
+    }
+}
@@ -1,36 +1,53 @@
-use std::str::FromStr;
-use std::sync::Arc;
-use std::time::Duration;
-
-use once_cell::sync::Lazy;
-use prometheus::{ register_gauge, Gauge};
-
-use tokio::sync::{broadcast, mpsc, Mutex, RwLock, Notify};
-use tokio::task::JoinHandle;
-use tracing::{info, instrument, warn};
-use crate::database::query::DatabaseQuery;
-use crate::database::types::TxStatus;
-use crate::database::Database;
-use crate::processor::status::BridgeStatus;
-use crate::utils::shutdown::Shutdown;
-use crate::app::App;
+use std::{future::Future, sync::Arc, time::Duration};
 
+use crate::{
+    app::App,
+    database,
+    database::{
+        store::StatusStore,
+        types::{RootSource, TxStatus},
+    },
+    metrics::SYNCED_STATE,
+    processor::{
+        self,
+        status::{BridgeStatus, SyncState},
+        Processor,
+    },
+    utils::shutdown::Shutdown,
+};
+use tokio::{
+    sync::{broadcast, RwLock},
+    task::JoinHandle,
+};
+use tracing::{error, info, instrument, warn};
+
+pub mod health;
+mod supervisor;
+mod sync_context;
 pub mod tasks;
+pub mod tx_queue;
+pub mod wake;
+
+use self::{supervisor::Supervisor, sync_context::SyncContext, tx_queue::TxQueue};
 
 const PROPAGATE_ROOT_BACKOFF: Duration = Duration::from_secs(5);
 const CHECK_SYNC_STATE_BACKOFF: Duration = Duration::from_secs(5);
 const MONITOR_TXNS_BACKOFF: Duration = Duration::from_secs(5);
 const FINALIZE_TXNS_BACKOFF: Duration = Duration::from_secs(5);
+const MAINTENANCE_BACKOFF: Duration = Duration::from_secs(5);
+const RETENTION_BACKOFF: Duration = Duration::from_secs(5);
+const ROOT_FINALITY_BACKOFF: Duration = Duration::from_secs(5);
+const SCAN_ROOTS_BACKOFF: Duration = Duration::from_secs(5);
+const SUBSCRIBE_ROOTS_BACKOFF: Duration = Duration::from_secs(5);
+const WATCH_PAUSE_BACKOFF: Duration = Duration::from_secs(5);
+const WALLET_BALANCE_BACKOFF: Duration = Duration::from_secs(5);
+const LEADER_ELECTION_BACKOFF: Duration = Duration::from_secs(5);
 
 struct RunningInstance {
     handles:         Vec<JoinHandle<()>>,
     shutdown_sender: broadcast::Sender<()>,
 }
 
-static SYNCED_STATE: Lazy<Gauge> = Lazy::new(|| {
-    register_gauge!("synced_state", "current scroll bridge sync status").unwrap()
-});
-
 impl RunningInstance {
     async fn shutdown(self) -> anyhow::Result<()> {
         info!("Sending a shutdown signal to the service.");
@@ -49,6 +66,27 @@ impl RunningInstance {
     }
 }
 
+/// The running tasks, as returned by [`App::spawn_tasks`].
+pub struct TaskHandles {
+    monitor: TaskMonitor,
+}
+
+impl TaskHandles {
+    pub(crate) const fn new(monitor: TaskMonitor) -> Self {
+        Self { monitor }
+    }
+
+    /// Stops the tasks and waits for them to finish. Call
+    /// [`App::shutdown`] afterwards to write what they buffered.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a task panicked.
+    pub async fn shutdown(self) -> anyhow::Result<()> {
+        self.monitor.shutdown().await
+    }
+}
+
 /// A worker that commits identities to the blockchain.
 ///
 /// This uses the database to keep track of identities that need to be
@@ -85,78 +123,178 @@ impl TaskMonitor {
         // but for symmetry's sake we create it for every task with `.subscribe()`
         let (shutdown_sender, _) = broadcast::channel(1);
 
-        let (monitored_txs_sender, monitored_txs_receiver) =
-            mpsc::channel(self.app.config.app.monitored_txs_capacity);
+        let monitored_txs = Arc::new(TxQueue::new(
+            self.app.config.app.propagation.monitored_txs_capacity,
+        ));
 
-        let monitored_txs_sender = Arc::new(monitored_txs_sender);
-        let monitored_txs_receiver = Arc::new(Mutex::new(monitored_txs_receiver));
+        let mut handles = Vec::new();
 
+        // Shared with the app so a propagation can be triggered from outside
+        let base_wake_up_notify = self.app.wake.notify();
 
-        let mut handles = Vec::new();
+        // Decide which replica propagates and monitors transactions
+        if self.app.config.leadership.enabled {
+            let app = self.app.clone();
+            let wake_up_notify = base_wake_up_notify.clone();
+            let elect = move || tasks::leader_election::elect(app.clone(), wake_up_notify.clone());
+            let leader_election_handle = self.spawn_monitored(
+                "leader_election",
+                elect,
+                shutdown_sender.clone(),
+                LEADER_ELECTION_BACKOFF,
+            );
+            handles.push(leader_election_handle);
+        }
 
-        let base_wake_up_notify = Arc::new(Notify::new());
-        
         // Propagate Root
-        let app = self.app.clone();
-        let wake_up_notify = base_wake_up_notify.clone();
-        let propagate_root = move || {
-            tasks::propagate_root::propagate_root(
-                app.clone(),
-                monitored_txs_sender.clone(),
-                wake_up_notify.clone()
-            )
-        };
-        let propagate_root_handle = crate::utils::spawn_monitored_with_backoff(
+        let context = SyncContext::new(&self.app);
+        let queue = monitored_txs.clone();
+        let propagate_root =
+            move || tasks::propagate_root::propagate_root(context.clone(), queue.clone());
+        let propagate_root_handle = self.spawn_monitored(
+            "propagate_root",
             propagate_root,
             shutdown_sender.clone(),
             PROPAGATE_ROOT_BACKOFF,
-            self.shutdown.clone(),
         );
         handles.push(propagate_root_handle);
-      
+
         // Check Status
-        let app = self.app.clone();
-        let wake_up_notify = base_wake_up_notify.clone();
-        let check_sync_state = move || {
-            tasks::check_sync::check_sync(
-                app.clone(),
-                wake_up_notify.clone()
-            )
-        };
+        let context = SyncContext::new(&self.app);
+        let check_sync_state = move || tasks::check_sync::check_sync(context.clone());
 
-        let check_sync_state_handle = crate::utils::spawn_monitored_with_backoff(
+        let check_sync_state_handle = self.spawn_monitored(
+            "check_sync",
             check_sync_state,
             shutdown_sender.clone(),
             CHECK_SYNC_STATE_BACKOFF,
-            self.shutdown.clone(),
         );
         handles.push(check_sync_state_handle);
 
-        
         // Finalize transactions
         let app = self.app.clone();
-        let finalize_txs =
-            move || tasks::finalize_txs::finalize_txs(app.clone());
-        let finalize_txs_handle = crate::utils::spawn_monitored_with_backoff(
+        let finalize_txs = move || tasks::finalize_txs::finalize_txs(app.clone());
+        let finalize_txs_handle = self.spawn_leading(
+            "finalize_txs",
             finalize_txs,
             shutdown_sender.clone(),
             FINALIZE_TXNS_BACKOFF,
-            self.shutdown.clone(),
         );
         handles.push(finalize_txs_handle);
 
         // Monitor transactions
         let app = self.app.clone();
         let monitor_txs =
-            move || tasks::monitor_txs::monitor_txs(app.clone(), monitored_txs_receiver.clone());
-        let monitor_txs_handle = crate::utils::spawn_monitored_with_backoff(
+            move || tasks::monitor_txs::monitor_txs(app.clone(), monitored_txs.clone());
+        let monitor_txs_handle = self.spawn_leading(
+            "monitor_txs",
             monitor_txs,
             shutdown_sender.clone(),
             MONITOR_TXNS_BACKOFF,
-            self.shutdown.clone(),
         );
         handles.push(monitor_txs_handle);
 
+        // Resolve stuck transactions
+        let app = self.app.clone();
+        let maintenance = move || tasks::maintenance::maintenance(app.clone());
+        let maintenance_handle = self.spawn_leading(
+            "maintenance",
+            maintenance,
+            shutdown_sender.clone(),
+            MAINTENANCE_BACKOFF,
+        );
+        handles.push(maintenance_handle);
+
+        // Prune old roots and transactions
+        if self.app.config.retention.enabled {
+            let app = self.app.clone();
+            let retention = move || tasks::retention::retention(app.clone());
+            let retention_handle = self.spawn_monitored(
+                "retention",
+                retention,
+                shutdown_sender.clone(),
+                RETENTION_BACKOFF,
+            );
+            handles.push(retention_handle);
+        } else {
+            info!("Retention is disabled, old rows are never pruned");
+        }
+
+        // Follow Scroll's finalized block
+        if self.app.config.finality.enabled {
+            let app = self.app.clone();
+            let root_finality = move || tasks::root_finality::root_finality(app.clone());
+            let root_finality_handle = self.spawn_leading(
+                "root_finality",
+                root_finality,
+                shutdown_sender.clone(),
+                ROOT_FINALITY_BACKOFF,
+            );
+            handles.push(root_finality_handle);
+        }
+
+        // Scan roots
+        let app = self.app.clone();
+        let scan_roots = move || tasks::scan_roots::scan_roots(app.clone());
+        let scan_roots_handle = self.spawn_monitored(
+            "scan_roots",
+            scan_roots,
+            shutdown_sender.clone(),
+            SCAN_ROOTS_BACKOFF,
+        );
+        handles.push(scan_roots_handle);
+
+        // Follow the subscribed chains, which the scans skip
+        let subscriptions = self
+            .app
+            .config
+            .providers
+            .as_ref()
+            .map(processor::subscriptions)
+            .unwrap_or_default();
+        for (source, url) in subscriptions {
+            let name = match source {
+                RootSource::Bridge => "subscribe_bridge_roots",
+                RootSource::Scroll => "subscribe_scroll_roots",
+                RootSource::WorldId => "subscribe_world_id_roots",
+            };
+            let app = self.app.clone();
+            let subscribe_roots =
+                move || tasks::subscribe_roots::subscribe_roots(app.clone(), source, url.clone());
+            let subscribe_roots_handle = self.spawn_monitored(
+                name,
+                subscribe_roots,
+                shutdown_sender.clone(),
+                SUBSCRIBE_ROOTS_BACKOFF,
+            );
+            handles.push(subscribe_roots_handle);
+        }
+
+        // Expire pauses and remind about open-ended ones
+        let app = self.app.clone();
+        let wake_up_notify = base_wake_up_notify.clone();
+        let watch_pause = move || tasks::pause::watch_pause(app.clone(), wake_up_notify.clone());
+        let watch_pause_handle = self.spawn_monitored(
+            "watch_pause",
+            watch_pause,
+            shutdown_sender.clone(),
+            WATCH_PAUSE_BACKOFF,
+        );
+        handles.push(watch_pause_handle);
+
+        // Stop propagating while the wallet can't pay for it
+        let app = self.app.clone();
+        let wake_up_notify = base_wake_up_notify.clone();
+        let wallet_balance =
+            move || tasks::wallet_balance::wallet_balance(app.clone(), wake_up_notify.clone());
+        let wallet_balance_handle = self.spawn_monitored(
+            "wallet_balance",
+            wallet_balance,
+            shutdown_sender.clone(),
+            WALLET_BALANCE_BACKOFF,
+        );
+        handles.push(wallet_balance_handle);
+
         // Create the instance
         *instance = Some(RunningInstance {
             handles,
@@ -164,21 +302,127 @@ impl TaskMonitor {
         });
     }
 
-    async fn check_synced_state(app: &Arc<App>) -> anyhow::Result<bool> {
-        let state = app.bridge_processor.check_sync_state().await?;
-        let gauge_value = if state { 1.0 } else { 0.0 };
+    /// Spawns a task that is restarted after `backoff` when it fails or
+    /// panics, and whose state is reported in `app.task_health`.
+    fn spawn_monitored<S, F>(
+        &self,
+        name: &'static str,
+        future_spawner: S,
+        shutdown_sender: broadcast::Sender<()>,
+        backoff: Duration,
+    ) -> JoinHandle<()>
+    where
+        F: Future<Output = anyhow::Result<()>> + Send + 'static,
+        S: Fn() -> F + Send + Sync + 'static,
+    {
+        let supervisor = Supervisor::new(
+            self.app.config.supervisor.clone(),
+            self.app.task_health.clone(),
+            self.shutdown.clone(),
+            self.app.notifier.clone(),
+        )
+        .with_status_store(self.app.database.clone());
+
+        supervisor.spawn(name, future_spawner, shutdown_sender, backoff)
+    }
+
+    /// Like [`Self::spawn_monitored`], for a task that only runs while this
+    /// replica leads.
+    fn spawn_leading<S, F>(
+        &self,
+        name: &'static str,
+        future_spawner: S,
+        shutdown_sender: broadcast::Sender<()>,
+        backoff: Duration,
+    ) -> JoinHandle<()>
+    where
+        F: Future<Output = anyhow::Result<()>> + Send + 'static,
+        S: Fn() -> F + Send + Sync + 'static,
+    {
+        let leadership = self.app.leadership.clone();
+        let future_spawner = Arc::new(future_spawner);
+        let leading = move || {
+            let future_spawner = future_spawner.clone();
+            tasks::leader_election::while_leading(leadership.clone(), name, move || {
+                (*future_spawner)()
+            })
+        };
+
+        self.spawn_monitored(name, leading, shutdown_sender, backoff)
+    }
+
+    async fn check_synced_state(processor: &dyn Processor) -> anyhow::Result<SyncState> {
+        let state = processor.sync_state().await?;
+        let gauge_value = if state.is_synced() { 1.0 } else { 0.0 };
         SYNCED_STATE.set(gauge_value);
         Ok(state)
     }
 
-    async fn check_db_state(database: &Database, status_check: BridgeStatus) -> anyhow::Result<bool> {
-        let status = database.get_db_status().await?.unwrap_or_else(|| "unsynced".to_string());
-        let bridge_status = BridgeStatus::from_str(&status).unwrap_or(BridgeStatus::Unsynced);
-        Ok(bridge_status == status_check)
+    async fn db_state(store: &dyn StatusStore) -> anyhow::Result<BridgeStatus> {
+        Ok(store
+            .get_db_status()
+            .await?
+            .unwrap_or(BridgeStatus::Unsynced))
+    }
+
+    /// Repairs the status row a task failed on with `err`: a missing row is
+    /// created again, an unknown status is overwritten. Either way the
+    /// status starts over as unsynced, which the sync check corrects on its
+    /// next pass. Returns whether the row was repaired, so the task can be
+    /// restarted against it.
+    pub(crate) async fn recover_status(store: &dyn StatusStore, err: &anyhow::Error) -> bool {
+        let Some(cause) = err
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<database::Error>())
+        else {
+            return false;
+        };
+
+        let repaired = match cause {
+            database::Error::StatusRowMissing => store.initialize_server().await.map(|_| ()),
+            database::Error::UnknownStatus { .. } => store.mark_status_as_unsynced(None).await,
+            _ => return false,
+        };
+
+        match repaired {
+            Ok(()) => {
+                warn!(%cause, "Repaired the service status, starting over as unsynced");
+                true
+            }
+            Err(err) => {
+                error!(?err, %cause, "Failed to repair the service status");
+                false
+            }
+        }
+    }
+
+    /// Whether a status change was applied. Another task moving the status
+    /// first is not a failure: the caller re-reads it on its next pass.
+    pub(crate) fn status_changed(
+        result: Result<(), database::Error>,
+    ) -> Result<bool, database::Error> {
+        match result {
+            Ok(()) => Ok(true),
+            Err(database::Error::InvalidStatusTransition { from, to }) => {
+                warn!(?from, ?to, "Status changed underneath, re-reading it");
+                Ok(false)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn check_db_state(
+        store: &dyn StatusStore,
+        status_check: BridgeStatus,
+    ) -> anyhow::Result<bool> {
+        Ok(Self::db_state(store).await? == status_check)
     }
 
-    async fn check_last_transaction_status(database: &Database, status_check: TxStatus) -> anyhow::Result<bool> {
-        let status = database.get_last_transaction_status().await?;
+    async fn check_last_transaction_status(
+        store: &dyn StatusStore,
+        status_check: TxStatus,
+    ) -> anyhow::Result<bool> {
+        let status = store.get_last_transaction_status().await?;
         // let tx_status = TxStatus::from_str(&status).unwrap_or(TxStatus::Pending);
         Ok(status == Some(status_check))
     }
@@ -0,0 +1,413 @@
+//! Restarts the task monitor's tasks when they fail or panic.
+//!
+//! Every attempt runs as its own tokio task, so a panic surfaces as a
+//! `JoinError` instead of unwinding through its siblings. A task that keeps
+//! crashing trips a circuit breaker, which fails `/ready` until the task has
+//! been stable for a whole window. So does a task failing with a database
+//! error that restarting won't fix, on its first failure, unless it was a
+//! missing or corrupted status row the supervisor could repair.
+
+use std::{collections::VecDeque, future::Future, sync::Arc, time::Duration};
+
+use once_cell::sync::Lazy;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use tokio::{
+    sync::broadcast,
+    task::JoinHandle,
+    time::{self, Instant},
+};
+use tracing::{error, info};
+
+use crate::{
+    config::SupervisorConfig,
+    database,
+    database::store::StatusStore,
+    exit,
+    notifier::{Event, EventKind, Notifier},
+    task_monitor::{health::TaskHealth, TaskMonitor},
+    utils::shutdown::Shutdown,
+};
+
+static RESTARTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "task_restarts",
+        "task monitor tasks restarted after failing or panicking",
+        &["task"]
+    )
+    .unwrap()
+});
+
+/// Opens once more than `max_restarts` restarts happened within `window`.
+#[derive(Debug)]
+struct CircuitBreaker {
+    max_restarts: usize,
+    window:       Duration,
+    restarts:     VecDeque<Instant>,
+}
+
+impl CircuitBreaker {
+    fn new(config: &SupervisorConfig) -> Self {
+        Self {
+            max_restarts: config.max_restarts,
+            window:       config.restart_window,
+            restarts:     VecDeque::new(),
+        }
+    }
+
+    /// Records a restart, returning until when the breaker is open if this
+    /// restart tripped it.
+    fn record(&mut self, now: Instant) -> Option<Instant> {
+        while self
+            .restarts
+            .front()
+            .is_some_and(|restart| now.duration_since(*restart) >= self.window)
+        {
+            self.restarts.pop_front();
+        }
+        self.restarts.push_back(now);
+
+        (self.restarts.len() > self.max_restarts).then(|| now + self.window)
+    }
+}
+
+/// Whether restarting may get a task past `err`. Only database errors are
+/// known not to be, anything else is assumed to be transient.
+fn is_retryable(err: &anyhow::Error) -> bool {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<database::Error>())
+        .map_or(true, database::Error::is_retryable)
+}
+
+#[derive(Clone)]
+pub struct Supervisor {
+    config:       SupervisorConfig,
+    health:       Arc<TaskHealth>,
+    shutdown:     Arc<Shutdown>,
+    notifier:     Arc<Notifier>,
+    /// Where a missing or corrupted status row is repaired
+    status_store: Option<Arc<dyn StatusStore>>,
+}
+
+impl Supervisor {
+    pub const fn new(
+        config: SupervisorConfig,
+        health: Arc<TaskHealth>,
+        shutdown: Arc<Shutdown>,
+        notifier: Arc<Notifier>,
+    ) -> Self {
+        Self {
+            config,
+            health,
+            shutdown,
+            notifier,
+            status_store: None,
+        }
+    }
+
+    /// Repairs the status row in `store` when a task fails on it, see
+    /// [`TaskMonitor::recover_status`].
+    #[must_use]
+    pub fn with_status_store(mut self, store: Arc<dyn StatusStore>) -> Self {
+        self.status_store = Some(store);
+        self
+    }
+
+    /// Runs `future_spawner`'s task until it returns `Ok` or a shutdown is
+    /// signalled, restarting it `backoff` after every failure.
+    pub fn spawn<S, F>(
+        &self,
+        name: &'static str,
+        future_spawner: S,
+        shutdown_sender: broadcast::Sender<()>,
+        backoff: Duration,
+    ) -> JoinHandle<()>
+    where
+        F: Future<Output = anyhow::Result<()>> + Send + 'static,
+        S: Fn() -> F + Send + Sync + 'static,
+    {
+        let Self {
+            config,
+            health,
+            shutdown,
+            notifier,
+            status_store,
+        } = self.clone();
+
+        tokio::spawn(async move {
+            let mut breaker = CircuitBreaker::new(&config);
+
+            loop {
+                let mut shutdown_receiver = shutdown_sender.subscribe();
+                let attempt = exit::supervised(name, health.clone().track(name, future_spawner()));
+                let mut attempt = tokio::spawn(attempt);
+
+                let result = tokio::select! {
+                    result = &mut attempt => result,
+                    _ = shutdown_receiver.recv() => {
+                        info!(task = name, "Woke up by shutdown signal, exiting.");
+                        attempt.abort();
+                        _ = attempt.await;
+                        return;
+                    }
+                    () = shutdown.await_shutdown() => {
+                        info!(task = name, "Shutting down, exiting.");
+                        attempt.abort();
+                        _ = attempt.await;
+                        return;
+                    }
+                };
+
+                let (retryable, failure) = match result {
+                    Ok(Ok(())) => return,
+                    Ok(Err(err)) => {
+                        error!(task = name, ?err, "Task failed");
+                        let repaired = match &status_store {
+                            Some(store) => TaskMonitor::recover_status(&**store, &err).await,
+                            None => false,
+                        };
+                        (repaired || is_retryable(&err), format!("{err:#}"))
+                    }
+                    Err(err) if err.is_panic() => {
+                        error!(task = name, %err, "Task panicked");
+                        (true, err.to_string())
+                    }
+                    // Cancelled outside of a shutdown, nothing left to supervise
+                    Err(_) => return,
+                };
+
+                if shutdown.is_shutting_down() {
+                    return;
+                }
+
+                notifier.notify(Event::new(
+                    EventKind::TaskRestarted,
+                    format!("Task {name} is restarted after it failed: {failure}"),
+                ));
+
+                RESTARTS.with_label_values(&[name]).inc();
+                if let Some(until) = breaker.record(Instant::now()) {
+                    error!(
+                        task = name,
+                        max_restarts = config.max_restarts,
+                        window = ?config.restart_window,
+                        "Task keeps crashing, reporting the service as not ready"
+                    );
+                    health.trip(name, until);
+                } else if !retryable {
+                    error!(
+                        task = name,
+                        "Task failed with a database error restarting won't fix, reporting the \
+                         service as not ready"
+                    );
+                    health.trip(name, Instant::now() + config.restart_window);
+                }
+
+                tokio::select! {
+                    () = time::sleep(backoff) => {}
+                    () = shutdown.await_shutdown() => return,
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::{config::NotificationsConfig, task_monitor::health::TaskState};
+
+    const BACKOFF: Duration = Duration::from_secs(1);
+
+    fn supervisor(max_restarts: usize) -> (Supervisor, Arc<TaskHealth>, Arc<Shutdown>) {
+        let health = Arc::new(TaskHealth::default());
+        let shutdown = Arc::new(Shutdown::new());
+        let config = SupervisorConfig {
+            max_restarts,
+            restart_window: Duration::from_secs(60),
+        };
+        let notifier = Arc::new(Notifier::new(
+            NotificationsConfig::default(),
+            "test".to_string(),
+        ));
+
+        (
+            Supervisor::new(config, health.clone(), shutdown.clone(), notifier),
+            health,
+            shutdown,
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failing_task_is_restarted_until_it_succeeds() -> anyhow::Result<()> {
+        let (supervisor, health, _shutdown) = supervisor(5);
+        let (shutdown_sender, _) = broadcast::channel(1);
+        let attempts = Arc::new(AtomicUsize::new(0));
+
+        let handle = supervisor.spawn(
+            "fails_twice",
+            {
+                let attempts = attempts.clone();
+                move || {
+                    let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+                    async move {
+                        match attempt {
+                            0 => Err(anyhow::anyhow!("rpc down")),
+                            1 => panic!("boom"),
+                            _ => Ok(()),
+                        }
+                    }
+                }
+            },
+            shutdown_sender,
+            BACKOFF,
+        );
+
+        time::timeout(BACKOFF * 5, handle).await??;
+
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(RESTARTS.with_label_values(&["fails_twice"]).get(), 2);
+        assert_eq!(health.snapshot()["fails_twice"], TaskState::Stopped);
+        assert!(health.tripped().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn crash_loop_trips_the_breaker_for_a_window() {
+        let (supervisor, health, shutdown) = supervisor(2);
+        let (shutdown_sender, _) = broadcast::channel(1);
+
+        let handle = supervisor.spawn(
+            "crash_loop",
+            || async { Err(anyhow::anyhow!("rpc down")) },
+            shutdown_sender,
+            BACKOFF,
+        );
+
+        // Two restarts are tolerated, the third trips the breaker
+        time::sleep(BACKOFF + BACKOFF / 2).await;
+        assert!(health.tripped().is_empty());
+        time::sleep(BACKOFF).await;
+        assert_eq!(health.tripped(), vec!["crash_loop"]);
+
+        shutdown.shutdown();
+        handle.await.unwrap();
+
+        // Stable for a whole window closes it again
+        time::sleep(Duration::from_secs(61)).await;
+        assert!(health.tripped().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn non_retryable_database_error_trips_the_breaker_at_once() {
+        let (supervisor, health, shutdown) = supervisor(5);
+        let (shutdown_sender, _) = broadcast::channel(1);
+
+        let handle = supervisor.spawn(
+            "status_row_missing",
+            || async {
+                Err(anyhow::Error::from(database::Error::StatusRowMissing).context("sync check"))
+            },
+            shutdown_sender,
+            BACKOFF,
+        );
+
+        time::sleep(BACKOFF / 2).await;
+        assert_eq!(health.tripped(), vec!["status_row_missing"]);
+
+        // The task is still restarted, in case the row shows up
+        time::sleep(BACKOFF).await;
+        assert!(RESTARTS.with_label_values(&["status_row_missing"]).get() >= 2);
+
+        shutdown.shutdown();
+        handle.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn running_task_stops_on_shutdown_signal() -> anyhow::Result<()> {
+        let (supervisor, health, shutdown) = supervisor(5);
+        let (shutdown_sender, _) = broadcast::channel(1);
+
+        let handle = supervisor.spawn("forever", std::future::pending, shutdown_sender, BACKOFF);
+        while health.snapshot().get("forever") != Some(&TaskState::Running) {
+            tokio::task::yield_now().await;
+        }
+
+        shutdown.shutdown();
+        time::timeout(BACKOFF, handle).await??;
+        assert_eq!(health.snapshot()["forever"], TaskState::Stopped);
+
+        Ok(())
+    }
+
+    /// Re-runs the test in a child process, which installs the binary's panic
+    /// hook. The hook must leave a panicking task to its supervisor.
+    const PANIC_HOOK_CHILD_ENV: &str = "SCROLL_SERVICE_SUPERVISOR_PANIC_CHILD";
+
+    #[test]
+    fn a_panicking_task_is_restarted_with_the_panic_hook_installed() {
+        if std::env::var_os(PANIC_HOOK_CHILD_ENV).is_some() {
+            exit::install_panic_hook("test-version");
+
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .start_paused(true)
+                .build()
+                .unwrap();
+            runtime.block_on(async {
+                let (supervisor, health, _shutdown) = supervisor(5);
+                let (shutdown_sender, _) = broadcast::channel(1);
+                let attempts = Arc::new(AtomicUsize::new(0));
+
+                let handle = supervisor.spawn(
+                    "panics_once",
+                    {
+                        let attempts = attempts.clone();
+                        move || {
+                            let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+                            async move {
+                                assert!(attempt > 0, "boom");
+                                Ok(())
+                            }
+                        }
+                    },
+                    shutdown_sender,
+                    BACKOFF,
+                );
+
+                time::timeout(BACKOFF * 5, handle).await.unwrap().unwrap();
+                assert_eq!(attempts.load(Ordering::SeqCst), 2);
+                assert_eq!(RESTARTS.with_label_values(&["panics_once"]).get(), 1);
+                assert_eq!(health.snapshot()["panics_once"], TaskState::Stopped);
+            });
+            return;
+        }
+
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([
+                "a_panicking_task_is_restarted_with_the_panic_hook_installed",
+                "--nocapture",
+                "--test-threads=1",
+            ])
+            .env(PANIC_HOOK_CHILD_ENV, "1")
+            .output()
+            .unwrap();
+
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(
+            output.status.success(),
+            "{:?}: {stderr}",
+            output.status.code()
+        );
+
+        let line = stderr
+            .lines()
+            .find(|line| line.starts_with('{'))
+            .expect("missing panic report");
+        let report: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(report["message"], "boom");
+        assert_eq!(report["task"], "panics_once");
+    }
+}
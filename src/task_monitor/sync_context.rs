@@ -0,0 +1,701 @@
+//! What the sync check and the propagation task run against. The service
+//! builds it from the [`App`], tests from an in-memory store and processor,
+//! so both tasks can be driven on a paused clock.
+
+use std::{sync::Arc, time::Duration};
+
+use tokio::sync::Notify;
+
+use crate::{
+    app::App,
+    balance::BalanceState,
+    config::PropagationConfig,
+    database::store::StatusStore,
+    leadership::Leadership,
+    notifier::Notifier,
+    pause::PauseControl,
+    processor::Processor,
+    task_monitor::{tasks::check_sync::CHECK_INTERVAL, wake::PropagationWake},
+    unsynced::UnsyncedState,
+};
+
+#[derive(Clone)]
+pub struct SyncContext {
+    pub store:             Arc<dyn StatusStore>,
+    pub processor:         Arc<dyn Processor>,
+    pub notifier:          Arc<Notifier>,
+    pub wake:              Arc<PropagationWake>,
+    /// Wakes the sync check ahead of `check_interval`
+    pub sync_check:        Arc<Notify>,
+    pub pause:             Arc<PauseControl>,
+    pub balance:           Arc<BalanceState>,
+    pub unsynced:          Arc<UnsyncedState>,
+    pub leadership:        Arc<Leadership>,
+    /// How often the sync check runs
+    pub check_interval:    Duration,
+    /// `unsynced_deadline` under `[maintenance]`
+    pub unsynced_deadline: Duration,
+    pub propagation:       PropagationConfig,
+}
+
+impl SyncContext {
+    #[must_use]
+    pub fn new(app: &App) -> Self {
+        Self {
+            store:             app.database.clone(),
+            processor:         app.bridge_processor.clone(),
+            notifier:          app.notifier.clone(),
+            wake:              app.wake.clone(),
+            sync_check:        app.sync_check.clone(),
+            pause:             app.pause.clone(),
+            balance:           app.balance.clone(),
+            unsynced:          app.unsynced.clone(),
+            leadership:        app.leadership.clone(),
+            check_interval:    CHECK_INTERVAL,
+            unsynced_deadline: app.config.maintenance.unsynced_deadline,
+            propagation:       app.config.app.propagation.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+    };
+
+    use async_trait::async_trait;
+    use chrono::{DateTime, Utc};
+    use ethers::types::{Filter, Log, H256, U256};
+    use tokio::task::JoinHandle;
+
+    use super::*;
+    use crate::{
+        config::NotificationsConfig,
+        contracts::{RootLookup, SimulationError},
+        database::{
+            types::{
+                PendingPropagation, PropagationTimes, RootSource, ServerStatus, Spend, TxStatus,
+            },
+            Error,
+        },
+        ethereum::{PendingTransaction, TransactionState},
+        processor::{
+            status::{BridgeStatus, SyncState, UnsyncedReason},
+            LatestRoots, Propagation, ScannersStatus,
+        },
+        task_monitor::{
+            tasks::{check_sync, propagate_root},
+            tx_queue::TxQueue,
+            TaskMonitor,
+        },
+        utils::TransactionId,
+    };
+
+    #[derive(Debug)]
+    struct FakeStore {
+        /// `None` once the row is deleted
+        status:         Mutex<Option<ServerStatus>>,
+        /// Written over the row's status by hand, which reads fail on
+        unknown_status: Mutex<Option<String>>,
+        /// Oldest first
+        transactions:   Mutex<Vec<(TransactionId, U256, TxStatus)>>,
+        /// Spent over the trailing 24 hours
+        spent:          Mutex<U256>,
+    }
+
+    impl FakeStore {
+        fn new(status: BridgeStatus) -> Self {
+            Self {
+                status:         Mutex::new(Some(ServerStatus {
+                    status,
+                    reason: None,
+                    last_synced: Some(Utc::now()),
+                })),
+                unknown_status: Mutex::default(),
+                transactions:   Mutex::default(),
+                spent:          Mutex::default(),
+            }
+        }
+
+        fn status(&self) -> BridgeStatus {
+            self.status.lock().unwrap().as_ref().unwrap().status
+        }
+
+        fn transactions(&self) -> Vec<(String, TxStatus)> {
+            self.transactions
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(id, _, status)| (id.to_string(), *status))
+                .collect()
+        }
+
+        /// Follows the transitions `service_status` allows.
+        fn transition(
+            &self,
+            to: BridgeStatus,
+            reason: Option<UnsyncedReason>,
+        ) -> Result<(), Error> {
+            let mut row = self.status.lock().unwrap();
+            let Some(status) = row.as_mut() else {
+                return Err(Error::StatusRowMissing);
+            };
+            // Unknown statuses count as unsynced, as in `update_server_status`
+            let unknown = self.unknown_status.lock().unwrap().is_some();
+            let from = if unknown {
+                BridgeStatus::Unsynced
+            } else {
+                status.status
+            };
+            if !from.can_transition_to(to) {
+                return Err(Error::InvalidStatusTransition { from, to });
+            }
+
+            if to == BridgeStatus::Synced || from == BridgeStatus::Synced {
+                status.last_synced = Some(Utc::now());
+            }
+            status.status = to;
+            status.reason = reason.map(|reason| <&str>::from(reason).to_string());
+            *self.unknown_status.lock().unwrap() = None;
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl StatusStore for FakeStore {
+        async fn initialize_server(&self) -> Result<bool, Error> {
+            let mut status = self.status.lock().unwrap();
+            if status.is_some() {
+                return Ok(false);
+            }
+            *status = Some(ServerStatus {
+                status:      BridgeStatus::Unsynced,
+                reason:      None,
+                last_synced: Some(Utc::now()),
+            });
+            Ok(true)
+        }
+
+        async fn get_service_status(&self) -> Result<Option<ServerStatus>, Error> {
+            if let Some(status) = self.unknown_status.lock().unwrap().clone() {
+                return Err(Error::UnknownStatus { status });
+            }
+            Ok(self.status.lock().unwrap().clone())
+        }
+
+        async fn get_db_status(&self) -> Result<Option<BridgeStatus>, Error> {
+            Ok(self.get_service_status().await?.map(|status| status.status))
+        }
+
+        async fn mark_status_as_unsynced(
+            &self,
+            reason: Option<UnsyncedReason>,
+        ) -> Result<(), Error> {
+            self.transition(BridgeStatus::Unsynced, reason)
+        }
+
+        async fn mark_status_as_pending(&self) -> Result<(), Error> {
+            self.transition(BridgeStatus::Pending, None)
+        }
+
+        async fn mark_status_as_synced(&self) -> Result<(), Error> {
+            self.transition(BridgeStatus::Synced, None)
+        }
+
+        async fn get_last_transaction_status(&self) -> Result<Option<TxStatus>, Error> {
+            Ok(self
+                .transactions
+                .lock()
+                .unwrap()
+                .last()
+                .map(|(_, _, status)| *status))
+        }
+
+        async fn get_pending_propagation(&self) -> Result<Option<PendingPropagation>, Error> {
+            let transactions = self.transactions.lock().unwrap();
+            let pending = transactions
+                .iter()
+                .rev()
+                .find(|(_, _, status)| *status == TxStatus::Pending)
+                .map(|(transaction_id, root, _)| PendingPropagation {
+                    transaction_id: transaction_id.clone(),
+                    root:           Some(*root),
+                });
+            Ok(pending)
+        }
+
+        async fn insert_new_transaction(
+            &self,
+            transaction_id: &TransactionId,
+            root: U256,
+        ) -> Result<(), Error> {
+            let mut transactions = self.transactions.lock().unwrap();
+            transactions.push((transaction_id.clone(), root, TxStatus::Pending));
+            Ok(())
+        }
+
+        async fn resolve_transaction(
+            &self,
+            transaction_id: &TransactionId,
+            tx_status: TxStatus,
+            _tx_hash: Option<String>,
+            _reason: Option<&str>,
+        ) -> Result<bool, Error> {
+            let mut transactions = self.transactions.lock().unwrap();
+            let pending = transactions
+                .iter_mut()
+                .find(|(id, _, status)| id == transaction_id && *status == TxStatus::Pending);
+            let Some((_, _, status)) = pending else {
+                return Ok(false);
+            };
+            *status = tx_status;
+            Ok(true)
+        }
+
+        async fn set_mined_at(
+            &self,
+            _transaction_id: &TransactionId,
+        ) -> Result<Option<PropagationTimes>, Error> {
+            Ok(None)
+        }
+
+        async fn get_last_mined_propagation_at(&self) -> Result<Option<DateTime<Utc>>, Error> {
+            Ok(None)
+        }
+
+        async fn get_spend_since(&self, _cutoff: DateTime<Utc>) -> Result<Spend, Error> {
+            Ok(Spend {
+                total: *self.spent.lock().unwrap(),
+                ..Spend::default()
+            })
+        }
+    }
+
+    /// Both chains' latest roots, which the test moves by hand, and the
+    /// outcomes of the next propagations. A propagation without a scripted
+    /// outcome is submitted.
+    #[derive(Debug, Default)]
+    struct FakeProcessor {
+        roots:        Mutex<(U256, U256)>,
+        propagations: Mutex<VecDeque<anyhow::Result<Propagation>>>,
+        calls:        AtomicUsize,
+    }
+
+    impl FakeProcessor {
+        fn new(mainnet: u64, scroll: u64) -> Self {
+            Self {
+                roots: Mutex::new((mainnet.into(), scroll.into())),
+                ..Self::default()
+            }
+        }
+
+        /// Mainnet moves to `root`.
+        fn insert(&self, root: u64) {
+            self.roots.lock().unwrap().0 = root.into();
+        }
+
+        fn deliver(&self) {
+            let mut roots = self.roots.lock().unwrap();
+            roots.1 = roots.0;
+        }
+
+        fn script(&self, propagation: anyhow::Result<Propagation>) {
+            self.propagations.lock().unwrap().push_back(propagation);
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl Processor for FakeProcessor {
+        async fn propagate_root(&self) -> anyhow::Result<Propagation> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            if let Some(propagation) = self.propagations.lock().unwrap().pop_front() {
+                return propagation;
+            }
+
+            Ok(Propagation::Submitted {
+                transaction_id: TransactionId::job(format!("tx-{call}"))?,
+                root:           self.roots.lock().unwrap().0,
+            })
+        }
+
+        async fn sync_state(&self) -> anyhow::Result<SyncState> {
+            let (mainnet, scroll) = *self.roots.lock().unwrap();
+            Ok(SyncState::from_roots(mainnet, scroll, false))
+        }
+
+        async fn latest_roots(&self) -> anyhow::Result<LatestRoots> {
+            let (mainnet, scroll) = *self.roots.lock().unwrap();
+            Ok(LatestRoots {
+                mainnet,
+                scroll: Some(scroll),
+            })
+        }
+
+        async fn is_root_mined(&self, root: U256) -> anyhow::Result<bool> {
+            Ok(self.roots.lock().unwrap().1 == root)
+        }
+
+        async fn lookup_root(&self, _root: U256) -> anyhow::Result<RootLookup> {
+            unimplemented!()
+        }
+
+        async fn lookup_roots(&self, _roots: &[U256]) -> anyhow::Result<Vec<RootLookup>> {
+            unimplemented!()
+        }
+
+        async fn get_mined_transactions(&self) -> anyhow::Result<Vec<TransactionId>> {
+            unimplemented!()
+        }
+
+        async fn mine_transaction(&self, _transaction_id: TransactionId) -> anyhow::Result<bool> {
+            unimplemented!()
+        }
+
+        async fn transaction_state(
+            &self,
+            _transaction_id: TransactionId,
+        ) -> anyhow::Result<TransactionState> {
+            unimplemented!()
+        }
+
+        async fn pending_transactions(
+            &self,
+            _transaction_ids: Vec<TransactionId>,
+        ) -> anyhow::Result<Vec<PendingTransaction>> {
+            unimplemented!()
+        }
+
+        async fn cancel_transaction(&self, _transaction_id: TransactionId) -> anyhow::Result<bool> {
+            unimplemented!()
+        }
+
+        async fn replace_transaction(
+            &self,
+            _transaction: &PendingTransaction,
+        ) -> anyhow::Result<bool> {
+            unimplemented!()
+        }
+
+        async fn cancel_in_flight(
+            &self,
+            _transaction: &PendingTransaction,
+        ) -> anyhow::Result<bool> {
+            unimplemented!()
+        }
+
+        async fn sent_to_self(&self, _hash: H256) -> anyhow::Result<bool> {
+            unimplemented!()
+        }
+
+        async fn revert_reason(&self, _hash: H256) -> anyhow::Result<Option<String>> {
+            unimplemented!()
+        }
+
+        async fn scan_roots(&self) -> anyhow::Result<usize> {
+            unimplemented!()
+        }
+
+        async fn scan_source(&self, _source: RootSource) -> anyhow::Result<usize> {
+            unimplemented!()
+        }
+
+        async fn reconcile_scans(&self) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn catch_up(&self, _source: RootSource) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        fn log_filter(&self, _source: RootSource) -> Filter {
+            unimplemented!()
+        }
+
+        async fn record_subscribed_log(
+            &self,
+            _source: RootSource,
+            _log: &Log,
+        ) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        fn scanner_status(&self) -> ScannersStatus {
+            unimplemented!()
+        }
+    }
+
+    struct Harness {
+        context:   SyncContext,
+        store:     Arc<FakeStore>,
+        processor: Arc<FakeProcessor>,
+        tasks:     Vec<JoinHandle<anyhow::Result<()>>>,
+    }
+
+    impl Harness {
+        fn new(status: BridgeStatus, processor: FakeProcessor) -> Self {
+            let store = Arc::new(FakeStore::new(status));
+            let processor = Arc::new(processor);
+            let context = SyncContext {
+                store:             store.clone(),
+                processor:         processor.clone(),
+                notifier:          Arc::new(Notifier::new(
+                    NotificationsConfig::default(),
+                    "test".to_string(),
+                )),
+                wake:              Arc::default(),
+                sync_check:        Arc::default(),
+                pause:             Arc::new(PauseControl::new(None)),
+                balance:           Arc::default(),
+                unsynced:          Arc::default(),
+                leadership:        Arc::new(Leadership::new(false)),
+                check_interval:    CHECK_INTERVAL,
+                unsynced_deadline: Duration::from_secs(2 * 60 * 60),
+                propagation:       PropagationConfig::default(),
+            };
+
+            Self {
+                context,
+                store,
+                processor,
+                tasks: Vec::new(),
+            }
+        }
+
+        fn with_propagation(mut self, propagation: PropagationConfig) -> Self {
+            self.context.propagation = propagation;
+            self
+        }
+
+        fn spawn_propagation(&mut self) {
+            let queue = Arc::new(TxQueue::new(8));
+            let task = propagate_root::propagate_root(self.context.clone(), queue);
+            self.tasks.push(tokio::spawn(task));
+        }
+
+        fn spawn_sync_check(&mut self) {
+            self.tasks
+                .push(tokio::spawn(check_sync::check_sync(self.context.clone())));
+        }
+
+        /// Until the next sync check has run.
+        async fn next_check(&self) {
+            tokio::time::sleep(self.context.check_interval).await;
+        }
+    }
+
+    /// Lets the tasks run until they wait on the clock or a wake.
+    async fn settle() {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+
+    impl Drop for Harness {
+        fn drop(&mut self) {
+            for task in &self.tasks {
+                task.abort();
+            }
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_propagation_takes_the_bridge_from_unsynced_to_synced() {
+        let mut harness = Harness::new(BridgeStatus::Synced, FakeProcessor::new(2, 1));
+        harness.spawn_propagation();
+        harness.spawn_sync_check();
+
+        // The first check finds the roots apart and wakes the propagation
+        settle().await;
+        assert_eq!(harness.processor.calls(), 1);
+        assert_eq!(harness.store.status(), BridgeStatus::Pending);
+        assert_eq!(harness.store.transactions(), vec![(
+            "tx-1".to_string(),
+            TxStatus::Pending
+        )]);
+
+        // Nothing new is sent while the root is in flight
+        harness.next_check().await;
+        assert_eq!(harness.processor.calls(), 1);
+        assert_eq!(harness.store.status(), BridgeStatus::Pending);
+
+        harness.processor.deliver();
+        harness.next_check().await;
+        assert_eq!(harness.processor.calls(), 1);
+        assert_eq!(harness.store.status(), BridgeStatus::Synced);
+        assert_eq!(harness.store.transactions(), vec![(
+            "tx-1".to_string(),
+            TxStatus::Mined
+        )]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_new_mainnet_root_wakes_the_sync_check_early() {
+        let mut harness = Harness::new(BridgeStatus::Synced, FakeProcessor::new(1, 1));
+        harness.spawn_propagation();
+        harness.spawn_sync_check();
+
+        settle().await;
+        assert_eq!(harness.processor.calls(), 0);
+
+        // What the scan does once WorldID moved, well before the next check
+        harness.processor.insert(2);
+        harness.context.sync_check.notify_one();
+        settle().await;
+        assert_eq!(harness.processor.calls(), 1);
+        assert_eq!(harness.store.status(), BridgeStatus::Pending);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_failed_simulation_is_retried_on_the_next_check() {
+        let mut harness = Harness::new(BridgeStatus::Unsynced, FakeProcessor::new(2, 1));
+        let revert = SimulationError::WouldRevert {
+            reason: "GasLimitZero".to_string(),
+        };
+        harness.processor.script(Err(revert.into()));
+        harness.spawn_propagation();
+        harness.spawn_sync_check();
+
+        settle().await;
+        assert_eq!(harness.processor.calls(), 1);
+        assert_eq!(harness.store.status(), BridgeStatus::Unsynced);
+        assert!(harness.store.transactions().is_empty());
+
+        harness.next_check().await;
+        assert_eq!(harness.processor.calls(), 2);
+        assert_eq!(harness.store.status(), BridgeStatus::Pending);
+        assert_eq!(harness.store.transactions(), vec![(
+            "tx-2".to_string(),
+            TxStatus::Pending
+        )]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_failed_transaction_is_propagated_again() {
+        let mut harness = Harness::new(BridgeStatus::Unsynced, FakeProcessor::new(2, 1));
+        harness.spawn_propagation();
+        harness.spawn_sync_check();
+
+        settle().await;
+        assert_eq!(harness.store.status(), BridgeStatus::Pending);
+
+        // What the transaction monitor records when a propagation reverts
+        let failed = TransactionId::job("tx-1").unwrap();
+        let store = &harness.context.store;
+        assert!(store
+            .resolve_transaction(&failed, TxStatus::Failed, None, None)
+            .await
+            .unwrap());
+        store
+            .mark_status_as_unsynced(Some(UnsyncedReason::PropagationFailed))
+            .await
+            .unwrap();
+        harness.context.wake.wake();
+
+        settle().await;
+        assert_eq!(harness.processor.calls(), 2);
+        assert_eq!(harness.store.status(), BridgeStatus::Pending);
+        assert_eq!(harness.store.transactions(), vec![
+            ("tx-1".to_string(), TxStatus::Failed),
+            ("tx-2".to_string(), TxStatus::Pending),
+        ]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_root_scroll_already_has_syncs_without_a_propagation() {
+        // Delivered by another replica, or sent before a restore
+        let mut harness = Harness::new(BridgeStatus::Unsynced, FakeProcessor::new(2, 1));
+        harness.processor.script(Ok(Propagation::AlreadySynced {
+            root: U256::from(2),
+        }));
+        harness.spawn_propagation();
+
+        harness.context.wake.wake();
+        settle().await;
+        assert_eq!(harness.processor.calls(), 1);
+        assert_eq!(harness.store.status(), BridgeStatus::Synced);
+        assert!(harness.store.transactions().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn wakes_in_quick_succession_send_one_propagation() {
+        let mut harness = Harness::new(BridgeStatus::Unsynced, FakeProcessor::new(2, 1));
+        harness.spawn_propagation();
+
+        harness.context.wake.wake();
+        harness.context.wake.wake();
+        settle().await;
+        assert_eq!(harness.processor.calls(), 1);
+
+        // A wake after the propagation went out finds it pending
+        harness.context.wake.wake();
+        settle().await;
+        assert_eq!(harness.processor.calls(), 1);
+        assert_eq!(harness.store.status(), BridgeStatus::Pending);
+        assert_eq!(harness.store.transactions(), vec![(
+            "tx-1".to_string(),
+            TxStatus::Pending
+        )]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn an_exceeded_daily_spend_limit_holds_propagation_back() {
+        let propagation = PropagationConfig::default().with_daily_spend_limit(U256::from(100));
+        let mut harness = Harness::new(BridgeStatus::Unsynced, FakeProcessor::new(2, 1))
+            .with_propagation(propagation);
+        *harness.store.spent.lock().unwrap() = U256::from(101);
+        harness.spawn_propagation();
+
+        harness.context.wake.wake();
+        settle().await;
+        assert_eq!(harness.processor.calls(), 0);
+        assert!(harness.store.transactions().is_empty());
+
+        // The oldest of the spend left the trailing 24 hours, spending the
+        // limit exactly is within it
+        *harness.store.spent.lock().unwrap() = U256::from(100);
+        harness.context.wake.wake();
+        settle().await;
+        assert_eq!(harness.processor.calls(), 1);
+        assert_eq!(harness.store.status(), BridgeStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn a_missing_or_corrupted_status_row_is_repaired() {
+        let store = FakeStore::new(BridgeStatus::Synced);
+
+        // A manual edit deleted the row
+        *store.status.lock().unwrap() = None;
+        let err = store.mark_status_as_pending().await.unwrap_err();
+        assert!(matches!(err, Error::StatusRowMissing), "{err:?}");
+        let err = anyhow::Error::from(err).context("propagation");
+        assert!(TaskMonitor::recover_status(&store, &err).await);
+        assert_eq!(store.status(), BridgeStatus::Unsynced);
+
+        // Or left a status this service doesn't know
+        *store.unknown_status.lock().unwrap() = Some("syncd".to_string());
+        let err = store.get_service_status().await.unwrap_err();
+        assert!(
+            matches!(&err, Error::UnknownStatus { status } if status == "syncd"),
+            "{err:?}"
+        );
+        assert!(TaskMonitor::recover_status(&store, &err.into()).await);
+        assert_eq!(
+            store.get_db_status().await.unwrap(),
+            Some(BridgeStatus::Unsynced)
+        );
+
+        // Anything else is left to the supervisor
+        let err = Error::InvalidStatusTransition {
+            from: BridgeStatus::Pending,
+            to:   BridgeStatus::Pending,
+        };
+        assert!(!TaskMonitor::recover_status(&store, &err.into()).await);
+    }
+}
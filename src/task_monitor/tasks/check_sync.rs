@@ -1,37 +1,213 @@
-use std::sync::Arc;
 use std::time::Duration;
 
-use crate::database::types::TxStatus;
-use crate::processor::status::BridgeStatus;
-use crate::task_monitor::{App, TaskMonitor};
-use tokio::sync::Notify;
-use tokio::time;
-use tracing::info;
+use chrono::Utc;
 
-pub async fn check_sync(app: Arc<App>, wake_up_notify: Arc<Notify>) -> anyhow::Result<()> {
-    let mut timer = time::interval(Duration::from_secs(120));
+use crate::{
+    database::types::{PendingPropagation, TxStatus},
+    metrics,
+    notifier::{Event, EventKind},
+    processor::status::{BridgeStatus, SyncState},
+    task_monitor::{sync_context::SyncContext, tasks::delivery, TaskMonitor},
+    unsynced::{self, Overdue},
+};
+use tokio::time::{self, Instant};
+use tracing::{error, info, info_span, warn};
+
+/// How often the roots are compared.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(120);
+
+/// When the roots were first seen apart, and whether that was notified.
+#[derive(Debug, Default)]
+struct UnsyncedAlert {
+    since:   Option<Instant>,
+    alerted: bool,
+}
+
+pub async fn check_sync(ctx: SyncContext) -> anyhow::Result<()> {
+    let mut timer = time::interval(ctx.check_interval);
+    let mut alert = UnsyncedAlert::default();
     loop {
-        _ = timer.tick().await;
-        info!("Sync processor woken due to timeout.");
+        tokio::select! {
+            _ = timer.tick() => info!("Sync processor woken due to timeout."),
+            () = ctx.sync_check.notified() => {
+                info!("Sync processor woken by a new mainnet root.");
+                // The next check is a full interval after this one
+                timer.reset();
+            }
+        }
+
+        check_once(&ctx, &mut alert).await?;
+    }
+}
 
-        let is_synced = TaskMonitor::check_synced_state(&app).await?;
+async fn check_once(ctx: &SyncContext, alert: &mut UnsyncedAlert) -> anyhow::Result<()> {
+    let sync_state = TaskMonitor::check_synced_state(&*ctx.processor).await?;
+    let is_synced = sync_state.is_synced();
 
-        let tx_pending = TaskMonitor::check_last_transaction_status(&app.database, TxStatus::Pending).await?;
+    if is_synced {
+        *alert = UnsyncedAlert::default();
+    } else {
+        let since = *alert.since.get_or_insert_with(Instant::now);
+        let unsynced_after = ctx.notifier.unsynced_after();
+        if !alert.alerted && since.elapsed() >= unsynced_after {
+            alert.alerted = true;
+            ctx.notifier.notify(Event::new(
+                EventKind::Unsynced,
+                format!(
+                    "Roots have differed for over {}: {sync_state:?}",
+                    humantime::format_duration(unsynced_after)
+                ),
+            ));
+        }
+    }
+
+    // A pending propagation whose root is on Scroll has landed, even if
+    // the relayer hasn't reported it yet. Resolving it lets a newer root
+    // be propagated instead of waiting on a stale transaction.
+    let pending = ctx.store.get_pending_propagation().await?;
+    if let Some(PendingPropagation {
+        transaction_id,
+        root: Some(root),
+    }) = &pending
+    {
+        if ctx.processor.is_root_mined(*root).await? {
+            info!(%transaction_id, ?root, "Pending propagation delivered its root");
+            if ctx
+                .store
+                .resolve_transaction(
+                    transaction_id,
+                    TxStatus::Mined,
+                    None,
+                    Some("root delivered"),
+                )
+                .await?
+            {
+                delivery::record_mined(&*ctx.store, transaction_id).await?;
+            }
+        }
+    }
+
+    let tx_pending =
+        TaskMonitor::check_last_transaction_status(&*ctx.store, TxStatus::Pending).await?;
 
-        let db_state_pending = TaskMonitor::check_db_state(&app.database, BridgeStatus::Pending).await?;
+    let db_status = TaskMonitor::db_state(&*ctx.store).await?;
 
-        info!(?is_synced, ?tx_pending, ?db_state_pending);
+    if let Some(status) = ctx.store.get_service_status().await? {
+        if let Some(last_synced) = status.last_synced {
+            metrics::record_last_synced(last_synced);
+        }
 
-        // if still synced or pending continue so as not to call the propagate
-        if is_synced && db_state_pending {
-          app.database.mark_status_as_synced().await?;
-          continue;
-        } else if is_synced || (tx_pending && db_state_pending) {
-          continue;
+        let now = Utc::now();
+        metrics::record_unsynced_for(unsynced::unsynced_for(&status, now));
+        let overdue = Overdue::of(&status, ctx.unsynced_deadline, now);
+        let crossed = ctx.unsynced.record(overdue);
+        if let Some(overdue) = overdue {
+            escalate(ctx, overdue).await;
+            if crossed {
+                ctx.notifier.notify(Event::new(
+                    EventKind::UnsyncedDeadline,
+                    format!(
+                        "Bridge hasn't been synced for {}, since {}",
+                        humantime::format_duration(round_to_secs(overdue.unsynced_for)),
+                        overdue.last_synced.to_rfc3339(),
+                    ),
+                ));
+            }
         }
+    }
 
-        app.database.mark_status_as_unsynced().await?;
+    info!(?sync_state, ?tx_pending, ?db_status);
 
-        wake_up_notify.notify_one()
+    if is_synced {
+        // Pending -> Synced is the only way the check syncs. Roots that
+        // match without a propagation of ours landing don't count, the next
+        // propagation settles the status: it goes from unsynced to synced
+        // itself when it finds Scroll already has the root.
+        match db_status {
+            BridgeStatus::Pending => {
+                if TaskMonitor::status_changed(ctx.store.mark_status_as_synced().await)? {
+                    ctx.notifier.notify(Event::new(
+                        EventKind::StatusChanged,
+                        "Bridge status changed from pending to synced",
+                    ));
+                }
+            }
+            BridgeStatus::Unsynced => {
+                warn!(
+                    ?db_status,
+                    "Roots match on chain but no propagation is pending, leaving status unsynced"
+                );
+            }
+            BridgeStatus::Synced => {}
+        }
+        return Ok(());
+    }
+
+    // a propagation is in flight, give it time to land
+    if tx_pending && db_status == BridgeStatus::Pending {
+        if let Some(PendingPropagation {
+            root: Some(root), ..
+        }) = pending
+        {
+            let mainnet_root = ctx.processor.latest_roots().await?.mainnet;
+            if mainnet_root != root {
+                info!(
+                    pending_root = ?root,
+                    ?mainnet_root,
+                    "Mainnet root moved on while propagating, propagating again once it lands"
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    if db_status == BridgeStatus::Synced {
+        warn!(
+            ?db_status,
+            "Roots differ on chain but the database says synced, correcting status"
+        );
+    }
+
+    let reason = match sync_state {
+        SyncState::Unsynced(reason) => Some(reason),
+        SyncState::Synced => None,
+    };
+    ctx.store.mark_status_as_unsynced(reason).await?;
+    if db_status != BridgeStatus::Unsynced {
+        ctx.notifier.notify(Event::new(
+            EventKind::StatusChanged,
+            format!("Bridge status changed from {db_status:?} to unsynced ({reason:?})")
+                .to_lowercase(),
+        ));
     }
+
+    // The propagation it sets off continues this span's trace
+    info_span!("sync_check", ?reason).in_scope(|| ctx.wake.wake());
+
+    Ok(())
+}
+
+/// Logged on every check while the status stays past `unsynced_deadline`,
+/// with both roots so the logs alone tell what is stuck.
+async fn escalate(ctx: &SyncContext, overdue: Overdue) {
+    let unsynced_for = humantime::format_duration(round_to_secs(overdue.unsynced_for));
+    match ctx.processor.latest_roots().await {
+        Ok(roots) => error!(
+            %unsynced_for,
+            last_synced = %overdue.last_synced,
+            mainnet_root = ?roots.mainnet,
+            scroll_root = ?roots.scroll,
+            "Bridge unsynced past unsynced_deadline"
+        ),
+        Err(err) => error!(
+            %unsynced_for,
+            last_synced = %overdue.last_synced,
+            %err,
+            "Bridge unsynced past unsynced_deadline, failed to read the roots"
+        ),
+    }
+}
+
+fn round_to_secs(duration: Duration) -> Duration {
+    Duration::from_secs(duration.as_secs())
 }
@@ -0,0 +1,74 @@
+//! How long propagations take to land: from submission until they are seen
+//! mined on mainnet, and from then until the scans see Scroll add their root.
+//! The two are recorded in whichever order they are observed, and each
+//! histogram is observed once both of its ends are known.
+
+use chrono::Utc;
+use tracing::{info, warn};
+
+use crate::{
+    app::App,
+    database,
+    database::{query::DatabaseQuery, store::StatusStore, types::PropagationTimes},
+    metrics::{PROPAGATION_DELIVERED, PROPAGATION_MINED, PROPAGATION_TIMEOUTS},
+    notifier::{Event, EventKind},
+    utils::TransactionId,
+};
+
+/// Records that `transaction_id` mined, for every path that resolves a
+/// propagation as mined.
+pub(crate) async fn record_mined(
+    store: &dyn StatusStore,
+    transaction_id: &TransactionId,
+) -> Result<(), database::Error> {
+    let Some(times) = store.set_mined_at(transaction_id).await? else {
+        return Ok(());
+    };
+
+    if let Some(until_mined) = times.until_mined() {
+        PROPAGATION_MINED.observe(until_mined.as_secs_f64());
+    }
+    // Scroll was seen adding the root first
+    observe_delivery(&times);
+
+    Ok(())
+}
+
+/// Records the propagations Scroll has since delivered, then times out the
+/// ones it hasn't within `delivery_deadline`.
+pub(super) async fn check_deliveries(app: &App) -> anyhow::Result<()> {
+    for times in app.database.set_delivered_at().await? {
+        observe_delivery(&times);
+    }
+
+    let deadline = app.config.maintenance.delivery_deadline;
+    let cutoff = Utc::now() - chrono::Duration::from_std(deadline)?;
+
+    for (transaction_id, root) in app.database.set_delivery_timed_out(cutoff).await? {
+        PROPAGATION_TIMEOUTS.inc();
+        warn!(%transaction_id, ?root, "Scroll hasn't added the propagated root in time");
+
+        let mut event = Event::new(
+            EventKind::PropagationTimedOut,
+            format!(
+                "Propagation mined over {} ago, but Scroll hasn't added its root",
+                humantime::format_duration(deadline)
+            ),
+        )
+        .with_transaction(transaction_id);
+        if let Some(root) = root {
+            event = event.with_root(root);
+        }
+        app.notifier.notify(event);
+    }
+
+    Ok(())
+}
+
+/// Observes the time from mined to delivered, once both were seen.
+fn observe_delivery(times: &PropagationTimes) {
+    if let Some(until_delivered) = times.until_delivered() {
+        info!(?until_delivered, "Propagated root delivered to Scroll");
+        PROPAGATION_DELIVERED.observe(until_delivered.as_secs_f64());
+    }
+}
@@ -0,0 +1,139 @@
+//! Leader election over a Postgres advisory lock. The lock is held by a
+//! connection taken out of the pool, and released when that connection
+//! closes: when the leader steps down or stops, or when the database gives
+//! up on a leader that stopped answering after `takeover_timeout`.
+
+use std::{future::Future, sync::Arc, time::Duration};
+
+use sqlx::{Connection, Executor, PgConnection};
+use tokio::{
+    sync::Notify,
+    time::{self, MissedTickBehavior},
+};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    app::App, config::LeadershipConfig, database::query::DatabaseQuery, leadership::Leadership,
+    metrics,
+};
+
+/// The connection holding the lock. Dropping it closes the connection, which
+/// releases the lock, and steps down.
+struct Lease {
+    conn:       PgConnection,
+    leadership: Arc<Leadership>,
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        self.leadership.set(false);
+        metrics::record_leader(false);
+    }
+}
+
+/// Takes the lock when it is free and holds it with a heartbeat every
+/// `heartbeat_interval`. Run by every replica when `[leadership]` is enabled.
+pub async fn elect(app: Arc<App>, wake_up_notify: Arc<Notify>) -> anyhow::Result<()> {
+    let config = &app.config.leadership;
+    let mut timer = time::interval(config.heartbeat_interval);
+    timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut lease: Option<Lease> = None;
+
+    loop {
+        _ = timer.tick().await;
+
+        if let Some(held) = &mut lease {
+            if let Err(err) = heartbeat(&mut held.conn, config.heartbeat_interval).await {
+                error!(%err, "Leadership heartbeat failed, stepping down");
+                lease = None;
+            }
+            continue;
+        }
+
+        match campaign(&app).await {
+            Ok(Some(conn)) => {
+                info!(
+                    instance = app.config.service.service_name,
+                    "Took the leadership"
+                );
+                lease = Some(Lease {
+                    conn,
+                    leadership: app.leadership.clone(),
+                });
+                app.leadership.set(true);
+                metrics::record_leader(true);
+                // Whatever the sync check found while following is stale
+                wake_up_notify.notify_one();
+            }
+            Ok(None) => debug!("Another replica leads"),
+            Err(err) => warn!(%err, "Failed to take the leadership lock"),
+        }
+    }
+}
+
+/// The connection holding the lock if it was free.
+async fn campaign(app: &App) -> anyhow::Result<Option<PgConnection>> {
+    let config = &app.config.leadership;
+
+    let mut conn = app.database.pool.acquire().await?;
+    if !(&mut *conn).try_advisory_lock(config.lock_key).await? {
+        return Ok(None);
+    }
+
+    // Never back in the pool while it holds the lock
+    let mut conn = conn.detach();
+    let recorded = async {
+        conn.execute(session_timeouts(config).as_str()).await?;
+        conn.record_leader(&app.config.service.service_name).await?;
+        anyhow::Ok(())
+    }
+    .await;
+
+    if let Err(err) = recorded {
+        // Releases the lock
+        _ = conn.close().await;
+        return Err(err);
+    }
+
+    Ok(Some(conn))
+}
+
+/// Has the database drop the session of a leader that stops answering after
+/// `takeover_timeout`, releasing its lock. Only applies over TCP.
+fn session_timeouts(config: &LeadershipConfig) -> String {
+    let idle = config.heartbeat_interval.as_secs().max(1);
+    format!(
+        "SET tcp_keepalives_idle = {idle}; SET tcp_keepalives_interval = {idle}; SET \
+         tcp_user_timeout = {}",
+        config.takeover_timeout.as_millis()
+    )
+}
+
+async fn heartbeat(conn: &mut PgConnection, timeout: Duration) -> anyhow::Result<()> {
+    time::timeout(timeout, conn.record_leader_heartbeat()).await??;
+    Ok(())
+}
+
+/// Runs `task` while this replica leads and cancels it as leadership is
+/// lost. Whatever it was waiting on is in the database, where the next
+/// leader picks it up.
+pub async fn while_leading<S, F>(
+    leadership: Arc<Leadership>,
+    name: &'static str,
+    task: S,
+) -> anyhow::Result<()>
+where
+    S: Fn() -> F,
+    F: Future<Output = anyhow::Result<()>>,
+{
+    loop {
+        leadership.acquired().await;
+
+        tokio::select! {
+            result = task() => return result,
+            () = leadership.lost() => {
+                info!(task = name, "Lost the leadership, stopping until it is regained");
+            }
+        }
+    }
+}
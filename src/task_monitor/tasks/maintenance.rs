@@ -0,0 +1,254 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use tokio::time;
+use tracing::{info, warn};
+
+use super::{delivery, monitor_txs};
+use crate::{
+    app::App,
+    database::{query::DatabaseQuery, types::TxStatus},
+    ethereum::TransactionState,
+};
+
+static SWEPT_TRANSACTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "swept_transactions",
+        "pending transactions moved to a terminal status by the maintenance task",
+        &["resolution"]
+    )
+    .unwrap()
+});
+
+/// The terminal record for a transaction that was stuck in pending.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Resolution {
+    pub status:  TxStatus,
+    pub tx_hash: Option<String>,
+    pub reason:  Option<String>,
+}
+
+pub async fn maintenance(app: Arc<App>) -> anyhow::Result<()> {
+    let mut timer = time::interval(app.config.maintenance.interval);
+
+    loop {
+        _ = timer.tick().await;
+
+        sweep_stuck_transactions(&app).await?;
+        delivery::check_deliveries(&app).await?;
+    }
+}
+
+/// Resolves every transaction that has been pending for longer than the
+/// configured threshold, so no propagation attempt is left without a
+/// terminal record after a crash or a lost relayer response.
+async fn sweep_stuck_transactions(app: &App) -> anyhow::Result<()> {
+    let config = &app.config.maintenance;
+    let cutoff = Utc::now() - chrono::Duration::from_std(config.stuck_transaction_threshold)?;
+
+    let stuck = app.database.get_transactions_pending_since(cutoff).await?;
+
+    if stuck.is_empty() {
+        return Ok(());
+    }
+
+    info!(count = stuck.len(), "Resolving stuck transactions");
+
+    for (transaction_id, created_at) in stuck {
+        let state = match app
+            .bridge_processor
+            .transaction_state(transaction_id.clone())
+            .await
+        {
+            Ok(state) => state,
+            Err(err) => {
+                warn!(%transaction_id, %err, "Failed to query stuck transaction");
+                continue;
+            }
+        };
+
+        let age = (Utc::now() - created_at).to_std().unwrap_or_default();
+
+        let Some(resolution) = classify(&state, age, config.abandon_transaction_after) else {
+            continue;
+        };
+
+        let resolved = app
+            .database
+            .resolve_transaction(
+                &transaction_id,
+                resolution.status,
+                resolution.tx_hash.clone(),
+                resolution.reason.as_deref(),
+            )
+            .await?;
+
+        monitor_txs::record_cost(&app.database, &transaction_id, Some(&state)).await?;
+
+        if resolved && resolution.status == TxStatus::Mined {
+            delivery::record_mined(&*app.database, &transaction_id).await?;
+        }
+
+        if resolved {
+            SWEPT_TRANSACTIONS
+                .with_label_values(&[resolution.status.into()])
+                .inc();
+
+            warn!(
+                %transaction_id,
+                status = ?resolution.status,
+                reason = ?resolution.reason,
+                "Resolved stuck transaction"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Decides the terminal status of a stuck transaction. Returns `None` when
+/// the transaction may still land and should be looked at again later.
+pub(crate) fn classify(
+    state: &TransactionState,
+    age: Duration,
+    abandon_after: Duration,
+) -> Option<Resolution> {
+    let resolution = match *state {
+        TransactionState::Mined {
+            hash,
+            succeeded: true,
+            ..
+        } => Resolution {
+            status:  TxStatus::Mined,
+            tx_hash: Some(format!("{hash:?}")),
+            reason:  None,
+        },
+        TransactionState::Mined {
+            hash,
+            succeeded: false,
+            ..
+        } => Resolution {
+            status:  TxStatus::Failed,
+            tx_hash: Some(format!("{hash:?}")),
+            reason:  Some("reverted".to_string()),
+        },
+        TransactionState::Failed => Resolution {
+            status:  TxStatus::Failed,
+            tx_hash: None,
+            reason:  Some("relayer reported failure".to_string()),
+        },
+        TransactionState::NonceConsumed { nonce } => Resolution {
+            status:  TxStatus::Superseded,
+            tx_hash: None,
+            reason:  Some(format!("nonce {nonce} used by another transaction")),
+        },
+        TransactionState::Unknown => Resolution {
+            status:  TxStatus::Abandoned,
+            tx_hash: None,
+            reason:  Some("unknown to relayer".to_string()),
+        },
+        TransactionState::Pending if age >= abandon_after => Resolution {
+            status:  TxStatus::Abandoned,
+            tx_hash: None,
+            reason:  Some(format!(
+                "still pending after {}",
+                humantime::format_duration(age)
+            )),
+        },
+        TransactionState::Pending => return None,
+    };
+
+    Some(resolution)
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::H256;
+
+    use super::*;
+
+    const ABANDON_AFTER: Duration = Duration::from_secs(6 * 60 * 60);
+    const STUCK: Duration = Duration::from_secs(60 * 60);
+
+    // Crashed after the relayer accepted the transaction but before the
+    // receipt was recorded.
+    #[test]
+    fn mined_transaction_is_recorded_as_mined() {
+        let hash = H256::repeat_byte(0xab);
+        let resolution = classify(
+            &TransactionState::Mined {
+                hash,
+                succeeded: true,
+                cost: None,
+            },
+            STUCK,
+            ABANDON_AFTER,
+        )
+        .unwrap();
+
+        assert_eq!(resolution.status, TxStatus::Mined);
+        assert_eq!(resolution.tx_hash, Some(format!("{hash:?}")));
+        assert_eq!(resolution.reason, None);
+    }
+
+    #[test]
+    fn reverted_transaction_is_recorded_as_failed() {
+        let resolution = classify(
+            &TransactionState::Mined {
+                hash:      H256::zero(),
+                succeeded: false,
+                cost:      None,
+            },
+            STUCK,
+            ABANDON_AFTER,
+        )
+        .unwrap();
+
+        assert_eq!(resolution.status, TxStatus::Failed);
+        assert_eq!(resolution.reason.as_deref(), Some("reverted"));
+    }
+
+    #[test]
+    fn relayer_failure_is_recorded_as_failed() {
+        let resolution = classify(&TransactionState::Failed, STUCK, ABANDON_AFTER).unwrap();
+
+        assert_eq!(resolution.status, TxStatus::Failed);
+    }
+
+    // Crashed before the relayer response was stored, and the nonce was
+    // later reused.
+    #[test]
+    fn consumed_nonce_is_recorded_as_superseded() {
+        let resolution = classify(
+            &TransactionState::NonceConsumed { nonce: 7 },
+            STUCK,
+            ABANDON_AFTER,
+        )
+        .unwrap();
+
+        assert_eq!(resolution.status, TxStatus::Superseded);
+        assert!(resolution.reason.unwrap().contains("nonce 7"));
+    }
+
+    // Crashed between inserting the row and submitting to the relayer.
+    #[test]
+    fn transaction_unknown_to_relayer_is_abandoned() {
+        let resolution = classify(&TransactionState::Unknown, STUCK, ABANDON_AFTER).unwrap();
+
+        assert_eq!(resolution.status, TxStatus::Abandoned);
+    }
+
+    #[test]
+    fn pending_transaction_is_left_alone_until_abandon_threshold() {
+        assert_eq!(
+            classify(&TransactionState::Pending, STUCK, ABANDON_AFTER),
+            None
+        );
+
+        let resolution =
+            classify(&TransactionState::Pending, ABANDON_AFTER, ABANDON_AFTER).unwrap();
+        assert_eq!(resolution.status, TxStatus::Abandoned);
+    }
+}
@@ -1,4 +1,13 @@
-pub mod propagate_root;
-pub mod monitor_txs;
 pub mod check_sync;
-pub mod finalize_txs;
\ No newline at end of file
+pub mod delivery;
+pub mod finalize_txs;
+pub mod leader_election;
+pub mod maintenance;
+pub mod monitor_txs;
+pub mod pause;
+pub mod propagate_root;
+pub mod retention;
+pub mod root_finality;
+pub mod scan_roots;
+pub mod subscribe_roots;
+pub mod wallet_balance;
@@ -1,32 +1,263 @@
-use std::sync::Arc;
-
-use tokio::sync::{mpsc, Mutex};
-use tracing::{error, info};
-
-use crate::app::App;
-use crate::database::query::DatabaseQuery;
-use crate::database::types::TxStatus;
-use crate::utils::TransactionId;
-
-pub async fn monitor_txs(
-    app: Arc<App>,
-    monitored_txs_receiver: Arc<Mutex<mpsc::Receiver<TransactionId>>>,
-) -> anyhow::Result<()> {
-    let mut monitored_txs_receiver = monitored_txs_receiver.lock().await;
-
-    while let Some(tx) = monitored_txs_receiver.recv().await {
-        // assert!(
-        match app.bridge_processor.mine_transaction(tx.clone()).await  {
-            Ok(id) => {
-                info!("Transaction Status: {:?}", id);
-                app.database.update_transaction(&tx, TxStatus::Mined).await?;
-            },
-            Err(err) => {
-                error!(%err, "Transaction failed");
-                app.database.update_transaction(&tx, TxStatus::Mined).await?;
+//! Waits for submitted propagations to mine, up to `monitor_parallelism` at
+//! once. The transactions table is the queue: whatever is still pending is
+//! picked up again whenever the task starts or the [`TxQueue`] overflowed,
+//! and the queue only brings what is submitted afterwards.
+
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use ethers::types::H256;
+use futures::stream::{FuturesUnordered, StreamExt};
+use tracing::{debug, error, info, info_span, warn, Instrument, Span};
+
+use super::{
+    delivery,
+    maintenance::{classify, Resolution},
+};
+use crate::{
+    app::App,
+    database::{self, query::DatabaseQuery, types::TxStatus, Database},
+    ethereum::TransactionState,
+    metrics,
+    notifier::{Event, EventKind},
+    processor::status::UnsyncedReason,
+    task_monitor::{
+        tx_queue::{Next, TxQueue},
+        TaskMonitor,
+    },
+    utils::TransactionId,
+};
+
+/// A submitted propagation, with the span it was submitted in so waiting for
+/// it continues the same trace.
+#[derive(Debug)]
+pub struct MonitoredTx {
+    pub id:   TransactionId,
+    pub span: Span,
+}
+
+pub async fn monitor_txs(app: Arc<App>, queue: Arc<TxQueue>) -> anyhow::Result<()> {
+    let parallelism = app.config.app.propagation.monitor_parallelism;
+    // Waited for by this run, so one loaded from the db and also still
+    // queued is only waited for once
+    let mut monitored = HashSet::new();
+    let mut in_flight = FuturesUnordered::new();
+
+    // Submitted before a restart of the service or of this task
+    let mut backlog = pending(&app).await?;
+    if !backlog.is_empty() {
+        info!(
+            count = backlog.len(),
+            "Resuming monitoring of pending transactions"
+        );
+    }
+
+    loop {
+        while in_flight.len() < parallelism {
+            let Some(MonitoredTx { id, span }) = backlog.pop_front() else {
+                break;
+            };
+            if !monitored.insert(id.clone()) {
+                debug!(tx = %id, "Transaction already monitored");
+                continue;
+            }
+
+            let span = info_span!(parent: &span, "monitor_tx", tx = %id);
+            in_flight.push(monitor_tx(&app, id).instrument(span));
+        }
+
+        tokio::select! {
+            next = queue.pop(), if in_flight.len() < parallelism && backlog.is_empty() => {
+                match next {
+                    Next::Tx(tx) => backlog.push_back(tx),
+                    Next::Overflowed => {
+                        warn!(
+                            "Transaction queue overflowed, reading pending transactions from the db"
+                        );
+                        backlog = pending(&app).await?;
+                    }
+                }
+            }
+            Some(result) = in_flight.next() => {
+                result?;
+            }
+        }
+    }
+}
+
+/// Every pending transaction in the db, oldest first.
+async fn pending(app: &App) -> anyhow::Result<VecDeque<MonitoredTx>> {
+    let pending = app.database.get_pending_transactions().await?;
+
+    Ok(pending
+        .into_iter()
+        .map(|id| MonitoredTx {
+            id,
+            span: Span::none(),
+        })
+        .collect())
+}
+
+/// Waits for `tx` and records how it ended, returning whether it mined.
+/// Errors leave it pending, to be picked up again when the task restarts.
+pub(crate) async fn monitor_tx(app: &App, tx: TransactionId) -> anyhow::Result<bool> {
+    let mined = app.bridge_processor.mine_transaction(tx.clone()).await?;
+
+    let state = app.bridge_processor.transaction_state(tx.clone()).await;
+    if let Err(err) = &state {
+        warn!(%tx, %err, "Failed to look up the transaction's receipt");
+    }
+    record_cost(&app.database, &tx, state.as_ref().ok()).await?;
+
+    if mined {
+        if app
+            .database
+            .resolve_transaction(&tx, TxStatus::Mined, None, Some("mined"))
+            .await?
+        {
+            info!(%tx, "Transaction mined");
+            delivery::record_mined(&*app.database, &tx).await?;
+        }
+        metrics::CONSECUTIVE_FAILURES.set(0);
+        return Ok(true);
+    }
+
+    let state = state.ok();
+    let mut resolution = failure(state.as_ref());
+    let revert_reason = match state {
+        Some(TransactionState::Mined {
+            hash,
+            succeeded: false,
+            ..
+        }) => replay_revert(app, &tx, hash).await,
+        _ => None,
+    };
+    if let Some(revert_reason) = &revert_reason {
+        resolution.reason = Some(format!("reverted: {revert_reason}"));
+    }
+
+    if !app
+        .database
+        .resolve_transaction(
+            &tx,
+            resolution.status,
+            resolution.tx_hash,
+            resolution.reason.as_deref(),
+        )
+        .await?
+    {
+        warn!(%tx, "Transaction failed after it was already resolved");
+        return Ok(false);
+    }
+    warn!(
+        %tx,
+        status = ?resolution.status,
+        reason = ?resolution.reason,
+        ?revert_reason,
+        "Propagation failed"
+    );
+    // Lowercased ahead of the reason, revert reasons carry addresses
+    let status = format!("{:?}", resolution.status).to_lowercase();
+    let mut event = Event::new(
+        EventKind::PropagationFailed,
+        format!(
+            "propagation {status}: {}",
+            resolution.reason.as_deref().unwrap_or("no reason given")
+        ),
+    )
+    .with_transaction(tx.clone());
+    if let Some(revert_reason) = revert_reason {
+        event = event.with_revert_reason(revert_reason);
+    }
+    app.notifier.notify(event);
+
+    let failures = app.database.count_consecutive_failures().await?;
+    metrics::CONSECUTIVE_FAILURES.set(failures.try_into().unwrap_or(i64::MAX));
+
+    TaskMonitor::status_changed(
+        app.database
+            .mark_status_as_unsynced(Some(UnsyncedReason::PropagationFailed))
+            .await,
+    )?;
+
+    let max_failures = app.config.app.propagation.max_consecutive_failures;
+    if failures >= max_failures {
+        error!(
+            failures,
+            max_failures, "Propagations keep failing, pausing propagation until resumed"
+        );
+        app.pause(format!("{failures} consecutive propagations failed"), None)
+            .await?;
+    } else {
+        // Propagate again right away rather than at the next sync check
+        app.wake_propagation();
+    }
+
+    Ok(false)
+}
+
+/// Why the reverted transaction `hash` reverted, `None` when replaying it
+/// doesn't tell.
+async fn replay_revert(app: &App, tx: &TransactionId, hash: H256) -> Option<String> {
+    match app.bridge_processor.revert_reason(hash).await {
+        Ok(Some(reason)) => Some(reason),
+        Ok(None) => {
+            warn!(
+                %tx,
+                ?hash,
+                "Replaying the reverted transaction doesn't revert, its revert reason is unknown"
+            );
+            None
+        }
+        Err(err) => {
+            warn!(%tx, ?hash, %err, "Failed to replay the reverted transaction");
+            None
+        }
+    }
+}
+
+/// Records what a transaction that made it on chain cost, for every path
+/// that reads its receipt. One whose receipt can't be read is left unknown,
+/// the spend limit counts it as zero.
+pub(crate) async fn record_cost(
+    database: &Database,
+    tx: &TransactionId,
+    state: Option<&TransactionState>,
+) -> Result<(), database::Error> {
+    match state {
+        Some(TransactionState::Mined {
+            cost: Some(cost), ..
+        }) => {
+            if database.set_transaction_cost(tx, cost).await? {
+                if cost.estimated {
+                    warn!(%tx, "Receipt has no effectiveGasPrice, the cost is estimated");
+                }
+                metrics::record_propagation_cost(cost);
             }
-        };
+        }
+        Some(TransactionState::Mined { cost: None, .. }) => {
+            warn!(%tx, "Receipt doesn't say what the transaction cost");
+        }
+        _ => {}
     }
 
     Ok(())
 }
+
+/// Why a transaction failed, from its receipt when it was mined.
+fn failure(state: Option<&TransactionState>) -> Resolution {
+    let resolution = state.and_then(|state| classify(state, Duration::ZERO, Duration::MAX));
+
+    // The relayer already said the transaction failed, whatever its state
+    // says now
+    resolution
+        .filter(|resolution| resolution.status != TxStatus::Mined)
+        .unwrap_or_else(|| Resolution {
+            status:  TxStatus::Failed,
+            tx_hash: None,
+            reason:  Some("failed in the relayer".to_string()),
+        })
+}
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use crate::{database::query::DatabaseQuery, metrics, pause::PauseEvent, task_monitor::App};
+
+/// Lifts pauses that reach their expiry and keeps reminding about pauses
+/// without one. Propagation is woken up whenever a pause ends, rather than
+/// waiting for the next sync check.
+pub async fn watch_pause(app: Arc<App>, wake_up_notify: Arc<Notify>) -> anyhow::Result<()> {
+    let mut watcher = app
+        .pause
+        .watcher(app.config.maintenance.pause_reminder_interval);
+
+    loop {
+        match watcher.next_event().await {
+            PauseEvent::Expired(pause) => {
+                // The operator may have extended the pause in the meantime
+                if !app.pause.resume_if_current(&pause) {
+                    continue;
+                }
+
+                app.database.resume_service("pause expired").await?;
+                metrics::PAUSED.set(0.0);
+                info!(reason = %pause.reason, "Pause expired, resuming");
+            }
+            PauseEvent::StillPaused(pause) => {
+                metrics::PAUSE_REMINDERS.inc();
+                warn!(
+                    reason = %pause.reason,
+                    paused_at = %pause.paused_at,
+                    "Still paused, propagation stays stopped until resumed"
+                );
+            }
+            PauseEvent::Resumed => wake_up_notify.notify_one(),
+        }
+    }
+}
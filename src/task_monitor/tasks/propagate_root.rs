@@ -1,47 +1,261 @@
-use std::sync::Arc;
-use tokio::sync::{mpsc, Notify};
-use crate::database::query::DatabaseQuery;
-use crate::database::types::TxStatus;
-use crate::processor::status::BridgeStatus;
-use crate::task_monitor::{App, TaskMonitor};
-use crate::utils::TransactionId;
-use tracing::error;
-
-pub async fn propagate_root(
-    app: Arc<App>, 
-    monitored_txs_sender: Arc<mpsc::Sender<TransactionId>>,
-    wake_up_notify: Arc<Notify>
-) -> anyhow::Result<()> {
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    contracts::SimulationError,
+    database::types::TxStatus,
+    ethereum::TxError,
+    metrics::{self, SkipReason},
+    notifier::{Event, EventKind},
+    processor::{status::BridgeStatus, Propagation},
+    task_monitor::{
+        sync_context::SyncContext,
+        tasks::monitor_txs::MonitoredTx,
+        tx_queue::{Pushed, TxQueue},
+        TaskMonitor,
+    },
+};
+use chrono::{DateTime, Utc};
+use ethers::types::U256;
+use tracing::{debug, error, info, info_span, warn, Instrument, Span};
+
+pub async fn propagate_root(ctx: SyncContext, monitored_txs: Arc<TxQueue>) -> anyhow::Result<()> {
+    let mut spend_limit_reached = false;
     loop {
-        _ = wake_up_notify.notified();
+        ctx.wake.notified().await;
+        // Continues the trace of the sync check or request that woke us, a
+        // trace of its own otherwise
+        let parent = ctx.wake.take_parent();
+        let span = info_span!(parent: &parent, "propagation");
 
-        let is_unsynced = TaskMonitor::check_db_state(&app.database, BridgeStatus::Unsynced).await?;
+        propagate_once(&ctx, &monitored_txs, span, &mut spend_limit_reached).await?;
+    }
+}
 
-        // there is an existing tx pending
-        let tx_pending = TaskMonitor::check_last_transaction_status(&app.database, TxStatus::Pending).await?;
+/// One pass after a wake. Anything that keeps the root from being sent
+/// returns early, the next wake looks again.
+async fn propagate_once(
+    ctx: &SyncContext,
+    monitored_txs: &TxQueue,
+    span: Span,
+    spend_limit_reached: &mut bool,
+) -> anyhow::Result<()> {
+    if !ctx.leadership.is_leader() {
+        debug!("Another replica leads, not propagating");
+        metrics::record_skipped_propagation(SkipReason::NotLeader);
+        return Ok(());
+    }
 
+    if let Some(pause) = ctx.pause.current() {
+        info!(reason = %pause.reason, "Paused, not propagating");
+        metrics::record_skipped_propagation(SkipReason::Paused);
+        return Ok(());
+    }
 
-        if !is_unsynced || tx_pending {
-            continue;
-        }
-        
-        let tx_id = app.bridge_processor
-            .propagate_root()
+    if let Some(low) = ctx.balance.low() {
+        warn!(balance = %low.balance, "Wallet balance is critically low, not propagating");
+        metrics::record_skipped_propagation(SkipReason::LowBalance);
+        return Ok(());
+    }
+
+    let is_unsynced = TaskMonitor::check_db_state(&*ctx.store, BridgeStatus::Unsynced).await?;
+
+    // there is an existing tx pending
+    let tx_pending =
+        TaskMonitor::check_last_transaction_status(&*ctx.store, TxStatus::Pending).await?;
+
+    if !is_unsynced || tx_pending {
+        return Ok(());
+    }
+
+    let config = &ctx.propagation;
+    let last_mined = ctx.store.get_last_mined_propagation_at().await?;
+    if let Some(remaining) =
+        interval_remaining(last_mined, Utc::now(), config.min_propagation_interval)
+    {
+        info!(
+            remaining = %humantime::format_duration(remaining),
+            "Propagated recently, waiting for min_propagation_interval"
+        );
+        metrics::record_skipped_propagation(SkipReason::MinInterval);
+        return Ok(());
+    }
+
+    if let Some(limit) = config.daily_spend_limit {
+        let spend = ctx
+            .store
+            .get_spend_since(Utc::now() - chrono::Duration::days(1))
             .await?;
+        if spend.unknown > 0 {
+            warn!(
+                transactions = spend.unknown,
+                "Cost of recent propagations is unknown, counting it as zero"
+            );
+        }
+        metrics::record_propagation_spend(spend.total);
 
-        // add tx_id to db
-        app.database.insert_new_transaction(&tx_id).await?;
+        let reached = limit_reached(spend.total, limit);
+        metrics::SPEND_LIMIT_REACHED.set(if reached { 1.0 } else { 0.0 });
+        if reached && !*spend_limit_reached {
+            error!(spent = %spend.total, %limit, "Daily spend limit exceeded, not propagating");
+            ctx.notifier.notify(Event::new(
+                EventKind::SpendLimitReached,
+                format!(
+                    "Propagations spent {} wei over the last 24h, over the limit of {limit} wei, \
+                     propagation is stopped",
+                    spend.total
+                ),
+            ));
+        }
+        *spend_limit_reached = reached;
+        if reached {
+            metrics::record_skipped_propagation(SkipReason::SpendLimit);
+            return Ok(());
+        }
+    }
 
-        match monitored_txs_sender.send(tx_id.clone()).await {
-            Ok(id) => {
-                id
-            },
-            Err(err) => {
-                error!(%err, "Transaction failed");
+    let (tx_id, root) = match ctx
+        .processor
+        .propagate_root()
+        .instrument(span.clone())
+        .await
+    {
+        Ok(Propagation::Submitted {
+            transaction_id,
+            root,
+        }) => (transaction_id, root),
+        // Still unsynced, the next sync check looks at the root again
+        Ok(Propagation::Deferred { .. } | Propagation::Simulated) => return Ok(()),
+        Ok(Propagation::AlreadySynced { root }) => {
+            if TaskMonitor::status_changed(ctx.store.mark_status_as_synced().await)? {
+                ctx.notifier.notify(
+                    Event::new(
+                        EventKind::StatusChanged,
+                        format!(
+                            "Bridge status changed from unsynced to synced, Scroll already had \
+                             root {root:#x}"
+                        ),
+                    )
+                    .with_root(root),
+                );
             }
-        };
+            return Ok(());
+        }
+        // A revert or an estimate above the ceiling won't go away by
+        // retrying right away, wait for the next sync check instead of
+        // restarting the task.
+        Err(err)
+            if err
+                .downcast_ref::<SimulationError>()
+                .is_some_and(|err| matches!(err, SimulationError::WouldRevert { .. })) =>
+        {
+            return Ok(())
+        }
+        Err(err)
+            if err.downcast_ref::<TxError>().is_some_and(|err| {
+                matches!(
+                    err,
+                    TxError::EstimationReverted { .. } | TxError::GasLimitExceeded { .. }
+                )
+            }) =>
+        {
+            return Ok(())
+        }
+        // The nonce is reconciled before the next send, and a gap only
+        // clears once it mines or is cancelled, so sending again right
+        // away would hit the same conflict.
+        Err(err)
+            if err.downcast_ref::<TxError>().is_some_and(|err| {
+                matches!(
+                    err,
+                    TxError::NonceTooLow { .. }
+                        | TxError::ReplacementUnderpriced { .. }
+                        | TxError::NonceGap { .. }
+                )
+            }) =>
+        {
+            warn!(%err, "Nonce conflict, waiting for the next sync check");
+            return Ok(());
+        }
+        Err(err) => return Err(err),
+    };
+
+    // add tx_id to db before monitoring it, so a restart resumes
+    // monitoring from the db
+    ctx.store.insert_new_transaction(&tx_id, root).await?;
+    ctx.notifier.notify(
+        Event::new(
+            EventKind::PropagationSubmitted,
+            format!("Propagating root {root:#x}"),
+        )
+        .with_root(root)
+        .with_transaction(tx_id.clone()),
+    );
+
+    let monitored = MonitoredTx {
+        id: tx_id.clone(),
+        span,
+    };
+    if let Pushed::Dropped(reason) = monitored_txs.push(monitored) {
+        warn!(
+            tx = %tx_id,
+            ?reason,
+            "Transaction not queued for the monitor, it is picked up from the db"
+        );
+    }
+
+    // update db state to pending
+    TaskMonitor::status_changed(ctx.store.mark_status_as_pending().await)?;
+
+    Ok(())
+}
+
+/// How long until `min_interval` has passed since the last propagation that
+/// mined, `None` if it has.
+fn interval_remaining(
+    last_mined: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    min_interval: Duration,
+) -> Option<Duration> {
+    let elapsed = (now - last_mined?).to_std().unwrap_or_default();
+    min_interval
+        .checked_sub(elapsed)
+        .filter(|remaining| !remaining.is_zero())
+}
+
+/// Spending exactly the limit is still within it.
+fn limit_reached(spent: U256, limit: U256) -> bool {
+    spent > limit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn propagation_waits_out_the_interval_since_the_last_mined_one() {
+        let now = Utc::now();
+        let interval = Duration::from_secs(30 * 60);
+        let ago = |minutes| Some(now - chrono::Duration::minutes(minutes));
+
+        assert_eq!(interval_remaining(None, now, interval), None);
+        assert_eq!(
+            interval_remaining(ago(10), now, interval),
+            Some(Duration::from_secs(20 * 60))
+        );
+        assert_eq!(interval_remaining(ago(30), now, interval), None);
+        assert_eq!(interval_remaining(ago(10), now, Duration::ZERO), None);
+    }
+
+    #[test]
+    fn spend_limit_is_reached_past_the_limit() {
+        let limit = U256::from(100);
+        assert!(!limit_reached(U256::from(99), limit));
+        assert!(!limit_reached(limit, limit));
+        assert!(limit_reached(U256::from(101), limit));
 
-        // update db state to pending
-        app.database.mark_status_as_pending().await?;
+        // Past what a u64 holds
+        let limit = U256::exp10(20);
+        assert!(!limit_reached(U256::from(u64::MAX), limit));
+        assert!(limit_reached(limit + 1, limit));
     }
 }
@@ -0,0 +1,105 @@
+use std::{future::Future, sync::Arc};
+
+use chrono::Utc;
+use tokio::time;
+use tracing::info;
+
+use crate::{
+    app::App,
+    config::RetentionConfig,
+    database,
+    database::query::DatabaseQuery,
+    metrics::{self, PrunedTable},
+};
+
+/// Deletes the roots and transactions older than the retention windows. Each
+/// statement deletes at most `batch_size` rows, so no lock is held for long.
+pub async fn retention(app: Arc<App>) -> anyhow::Result<()> {
+    let config = &app.config.retention;
+    let database = &*app.database;
+    let mut timer = time::interval(config.interval);
+
+    loop {
+        _ = timer.tick().await;
+
+        let roots_cutoff = Utc::now() - chrono::Duration::from_std(config.roots_max_age)?;
+        let roots = prune(PrunedTable::Roots, config, |limit| {
+            database.prune_roots(roots_cutoff, limit)
+        })
+        .await?;
+
+        let transactions_cutoff =
+            Utc::now() - chrono::Duration::from_std(config.transactions_max_age)?;
+        let transactions = prune(PrunedTable::Transactions, config, |limit| {
+            database.prune_transactions(transactions_cutoff, limit)
+        })
+        .await?;
+
+        if roots > 0 || transactions > 0 {
+            info!(roots, transactions, "Pruned old rows");
+        }
+    }
+}
+
+/// Runs `batch` until it deletes fewer than `batch_size` rows, sleeping
+/// `batch_delay` in between. Returns how many rows were deleted.
+async fn prune<F, Fut>(
+    table: PrunedTable,
+    config: &RetentionConfig,
+    mut batch: F,
+) -> anyhow::Result<u64>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = Result<u64, database::Error>>,
+{
+    let mut total = 0;
+
+    loop {
+        let pruned = batch(config.batch_size).await?;
+        metrics::record_pruned_rows(table, pruned);
+        total += pruned;
+
+        if pruned < config.batch_size {
+            return Ok(total);
+        }
+
+        time::sleep(config.batch_delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// Prunes `rows` rows two at a time, returning how many were deleted and
+    /// in how many statements.
+    async fn pruned(mut rows: u64) -> (u64, usize) {
+        let config = RetentionConfig {
+            batch_size: 2,
+            batch_delay: Duration::ZERO,
+            ..RetentionConfig::default()
+        };
+        let mut statements = 0;
+
+        let pruned = prune(PrunedTable::Roots, &config, |limit| {
+            let deleted = rows.min(limit);
+            rows -= deleted;
+            statements += 1;
+            async move { Ok(deleted) }
+        })
+        .await
+        .unwrap();
+
+        (pruned, statements)
+    }
+
+    #[tokio::test]
+    async fn batches_run_until_one_is_short() {
+        assert_eq!(pruned(0).await, (0, 1));
+        assert_eq!(pruned(5).await, (5, 3));
+        // A full last batch takes one more, empty, statement to notice
+        assert_eq!(pruned(4).await, (4, 3));
+    }
+}
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use ethers::{providers::Middleware, types::BlockNumber};
+use tokio::time;
+use tracing::{info, warn};
+
+use crate::{app::App, config::FinalityTag, database::query::DatabaseQuery, metrics};
+
+/// Compares the roots Scroll added against its finalized block every
+/// `refresh_interval`, marking those at or below it finalized. Those above
+/// it go back to added, the block only goes back when the node serving it
+/// changed or its view was reorged.
+pub async fn root_finality(app: Arc<App>) -> anyhow::Result<()> {
+    let config = &app.config.finality;
+    let mut timer = time::interval(config.refresh_interval);
+
+    loop {
+        _ = timer.tick().await;
+
+        let finalized_block = finalized_block(&app, config.block_tag).await?;
+        metrics::record_scroll_finalized_block(finalized_block);
+
+        let finalized = app.database.finalize_roots(finalized_block).await?;
+        if finalized > 0 {
+            info!(finalized, finalized_block, "Roots finalized on Scroll");
+        }
+
+        let demoted = app.database.unfinalize_roots(finalized_block).await?;
+        if demoted > 0 {
+            warn!(
+                demoted,
+                finalized_block,
+                "Scroll's finalized block went back, demoted roots above it to added"
+            );
+        }
+    }
+}
+
+async fn finalized_block(app: &App, tag: FinalityTag) -> anyhow::Result<u64> {
+    let tag = match tag {
+        FinalityTag::Finalized => BlockNumber::Finalized,
+        FinalityTag::Safe => BlockNumber::Safe,
+    };
+
+    let block = app
+        .ethereum
+        .l2_provider()
+        .get_block(tag)
+        .await?
+        .with_context(|| format!("Scroll has no {tag} block"))?;
+
+    Ok(block
+        .number
+        .context("the finalized block has no number")?
+        .as_u64())
+}
@@ -0,0 +1,98 @@
+//! Every contract is scanned in a loop of its own, so a chain whose provider
+//! is down only holds back its own scanner. What the scans find is
+//! reconciled in one place: the roots are written, the cursors persisted,
+//! and new WorldID roots wake the sync check.
+
+use std::sync::Arc;
+
+use futures::future;
+use tokio::{sync::mpsc, time};
+use tracing::{debug, warn};
+
+use super::subscribe_roots::Backoff;
+use crate::{app::App, database::types::RootSource};
+
+/// What a scan that succeeded found.
+#[derive(Debug, Clone, Copy)]
+struct Scanned {
+    source: RootSource,
+    roots:  usize,
+}
+
+pub async fn scan_roots(app: Arc<App>) -> anyhow::Result<()> {
+    let (sender, receiver) = mpsc::channel(RootSource::ALL.len());
+    let scanners = RootSource::ALL.map(|source| scan(app.clone(), source, sender.clone()));
+    drop(sender);
+
+    tokio::select! {
+        result = reconcile(&app, receiver) => result,
+        result = future::try_join_all(scanners) => result.map(|_| ()),
+    }
+}
+
+/// Scans `source` every `time_between_scans`, and while its scans fail
+/// waits twice as long after each up to `max_scan_backoff`. Only returns
+/// once the reconciliation stopped.
+async fn scan(
+    app: Arc<App>,
+    source: RootSource,
+    sender: mpsc::Sender<Scanned>,
+) -> anyhow::Result<()> {
+    let config = &app.config.app.scanner;
+    let mut backoff = Backoff::new(config.time_between_scans, config.max_scan_backoff);
+
+    loop {
+        debug!(?source, "Scanning for roots");
+
+        let wait = match app.bridge_processor.scan_source(source).await {
+            Ok(roots) => {
+                backoff.reset();
+                sender
+                    .send(Scanned { source, roots })
+                    .await
+                    .map_err(|_| anyhow::anyhow!("the scan reconciliation stopped"))?;
+                config.time_between_scans
+            }
+            Err(err) => {
+                let wait = backoff.next();
+                warn!(
+                    ?source,
+                    ?err,
+                    ?wait,
+                    "Scan failed, the other scanners carry on"
+                );
+                wait
+            }
+        };
+
+        time::sleep(wait).await;
+    }
+}
+
+/// Reconciles the scans as they arrive, once for the scans that arrived
+/// together.
+async fn reconcile(app: &App, mut receiver: mpsc::Receiver<Scanned>) -> anyhow::Result<()> {
+    while let Some(scanned) = receiver.recv().await {
+        let mut batch = vec![scanned];
+        while let Ok(scanned) = receiver.try_recv() {
+            batch.push(scanned);
+        }
+
+        app.bridge_processor.reconcile_scans().await?;
+
+        let new_roots: usize = batch
+            .iter()
+            .filter(|scanned| scanned.source == RootSource::WorldId)
+            .map(|scanned| scanned.roots)
+            .sum();
+        if new_roots > 0 {
+            debug!(
+                new_roots,
+                "WorldID moved to a new root, waking the sync check"
+            );
+            app.sync_check.notify_one();
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use std::{sync::Arc, time::Duration};
+
+use ethers::providers::{Middleware, Provider, Ws};
+use futures::StreamExt;
+use tokio::time::{self, Instant};
+use tracing::{info, warn};
+
+use crate::{app::App, database::types::RootSource, metrics, utils::secret::SecretUrl};
+
+/// Follows the logs of `source` over an `eth_subscribe` subscription. Every
+/// time the subscription is made, the chain is scanned from the cursor up
+/// to the head, so logs emitted while it was down aren't missed.
+pub async fn subscribe_roots(
+    app: Arc<App>,
+    source: RootSource,
+    url: SecretUrl,
+) -> anyhow::Result<()> {
+    let Some(config) = &app.config.providers else {
+        anyhow::bail!("providers config is required to subscribe to logs");
+    };
+    let mut backoff = Backoff::new(config.resubscribe_backoff, config.max_resubscribe_backoff);
+
+    loop {
+        let started = Instant::now();
+        match follow(&app, source, &url).await {
+            Ok(()) => warn!(?source, "Log subscription ended"),
+            Err(err) => warn!(?source, ?err, "Log subscription failed"),
+        }
+
+        if started.elapsed() >= config.max_resubscribe_backoff {
+            backoff.reset();
+        }
+        let wait = backoff.next();
+        info!(?source, ?wait, "Subscribing to logs again");
+        time::sleep(wait).await;
+        metrics::record_resubscription(source);
+    }
+}
+
+/// Subscribes, catches up and records logs until the subscription drops.
+async fn follow(app: &App, source: RootSource, url: &SecretUrl) -> anyhow::Result<()> {
+    // Reconnecting is left to the caller, which catches up after it
+    let ws = Ws::connect_with_reconnects(url.expose(), 0).await?;
+    let provider = Provider::new(ws);
+
+    // Subscribed before catching up, so logs emitted during the catch-up
+    // scan arrive on the subscription. Logs seen both ways are recorded once
+    let filter = app.bridge_processor.log_filter(source);
+    let mut logs = provider.subscribe_logs(&filter).await?;
+    app.bridge_processor.catch_up(source).await?;
+    info!(?source, provider = %url, "Subscribed to logs");
+
+    while let Some(log) = logs.next().await {
+        app.bridge_processor
+            .record_subscribed_log(source, &log)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Doubling waits between subscriptions, so a provider that keeps dropping
+/// them isn't hammered.
+#[derive(Debug)]
+pub(super) struct Backoff {
+    initial: Duration,
+    max:     Duration,
+    next:    Duration,
+}
+
+impl Backoff {
+    pub(super) const fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            next: initial,
+        }
+    }
+
+    pub(super) fn next(&mut self) -> Duration {
+        let wait = self.next;
+        self.next = (self.next * 2).min(self.max);
+        wait
+    }
+
+    pub(super) fn reset(&mut self) {
+        self.next = self.initial;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_max_and_resets() {
+        let secs = Duration::from_secs;
+        let mut backoff = Backoff::new(secs(1), secs(5));
+
+        let waits: Vec<_> = (0..5).map(|_| backoff.next()).collect();
+        assert_eq!(waits, vec![secs(1), secs(2), secs(4), secs(5), secs(5)]);
+
+        backoff.reset();
+        assert_eq!(backoff.next(), secs(1));
+    }
+}
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use ethers::providers::Middleware;
+use tokio::{sync::Notify, time};
+use tracing::{error, info, warn};
+
+use crate::{
+    app::App,
+    balance::{Level, LowBalance},
+    metrics,
+    notifier::{Event, EventKind},
+};
+
+/// Watches the relayer wallet on mainnet. Below the critical threshold
+/// propagation stops, rather than failing on insufficient funds, and it is
+/// woken up again as soon as a check sees the wallet topped up.
+pub async fn wallet_balance(app: Arc<App>, wake_up_notify: Arc<Notify>) -> anyhow::Result<()> {
+    let config = &app.config.balance;
+    let address = app.ethereum.address();
+    let mut timer = time::interval(config.check_interval);
+
+    loop {
+        _ = timer.tick().await;
+
+        let balance = app
+            .ethereum
+            .l1_provider()
+            .get_balance(address, None)
+            .await?;
+        metrics::record_wallet_balance(balance);
+
+        let level = Level::of(balance, config);
+        let low = (level == Level::Critical).then(|| LowBalance {
+            balance,
+            critical_threshold: config.critical_threshold.into(),
+        });
+        let changed = app.balance.record(low);
+        metrics::LOW_BALANCE.set(if low.is_some() { 1.0 } else { 0.0 });
+
+        match level {
+            Level::Critical => error!(
+                ?address,
+                %balance,
+                threshold = config.critical_threshold,
+                "Wallet balance is critically low, propagation is stopped until it is topped up"
+            ),
+            Level::Low => warn!(
+                ?address,
+                %balance,
+                threshold = config.warning_threshold,
+                "Wallet balance is low"
+            ),
+            Level::Healthy => {}
+        }
+
+        if changed && low.is_some() {
+            app.notifier.notify(Event::new(
+                EventKind::LowBalance,
+                format!(
+                    "Wallet {address:?} holds {balance} wei, below the critical threshold of {} \
+                     wei, propagation is stopped",
+                    config.critical_threshold
+                ),
+            ));
+        }
+
+        if changed && low.is_none() {
+            info!(?address, %balance, "Wallet balance recovered, resuming propagation");
+            wake_up_notify.notify_one();
+        }
+    }
+}
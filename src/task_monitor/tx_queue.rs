@@ -0,0 +1,196 @@
+//! Submitted transactions on their way to the transaction monitor.
+//!
+//! Pushing never waits: a transaction already queued is dropped, and so is
+//! one that finds the queue full. Every propagation is in the transactions
+//! table before it is queued, so a dropped one isn't lost, the monitor is
+//! told to look there instead.
+
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Mutex,
+};
+
+use tokio::sync::Notify;
+
+use crate::{
+    metrics::{self, DropReason},
+    task_monitor::tasks::monitor_txs::MonitoredTx,
+    utils::TransactionId,
+};
+
+/// What [`TxQueue::push`] did with a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pushed {
+    Queued,
+    Dropped(DropReason),
+}
+
+/// What the monitor should wait for next.
+#[derive(Debug)]
+pub enum Next {
+    Tx(MonitoredTx),
+    /// Transactions were dropped while the queue was full, they are pending
+    /// in the db
+    Overflowed,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    queue:      VecDeque<MonitoredTx>,
+    queued:     HashSet<TransactionId>,
+    overflowed: bool,
+}
+
+#[derive(Debug)]
+pub struct TxQueue {
+    capacity: usize,
+    inner:    Mutex<Inner>,
+    notify:   Notify,
+}
+
+impl TxQueue {
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::default(),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn push(&self, tx: MonitoredTx) -> Pushed {
+        let pushed = {
+            let mut inner = self.inner.lock().unwrap();
+            if inner.queued.contains(&tx.id) {
+                Pushed::Dropped(DropReason::Duplicate)
+            } else if inner.queue.len() >= self.capacity {
+                inner.overflowed = true;
+                Pushed::Dropped(DropReason::Full)
+            } else {
+                inner.queued.insert(tx.id.clone());
+                inner.queue.push_back(tx);
+                metrics::record_monitored_txs_queued(inner.queue.len());
+                Pushed::Queued
+            }
+        };
+
+        match pushed {
+            Pushed::Queued => self.notify.notify_one(),
+            Pushed::Dropped(reason) => {
+                metrics::record_dropped_monitored_tx(reason);
+                // Wakes the monitor to read the db once it is through the
+                // queue
+                if reason == DropReason::Full {
+                    self.notify.notify_one();
+                }
+            }
+        }
+
+        pushed
+    }
+
+    /// Waits for the next transaction, oldest first. An overflow is reported
+    /// once the transactions queued before it are taken.
+    pub async fn pop(&self) -> Next {
+        loop {
+            if let Some(next) = self.try_pop() {
+                return next;
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    fn try_pop(&self) -> Option<Next> {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(tx) = inner.queue.pop_front() {
+            inner.queued.remove(&tx.id);
+            metrics::record_monitored_txs_queued(inner.queue.len());
+            return Some(Next::Tx(tx));
+        }
+
+        std::mem::take(&mut inner.overflowed).then_some(Next::Overflowed)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().queue.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tracing::Span;
+
+    use super::*;
+
+    fn tx(id: &str) -> MonitoredTx {
+        MonitoredTx {
+            id:   TransactionId::job(id).unwrap(),
+            span: Span::none(),
+        }
+    }
+
+    async fn popped(queue: &TxQueue) -> Option<String> {
+        match tokio::time::timeout(Duration::from_millis(50), queue.pop())
+            .await
+            .ok()?
+        {
+            Next::Tx(tx) => Some(tx.id.as_str().to_string()),
+            Next::Overflowed => Some("overflowed".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn queued_transactions_are_deduplicated() {
+        let queue = TxQueue::new(4);
+
+        assert_eq!(queue.push(tx("tx-1")), Pushed::Queued);
+        assert_eq!(
+            queue.push(tx("tx-1")),
+            Pushed::Dropped(DropReason::Duplicate)
+        );
+        assert_eq!(queue.push(tx("tx-2")), Pushed::Queued);
+        assert_eq!(queue.len(), 2);
+
+        assert_eq!(popped(&queue).await.as_deref(), Some("tx-1"));
+        // Taken by the monitor, which skips what it already monitors
+        assert_eq!(queue.push(tx("tx-1")), Pushed::Queued);
+        assert_eq!(popped(&queue).await.as_deref(), Some("tx-2"));
+        assert_eq!(popped(&queue).await.as_deref(), Some("tx-1"));
+        assert_eq!(popped(&queue).await, None);
+    }
+
+    #[tokio::test]
+    async fn a_full_queue_drops_and_reports_the_overflow_once() {
+        let queue = TxQueue::new(1);
+
+        assert_eq!(queue.push(tx("tx-1")), Pushed::Queued);
+        assert_eq!(queue.push(tx("tx-2")), Pushed::Dropped(DropReason::Full));
+        assert_eq!(queue.push(tx("tx-3")), Pushed::Dropped(DropReason::Full));
+
+        assert_eq!(popped(&queue).await.as_deref(), Some("tx-1"));
+        assert_eq!(popped(&queue).await.as_deref(), Some("overflowed"));
+        assert_eq!(popped(&queue).await, None);
+    }
+
+    #[tokio::test]
+    async fn pop_waits_for_a_push() {
+        let queue = std::sync::Arc::new(TxQueue::new(1));
+
+        let waiting = tokio::spawn({
+            let queue = queue.clone();
+            async move { popped(&queue).await }
+        });
+        tokio::task::yield_now().await;
+        queue.push(tx("tx-1"));
+
+        assert_eq!(waiting.await.unwrap().as_deref(), Some("tx-1"));
+    }
+}
@@ -0,0 +1,51 @@
+//! Wakes the propagation task, carrying the span that woke it so both end up
+//! in one trace.
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use tokio::sync::Notify;
+use tracing::Span;
+
+#[derive(Debug)]
+pub struct PropagationWake {
+    notify: Arc<Notify>,
+    /// The span that last woke the propagation task
+    parent: Mutex<Span>,
+}
+
+impl Default for PropagationWake {
+    fn default() -> Self {
+        Self {
+            notify: Arc::new(Notify::new()),
+            parent: Mutex::new(Span::none()),
+        }
+    }
+}
+
+impl PropagationWake {
+    /// Wakes the propagation task, which continues the trace of the current
+    /// span. Wakes while it is busy fold into one more pass.
+    pub fn wake(&self) {
+        *self.parent.lock().unwrap_or_else(PoisonError::into_inner) = Span::current();
+        self.notify.notify_one();
+    }
+
+    /// Waits for the next [`Self::wake`].
+    pub async fn notified(&self) {
+        self.notify.notified().await;
+    }
+
+    /// The span that last woke the propagation task, `Span::none()` once
+    /// taken.
+    #[must_use]
+    pub fn take_parent(&self) -> Span {
+        let mut parent = self.parent.lock().unwrap_or_else(PoisonError::into_inner);
+        std::mem::replace(&mut *parent, Span::none())
+    }
+
+    /// For tasks that wake the propagation without a trace to continue.
+    #[must_use]
+    pub fn notify(&self) -> Arc<Notify> {
+        self.notify.clone()
+    }
+}
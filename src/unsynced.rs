@@ -0,0 +1,115 @@
+//! How long the bridge has gone without being synced, and whether that is
+//! past `unsynced_deadline`. Read from `last_synced`, so a restart doesn't
+//! start the clock over.
+
+use std::{sync::RwLock, time::Duration};
+
+use chrono::{DateTime, Utc};
+
+use crate::{database::types::ServerStatus, processor::status::BridgeStatus};
+
+/// The status has been unsynced or pending for longer than
+/// `unsynced_deadline`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overdue {
+    pub last_synced:  DateTime<Utc>,
+    pub unsynced_for: Duration,
+}
+
+impl Overdue {
+    /// `None` while `status` is synced or within `deadline`.
+    #[must_use]
+    pub fn of(status: &ServerStatus, deadline: Duration, now: DateTime<Utc>) -> Option<Self> {
+        let last_synced = status.last_synced?;
+        let unsynced_for = unsynced_for(status, now)?;
+
+        (unsynced_for >= deadline).then_some(Self {
+            last_synced,
+            unsynced_for,
+        })
+    }
+}
+
+/// How long `status` has been unsynced or pending, `None` while synced.
+/// `last_synced` is also set as the status leaves synced, so it is when the
+/// bridge was last synced either way.
+#[must_use]
+pub fn unsynced_for(status: &ServerStatus, now: DateTime<Utc>) -> Option<Duration> {
+    if status.status == BridgeStatus::Synced {
+        return None;
+    }
+
+    Some((now - status.last_synced?).to_std().unwrap_or_default())
+}
+
+#[derive(Debug, Default)]
+pub struct UnsyncedState {
+    overdue: RwLock<Option<Overdue>>,
+}
+
+impl UnsyncedState {
+    #[must_use]
+    pub fn overdue(&self) -> Option<Overdue> {
+        *self.overdue.read().unwrap()
+    }
+
+    /// Records the latest check. Returns whether it crossed the deadline.
+    pub fn record(&self, overdue: Option<Overdue>) -> bool {
+        let mut current = self.overdue.write().unwrap();
+        let crossed = current.is_none() && overdue.is_some();
+        *current = overdue;
+        crossed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(status: BridgeStatus, hours_ago: i64, now: DateTime<Utc>) -> ServerStatus {
+        ServerStatus {
+            status,
+            reason: None,
+            last_synced: Some(now - chrono::Duration::hours(hours_ago)),
+        }
+    }
+
+    #[test]
+    fn the_deadline_counts_from_the_last_sync() {
+        let now = Utc::now();
+        let deadline = Duration::from_secs(2 * 60 * 60);
+
+        assert_eq!(
+            Overdue::of(&status(BridgeStatus::Synced, 5, now), deadline, now),
+            None
+        );
+        assert_eq!(
+            Overdue::of(&status(BridgeStatus::Unsynced, 1, now), deadline, now),
+            None
+        );
+
+        let pending = status(BridgeStatus::Pending, 3, now);
+        assert_eq!(
+            Overdue::of(&pending, deadline, now),
+            Some(Overdue {
+                last_synced:  pending.last_synced.unwrap(),
+                unsynced_for: Duration::from_secs(3 * 60 * 60),
+            })
+        );
+    }
+
+    #[test]
+    fn only_crossing_the_deadline_is_reported() {
+        let state = UnsyncedState::default();
+        let overdue = Overdue {
+            last_synced:  Utc::now(),
+            unsynced_for: Duration::from_secs(60),
+        };
+
+        assert!(state.record(Some(overdue)));
+        assert!(!state.record(Some(overdue)));
+        assert!(!state.record(None));
+        assert_eq!(state.overdue(), None);
+        assert!(state.record(Some(overdue)));
+    }
+}
@@ -0,0 +1,319 @@
+//! Accumulates rows in memory and writes them in batches.
+//!
+//! Rows are flushed when the buffer reaches a configured size, when the oldest
+//! buffered row reaches a configured age, on request, and when the buffer is
+//! closed during shutdown.
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use prometheus::{register_gauge_vec, GaugeVec};
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::{self, Instant},
+};
+use tracing::{error, info};
+
+use crate::config::PersistenceConfig;
+
+static BUFFERED_ROWS: Lazy<GaugeVec> = Lazy::new(|| {
+    register_gauge_vec!("persist_buffer_rows", "rows waiting to be written", &[
+        "buffer"
+    ])
+    .unwrap()
+});
+
+static FLUSH_LATENCY: Lazy<GaugeVec> = Lazy::new(|| {
+    register_gauge_vec!(
+        "persist_buffer_flush_latency_seconds",
+        "duration of the last flush",
+        &["buffer"]
+    )
+    .unwrap()
+});
+
+/// Where a [`PersistBuffer`] writes its rows.
+#[async_trait]
+pub trait Flush<T>: Send + Sync + 'static {
+    async fn flush(&self, rows: &[T]) -> anyhow::Result<()>;
+}
+
+enum Command<T> {
+    Push(Vec<T>),
+    Flush(oneshot::Sender<anyhow::Result<()>>),
+    Close(oneshot::Sender<anyhow::Result<()>>),
+}
+
+pub struct PersistBuffer<T> {
+    name:   &'static str,
+    sender: mpsc::Sender<Command<T>>,
+}
+
+impl<T> PersistBuffer<T>
+where
+    T: Send + Sync + 'static,
+{
+    /// Starts the background flusher.
+    pub fn spawn(name: &'static str, config: &PersistenceConfig, sink: Arc<dyn Flush<T>>) -> Self {
+        let (sender, receiver) = mpsc::channel(64);
+
+        tokio::spawn(run(
+            name,
+            config.flush_rows,
+            config.flush_interval,
+            sink,
+            receiver,
+        ));
+
+        Self { name, sender }
+    }
+
+    /// Adds rows to the buffer, flushing if it is full.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the buffer was closed.
+    pub async fn push(&self, rows: Vec<T>) -> anyhow::Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        self.sender
+            .send(Command::Push(rows))
+            .await
+            .map_err(|_| anyhow::anyhow!("{} buffer is closed", self.name))
+    }
+
+    /// Writes every buffered row and waits for the write to finish.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the buffer was closed or the write failed.
+    pub async fn flush(&self) -> anyhow::Result<()> {
+        self.request(Command::Flush).await
+    }
+
+    /// Writes every buffered row and stops the flusher. Rows pushed afterwards
+    /// are rejected.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the buffer was already closed or the write failed.
+    pub async fn close(&self) -> anyhow::Result<()> {
+        self.request(Command::Close).await
+    }
+
+    async fn request(
+        &self,
+        command: impl FnOnce(oneshot::Sender<anyhow::Result<()>>) -> Command<T>,
+    ) -> anyhow::Result<()> {
+        let (ack_sender, ack_receiver) = oneshot::channel();
+
+        self.sender
+            .send(command(ack_sender))
+            .await
+            .map_err(|_| anyhow::anyhow!("{} buffer is closed", self.name))?;
+
+        ack_receiver
+            .await
+            .map_err(|_| anyhow::anyhow!("{} buffer flusher stopped", self.name))?
+    }
+}
+
+async fn run<T>(
+    name: &'static str,
+    flush_rows: usize,
+    flush_interval: Duration,
+    sink: Arc<dyn Flush<T>>,
+    mut receiver: mpsc::Receiver<Command<T>>,
+) where
+    T: Send + Sync + 'static,
+{
+    let mut buffer = Buffer {
+        name,
+        flush_interval,
+        sink,
+        rows: Vec::new(),
+        deadline: None,
+    };
+
+    loop {
+        let deadline = buffer.deadline;
+        let expired = async move {
+            match deadline {
+                Some(deadline) => time::sleep_until(deadline).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            command = receiver.recv() => match command {
+                Some(Command::Push(rows)) => {
+                    buffer.extend(rows);
+                    if buffer.rows.len() >= flush_rows {
+                        _ = buffer.flush().await;
+                    }
+                }
+                Some(Command::Flush(ack)) => {
+                    _ = ack.send(buffer.flush().await);
+                }
+                Some(Command::Close(ack)) => {
+                    _ = ack.send(buffer.flush().await);
+                    info!(name, "Buffer closed");
+                    return;
+                }
+                None => {
+                    _ = buffer.flush().await;
+                    return;
+                }
+            },
+            () = expired => {
+                _ = buffer.flush().await;
+            }
+        }
+    }
+}
+
+struct Buffer<T> {
+    name:           &'static str,
+    flush_interval: Duration,
+    sink:           Arc<dyn Flush<T>>,
+    rows:           Vec<T>,
+    /// When the oldest buffered row must be written
+    deadline:       Option<Instant>,
+}
+
+impl<T> Buffer<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn extend(&mut self, rows: Vec<T>) {
+        if self.rows.is_empty() {
+            self.deadline = Some(Instant::now() + self.flush_interval);
+        }
+
+        self.rows.extend(rows);
+        self.update_gauge();
+    }
+
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+
+        let start = Instant::now();
+        let result = self.sink.flush(&self.rows).await;
+        FLUSH_LATENCY
+            .with_label_values(&[self.name])
+            .set(start.elapsed().as_secs_f64());
+
+        match &result {
+            Ok(()) => {
+                self.rows.clear();
+                self.deadline = None;
+            }
+            Err(err) => {
+                // Keep the rows and try again once the interval has passed
+                error!(name = self.name, rows = self.rows.len(), %err, "Failed to flush buffer");
+                self.deadline = Some(Instant::now() + self.flush_interval);
+            }
+        }
+
+        self.update_gauge();
+
+        result
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn update_gauge(&self) {
+        BUFFERED_ROWS
+            .with_label_values(&[self.name])
+            .set(self.rows.len() as f64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        batches: Mutex<Vec<Vec<u32>>>,
+    }
+
+    #[async_trait]
+    impl Flush<u32> for RecordingSink {
+        async fn flush(&self, rows: &[u32]) -> anyhow::Result<()> {
+            self.batches.lock().unwrap().push(rows.to_vec());
+            Ok(())
+        }
+    }
+
+    impl RecordingSink {
+        fn batches(&self) -> Vec<Vec<u32>> {
+            self.batches.lock().unwrap().clone()
+        }
+    }
+
+    fn config() -> PersistenceConfig {
+        PersistenceConfig {
+            flush_rows:     3,
+            flush_interval: Duration::from_secs(5),
+        }
+    }
+
+    fn buffer(sink: &Arc<RecordingSink>) -> PersistBuffer<u32> {
+        PersistBuffer::spawn("test", &config(), sink.clone())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn flushes_when_full() -> anyhow::Result<()> {
+        let sink = Arc::new(RecordingSink::default());
+        let buffer = buffer(&sink);
+
+        buffer.push(vec![1, 2]).await?;
+        time::sleep(Duration::from_millis(1)).await;
+        assert!(sink.batches().is_empty());
+
+        buffer.push(vec![3]).await?;
+        time::sleep(Duration::from_millis(1)).await;
+        assert_eq!(sink.batches(), vec![vec![1, 2, 3]]);
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn flushes_when_oldest_row_expires() -> anyhow::Result<()> {
+        let sink = Arc::new(RecordingSink::default());
+        let buffer = buffer(&sink);
+
+        buffer.push(vec![1]).await?;
+        time::sleep(Duration::from_secs(4)).await;
+        buffer.push(vec![2]).await?;
+        time::sleep(Duration::from_millis(500)).await;
+        assert!(sink.batches().is_empty());
+
+        // The deadline is set by the first row, not the latest
+        time::sleep(Duration::from_millis(600)).await;
+        assert_eq!(sink.batches(), vec![vec![1, 2]]);
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn close_flushes_buffered_rows() -> anyhow::Result<()> {
+        let sink = Arc::new(RecordingSink::default());
+        let buffer = buffer(&sink);
+
+        buffer.push(vec![1, 2]).await?;
+        buffer.close().await?;
+
+        assert_eq!(sink.batches(), vec![vec![1, 2]]);
+        assert!(buffer.push(vec![3]).await.is_err());
+
+        Ok(())
+    }
+}
@@ -1,9 +1,6 @@
-use std::borrow::Cow;
-use std::fmt;
-use std::str::FromStr;
+use std::{borrow::Cow, fmt, str::FromStr};
 
-use serde::de::DeserializeOwned;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub struct JsonStrWrapper<T>(pub T);
@@ -62,6 +59,87 @@ impl<T> From<T> for JsonStrWrapper<T> {
     }
 }
 
+/// A wei amount in the config, for `#[serde(with = "wei")]`. Written as an
+/// integer like the other amounts, or as a decimal string past what a TOML
+/// integer holds, about 9.2 ether.
+pub mod wei {
+    use std::fmt;
+
+    use ethers::types::U256;
+    use serde::{de, Deserializer, Serializer};
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the serializer fails.
+    pub fn serialize<S: Serializer>(value: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+        if *value <= U256::from(i64::MAX) {
+            serializer.serialize_u64(value.as_u64())
+        } else {
+            serializer.collect_str(value)
+        }
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the value is negative or not a decimal number.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+        deserializer.deserialize_any(WeiVisitor)
+    }
+
+    struct WeiVisitor;
+
+    impl<'de> de::Visitor<'de> for WeiVisitor {
+        type Value = U256;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("an amount of wei, as an integer or a decimal string")
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<U256, E> {
+            Ok(value.into())
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<U256, E> {
+            u64::try_from(value)
+                .map(U256::from)
+                .map_err(|_| E::invalid_value(de::Unexpected::Signed(value), &self))
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<U256, E> {
+            U256::from_dec_str(value)
+                .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+        }
+    }
+
+    /// [`wei`](self) for an optional amount, unset when missing.
+    pub mod option {
+        use ethers::types::U256;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        #[derive(Serialize, Deserialize)]
+        struct Wei(#[serde(with = "super")] U256);
+
+        /// # Errors
+        ///
+        /// Will return `Err` if the serializer fails.
+        pub fn serialize<S: Serializer>(
+            value: &Option<U256>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            value.map(Wei).serialize(serializer)
+        }
+
+        /// # Errors
+        ///
+        /// Will return `Err` if the value is negative or not a decimal number.
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<U256>, D::Error> {
+            Ok(Option::<Wei>::deserialize(deserializer)?.map(|Wei(value)| value))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::Value;
@@ -81,6 +159,36 @@ mod tests {
         assert_eq!(wrapper.0, vec![1, 2, 3]);
     }
 
+    #[test]
+    fn wei_past_a_toml_integer_is_a_decimal_string() {
+        use ethers::types::U256;
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Amounts {
+            #[serde(with = "wei")]
+            small: U256,
+            #[serde(with = "wei::option")]
+            large: Option<U256>,
+        }
+
+        let amounts: Amounts = toml::from_str(indoc::indoc! {r#"
+            small = 500000000000000000
+            large = "100000000000000000000"
+        "#})
+        .unwrap();
+        assert_eq!(amounts, Amounts {
+            small: U256::exp10(17) * 5,
+            large: Some(U256::exp10(20)),
+        });
+        assert_eq!(
+            toml::to_string(&amounts).unwrap(),
+            "small = 500000000000000000\nlarge = \"100000000000000000000\"\n"
+        );
+
+        assert!(toml::from_str::<Amounts>("small = -1").is_err());
+        assert!(toml::from_str::<Amounts>(r#"small = "0x10""#).is_err());
+    }
+
     #[test]
     fn json_value() {
         let wrapper = JsonStrWrapper(vec![1, 2, 3]);
@@ -0,0 +1,220 @@
+use std::{borrow::Borrow, fmt, str::FromStr};
+
+use ethers::types::H256;
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    encode::IsNull,
+    error::BoxDynError,
+    postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef},
+    Decode, Encode, Postgres, Type,
+};
+use thiserror::Error;
+
+/// Longest relayer job id accepted, well past a UUID.
+const MAX_JOB_ID_LEN: usize = 128;
+
+/// A propagation as the relayer knows it: the transaction hash for the raw
+/// signer, a job id for OZ Defender and TxSitter.
+///
+/// Both forms are validated when constructed, so an id that made it into a
+/// channel, the database or a log is one the relayer can be asked about.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TransactionId(String);
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum InvalidTransactionId {
+    #[error("transaction id is empty")]
+    Empty,
+    #[error("{0:?} is not a 0x-prefixed 32-byte transaction hash")]
+    Hash(String),
+    #[error("{0:?} is not a relayer job id")]
+    JobId(String),
+}
+
+impl TransactionId {
+    #[must_use]
+    pub fn from_hash(hash: H256) -> Self {
+        // Debug renders the full lowercase hash
+        Self(format!("{hash:?}"))
+    }
+
+    /// A relayer job id: up to 128 ASCII letters, digits, `-` and `_`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `id` is empty or isn't shaped like a job id.
+    pub fn job(id: impl Into<String>) -> Result<Self, InvalidTransactionId> {
+        let id = id.into();
+        if id.is_empty() {
+            return Err(InvalidTransactionId::Empty);
+        }
+
+        let valid = id.len() <= MAX_JOB_ID_LEN
+            && !id.starts_with("0x")
+            && id
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_');
+        if !valid {
+            return Err(InvalidTransactionId::JobId(id));
+        }
+
+        Ok(Self(id))
+    }
+
+    /// The transaction hash, for ids of the raw signer.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the id is a job id.
+    pub fn to_hash(&self) -> Result<H256, InvalidTransactionId> {
+        parse_hash(&self.0)
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+fn parse_hash(id: &str) -> Result<H256, InvalidTransactionId> {
+    let invalid = || InvalidTransactionId::Hash(id.to_string());
+
+    let digits = id.strip_prefix("0x").ok_or_else(invalid)?;
+    if digits.len() != 64 {
+        return Err(invalid());
+    }
+    digits.parse().map_err(|_| invalid())
+}
+
+/// Either form, told apart by the `0x` prefix.
+impl FromStr for TransactionId {
+    type Err = InvalidTransactionId;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("0x") {
+            parse_hash(s).map(Self::from_hash)
+        } else {
+            Self::job(s)
+        }
+    }
+}
+
+impl TryFrom<String> for TransactionId {
+    type Error = InvalidTransactionId;
+
+    fn try_from(id: String) -> Result<Self, Self::Error> {
+        id.parse()
+    }
+}
+
+impl From<TransactionId> for String {
+    fn from(id: TransactionId) -> Self {
+        id.0
+    }
+}
+
+impl AsRef<str> for TransactionId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Lets maps keyed by id be looked up with a `&str`.
+impl Borrow<str> for TransactionId {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for TransactionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Type<Postgres> for TransactionId {
+    fn type_info() -> PgTypeInfo {
+        <String as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <String as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, Postgres> for TransactionId {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        <&str as Encode<Postgres>>::encode(self.as_str(), buf)
+    }
+}
+
+/// Rows that don't hold a valid id fail to decode rather than reaching the
+/// relayer.
+impl<'r> Decode<'r, Postgres> for TransactionId {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(<&str as Decode<Postgres>>::decode(value)?.parse()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_and_job_ids_are_told_apart() {
+        let hash = H256::repeat_byte(0xab);
+        let id: TransactionId = format!("{hash:?}").parse().unwrap();
+        assert_eq!(id, TransactionId::from_hash(hash));
+        assert_eq!(id.to_hash(), Ok(hash));
+
+        let job: TransactionId = "3f2b8e4c-1d2a-4b6f-9c1e-0a7d5e6f8b9c".parse().unwrap();
+        assert_eq!(
+            job,
+            TransactionId::job("3f2b8e4c-1d2a-4b6f-9c1e-0a7d5e6f8b9c").unwrap()
+        );
+        assert!(matches!(job.to_hash(), Err(InvalidTransactionId::Hash(_))));
+    }
+
+    #[test]
+    fn malformed_ids_are_rejected() {
+        assert_eq!(
+            "".parse::<TransactionId>(),
+            Err(InvalidTransactionId::Empty)
+        );
+        assert_eq!(TransactionId::job(""), Err(InvalidTransactionId::Empty));
+
+        for hash in [
+            "0x",
+            "0xabc",
+            &format!("0x{}", "g".repeat(64)),
+            &format!("0x{}", "a".repeat(66)),
+        ] {
+            assert!(
+                matches!(
+                    hash.parse::<TransactionId>(),
+                    Err(InvalidTransactionId::Hash(_))
+                ),
+                "{hash}"
+            );
+        }
+
+        for job in ["job id", "job\n", "jöb", &"a".repeat(MAX_JOB_ID_LEN + 1)] {
+            assert!(
+                matches!(TransactionId::job(job), Err(InvalidTransactionId::JobId(_))),
+                "{job}"
+            );
+        }
+    }
+
+    #[test]
+    fn serde_validates_like_parsing() {
+        let id = TransactionId::job("tx-1").unwrap();
+        assert_eq!(serde_json::to_string(&id).unwrap(), r#""tx-1""#);
+        assert_eq!(
+            serde_json::from_str::<TransactionId>(r#""tx-1""#).unwrap(),
+            id
+        );
+        assert!(serde_json::from_str::<TransactionId>(r#""""#).is_err());
+    }
+}
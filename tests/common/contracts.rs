@@ -0,0 +1,163 @@
+//! The mock contracts under `tests/contracts`, compiled with the `solc` on the
+//! path and deployed to the simulated chains.
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{bail, Context};
+use ethers::{
+    abi::Abi,
+    contract::{abigen, ContractFactory},
+    middleware::SignerMiddleware,
+    providers::{Http, Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::{Address, Bytes, U256},
+    utils::AnvilInstance,
+};
+use ethers_solc::{CompilerOutput, Solc};
+use tokio::task::JoinHandle;
+
+abigen!(
+    MockWorldId,
+    r#"[
+        function setRoot(uint256 postRoot) external
+        function latestRoot() external view returns (uint256)
+    ]"#,
+);
+
+abigen!(
+    MockScrollWorldId,
+    r#"[
+        function receiveRoot(uint256 root) external
+        function latestRoot() external view returns (uint256)
+    ]"#,
+);
+
+abigen!(
+    MockScrollStateBridge,
+    r#"[
+        event RootPropagated(uint256 root)
+    ]"#,
+);
+
+pub type Client = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+const CONTRACTS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/contracts");
+
+/// Signs with the `index`th dev account of `anvil`.
+pub fn client(anvil: &AnvilInstance, index: usize) -> anyhow::Result<Arc<Client>> {
+    let provider =
+        Provider::<Http>::try_from(anvil.endpoint())?.interval(Duration::from_millis(50));
+    let wallet = LocalWallet::from(anvil.keys()[index].clone()).with_chain_id(anvil.chain_id());
+
+    Ok(Arc::new(SignerMiddleware::new(provider, wallet)))
+}
+
+pub fn compile() -> anyhow::Result<CompilerOutput> {
+    let output = Solc::default()
+        .compile_source(CONTRACTS_DIR)
+        .context("compile the mock contracts, is solc installed?")?;
+    if output.has_error() {
+        bail!("the mock contracts don't compile: {:?}", output.errors);
+    }
+
+    Ok(output)
+}
+
+fn artifact(output: &CompilerOutput, name: &str) -> anyhow::Result<(Abi, Bytes)> {
+    let contract = output
+        .find(name)
+        .with_context(|| format!("no {name} in the mock contracts"))?;
+    let abi = contract.abi.context("no abi")?.clone();
+    let bytecode = contract
+        .bin
+        .and_then(|bin| bin.as_bytes())
+        .context("no bytecode")?
+        .clone();
+
+    Ok((abi, bytecode))
+}
+
+async fn deploy<T: ethers::abi::Tokenize>(
+    output: &CompilerOutput,
+    name: &str,
+    client: Arc<Client>,
+    args: T,
+) -> anyhow::Result<Address> {
+    let (abi, bytecode) = artifact(output, name)?;
+    let contract = ContractFactory::new(abi, bytecode, client)
+        .deploy(args)?
+        .send()
+        .await?;
+
+    Ok(contract.address())
+}
+
+/// The mocks, deployed as the service finds them: the bridge on mainnet
+/// knows both WorldIDs.
+pub struct Contracts {
+    pub world_id:        MockWorldId<Client>,
+    pub scroll_world_id: MockScrollWorldId<Client>,
+    pub bridge:          MockScrollStateBridge<Client>,
+}
+
+impl Contracts {
+    /// Deploys from `deployer` on each chain. Only `owner` may propagate.
+    pub async fn deploy(
+        mainnet_deployer: Arc<Client>,
+        scroll_deployer: Arc<Client>,
+        owner: Address,
+    ) -> anyhow::Result<Self> {
+        let output = compile()?;
+
+        let world_id = deploy(&output, "MockWorldId", mainnet_deployer.clone(), ()).await?;
+        let scroll_world_id =
+            deploy(&output, "MockScrollWorldId", scroll_deployer.clone(), ()).await?;
+        let bridge = deploy(
+            &output,
+            "MockScrollStateBridge",
+            mainnet_deployer.clone(),
+            (world_id, scroll_world_id, owner),
+        )
+        .await?;
+
+        Ok(Self {
+            world_id:        MockWorldId::new(world_id, mainnet_deployer.clone()),
+            scroll_world_id: MockScrollWorldId::new(scroll_world_id, scroll_deployer),
+            bridge:          MockScrollStateBridge::new(bridge, mainnet_deployer),
+        })
+    }
+
+    /// Inserts identities on mainnet, moving its root to `root`.
+    pub async fn set_mainnet_root(&self, root: U256) -> anyhow::Result<()> {
+        self.world_id.set_root(root).send().await?.await?;
+        Ok(())
+    }
+
+    /// Stands in for the Scroll messenger: every root propagated on mainnet
+    /// is added on Scroll, in order.
+    pub fn relay(&self) -> JoinHandle<anyhow::Result<()>> {
+        let bridge = self.bridge.clone();
+        let scroll_world_id = self.scroll_world_id.clone();
+
+        tokio::spawn(async move {
+            let mut from_block = 0;
+            loop {
+                let head = bridge.client().get_block_number().await?.as_u64();
+                if head >= from_block {
+                    let propagated = bridge
+                        .root_propagated_filter()
+                        .from_block(from_block)
+                        .to_block(head)
+                        .query()
+                        .await?;
+                    for RootPropagatedFilter { root } in propagated {
+                        scroll_world_id.receive_root(root).send().await?.await?;
+                    }
+                    from_block = head + 1;
+                }
+
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            }
+        })
+    }
+}
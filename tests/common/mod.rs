@@ -0,0 +1,161 @@
+//! Two simulated chains with the mock contracts deployed, a throwaway
+//! Postgres and a config pointing the service at them. Needs `anvil` and
+//! `solc` on the path and a running Docker daemon.
+
+// Each test crate uses a part of the harness
+#![allow(dead_code)]
+
+pub mod contracts;
+
+use std::{future::Future, time::Duration};
+
+use anyhow::bail;
+use ethers::{
+    types::U256,
+    utils::{Anvil, AnvilInstance},
+};
+use postgres_docker_utils::DockerContainer;
+use scroll_service::config::Config;
+use sqlx::{PgPool, Row};
+use testcontainers::clients::Cli;
+use tokio::task::JoinHandle;
+
+use self::contracts::Contracts;
+
+pub const MAINNET_CHAIN_ID: u64 = 31337;
+pub const SCROLL_CHAIN_ID: u64 = 534352;
+
+/// The dev account the service signs with, and the bridge's owner
+const SERVICE_ACCOUNT: usize = 0;
+/// The dev account that deploys the mocks and relays roots to Scroll
+const DEPLOYER_ACCOUNT: usize = 1;
+
+pub struct Harness<'a> {
+    pub mainnet:   AnvilInstance,
+    pub scroll:    AnvilInstance,
+    pub contracts: Contracts,
+    database:      DockerContainer<'a>,
+    relay:         JoinHandle<anyhow::Result<()>>,
+}
+
+impl<'a> Harness<'a> {
+    pub async fn start(docker: &'a Cli) -> anyhow::Result<Self> {
+        let mainnet = Anvil::new().chain_id(MAINNET_CHAIN_ID).spawn();
+        let scroll = Anvil::new().chain_id(SCROLL_CHAIN_ID).spawn();
+
+        let owner = contracts::client(&mainnet, SERVICE_ACCOUNT)?.address();
+        let contracts = Contracts::deploy(
+            contracts::client(&mainnet, DEPLOYER_ACCOUNT)?,
+            contracts::client(&scroll, DEPLOYER_ACCOUNT)?,
+            owner,
+        )
+        .await?;
+        let relay = contracts.relay();
+
+        let database = postgres_docker_utils::setup(docker).await?;
+
+        Ok(Self {
+            mainnet,
+            scroll,
+            contracts,
+            database,
+            relay,
+        })
+    }
+
+    pub fn database_url(&self) -> String {
+        format!(
+            "postgres://postgres:postgres@{}/database",
+            self.database.address()
+        )
+    }
+
+    /// The service against the harness, scanning every second and propagating
+    /// every root as soon as it is seen.
+    pub fn config(&self) -> anyhow::Result<Config> {
+        let signing_key = hex::encode(self.mainnet.keys()[SERVICE_ACCOUNT].to_bytes());
+        let config = format!(
+            r#"
+            [app]
+            time_between_scans = "1s"
+            scanning_chain_head_offset = 0
+
+            [network]
+            scroll_bridge_address = "{bridge:?}"
+
+            [providers]
+            l1_network_provider = "{mainnet}"
+            l2_network_provider = "{scroll}"
+            l1_expected_chain_id = {MAINNET_CHAIN_ID}
+            l2_expected_chain_id = {SCROLL_CHAIN_ID}
+
+            [relayer]
+            kind = "raw_signer"
+            signing_key = "0x{signing_key}"
+            signer_mine_timeout = "30s"
+
+            [database]
+            database = "{database}"
+
+            [server]
+            address = "127.0.0.1:0"
+
+            [retention]
+            enabled = false
+            "#,
+            bridge = self.contracts.bridge.address(),
+            mainnet = self.mainnet.endpoint(),
+            scroll = self.scroll.endpoint(),
+            database = self.database_url(),
+        );
+
+        Ok(toml::from_str(&config)?)
+    }
+
+    pub async fn scroll_latest_root(&self) -> anyhow::Result<U256> {
+        Ok(self.contracts.scroll_world_id.latest_root().call().await?)
+    }
+
+    /// The roots scanned from `source`, `bridge` or `scroll`, oldest first.
+    pub async fn scanned_roots(&self, source: &str) -> anyhow::Result<Vec<U256>> {
+        let pool = PgPool::connect(&self.database_url()).await?;
+        let rows = sqlx::query(
+            "SELECT root FROM roots WHERE source = $1::root_source ORDER BY block_number, \
+             log_index",
+        )
+        .bind(source)
+        .fetch_all(&pool)
+        .await?;
+        pool.close().await;
+
+        rows.iter()
+            .map(|row| Ok(row.get::<&str, _>(0).parse()?))
+            .collect()
+    }
+}
+
+impl Drop for Harness<'_> {
+    fn drop(&mut self) {
+        self.relay.abort();
+    }
+}
+
+/// Polls `condition` every second until it holds, failing after `timeout`.
+pub async fn wait_for<F, Fut>(timeout: Duration, what: &str, mut condition: F) -> anyhow::Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<bool>>,
+{
+    let deadline = tokio::time::Instant::now() + timeout;
+    while !condition().await? {
+        if tokio::time::Instant::now() >= deadline {
+            bail!(
+                "timed out after {} waiting for {what}",
+                humantime::format_duration(timeout)
+            );
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,33 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+interface IWorldId {
+    function latestRoot() external view returns (uint256);
+}
+
+/// The mainnet state bridge. Only `owner` may propagate, so a service
+/// sending from another account sees the same `OwnableUnauthorizedAccount`
+/// revert the deployed bridge answers with.
+contract MockScrollStateBridge {
+    error OwnableUnauthorizedAccount(address account);
+
+    event RootPropagated(uint256 root);
+
+    address public owner;
+    address public worldIDAddress;
+    address public scrollWorldIDAddress;
+
+    constructor(address worldId, address scrollWorldId, address _owner) {
+        worldIDAddress = worldId;
+        scrollWorldIDAddress = scrollWorldId;
+        owner = _owner;
+    }
+
+    function propagateRoot() external payable {
+        if (msg.sender != owner) {
+            revert OwnableUnauthorizedAccount(msg.sender);
+        }
+
+        emit RootPropagated(IWorldId(worldIDAddress).latestRoot());
+    }
+}
@@ -0,0 +1,24 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+/// The WorldID on Scroll. The test harness relays propagated roots to it in
+/// place of the Scroll messenger, from the account that deployed it.
+contract MockScrollWorldId {
+    event RootAdded(uint256 root, uint128 timestamp);
+
+    address public immutable messenger;
+    uint256 public latestRoot;
+    mapping(uint256 => uint128) public rootHistory;
+
+    constructor() {
+        messenger = msg.sender;
+    }
+
+    function receiveRoot(uint256 root) external {
+        require(msg.sender == messenger, "only the messenger");
+
+        latestRoot = root;
+        rootHistory[root] = uint128(block.timestamp);
+        emit RootAdded(root, uint128(block.timestamp));
+    }
+}
@@ -0,0 +1,54 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+/// The part of the mainnet WorldID identity manager the service reads.
+/// `setRoot` stands in for a batch of identity insertions.
+contract MockWorldId {
+    struct RootInfo {
+        uint256 root;
+        uint128 supersededTimestamp;
+        bool isValid;
+    }
+
+    error NonExistentRoot();
+
+    event TreeChanged(uint256 indexed preRoot, uint8 indexed kind, uint256 indexed postRoot);
+
+    address public owner;
+    address public identityOperator;
+    uint256 public latestRoot;
+
+    mapping(uint256 => uint128) internal supersededAt;
+    mapping(uint256 => bool) internal known;
+
+    constructor() {
+        owner = msg.sender;
+        identityOperator = msg.sender;
+    }
+
+    function setRoot(uint256 postRoot) external {
+        uint256 preRoot = latestRoot;
+        if (preRoot != 0) {
+            supersededAt[preRoot] = uint128(block.timestamp);
+        }
+
+        latestRoot = postRoot;
+        known[postRoot] = true;
+        // 0 is an insertion
+        emit TreeChanged(preRoot, 0, postRoot);
+    }
+
+    /// Reverts on roots it never had, like the identity manager.
+    function queryRoot(uint256 root) external view returns (RootInfo memory) {
+        if (!known[root]) {
+            revert NonExistentRoot();
+        }
+
+        uint128 superseded = supersededAt[root];
+        return RootInfo(root, superseded, true);
+    }
+
+    function getRootHistoryExpiry() external pure returns (uint256) {
+        return 1 weeks;
+    }
+}
@@ -0,0 +1,91 @@
+//! Runs the task graph through the library API alone, as an embedder would.
+//! These need a database and both chains with the contracts deployed, so they
+//! read the config from `SCROLL_SERVICE_TEST_CONFIG` and are ignored by
+//! default:
+//!
+//! ```sh
+//! SCROLL_SERVICE_TEST_CONFIG=config.toml cargo test --no-default-features \
+//!     --test embedded -- --ignored
+//! ```
+
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use scroll_service::{
+    app::App, config::load_config, task_monitor::health::TaskState, utils::shutdown::Shutdown,
+};
+
+const TASKS: [&str; 8] = [
+    "check_sync",
+    "finalize_txs",
+    "maintenance",
+    "monitor_txs",
+    "propagate_root",
+    "scan_roots",
+    "wallet_balance",
+    "watch_pause",
+];
+
+async fn app() -> Arc<App> {
+    let path = std::env::var_os("SCROLL_SERVICE_TEST_CONFIG")
+        .map(PathBuf::from)
+        .expect("SCROLL_SERVICE_TEST_CONFIG is set");
+    let config = load_config(Some(&path)).unwrap();
+
+    let app = App::new(config).await.unwrap();
+    app.initialize_server().await.unwrap();
+    app
+}
+
+fn task_states(app: &App) -> Vec<(&'static str, TaskState)> {
+    app.task_health.snapshot().into_iter().collect()
+}
+
+#[tokio::test]
+#[ignore = "requires a database and both chains"]
+async fn tasks_run_and_stop_without_the_server() {
+    let app = app().await;
+    let tasks = app.spawn_tasks(Arc::new(Shutdown::new())).await;
+
+    // Give the tasks time to fail if they are going to
+    tokio::time::sleep(Duration::from_secs(5)).await;
+
+    let names: Vec<_> = task_states(&app)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    assert_eq!(names, TASKS);
+    for (name, state) in task_states(&app) {
+        assert_eq!(state, TaskState::Running, "{name}");
+    }
+
+    app.status().await.unwrap();
+    app.latest_roots().await.unwrap();
+    app.trigger_propagation().await.unwrap();
+
+    tasks.shutdown().await.unwrap();
+    for (name, state) in task_states(&app) {
+        assert_eq!(state, TaskState::Stopped, "{name}");
+    }
+
+    app.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires a database and both chains"]
+async fn shutdown_signal_stops_the_tasks() {
+    let app = app().await;
+    let shutdown = Arc::new(Shutdown::new());
+    let tasks = app.spawn_tasks(shutdown.clone()).await;
+
+    shutdown.shutdown();
+
+    tokio::time::timeout(Duration::from_secs(10), tasks.shutdown())
+        .await
+        .expect("tasks stop once shutdown is signalled")
+        .unwrap();
+    assert!(task_states(&app)
+        .iter()
+        .all(|(_, state)| *state == TaskState::Stopped));
+
+    app.shutdown().await.unwrap();
+}
@@ -0,0 +1,87 @@
+//! Runs the whole service against two anvil chains with the mock contracts
+//! under `tests/contracts` deployed, and a Postgres container. Needs `anvil`,
+//! `solc` and Docker, so it is ignored by default:
+//!
+//! ```sh
+//! cargo test --test propagation -- --ignored
+//! ```
+
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use ethers::types::U256;
+use scroll_service::{
+    app::App, utils::shutdown::Shutdown, BridgeProcessor, BridgeStatus, Processor, Propagation,
+    SyncState,
+};
+use testcontainers::clients::Cli;
+
+use self::common::{wait_for, Harness};
+
+/// The sync check only marks the status synced on its next pass, 2 minutes
+/// after the one that set off the propagation.
+const SYNC_TIMEOUT: Duration = Duration::from_secs(4 * 60);
+
+#[tokio::test]
+#[ignore = "requires anvil, solc and docker"]
+async fn a_new_mainnet_root_is_propagated_until_synced() -> anyhow::Result<()> {
+    let docker = Cli::default();
+    let harness = Harness::start(&docker).await?;
+
+    let root = U256::from(0xabc);
+    harness.contracts.set_mainnet_root(root).await?;
+
+    let app = App::new(harness.config()?).await?;
+    app.initialize_server().await?;
+    let tasks = app.spawn_tasks(Arc::new(Shutdown::new())).await;
+
+    wait_for(SYNC_TIMEOUT, "the status to be synced", || async {
+        Ok(app.status().await?.status.status == BridgeStatus::Synced)
+    })
+    .await?;
+
+    assert_eq!(harness.scroll_latest_root().await?, root);
+    assert_eq!(harness.scanned_roots("bridge").await?, vec![root]);
+    assert_eq!(harness.scanned_roots("scroll").await?, vec![root]);
+
+    tasks.shutdown().await?;
+    app.shutdown().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "requires anvil, solc and docker"]
+async fn the_processor_propagates_without_the_service() -> anyhow::Result<()> {
+    let docker = Cli::default();
+    let harness = Harness::start(&docker).await?;
+
+    let root = U256::from(0xdef);
+    harness.contracts.set_mainnet_root(root).await?;
+
+    let processor = BridgeProcessor::new(&harness.config()?).await?;
+    processor.scan_roots().await?;
+    assert!(matches!(
+        processor.sync_state().await?,
+        SyncState::Unsynced(_)
+    ));
+
+    let Propagation::Submitted { transaction_id, .. } = processor.propagate_root().await? else {
+        anyhow::bail!("the root wasn't sent");
+    };
+    assert!(processor.mine_transaction(transaction_id).await?);
+
+    wait_for(
+        SYNC_TIMEOUT,
+        "the processor to see the roots synced",
+        || async {
+            processor.scan_roots().await?;
+            Ok(processor.sync_state().await?.is_synced())
+        },
+    )
+    .await?;
+    assert_eq!(harness.scroll_latest_root().await?, root);
+
+    Ok(())
+}